@@ -0,0 +1,11 @@
+#!/bin/bash -eu
+# OSS-Fuzz build script: builds every target and copies it, with its seed
+# corpus, to $OUT.
+cd "$SRC/femtoclaw-policy"
+cargo fuzz build -O
+for target in policy_json evaluate pattern; do
+    cp "fuzz/target/x86_64-unknown-linux-gnu/release/$target" "$OUT/"
+    if [ -d "fuzz/corpus/$target" ]; then
+        (cd "fuzz/corpus/$target" && zip -q "$OUT/${target}_seed_corpus.zip" ./*)
+    fi
+done
@@ -0,0 +1,172 @@
+//! Historical Policy Archive.
+//!
+//! An engine only retains its last few generations, and only for rollback.
+//! [`PolicyArchive`] keeps every generation it is shown, with the time it was
+//! activated, so an auditor can ask whether a request would have been allowed
+//! at some past instant: [`PolicyArchive::evaluate_at`] evaluates it against
+//! the policies active then, with time conditions evaluated at that instant.
+//!
+//! Call [`PolicyArchive::record`] after every activation and rollback. A
+//! rollback is archived as the restored generation becoming active again at
+//! the time of the call. Generations are evaluated with the engine
+//! configuration (default effect, categories, group resolver) of the latest
+//! `record`.
+
+use crate::args::view_of;
+use crate::authorizer::AuthorizationRequest;
+use crate::clock::format_utc;
+use crate::context::RequestContext;
+use crate::generation::{Generation, Generations, PolicySnapshot};
+use crate::policy::{Effect, PolicyEngine};
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ArchiveError {
+    #[error("no archived policy generation was active at {}", format_utc(*.0))]
+    NotActive(u64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoricalDecision {
+    /// The generation that was active at the requested instant.
+    pub generation: Generation,
+    pub effect: Effect,
+    /// The deciding rule's `policy#index`; `None` if the default applied.
+    pub rule: Option<String>,
+}
+
+#[derive(Clone, Default)]
+pub struct PolicyArchive {
+    template: PolicyEngine,
+    /// In activation order.
+    entries: Vec<(Generation, Arc<PolicySnapshot>)>,
+}
+
+impl PolicyArchive {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Archives the generations `engine` activated since the last call;
+    /// returns how many were added.
+    pub fn record(&mut self, engine: &PolicyEngine) -> usize {
+        let mut template = engine.clone();
+        *template.generations_mut() = Generations::default();
+        self.template = template;
+
+        let last = self.entries.last().map(|(generation, _)| generation.number);
+        let before = self.entries.len();
+        let generations = engine.generations_ref();
+        for (generation, snapshot) in generations.oldest_first() {
+            if last < Some(generation.number) {
+                self.entries.push((generation.clone(), snapshot.clone()));
+            }
+        }
+        let active = generations.oldest_first().last();
+        if let (true, Some(last), Some((generation, snapshot))) =
+            (self.entries.len() == before, last, active)
+        {
+            if generation.number != last {
+                let reactivated = Generation {
+                    activated_at_ms: engine.time_check().now_ms,
+                    ..generation.clone()
+                };
+                self.entries.push((reactivated, snapshot.clone()));
+            }
+        }
+        self.entries.len() - before
+    }
+
+    /// Archived activations, oldest first. A generation appears again for
+    /// every rollback to it.
+    pub fn generations(&self) -> Vec<Generation> {
+        self.entries.iter().map(|(g, _)| g.clone()).collect()
+    }
+
+    /// The generation active at `at_ms`.
+    pub fn generation_at(&self, at_ms: u64) -> Option<&Generation> {
+        self.entry_at(at_ms).map(|(generation, _)| generation)
+    }
+
+    fn entry_at(&self, at_ms: u64) -> Option<&(Generation, Arc<PolicySnapshot>)> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(generation, _)| generation.activated_at_ms <= at_ms)
+    }
+
+    /// Evaluates `request` against the policies active at `at_ms`, with time
+    /// conditions evaluated at `at_ms`.
+    pub fn evaluate_at(
+        &self,
+        at_ms: u64,
+        request: &AuthorizationRequest<'_>,
+    ) -> Result<HistoricalDecision, ArchiveError> {
+        let (generation, snapshot) = self.entry_at(at_ms).ok_or(ArchiveError::NotActive(at_ms))?;
+        let mut engine = self.template.clone();
+        engine.restore(PolicySnapshot::clone(snapshot));
+        let ctx = RequestContext {
+            evaluated_at_ms: Some(at_ms),
+            ..request.ctx.clone()
+        };
+        let found = engine.find_rule(&ctx, request.tool, "execute", view_of(request.args));
+        Ok(HistoricalDecision {
+            generation: generation.clone(),
+            effect: found
+                .as_ref()
+                .map(|m| m.rule.effect)
+                .unwrap_or_else(|| engine.default_effect_for(request.tool)),
+            rule: found.map(|m| m.id()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::PolicyBundle;
+    use crate::policy::{Policy, Rule};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    fn bundle(version: &str, rule: Rule) -> PolicyBundle {
+        PolicyBundle::new("prod", version)
+            .with_policy(Policy::new("tools", version).with_rule(rule))
+    }
+
+    #[test]
+    fn test_evaluate_at_uses_policies_active_then() {
+        let now = Arc::new(AtomicU64::new(1_000));
+        let clock = now.clone();
+        let mut engine = PolicyEngine::new()
+            .with_default_effect(Effect::Deny)
+            .with_clock(Arc::new(move || clock.load(Ordering::SeqCst)));
+        let mut archive = PolicyArchive::new();
+
+        engine
+            .activate_bundle(bundle("1", Rule::allow("shell")))
+            .unwrap();
+        assert_eq!(archive.record(&engine), 2);
+        now.store(2_000, Ordering::SeqCst);
+        engine
+            .activate_bundle(bundle("2", Rule::deny("shell")))
+            .unwrap();
+        assert_eq!(archive.record(&engine), 1);
+        now.store(3_000, Ordering::SeqCst);
+        engine.rollback(1).unwrap();
+        assert_eq!(archive.record(&engine), 1);
+
+        let ctx = RequestContext::new();
+        let request = AuthorizationRequest::new("shell", &(), &ctx);
+        let at = |ms| archive.evaluate_at(ms, &request).unwrap();
+        assert_eq!(at(1_500).effect, Effect::Allow);
+        assert_eq!(at(1_500).rule.as_deref(), Some("tools#0"));
+        assert_eq!(at(2_500).effect, Effect::Deny);
+        assert_eq!(at(2_500).generation.number, 2);
+        assert_eq!(at(3_500).generation.number, 1);
+        assert_eq!(
+            archive.evaluate_at(999, &request),
+            Err(ArchiveError::NotActive(999))
+        );
+    }
+}
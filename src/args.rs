@@ -0,0 +1,214 @@
+//! Tool Arguments.
+//!
+//! The gate accepts any [`Args`] implementation, so callers that gate purely by
+//! capability name can pass `&()` instead of building an empty JSON object.
+//! Arguments without a view are evaluated as `null`: no argument condition or
+//! parameter constraint matches them, so constrained `Allow` rules fail closed.
+//!
+//! Conditions read arguments through [`ArgView`], a key lookup that yields typed
+//! [`ArgValue`] leaves. `serde_json::Value` and flat string maps implement it;
+//! integrations carrying protobuf, CBOR or other payloads implement it over their
+//! own types, and only the looked-up leaves are ever converted.
+//!
+//! `serde_json` is still a required dependency: conditions, policies and
+//! decision records carry JSON values, so a build without it needs its own
+//! condition value model first. That is not done yet.
+
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgValue<'a> {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(Cow<'a, str>),
+    List(Vec<ArgValue<'a>>),
+    Json(&'a Value),
+}
+
+impl ArgValue<'_> {
+    /// The leaf as a JSON value, borrowed when it already is one.
+    pub fn to_value(&self) -> Cow<'_, Value> {
+        match self {
+            ArgValue::Json(v) => Cow::Borrowed(*v),
+            ArgValue::Null => Cow::Owned(Value::Null),
+            ArgValue::Bool(b) => Cow::Owned(Value::Bool(*b)),
+            ArgValue::Int(i) => Cow::Owned(Value::from(*i)),
+            ArgValue::Float(f) => Cow::Owned(Value::from(*f)),
+            ArgValue::Str(s) => Cow::Owned(Value::String(s.to_string())),
+            ArgValue::List(items) => Cow::Owned(Value::Array(
+                items.iter().map(|i| i.to_value().into_owned()).collect(),
+            )),
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ArgValue::Str(s) => Some(s),
+            ArgValue::Json(Value::String(s)) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+pub trait ArgView {
+    /// Looks up a dotted key path; a leading `args.` is already stripped.
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>>;
+
+    /// Looks up a `session.` condition key, prefix stripped, in the session
+    /// state the gate attached; see [`crate::session`]. Argument payloads never
+    /// answer these, so they cannot forge session history.
+    fn session_lookup(&self, _key: &str) -> Option<ArgValue<'_>> {
+        None
+    }
+
+    /// Stable textual identity of the whole payload, when one exists. Used to key
+    /// caches; views returning `None` are never served cached decisions.
+    fn cache_key(&self) -> Option<String> {
+        None
+    }
+}
+
+impl ArgView for Value {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        crate::condition::lookup(self, path).map(ArgValue::Json)
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+impl ArgView for BTreeMap<String, String> {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        self.get(path).map(|v| ArgValue::Str(Cow::Borrowed(v)))
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+impl ArgView for HashMap<String, String> {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        self.get(path).map(|v| ArgValue::Str(Cow::Borrowed(v)))
+    }
+}
+
+pub static NO_ARGS: Value = Value::Null;
+
+pub trait Args {
+    fn as_json(&self) -> Option<&Value>;
+
+    fn view(&self) -> Option<&dyn ArgView> {
+        self.as_json().map(|v| v as &dyn ArgView)
+    }
+}
+
+impl Args for Value {
+    fn as_json(&self) -> Option<&Value> {
+        Some(self)
+    }
+}
+
+impl Args for () {
+    fn as_json(&self) -> Option<&Value> {
+        None
+    }
+}
+
+impl Args for BTreeMap<String, String> {
+    fn as_json(&self) -> Option<&Value> {
+        None
+    }
+
+    fn view(&self) -> Option<&dyn ArgView> {
+        Some(self)
+    }
+}
+
+impl Args for HashMap<String, String> {
+    fn as_json(&self) -> Option<&Value> {
+        None
+    }
+
+    fn view(&self) -> Option<&dyn ArgView> {
+        Some(self)
+    }
+}
+
+impl<T: Args> Args for Option<T> {
+    fn as_json(&self) -> Option<&Value> {
+        self.as_ref().and_then(Args::as_json)
+    }
+
+    fn view(&self) -> Option<&dyn ArgView> {
+        self.as_ref().and_then(Args::view)
+    }
+}
+
+impl<T: Args + ?Sized> Args for &T {
+    fn as_json(&self) -> Option<&Value> {
+        (**self).as_json()
+    }
+
+    fn view(&self) -> Option<&dyn ArgView> {
+        (**self).view()
+    }
+}
+
+pub(crate) fn view_of<A: Args + ?Sized>(args: &A) -> &dyn ArgView {
+    args.view().unwrap_or(&NO_ARGS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_args_views() {
+        let value = serde_json::json!({ "path": "/tmp" });
+        assert_eq!(view_of(&value).cache_key(), Some(value.to_string()));
+        assert!(view_of(&()).lookup("path").is_none());
+        assert!(view_of(&None::<Value>).lookup("path").is_none());
+        assert_eq!(
+            view_of(&Some(value.clone())).lookup("path"),
+            Some(ArgValue::Json(&serde_json::json!("/tmp")))
+        );
+    }
+
+    #[test]
+    fn test_non_json_view() {
+        struct Proto {
+            command: &'static str,
+            retries: i64,
+        }
+        impl ArgView for Proto {
+            fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+                match path {
+                    "command" => Some(ArgValue::Str(Cow::Borrowed(self.command))),
+                    "retries" => Some(ArgValue::Int(self.retries)),
+                    _ => None,
+                }
+            }
+        }
+
+        let proto = Proto {
+            command: "status",
+            retries: 3,
+        };
+        assert_eq!(
+            proto.lookup("retries").unwrap().to_value().as_ref(),
+            &serde_json::json!(3)
+        );
+        let mut map = BTreeMap::new();
+        map.insert("command".to_string(), "status".to_string());
+        assert_eq!(
+            view_of(&map).lookup("command"),
+            Some(ArgValue::Str(Cow::Borrowed("status")))
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Authorization Audit Trail.
+//!
+//! Every authorization decision produced by the gate is emitted as an
+//! [`AuditEvent`] to the configured [`AuditSink`]. [`AuditLog`] is an append-only,
+//! hash-chained sink: each record commits to the previous record's hash, and a
+//! signature is attached every `sign_every` records, so any edit, removal or
+//! reordering of history is detected by [`AuditLog::verify_chain`].
+//!
+//! Rules can lower the audit volume of their `Allow` outcomes with an
+//! [`AuditMode`]; denials are always recorded.
+
+use crate::digest::{ct_eq, hmac_sha256, sha256_hex, to_hex};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub timestamp_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    pub tool: String,
+    pub decision: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// Set when the event was kept by sampling, so counts can be re-weighted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f64>,
+    /// Set when the decision was made in degraded mode.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// Set when the event stands for this many identical coalesced events; see
+    /// [`crate::debounce`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+impl AuditEvent {
+    pub fn new(tool: impl Into<String>, decision: impl Into<String>) -> Self {
+        Self {
+            timestamp_ms: now_ms(),
+            principal: None,
+            tool: tool.into(),
+            decision: decision.into(),
+            rule: None,
+            sample_rate: None,
+            degraded: false,
+            count: None,
+            details: BTreeMap::new(),
+        }
+    }
+}
+
+/// How a rule's `Allow` outcomes are audited. Denials are always audited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditMode {
+    #[default]
+    Always,
+    Sampled(f64),
+    Never,
+}
+
+impl AuditMode {
+    pub fn is_always(&self) -> bool {
+        *self == AuditMode::Always
+    }
+}
+
+/// Deterministic per-key sampler: with rate `r`, exactly one in every `1/r`
+/// events for a key is kept, evenly spaced.
+#[derive(Default)]
+pub struct Sampler {
+    counters: Mutex<HashMap<String, u64>>,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn keep(&self, key: &str, rate: f64) -> bool {
+        let rate = rate.clamp(0.0, 1.0);
+        let mut counters = self.counters.lock().unwrap();
+        let n = counters.entry(key.to_string()).or_insert(0);
+        let before = (*n as f64 * rate).floor();
+        *n += 1;
+        (*n as f64 * rate).floor() > before
+    }
+}
+
+pub(crate) fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub trait AuditSink: Send + Sync {
+    fn record(&self, event: &AuditEvent);
+}
+
+pub trait AuditSigner: Send + Sync {
+    fn sign(&self, message: &[u8]) -> String;
+    fn verify(&self, message: &[u8], signature: &str) -> bool;
+}
+
+/// Symmetric HMAC-SHA-256 signer.
+pub struct HmacSigner {
+    key: Vec<u8>,
+}
+
+impl HmacSigner {
+    pub fn new(key: impl Into<Vec<u8>>) -> Self {
+        Self { key: key.into() }
+    }
+}
+
+impl AuditSigner for HmacSigner {
+    fn sign(&self, message: &[u8]) -> String {
+        to_hex(&hmac_sha256(&self.key, message))
+    }
+
+    fn verify(&self, message: &[u8], signature: &str) -> bool {
+        ct_eq(self.sign(message).as_bytes(), signature.as_bytes())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub seq: u64,
+    pub event: AuditEvent,
+    pub prev_hash: String,
+    pub hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl AuditRecord {
+    fn compute_hash(seq: u64, event: &AuditEvent, prev_hash: &str) -> String {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            seq: u64,
+            event: &'a AuditEvent,
+            prev_hash: &'a str,
+        }
+        let body = serde_json::to_vec(&Body {
+            seq,
+            event,
+            prev_hash,
+        })
+        .expect("audit event serializes");
+        sha256_hex(&body)
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AuditChainError {
+    #[error("record {seq}: sequence gap (expected {expected})")]
+    SequenceGap { seq: u64, expected: u64 },
+    #[error("record {seq}: previous-hash link broken")]
+    BrokenLink { seq: u64 },
+    #[error("record {seq}: content hash mismatch")]
+    HashMismatch { seq: u64 },
+    #[error("record {seq}: signature missing")]
+    MissingSignature { seq: u64 },
+    #[error("record {seq}: signature invalid")]
+    BadSignature { seq: u64 },
+    #[error("invalid audit record: {0}")]
+    Parse(String),
+}
+
+pub struct AuditLog {
+    records: Mutex<Vec<AuditRecord>>,
+    signer: Option<Box<dyn AuditSigner>>,
+    sign_every: u64,
+}
+
+impl AuditLog {
+    pub fn new() -> Self {
+        Self {
+            records: Mutex::new(Vec::new()),
+            signer: None,
+            sign_every: 0,
+        }
+    }
+
+    pub fn with_signer(mut self, signer: impl AuditSigner + 'static, sign_every: u64) -> Self {
+        self.signer = Some(Box::new(signer));
+        self.sign_every = sign_every.max(1);
+        self
+    }
+
+    pub fn append(&self, event: AuditEvent) -> AuditRecord {
+        let mut records = self.records.lock().unwrap();
+        let seq = records.len() as u64;
+        let prev_hash = records
+            .last()
+            .map(|r| r.hash.clone())
+            .unwrap_or_else(|| GENESIS_HASH.to_string());
+        let hash = AuditRecord::compute_hash(seq, &event, &prev_hash);
+        let signature = match &self.signer {
+            Some(signer) if self.must_sign(seq) => Some(signer.sign(hash.as_bytes())),
+            _ => None,
+        };
+
+        let record = AuditRecord {
+            seq,
+            event,
+            prev_hash,
+            hash,
+            signature,
+        };
+        records.push(record.clone());
+        record
+    }
+
+    pub fn records(&self) -> Vec<AuditRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn to_jsonl(&self) -> String {
+        self.records
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|r| serde_json::to_string(r).expect("audit record serializes") + "\n")
+            .collect()
+    }
+
+    pub fn parse_jsonl(jsonl: &str) -> Result<Vec<AuditRecord>, AuditChainError> {
+        jsonl
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| AuditChainError::Parse(e.to_string()))
+            })
+            .collect()
+    }
+
+    pub fn verify_chain(&self) -> Result<(), AuditChainError> {
+        self.verify_records(&self.records.lock().unwrap())
+    }
+
+    pub fn verify_records(&self, records: &[AuditRecord]) -> Result<(), AuditChainError> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for (expected, record) in records.iter().enumerate() {
+            let seq = record.seq;
+            if seq != expected as u64 {
+                return Err(AuditChainError::SequenceGap {
+                    seq,
+                    expected: expected as u64,
+                });
+            }
+            if record.prev_hash != prev_hash {
+                return Err(AuditChainError::BrokenLink { seq });
+            }
+            if AuditRecord::compute_hash(seq, &record.event, &record.prev_hash) != record.hash {
+                return Err(AuditChainError::HashMismatch { seq });
+            }
+            if let Some(signer) = &self.signer {
+                match &record.signature {
+                    Some(sig) if !signer.verify(record.hash.as_bytes(), sig) => {
+                        return Err(AuditChainError::BadSignature { seq });
+                    }
+                    None if self.must_sign(seq) => {
+                        return Err(AuditChainError::MissingSignature { seq });
+                    }
+                    _ => {}
+                }
+            }
+            prev_hash = record.hash.clone();
+        }
+        Ok(())
+    }
+
+    fn must_sign(&self, seq: u64) -> bool {
+        self.sign_every > 0 && (seq + 1) % self.sign_every == 0
+    }
+}
+
+impl Default for AuditLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AuditSink for AuditLog {
+    fn record(&self, event: &AuditEvent) {
+        self.append(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn signed_log() -> AuditLog {
+        let log = AuditLog::new().with_signer(HmacSigner::new(b"secret".to_vec()), 2);
+        log.append(AuditEvent::new("fs.read", "AUTHORIZED"));
+        log.append(AuditEvent::new("shell", "DENIED_POLICY_VIOLATION"));
+        log.append(AuditEvent::new("fs.read", "AUTHORIZED"));
+        log
+    }
+
+    #[test]
+    fn test_chain_verifies() {
+        let log = signed_log();
+        assert!(log.verify_chain().is_ok());
+
+        let records = log.records();
+        assert_eq!(records[0].prev_hash, GENESIS_HASH);
+        assert_eq!(records[1].prev_hash, records[0].hash);
+        assert!(records[1].signature.is_some());
+        assert!(records[0].signature.is_none());
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let log = signed_log();
+        let mut records = AuditLog::parse_jsonl(&log.to_jsonl()).unwrap();
+        records[1].event.decision = "AUTHORIZED".to_string();
+        assert_eq!(
+            log.verify_records(&records),
+            Err(AuditChainError::HashMismatch { seq: 1 })
+        );
+
+        let mut records = log.records();
+        records.remove(1);
+        assert!(matches!(
+            log.verify_records(&records),
+            Err(AuditChainError::SequenceGap { seq: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_sampler_rate() {
+        let sampler = Sampler::new();
+        let kept = (0..1000).filter(|_| sampler.keep("rule", 0.01)).count();
+        assert_eq!(kept, 10);
+        assert!(!(0..100).any(|_| sampler.keep("never", 0.0)));
+    }
+
+    #[test]
+    fn test_forged_signature_detected() {
+        let log = signed_log();
+        let forger = AuditLog::new().with_signer(HmacSigner::new(b"other".to_vec()), 2);
+        assert_eq!(
+            forger.verify_records(&log.records()),
+            Err(AuditChainError::BadSignature { seq: 1 })
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! The Authorizer Trait.
+//!
+//! Executor frameworks depend on [`Authorizer`] rather than on
+//! `CapabilityGate` directly, so tests can inject fakes and deployments can
+//! stack several authorizers. `CapabilityGate` implements it, as do closures,
+//! [`FixedAuthorizer`] for tests, and the [`AllOf`] and [`AnyOf`] composites.
+
+use crate::args::Args;
+use crate::context::RequestContext;
+use crate::decision::{Decision, DecisionRecord};
+use crate::gate::CapabilityGate;
+use std::sync::Arc;
+
+pub struct AuthorizationRequest<'a> {
+    pub tool: &'a str,
+    pub args: &'a dyn Args,
+    pub ctx: &'a RequestContext,
+}
+
+impl<'a> AuthorizationRequest<'a> {
+    pub fn new(tool: &'a str, args: &'a dyn Args, ctx: &'a RequestContext) -> Self {
+        Self { tool, args, ctx }
+    }
+}
+
+pub trait Authorizer: Send + Sync {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord;
+}
+
+impl Authorizer for CapabilityGate {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        self.authorize_record(request.tool, request.args, request.ctx)
+    }
+}
+
+impl<F> Authorizer for F
+where
+    F: Fn(&AuthorizationRequest<'_>) -> DecisionRecord + Send + Sync,
+{
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        self(request)
+    }
+}
+
+impl<A: Authorizer + ?Sized> Authorizer for Arc<A> {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        (**self).authorize(request)
+    }
+}
+
+/// Returns the same decision for every request.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedAuthorizer(pub Decision);
+
+impl Authorizer for FixedAuthorizer {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        let mut record = DecisionRecord::new(self.0, request.tool);
+        record.principal = request.ctx.principal.clone();
+        record
+    }
+}
+
+/// Allows only if every authorizer allows; the first denial is returned as is.
+/// An empty `AllOf` denies.
+pub struct AllOf(pub Vec<Arc<dyn Authorizer>>);
+
+impl Authorizer for AllOf {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        let mut allowed = None;
+        for authorizer in &self.0 {
+            let record = authorizer.authorize(request);
+            if !record.is_allowed() {
+                return record;
+            }
+            allowed.get_or_insert(record);
+        }
+        allowed
+            .unwrap_or_else(|| DecisionRecord::new(Decision::DeniedPolicyViolation, request.tool))
+    }
+}
+
+/// Allows if any authorizer allows, consulting them in order; otherwise returns
+/// the first denial. An empty `AnyOf` denies.
+pub struct AnyOf(pub Vec<Arc<dyn Authorizer>>);
+
+impl Authorizer for AnyOf {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        let mut denied = None;
+        for authorizer in &self.0 {
+            let record = authorizer.authorize(request);
+            if record.is_allowed() {
+                return record;
+            }
+            denied.get_or_insert(record);
+        }
+        denied.unwrap_or_else(|| DecisionRecord::new(Decision::DeniedPolicyViolation, request.tool))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Policy, Rule};
+
+    /// Stands in for an executor framework that only knows the trait.
+    fn run(authorizer: &dyn Authorizer, tool: &str) -> Decision {
+        let ctx = RequestContext::default();
+        let args = serde_json::json!({ "path": "/tmp/x" });
+        authorizer
+            .authorize(&AuthorizationRequest::new(tool, &args, &ctx))
+            .decision
+    }
+
+    #[test]
+    fn test_gate_and_fakes_are_interchangeable() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("fs.read")));
+
+        assert_eq!(run(&gate, "fs.read"), Decision::Authorized);
+        assert_eq!(run(&gate, "shell"), Decision::DeniedCapabilityNotFound);
+        assert_eq!(
+            run(
+                &FixedAuthorizer(Decision::DeniedCapabilityDisabled),
+                "fs.read"
+            ),
+            Decision::DeniedCapabilityDisabled
+        );
+        let only_reads = |request: &AuthorizationRequest<'_>| {
+            let decision = match request.tool.starts_with("fs.") {
+                true => Decision::Authorized,
+                false => Decision::DeniedPolicyViolation,
+            };
+            DecisionRecord::new(decision, request.tool)
+        };
+        assert_eq!(run(&only_reads, "shell"), Decision::DeniedPolicyViolation);
+    }
+
+    #[test]
+    fn test_composites() {
+        let allow: Arc<dyn Authorizer> = Arc::new(FixedAuthorizer(Decision::Authorized));
+        let deny: Arc<dyn Authorizer> = Arc::new(FixedAuthorizer(Decision::DeniedLeaseExpired));
+
+        let all = AllOf(vec![allow.clone(), deny.clone()]);
+        assert_eq!(run(&all, "fs.read"), Decision::DeniedLeaseExpired);
+        let any = AnyOf(vec![deny.clone(), allow.clone()]);
+        assert_eq!(run(&any, "fs.read"), Decision::Authorized);
+        assert_eq!(
+            run(&AnyOf(vec![deny]), "fs.read"),
+            Decision::DeniedLeaseExpired
+        );
+        assert_eq!(
+            run(&AllOf(Vec::new()), "fs.read"),
+            Decision::DeniedPolicyViolation
+        );
+    }
+}
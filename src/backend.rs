@@ -0,0 +1,122 @@
+//! External Decision Backends.
+//!
+//! A [`DecisionBackend`] lets the gate consult an external policy decision point
+//! (OPA, a Cedar service, corporate IAM) instead of, or together with, the local
+//! engine. How the two answers combine is chosen with [`Combination`]. A backend
+//! that cannot answer is treated like an unavailable resolver, so the
+//! capability's degradation mode applies.
+
+use crate::args::ArgView;
+use crate::context::RequestContext;
+use crate::policy::Effect;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BackendError {
+    #[error("decision backend unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// What the backend is asked about. `capability` is the canonical name.
+pub struct BackendRequest<'a> {
+    pub ctx: &'a RequestContext,
+    pub capability: &'a str,
+    pub action: &'a str,
+    pub args: &'a dyn ArgView,
+}
+
+pub trait DecisionBackend: Send + Sync {
+    fn decide(&self, request: &BackendRequest<'_>) -> Result<Effect, BackendError>;
+}
+
+impl<F> DecisionBackend for F
+where
+    F: Fn(&BackendRequest<'_>) -> Result<Effect, BackendError> + Send + Sync,
+{
+    fn decide(&self, request: &BackendRequest<'_>) -> Result<Effect, BackendError> {
+        self(request)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Combination {
+    /// The backend alone decides; local policies are not evaluated.
+    BackendOnly,
+    /// A matching local rule decides; the backend is consulted only when no
+    /// local rule matches.
+    #[default]
+    LocalFirst,
+    /// Both the local engine and the backend must allow.
+    BothMustAllow,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::degradation::DegradationMode;
+    use crate::gate::{CapabilityGate, Decision};
+    use crate::policy::{Policy, Rule};
+    use std::sync::Arc;
+
+    fn gate(combination: Combination) -> CapabilityGate {
+        let backend = |req: &BackendRequest<'_>| match req.capability {
+            "http.get" => Ok(Effect::Allow),
+            "shell" => Err(BackendError::Unavailable("timeout".into())),
+            _ => Ok(Effect::Deny),
+        };
+        let mut gate = CapabilityGate::new().with_backend(Arc::new(backend), combination);
+        for name in ["http.get", "fs.read", "shell"] {
+            gate.register_capability(Capability::new(name, name));
+        }
+        gate.add_policy(
+            Policy::new("local", "1.0")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("shell")),
+        );
+        gate
+    }
+
+    #[test]
+    fn test_combination_strategies() {
+        let local_first = gate(Combination::LocalFirst);
+        assert!(local_first.check("fs.read"));
+        assert!(local_first.check("http.get"));
+        assert!(local_first.check("shell"));
+
+        let both = gate(Combination::BothMustAllow);
+        assert!(!both.check("fs.read"));
+        assert!(!both.check("http.get"));
+        assert_eq!(
+            both.authorize("shell", &()),
+            Decision::DeniedResolverUnavailable
+        );
+
+        let backend_only = gate(Combination::BackendOnly);
+        assert!(backend_only.check("http.get"));
+        assert!(!backend_only.check("fs.read"));
+    }
+
+    #[test]
+    fn test_unavailable_backend_never_lifts_a_local_deny() {
+        let asked = Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let seen = asked.clone();
+        let backend = move |_: &BackendRequest<'_>| {
+            seen.store(true, std::sync::atomic::Ordering::SeqCst);
+            Err(BackendError::Unavailable("timeout".into()))
+        };
+        let mut gate = CapabilityGate::new()
+            .with_backend(Arc::new(backend), Combination::BothMustAllow)
+            .with_degradation("shell", DegradationMode::FailOpen);
+        gate.register_capability(Capability::new("shell", "shell"));
+        gate.add_policy(Policy::new("local", "1.0").with_rule(Rule::deny("shell")));
+
+        assert_eq!(
+            gate.authorize("shell", &()),
+            Decision::DeniedPolicyViolation
+        );
+        assert!(!asked.load(std::sync::atomic::Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,125 @@
+//! Denial Backoff.
+//!
+//! An agent loop that keeps retrying a denied call only burns evaluations.
+//! With [`crate::CapabilityGate::with_denial_backoff`], the gate counts
+//! consecutive denials of the same capability for the same principal; once a
+//! call is denied again within the window of the previous denial, the record
+//! carries a [`DecisionRecord::retry_after_ms`] hint that starts at the base
+//! delay and doubles with every further denial, up to the maximum. An allowed
+//! call, or a quiet window, resets the streak.
+//!
+//! The hint is advisory: the gate still evaluates every call.
+
+use crate::decision::DecisionRecord;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Key = (Option<String>, String);
+
+struct Streak {
+    last: Instant,
+    denials: u32,
+}
+
+pub struct DenialBackoff {
+    window: Duration,
+    base_ms: u64,
+    max_ms: u64,
+    streaks: Mutex<HashMap<Key, Streak>>,
+}
+
+impl DenialBackoff {
+    /// Backoff for denials less than `window` apart, starting at one second
+    /// and capped at five minutes.
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            base_ms: 1_000,
+            max_ms: 300_000,
+            streaks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_base(mut self, base: Duration) -> Self {
+        self.base_ms = base.as_millis() as u64;
+        self
+    }
+
+    pub fn with_max(mut self, max: Duration) -> Self {
+        self.max_ms = max.as_millis() as u64;
+        self
+    }
+
+    fn delay_ms(&self, denials: u32) -> Option<u64> {
+        let doublings = denials.checked_sub(2)?.min(63);
+        let delay = self.base_ms.saturating_mul(1u64 << doublings);
+        Some(delay.min(self.max_ms))
+    }
+
+    /// Updates the streak `record` belongs to and returns the hint for it.
+    pub fn observe(&self, record: &DecisionRecord) -> Option<u64> {
+        let key = (record.principal.clone(), record.capability.clone());
+        let mut streaks = self.streaks.lock().unwrap();
+        if record.is_allowed() {
+            streaks.remove(&key);
+            return None;
+        }
+        streaks.retain(|_, streak| streak.last.elapsed() <= self.window);
+        let streak = streaks.entry(key).or_insert(Streak {
+            last: Instant::now(),
+            denials: 0,
+        });
+        streak.last = Instant::now();
+        streak.denials = streak.denials.saturating_add(1);
+        self.delay_ms(streak.denials)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_repeated_denials_back_off_exponentially() {
+        let backoff = DenialBackoff::new(Duration::from_secs(60))
+            .with_base(Duration::from_millis(100))
+            .with_max(Duration::from_millis(300));
+        let mut gate = CapabilityGate::new().with_denial_backoff(backoff);
+        gate.register_capability(Capability::new("shell", "Run commands"));
+        gate.add_policy(Policy::new("p", "1").with_rule(
+            Rule::allow("shell").with_conditions(vec![Condition::new("cmd", "eq", json!("ls"))]),
+        ));
+
+        let agent = RequestContext::new().with_principal("agent");
+        let denied = json!({"cmd": "rm"});
+        let hints: Vec<Option<u64>> = (0..4)
+            .map(|_| {
+                gate.authorize_record("shell", &denied, &agent)
+                    .retry_after_ms
+            })
+            .collect();
+        assert_eq!(hints, vec![None, Some(100), Some(200), Some(300)]);
+
+        let other = RequestContext::new().with_principal("other");
+        assert_eq!(
+            gate.authorize_record("shell", &denied, &other)
+                .retry_after_ms,
+            None
+        );
+
+        assert!(gate
+            .authorize_record("shell", &json!({"cmd": "ls"}), &agent)
+            .is_allowed());
+        assert_eq!(
+            gate.authorize_record("shell", &denied, &agent)
+                .retry_after_ms,
+            None
+        );
+    }
+}
@@ -0,0 +1,98 @@
+//! Command-line tools for policy files.
+//!
+//! ```text
+//! femtoclaw-policy fmt [--check] <file>...
+//! femtoclaw-policy repl [<file>...]
+//! femtoclaw-policy codegen <capabilities.json>
+//! ```
+//!
+//! `fmt` rewrites each JSON policy file (an array of policies) in canonical
+//! form; with `--check` it only reports files that are not formatted.
+//!
+//! `repl` loads the given policy files and reads commands from stdin; see
+//! [`femtoclaw_policy::repl`] or type `help`.
+//!
+//! `codegen` reads a JSON array of capabilities and prints typed argument
+//! builders for them; see [`femtoclaw_policy::codegen`].
+
+use anyhow::{bail, Context, Result};
+use femtoclaw_policy::capability::Capability;
+use femtoclaw_policy::codegen;
+use femtoclaw_policy::format::format_policies;
+use femtoclaw_policy::repl::Repl;
+use femtoclaw_policy::Policy;
+use std::io::{BufRead, Write};
+use std::process::ExitCode;
+
+fn fmt(args: &[String]) -> Result<ExitCode> {
+    let check = args.iter().any(|a| a == "--check");
+    let files: Vec<&String> = args.iter().filter(|a| !a.starts_with("--")).collect();
+    if files.is_empty() {
+        bail!("usage: femtoclaw-policy fmt [--check] <file>...");
+    }
+
+    let mut unformatted = 0;
+    for file in files {
+        let source = std::fs::read_to_string(file).with_context(|| format!("reading {}", file))?;
+        let policies: Vec<Policy> =
+            serde_json::from_str(&source).with_context(|| format!("parsing {}", file))?;
+        let formatted = format_policies(&policies);
+        if formatted == source {
+            continue;
+        }
+        if check {
+            println!("{}", file);
+            unformatted += 1;
+        } else {
+            std::fs::write(file, formatted).with_context(|| format!("writing {}", file))?;
+        }
+    }
+    Ok(if unformatted > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn repl(files: &[String]) -> Result<ExitCode> {
+    let mut repl = Repl::new();
+    for file in files {
+        println!("{}", repl.execute(&format!("load {}", file))?);
+    }
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    loop {
+        write!(stdout, "> ")?;
+        stdout.flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(ExitCode::SUCCESS);
+        }
+        match repl.execute(&line) {
+            Ok(out) if out.is_empty() => {}
+            Ok(out) => println!("{}", out),
+            Err(e) => println!("error: {}", e),
+        }
+    }
+}
+
+fn generate(args: &[String]) -> Result<ExitCode> {
+    let [file] = args else {
+        bail!("usage: femtoclaw-policy codegen <capabilities.json>");
+    };
+    let source = std::fs::read_to_string(file).with_context(|| format!("reading {}", file))?;
+    let capabilities: Vec<Capability> =
+        serde_json::from_str(&source).with_context(|| format!("parsing {}", file))?;
+    print!("{}", codegen::generate(&capabilities));
+    Ok(ExitCode::SUCCESS)
+}
+
+fn main() -> Result<ExitCode> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("fmt") => fmt(&args[1..]),
+        Some("repl") => repl(&args[1..]),
+        Some("codegen") => generate(&args[1..]),
+        _ => bail!("usage: femtoclaw-policy <fmt|repl|codegen> ..."),
+    }
+}
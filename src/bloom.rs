@@ -0,0 +1,114 @@
+//! Bloom Filter.
+//!
+//! A small Bloom filter the [`crate::capability::CapabilityRegistry`] consults
+//! before its map, so lookups for unregistered capabilities — the common case
+//! for denied requests — are usually answered by a few bit tests on an FNV-1a
+//! hash. A filter never reports a present name as absent; false positives just
+//! fall through to the map.
+
+const BITS_PER_ITEM: usize = 10;
+const HASHES: u64 = 7;
+const MIN_CAPACITY: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    capacity: usize,
+    len: usize,
+}
+
+impl Default for BloomFilter {
+    fn default() -> Self {
+        Self::with_capacity(MIN_CAPACITY)
+    }
+}
+
+fn fnv1a(bytes: &[u8], basis: u64) -> u64 {
+    bytes.iter().fold(basis, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl BloomFilter {
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        let words = (capacity * BITS_PER_ITEM).div_ceil(64);
+        Self {
+            bits: vec![0; words],
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Builds a filter sized for `items`.
+    pub fn from_items<'a>(items: impl IntoIterator<Item = &'a str>) -> Self {
+        let items: Vec<&str> = items.into_iter().collect();
+        let mut filter = Self::with_capacity(items.len() * 2);
+        for item in items {
+            filter.insert(item);
+        }
+        filter
+    }
+
+    /// Bit positions for `item`, by double hashing two FNV-1a variants.
+    fn positions(&self, item: &str) -> impl Iterator<Item = usize> {
+        let h1 = fnv1a(item.as_bytes(), 0xcbf2_9ce4_8422_2325);
+        let h2 = fnv1a(item.as_bytes(), 0x8422_2325_cbf2_9ce4) | 1;
+        let m = (self.bits.len() * 64) as u64;
+        (0..HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    pub fn insert(&mut self, item: &str) {
+        let positions: Vec<usize> = self.positions(item).collect();
+        for p in positions {
+            self.bits[p / 64] |= 1 << (p % 64);
+        }
+        self.len += 1;
+    }
+
+    /// `false` means `item` was definitely never inserted.
+    pub fn may_contain(&self, item: &str) -> bool {
+        self.positions(item)
+            .all(|p| self.bits[p / 64] & (1 << (p % 64)) != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whether more items were inserted than the filter was sized for, so its
+    /// false-positive rate has degraded and it should be rebuilt.
+    pub fn is_saturated(&self) -> bool {
+        self.len > self.capacity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_false_negatives_and_few_false_positives() {
+        let names: Vec<String> = (0..500).map(|i| format!("tool.{}", i)).collect();
+        let filter = BloomFilter::from_items(names.iter().map(String::as_str));
+        assert!(names.iter().all(|n| filter.may_contain(n)));
+
+        let false_positives = (0..10_000)
+            .filter(|i| filter.may_contain(&format!("missing.{}", i)))
+            .count();
+        assert!(false_positives < 100, "{}", false_positives);
+    }
+
+    #[test]
+    fn test_saturation() {
+        let mut filter = BloomFilter::with_capacity(0);
+        for i in 0..=MIN_CAPACITY {
+            filter.insert(&i.to_string());
+        }
+        assert!(filter.is_saturated());
+    }
+}
@@ -0,0 +1,180 @@
+//! Denial Circuit Breaker.
+//!
+//! A principal that keeps getting denied is usually a misbehaving or
+//! compromised agent. A [`CircuitBreaker`] on the gate counts each principal's
+//! denials; after `threshold` denials within `window` it trips, and every
+//! further request from that principal is denied with
+//! [`Decision::DeniedQuarantined`] without being evaluated. Capabilities named
+//! with [`CircuitBreaker::with_quarantine`] stay available to a tripped
+//! principal, so it can be confined to a low-risk subset instead.
+//!
+//! A tripped breaker stays open until [`CapabilityGate::reset_quarantine`], or
+//! until the cooldown passes if [`CircuitBreaker::with_cooldown`] set one.
+//! Requests without a principal are not tracked.
+
+use crate::decision::{Decision, DecisionRecord};
+use crate::gate::CapabilityGate;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+struct PrincipalState {
+    denials: VecDeque<Instant>,
+    tripped: Option<Instant>,
+}
+
+pub struct CircuitBreaker {
+    threshold: usize,
+    window: Duration,
+    cooldown: Option<Duration>,
+    quarantine: BTreeSet<String>,
+    principals: Mutex<HashMap<String, PrincipalState>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(threshold: usize, window: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            window,
+            cooldown: None,
+            quarantine: BTreeSet::new(),
+            principals: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Closes a tripped breaker again after `cooldown`.
+    pub fn with_cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = Some(cooldown);
+        self
+    }
+
+    /// Capabilities a tripped principal may still use, by canonical name.
+    pub fn with_quarantine<I, S>(mut self, capabilities: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.quarantine
+            .extend(capabilities.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether `principal` is tripped, closing the breaker if its cooldown
+    /// has passed.
+    pub fn is_tripped(&self, principal: &str) -> bool {
+        let mut principals = self.principals.lock().unwrap();
+        let Some(state) = principals.get_mut(principal) else {
+            return false;
+        };
+        match (state.tripped, self.cooldown) {
+            (Some(at), Some(cooldown)) if at.elapsed() >= cooldown => {
+                principals.remove(principal);
+                false
+            }
+            (tripped, _) => tripped.is_some(),
+        }
+    }
+
+    /// Whether a request from `principal` for `capability` must be denied.
+    pub fn blocks(&self, principal: Option<&str>, capability: &str) -> bool {
+        principal.is_some_and(|p| self.is_tripped(p)) && !self.quarantine.contains(capability)
+    }
+
+    /// Counts `record` if it is a denial, tripping the breaker at the threshold.
+    pub fn observe(&self, record: &DecisionRecord) {
+        let Some(principal) = &record.principal else {
+            return;
+        };
+        if record.is_allowed() || record.decision == Decision::DeniedQuarantined {
+            return;
+        }
+        let mut principals = self.principals.lock().unwrap();
+        let state = principals.entry(principal.clone()).or_default();
+        if state.tripped.is_some() {
+            return;
+        }
+        let now = Instant::now();
+        state
+            .denials
+            .retain(|at| now.duration_since(*at) <= self.window);
+        state.denials.push_back(now);
+        if state.denials.len() >= self.threshold {
+            state.denials.clear();
+            state.tripped = Some(now);
+        }
+    }
+
+    /// Closes the breaker for `principal`; returns whether it was tripped.
+    pub fn reset(&self, principal: &str) -> bool {
+        let removed = self.principals.lock().unwrap().remove(principal);
+        removed.is_some_and(|state| state.tripped.is_some())
+    }
+}
+
+impl CapabilityGate {
+    /// Lifts the quarantine on `principal`; returns whether it was quarantined.
+    pub fn reset_quarantine(&self, principal: &str) -> bool {
+        self.breaker()
+            .is_some_and(|breaker| breaker.reset(principal))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::policy::{Policy, Rule};
+
+    fn gate(breaker: CircuitBreaker) -> CapabilityGate {
+        let mut gate = CapabilityGate::new().with_circuit_breaker(breaker);
+        for name in ["fs.read", "fs.write", "shell"] {
+            gate.register_capability(Capability::new(name, name));
+        }
+        gate.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("fs.write")),
+        );
+        gate
+    }
+
+    #[test]
+    fn test_repeated_denials_quarantine_the_principal() {
+        let gate = gate(CircuitBreaker::new(3, Duration::from_secs(60)));
+        let agent = RequestContext::new().with_principal("agent");
+        for _ in 0..3 {
+            assert_eq!(
+                gate.authorize_with("shell", &(), &agent),
+                Decision::DeniedPolicyViolation
+            );
+        }
+        assert_eq!(
+            gate.authorize_with("fs.read", &(), &agent),
+            Decision::DeniedQuarantined
+        );
+        let other = RequestContext::new().with_principal("other");
+        assert!(gate.authorize_with("fs.read", &(), &other).is_allowed());
+
+        assert!(gate.reset_quarantine("agent"));
+        assert!(!gate.reset_quarantine("agent"));
+        assert!(gate.authorize_with("fs.read", &(), &agent).is_allowed());
+    }
+
+    #[test]
+    fn test_quarantine_subset_and_cooldown() {
+        let mut denial = DecisionRecord::new(Decision::DeniedPolicyViolation, "shell");
+        denial.principal = Some("agent".into());
+
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60)).with_quarantine(["fs.read"]);
+        breaker.observe(&denial);
+        assert!(!breaker.blocks(Some("agent"), "fs.read"));
+        assert!(breaker.blocks(Some("agent"), "fs.write"));
+        assert!(!breaker.blocks(None, "fs.write"));
+
+        let cooled = CircuitBreaker::new(1, Duration::from_secs(60)).with_cooldown(Duration::ZERO);
+        cooled.observe(&denial);
+        assert!(!cooled.is_tripped("agent"));
+    }
+}
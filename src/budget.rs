@@ -0,0 +1,99 @@
+//! Evaluation Budget.
+//!
+//! Bounds the work a single authorization may perform, in evaluation steps (rules
+//! considered, conditions and constraints checked, group lookups) and wall-clock
+//! time. The clock is checked between steps, so one slow resolver call is only
+//! detected once it returns. Exceeding the budget fails closed.
+
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvaluationBudget {
+    pub max_steps: Option<u64>,
+    pub max_duration: Option<Duration>,
+}
+
+impl EvaluationBudget {
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_steps(mut self, steps: u64) -> Self {
+        self.max_steps = Some(steps);
+        self
+    }
+
+    pub fn with_max_duration(mut self, duration: Duration) -> Self {
+        self.max_duration = Some(duration);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum BudgetExceeded {
+    #[error("evaluation exceeded {0} steps")]
+    Steps(u64),
+    #[error("evaluation exceeded {0:?}")]
+    Time(Duration),
+}
+
+#[derive(Debug)]
+pub struct Meter {
+    budget: EvaluationBudget,
+    started: Instant,
+    steps: u64,
+}
+
+impl Meter {
+    pub fn new(budget: EvaluationBudget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            steps: 0,
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(EvaluationBudget::unlimited())
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn step(&mut self, n: u64) -> Result<(), BudgetExceeded> {
+        self.steps = self.steps.saturating_add(n);
+        if let Some(max) = self.budget.max_steps {
+            if self.steps > max {
+                return Err(BudgetExceeded::Steps(max));
+            }
+        }
+        if let Some(max) = self.budget.max_duration {
+            if self.started.elapsed() > max {
+                return Err(BudgetExceeded::Time(max));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_budget() {
+        let mut meter = Meter::new(EvaluationBudget::unlimited().with_max_steps(3));
+        assert!(meter.step(2).is_ok());
+        assert!(meter.step(1).is_ok());
+        assert_eq!(meter.step(1), Err(BudgetExceeded::Steps(3)));
+    }
+
+    #[test]
+    fn test_time_budget() {
+        let mut meter = Meter::new(EvaluationBudget::unlimited().with_max_duration(Duration::ZERO));
+        std::thread::sleep(Duration::from_millis(1));
+        assert!(matches!(meter.step(1), Err(BudgetExceeded::Time(_))));
+    }
+}
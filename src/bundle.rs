@@ -0,0 +1,214 @@
+//! Self-verifying Policy Bundles.
+//!
+//! A bundle ships its policies together with test vectors describing the decisions
+//! those policies must produce. [`PolicyEngine::load_bundle`] evaluates every vector
+//! against a candidate engine and only activates the bundle if all of them pass.
+
+use crate::policy::{Effect, Policy, PolicyEngine};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyBundle {
+    pub name: String,
+    pub version: String,
+    pub policies: Vec<Policy>,
+    #[serde(default)]
+    pub tests: Vec<TestVector>,
+    /// Grace period for deny rules this bundle adds; see [`crate::grace`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace_period_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub resource: String,
+    #[serde(default = "default_action")]
+    pub action: String,
+    #[serde(default)]
+    pub args: serde_json::Value,
+    pub expect: Effect,
+}
+
+fn default_action() -> String {
+    "execute".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestFailure {
+    pub name: String,
+    pub expected: Effect,
+    pub actual: Effect,
+}
+
+#[derive(Debug, Error)]
+pub enum BundleError {
+    #[error("invalid bundle: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("bundle {bundle} rejected: {} of {total} test vectors failed", failures.len())]
+    TestsFailed {
+        bundle: String,
+        total: usize,
+        failures: Vec<TestFailure>,
+    },
+}
+
+impl PolicyBundle {
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            policies: Vec::new(),
+            tests: Vec::new(),
+            grace_period_secs: None,
+        }
+    }
+
+    pub fn with_grace_period(mut self, period: std::time::Duration) -> Self {
+        self.grace_period_secs = Some(period.as_secs());
+        self
+    }
+
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        self.policies.push(policy);
+        self
+    }
+
+    pub fn with_test(mut self, test: TestVector) -> Self {
+        self.tests.push(test);
+        self
+    }
+
+    pub fn run_tests(&self, engine: &PolicyEngine) -> Vec<TestFailure> {
+        self.tests
+            .iter()
+            .filter_map(|test| {
+                let actual = engine.evaluate(&test.resource, &test.action, &test.args);
+                (actual != test.expect).then(|| TestFailure {
+                    name: test.name.clone(),
+                    expected: test.expect,
+                    actual,
+                })
+            })
+            .collect()
+    }
+}
+
+impl TestVector {
+    pub fn expect(name: impl Into<String>, resource: impl Into<String>, effect: Effect) -> Self {
+        Self {
+            name: name.into(),
+            resource: resource.into(),
+            action: default_action(),
+            args: serde_json::json!({}),
+            expect: effect,
+        }
+    }
+
+    pub fn with_args(mut self, args: serde_json::Value) -> Self {
+        self.args = args;
+        self
+    }
+}
+
+impl PolicyEngine {
+    pub fn load_bundle(&mut self, json: &str) -> Result<(), BundleError> {
+        let bundle: PolicyBundle = serde_json::from_str(json)?;
+        self.activate_bundle(bundle)
+    }
+
+    pub fn activate_bundle(&mut self, bundle: PolicyBundle) -> Result<(), BundleError> {
+        let mut candidate = self.clone();
+        let grace_until = bundle
+            .grace_period_secs
+            .map(|secs| self.time_check().now_ms + secs.saturating_mul(1000));
+        for policy in &bundle.policies {
+            let mut policy = policy.clone();
+            crate::provenance::mark_bundle(&mut policy, &bundle.name);
+            if let Some(until) = grace_until {
+                crate::grace::apply_grace(self, &mut policy, until);
+            }
+            candidate.add_policy(policy);
+        }
+
+        let failures = bundle.run_tests(&candidate);
+        if !failures.is_empty() {
+            return Err(BundleError::TestsFailed {
+                bundle: bundle.name,
+                total: bundle.tests.len(),
+                failures,
+            });
+        }
+
+        let previous = self.snapshot();
+        *self = candidate;
+        self.record_generation(previous, &bundle.name);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+
+    fn bundle() -> PolicyBundle {
+        PolicyBundle::new("prod", "1.0")
+            .with_policy(Policy::new("default", "1.0").with_rule(Rule::allow("fs.read")))
+            .with_test(TestVector::expect("read allowed", "fs.read", Effect::Allow))
+    }
+
+    #[test]
+    fn test_bundle_activates_when_tests_pass() {
+        let mut engine = PolicyEngine::new();
+        engine.activate_bundle(bundle()).unwrap();
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &serde_json::json!({})),
+            Effect::Allow
+        );
+    }
+
+    #[test]
+    fn test_bundle_rejected_when_tests_fail() {
+        let mut engine = PolicyEngine::new();
+        let bundle =
+            bundle().with_test(TestVector::expect("shell allowed", "shell", Effect::Allow));
+
+        let err = engine.activate_bundle(bundle).unwrap_err();
+        match err {
+            BundleError::TestsFailed { failures, .. } => {
+                assert_eq!(failures.len(), 1);
+                assert_eq!(failures[0].name, "shell allowed");
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &serde_json::json!({})),
+            Effect::Deny
+        );
+    }
+
+    #[test]
+    fn test_load_bundle_from_json() {
+        let json = r#"{
+            "name": "prod",
+            "version": "1.0",
+            "policies": [{
+                "name": "default",
+                "version": "1.0",
+                "rules": [{
+                    "effect": "Deny",
+                    "principal": "*",
+                    "resource": "shell",
+                    "action": "execute",
+                    "conditions": []
+                }]
+            }],
+            "tests": [{ "name": "shell denied", "resource": "shell", "expect": "Deny" }]
+        }"#;
+
+        let mut engine = PolicyEngine::new();
+        assert!(engine.load_bundle(json).is_ok());
+    }
+}
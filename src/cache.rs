@@ -0,0 +1,528 @@
+//! Partitioned Decision Cache.
+//!
+//! Caches engine decisions per request so hot paths skip rule evaluation. The
+//! cache is split into partitions by the context [`Dimension`]s chosen at
+//! construction (principal, tenant, environment, ...): a lookup only ever sees
+//! entries from its own partition, so a cached allow for one tenant can never
+//! be served to another. The principal is always part of the entry key.
+//!
+//! Entries expire after the cache TTL and the whole cache is cleared whenever
+//! the gate's policies or capabilities change. Capabilities with a rule that tests session
+//! state, a `time` condition or a grace period are never cached, as their
+//! decisions change without the policies changing. Partitions are created by the first insert into
+//! them, never by lookups, and at most `max_partitions` are kept: creating one
+//! beyond that drops the oldest, with its statistics.
+//!
+//! Entries are normally keyed by the whole argument payload. A rule can declare
+//! the fields its decision depends on with `cache_key`, e.g. `["args.path",
+//! "principal"]`; when every rule that may apply to a capability declares one
+//! that covers all its conditions and constraints, entries for the capability
+//! are keyed by those argument fields only, so calls differing in volatile
+//! fields share an entry. [`cache_key_lints`] reports declarations that miss a
+//! referenced field; such rules, and gates with a decision backend, fall back
+//! to the full payload.
+
+use crate::args::ArgView;
+use crate::clock::is_time_condition;
+use crate::condition::arg_key;
+use crate::context::RequestContext;
+use crate::gate::Decision;
+use crate::lint::Lint;
+use crate::policy::{Policy, PolicyEngine, Rule};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dimension {
+    Principal,
+    /// A [`RequestContext::attributes`] entry, e.g. `tenant` or `environment`.
+    Attribute(String),
+}
+
+impl Dimension {
+    pub fn attribute(name: impl Into<String>) -> Self {
+        Dimension::Attribute(name.into())
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            Dimension::Principal => "principal",
+            Dimension::Attribute(name) => name,
+        }
+    }
+
+    pub(crate) fn value<'a>(&self, ctx: &'a RequestContext) -> Option<&'a str> {
+        match self {
+            Dimension::Principal => ctx.principal.as_deref(),
+            Dimension::Attribute(name) => ctx.attributes.get(name).map(String::as_str),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedDecision {
+    pub decision: Decision,
+    pub rule: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PartitionStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+#[derive(Default)]
+struct Partition {
+    entries: HashMap<String, (Instant, CachedDecision)>,
+    order: VecDeque<String>,
+    stats: PartitionStats,
+}
+
+pub const DEFAULT_MAX_PARTITIONS: usize = 1024;
+
+#[derive(Default)]
+struct Partitions {
+    by_key: HashMap<Vec<Option<String>>, Partition>,
+    /// Partition keys in creation order.
+    order: VecDeque<Vec<Option<String>>>,
+}
+
+pub struct DecisionCache {
+    dimensions: Vec<Dimension>,
+    ttl: Duration,
+    max_entries: usize,
+    max_partitions: usize,
+    partitions: Mutex<Partitions>,
+}
+
+impl DecisionCache {
+    pub fn new(dimensions: Vec<Dimension>, ttl: Duration) -> Self {
+        Self {
+            dimensions,
+            ttl,
+            max_entries: usize::MAX,
+            max_partitions: DEFAULT_MAX_PARTITIONS,
+            partitions: Mutex::new(Partitions::default()),
+        }
+    }
+
+    /// Bounds the number of partitions; the oldest are dropped first.
+    pub fn with_max_partitions(mut self, max_partitions: usize) -> Self {
+        self.max_partitions = max_partitions.max(1);
+        self
+    }
+
+    /// Bounds each partition; the oldest entries are evicted first.
+    pub fn with_max_entries_per_partition(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn dimensions(&self) -> &[Dimension] {
+        &self.dimensions
+    }
+
+    fn partition_of(&self, ctx: &RequestContext) -> Vec<Option<String>> {
+        self.dimensions
+            .iter()
+            .map(|d| d.value(ctx).map(String::from))
+            .collect()
+    }
+
+    fn label(&self, partition: &[Option<String>]) -> String {
+        let parts: Vec<String> = self
+            .dimensions
+            .iter()
+            .zip(partition)
+            .map(|(d, v)| format!("{}={}", d.name(), v.as_deref().unwrap_or("-")))
+            .collect();
+        parts.join(",")
+    }
+
+    /// Entry key within a partition; `None` when the arguments cannot be cached
+    /// or the request is evaluated at an explicit time.
+    pub fn key(ctx: &RequestContext, capability: &str, args_key: Option<String>) -> Option<String> {
+        if ctx.evaluated_at_ms.is_some() {
+            return None;
+        }
+        Some(format!(
+            "{}\u{0}{}\u{0}{}",
+            ctx.principal.as_deref().unwrap_or(""),
+            capability,
+            args_key?
+        ))
+    }
+
+    /// A lookup in a partition that does not exist yet is not counted.
+    pub fn get(&self, ctx: &RequestContext, key: &str) -> Option<CachedDecision> {
+        let mut partitions = self.partitions.lock().unwrap();
+        let partition = partitions.by_key.get_mut(&self.partition_of(ctx))?;
+        let hit = partition
+            .entries
+            .get(key)
+            .filter(|(at, _)| at.elapsed() <= self.ttl)
+            .map(|(_, cached)| cached.clone());
+        match hit {
+            Some(_) => partition.stats.hits += 1,
+            None => partition.stats.misses += 1,
+        }
+        hit
+    }
+
+    pub fn insert(&self, ctx: &RequestContext, key: String, cached: CachedDecision) {
+        let mut partitions = self.partitions.lock().unwrap();
+        let partitions = &mut *partitions;
+        let id = self.partition_of(ctx);
+        if !partitions.by_key.contains_key(&id) {
+            while partitions.by_key.len() >= self.max_partitions {
+                let Some(oldest) = partitions.order.pop_front() else {
+                    break;
+                };
+                partitions.by_key.remove(&oldest);
+            }
+            partitions.order.push_back(id.clone());
+        }
+        let partition = partitions.by_key.entry(id).or_default();
+        if partition
+            .entries
+            .insert(key.clone(), (Instant::now(), cached))
+            .is_none()
+        {
+            partition.order.push_back(key);
+        }
+        while partition.entries.len() > self.max_entries {
+            let Some(oldest) = partition.order.pop_front() else {
+                break;
+            };
+            if partition.entries.remove(&oldest).is_some() {
+                partition.stats.evictions += 1;
+            }
+        }
+        partition.stats.entries = partition.entries.len();
+    }
+
+    /// Per-partition statistics keyed by a `dimension=value,...` label.
+    pub fn stats(&self) -> BTreeMap<String, PartitionStats> {
+        self.partitions
+            .lock()
+            .unwrap()
+            .by_key
+            .iter()
+            .map(|(partition, p)| (self.label(partition), p.stats))
+            .collect()
+    }
+
+    /// Drops the partition `ctx` belongs to, keeping its counters.
+    pub fn evict_partition(&self, ctx: &RequestContext) {
+        let mut partitions = self.partitions.lock().unwrap();
+        if let Some(partition) = partitions.by_key.get_mut(&self.partition_of(ctx)) {
+            partition.stats.evictions += partition.entries.len() as u64;
+            partition.entries.clear();
+            partition.order.clear();
+            partition.stats.entries = 0;
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut partitions = self.partitions.lock().unwrap();
+        partitions.by_key.clear();
+        partitions.order.clear();
+    }
+}
+
+const PRINCIPAL_FIELD: &str = "principal";
+
+/// Argument keys `rule` tests that its `cache_key` does not list.
+pub fn uncovered_cache_keys(rule: &Rule) -> Vec<String> {
+    let declared: Vec<&str> = rule.cache_key.iter().map(|k| arg_key(k)).collect();
+    let conditions = rule
+        .conditions
+        .iter()
+        .filter(|c| !is_time_condition(c))
+        .map(|c| arg_key(&c.key));
+    let params = rule.param_constraints.iter().map(|c| arg_key(&c.param));
+    let mut missing: Vec<String> = conditions
+        .chain(params)
+        .filter(|key| !declared.contains(key))
+        .map(String::from)
+        .collect();
+    missing.dedup();
+    missing
+}
+
+pub fn cache_key_lints(policy: &Policy) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        let missing = uncovered_cache_keys(rule);
+        if rule.cache_key.is_empty() || missing.is_empty() {
+            continue;
+        }
+        lints.push(Lint::warning(
+            "cache-key-incomplete",
+            &policy.name,
+            Some(index),
+            format!(
+                "`cache_key` omits {}, so it is ignored",
+                missing
+                    .iter()
+                    .map(|k| format!("`{}`", k))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        ));
+    }
+    lints
+}
+
+impl PolicyEngine {
+    /// The argument fields decisions for `resource` depend on, when every rule
+    /// that may apply declares a complete `cache_key`; `None` otherwise, or
+    /// when no rule applies.
+    pub fn cache_key_fields(&self, resource: &str) -> Option<Vec<String>> {
+        let mut fields = Vec::new();
+        let mut rules = self.candidate_rules(resource).peekable();
+        rules.peek()?;
+        for rule in rules {
+            if rule.cache_key.is_empty() || !uncovered_cache_keys(rule).is_empty() {
+                return None;
+            }
+            fields.extend(
+                rule.cache_key
+                    .iter()
+                    .filter(|k| k.as_str() != PRINCIPAL_FIELD)
+                    .map(|k| arg_key(k).to_string()),
+            );
+        }
+        fields.sort();
+        fields.dedup();
+        Some(fields)
+    }
+}
+
+/// An args key built from `fields` alone, for [`DecisionCache::key`].
+pub fn projected_args_key(args: &dyn ArgView, fields: &[String]) -> String {
+    let values: Vec<String> = fields
+        .iter()
+        .map(|field| match args.lookup(field) {
+            Some(value) => format!("{}={}", field, value.to_value()),
+            None => format!("{}!", field),
+        })
+        .collect();
+    values.join("\u{0}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Condition, Effect};
+    use serde_json::json;
+
+    fn tenant(name: &str) -> RequestContext {
+        RequestContext::new()
+            .with_principal("agent")
+            .with_attribute("tenant", name)
+    }
+
+    #[test]
+    fn test_partitions_are_isolated() {
+        let cache = DecisionCache::new(
+            vec![Dimension::attribute("tenant")],
+            Duration::from_secs(60),
+        )
+        .with_max_entries_per_partition(1);
+        let allow = CachedDecision {
+            decision: Decision::Authorized,
+            rule: None,
+        };
+        let key = DecisionCache::key(&tenant("a"), "shell", Some("{}".into())).unwrap();
+        cache.insert(&tenant("a"), key.clone(), allow.clone());
+        assert_eq!(cache.get(&tenant("a"), &key), Some(allow.clone()));
+        assert_eq!(cache.get(&tenant("b"), &key), None);
+
+        cache.insert(&tenant("a"), "other".into(), allow);
+        let stats = cache.stats();
+        assert_eq!(stats["tenant=a"].evictions, 1);
+        assert_eq!(stats["tenant=a"].hits, 1);
+        assert!(!stats.contains_key("tenant=b"));
+    }
+
+    #[test]
+    fn test_partitions_are_created_on_insert_and_bounded() {
+        let cache = DecisionCache::new(
+            vec![Dimension::attribute("tenant")],
+            Duration::from_secs(60),
+        )
+        .with_max_partitions(2);
+        let allow = CachedDecision {
+            decision: Decision::Authorized,
+            rule: None,
+        };
+        for name in ["a", "b", "c", "d"] {
+            assert_eq!(cache.get(&tenant(name), "k"), None);
+        }
+        assert!(cache.stats().is_empty());
+
+        for name in ["a", "b", "c"] {
+            cache.insert(&tenant(name), "k".into(), allow.clone());
+        }
+        let stats = cache.stats();
+        assert_eq!(
+            stats.keys().collect::<Vec<_>>(),
+            vec!["tenant=b", "tenant=c"]
+        );
+        assert_eq!(cache.get(&tenant("a"), "k"), None);
+    }
+
+    #[test]
+    fn test_gate_serves_cached_decisions_until_policy_changes() {
+        let mut gate = CapabilityGate::new().with_decision_cache(DecisionCache::new(
+            vec![Dimension::attribute("tenant")],
+            Duration::from_secs(60),
+        ));
+        gate.register_capability(Capability::new("shell", "Execute shell commands"));
+        gate.add_policy(Policy::new("p", "1.0").with_rule(Rule::allow("shell")));
+
+        let args = json!({"cmd": "ls"});
+        assert!(gate
+            .authorize_with("shell", &args, &tenant("a"))
+            .is_allowed());
+        let record = gate.authorize_record("shell", &args, &tenant("a"));
+        assert_eq!(
+            record.details.get("cached").map(String::as_str),
+            Some("true")
+        );
+
+        gate.add_policy(Policy::new("p", "1.0").with_rule(Rule::deny("shell")));
+        assert!(!gate
+            .authorize_with("shell", &args, &tenant("a"))
+            .is_allowed());
+
+        assert_eq!(
+            gate.authorize_with("fs.read", &args, &tenant("a")),
+            Decision::DeniedCapabilityNotFound
+        );
+        gate.register_capability(
+            Capability::new("fs.read", "Read files").with_default_effect(Effect::Allow),
+        );
+        assert!(gate
+            .authorize_with("fs.read", &args, &tenant("a"))
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_decisions_that_depend_on_the_clock_are_not_cached() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        use std::sync::Arc;
+
+        let now = Arc::new(AtomicU64::new(1_000));
+        let clock = now.clone();
+        let engine = PolicyEngine::new().with_clock(Arc::new(move || clock.load(Ordering::SeqCst)));
+        let mut gate = CapabilityGate::new()
+            .with_engine(engine)
+            .with_decision_cache(DecisionCache::new(Vec::new(), Duration::from_secs(60)));
+        gate.register_capability(Capability::new("shell", "Execute shell commands"));
+        gate.register_capability(Capability::new("fs.write", "Write files"));
+        gate.add_policy(
+            Policy::new("p", "1.0")
+                .with_rule(Rule::allow("shell").with_conditions(vec![Condition::new(
+                    "time",
+                    "before",
+                    json!(2_000),
+                )]))
+                .with_rule(Rule::deny("fs.write").with_grace_until(2_000))
+                .with_rule(Rule::allow("fs.write")),
+        );
+
+        let args = json!({"cmd": "ls"});
+        assert!(gate
+            .authorize_with("shell", &args, &tenant("a"))
+            .is_allowed());
+        assert!(gate
+            .authorize_with("fs.write", &args, &tenant("a"))
+            .is_allowed());
+        now.store(5_000, Ordering::SeqCst);
+        assert!(!gate
+            .authorize_with("shell", &args, &tenant("a"))
+            .is_allowed());
+        assert!(!gate
+            .authorize_with("fs.write", &args, &tenant("a"))
+            .is_allowed());
+        assert!(gate.decision_cache().unwrap().stats().is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_exclusions_share_cache_entries() {
+        use crate::fingerprint::{Fingerprinter, HashAlgorithm, FINGERPRINT_DETAIL};
+
+        let mut gate = CapabilityGate::new()
+            .with_decision_cache(DecisionCache::new(Vec::new(), Duration::from_secs(60)))
+            .with_args_fingerprint(Fingerprinter::new(HashAlgorithm::Blake3).excluding("sent_at"));
+        gate.register_capability(Capability::new("shell", "Execute shell commands"));
+        gate.add_policy(Policy::new("p", "1.0").with_rule(Rule::allow("shell")));
+
+        let first =
+            gate.authorize_record("shell", &json!({"cmd": "ls", "sent_at": 1}), &tenant("a"));
+        let second =
+            gate.authorize_record("shell", &json!({"cmd": "ls", "sent_at": 2}), &tenant("a"));
+        assert_eq!(
+            second.details.get("cached").map(String::as_str),
+            Some("true")
+        );
+        assert_eq!(
+            first.details[FINGERPRINT_DETAIL],
+            second.details[FINGERPRINT_DETAIL]
+        );
+        assert!(first.details[FINGERPRINT_DETAIL].starts_with("blake3:"));
+    }
+
+    #[test]
+    fn test_cache_key_hints_ignore_volatile_fields() {
+        let mut gate = CapabilityGate::new().with_decision_cache(DecisionCache::new(
+            vec![Dimension::Principal],
+            Duration::from_secs(60),
+        ));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.register_capability(Capability::new("shell", "Execute shell commands"));
+        let hinted = Rule::allow("fs.read")
+            .with_conditions(vec![Condition::new(
+                "args.path",
+                "starts_with",
+                json!("/tmp"),
+            )])
+            .with_cache_key(["args.path", "principal"]);
+        let incomplete = Rule::allow("shell")
+            .with_conditions(vec![Condition::new("cmd", "eq", json!("ls"))])
+            .with_cache_key(["principal"]);
+        let policy = Policy::new("p", "1")
+            .with_rule(hinted)
+            .with_rule(incomplete);
+        let lints = cache_key_lints(&policy);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].message,
+            "`cache_key` omits `cmd`, so it is ignored"
+        );
+        gate.add_policy(policy);
+
+        assert_eq!(
+            gate.engine().cache_key_fields("fs.read"),
+            Some(vec!["path".to_string()])
+        );
+        assert_eq!(gate.engine().cache_key_fields("shell"), None);
+        let ctx = RequestContext::new().with_principal("agent");
+        gate.authorize_record("fs.read", &json!({"path": "/tmp/a", "nonce": 1}), &ctx);
+        let record = gate.authorize_record("fs.read", &json!({"path": "/tmp/a", "nonce": 2}), &ctx);
+        assert_eq!(
+            record.details.get("cached").map(String::as_str),
+            Some("true")
+        );
+        let record = gate.authorize_record("fs.read", &json!({"path": "/etc/a", "nonce": 2}), &ctx);
+        assert!(!record.decision.is_allowed());
+        assert_eq!(record.details.get("cached"), None);
+    }
+}
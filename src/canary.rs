@@ -0,0 +1,177 @@
+//! Canary Evaluation of New Policy Versions.
+//!
+//! `CapabilityGate::with_canary` installs a candidate engine next to the stable
+//! one. A stable fraction of sessions — bucketed like
+//! [`PercentageRollout`](crate::flags::PercentageRollout), by the `session`
+//! attribute or the principal — is decided by the candidate; every other
+//! request is decided by the stable engine as before.
+//!
+//! Canary-routed requests are also evaluated against the stable policies, and
+//! the [`CanaryReport`] counts how often the two disagree, split into requests
+//! the candidate would newly allow and newly deny. Once the report looks right,
+//! `promote_canary` makes the candidate the stable engine.
+
+use crate::context::RequestContext;
+use crate::flags::PercentageRollout;
+use crate::policy::{Effect, PolicyEngine};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Detail key recording whether a canary-routed decision agreed with stable.
+pub const CANARY_DETAIL: &str = "canary";
+const BUCKET_SALT: &str = "policy-canary";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CanaryReport {
+    pub stable: u64,
+    pub canary: u64,
+    /// Canary requests the stable policies deny but the candidate allows.
+    pub loosened: u64,
+    /// Canary requests the stable policies allow but the candidate denies.
+    pub tightened: u64,
+}
+
+impl CanaryReport {
+    pub fn diverged(&self) -> u64 {
+        self.loosened + self.tightened
+    }
+
+    /// The fraction of canary-routed requests decided differently.
+    pub fn divergence_rate(&self) -> f64 {
+        match self.canary {
+            0 => 0.0,
+            n => self.diverged() as f64 / n as f64,
+        }
+    }
+}
+
+pub(crate) struct Canary {
+    pub(crate) engine: PolicyEngine,
+    fraction: f64,
+    stable: AtomicU64,
+    canary: AtomicU64,
+    loosened: AtomicU64,
+    tightened: AtomicU64,
+}
+
+impl Canary {
+    pub(crate) fn new(engine: PolicyEngine, fraction: f64) -> Self {
+        Self {
+            engine,
+            fraction: fraction.clamp(0.0, 1.0),
+            stable: AtomicU64::new(0),
+            canary: AtomicU64::new(0),
+            loosened: AtomicU64::new(0),
+            tightened: AtomicU64::new(0),
+        }
+    }
+
+    /// Whether the request goes to the candidate, counting it either way.
+    pub(crate) fn routes(&self, ctx: &RequestContext) -> bool {
+        let routed = PercentageRollout::bucket(BUCKET_SALT, ctx) < self.fraction;
+        let counter = if routed { &self.canary } else { &self.stable };
+        counter.fetch_add(1, Ordering::Relaxed);
+        routed
+    }
+
+    /// Records the stable and candidate effects for a canary request, returning
+    /// the `canary` detail value.
+    pub(crate) fn observe(&self, stable: Effect, candidate: Effect) -> &'static str {
+        match (stable.allows(), candidate.allows()) {
+            (false, true) => self.loosened.fetch_add(1, Ordering::Relaxed),
+            (true, false) => self.tightened.fetch_add(1, Ordering::Relaxed),
+            _ => return "agrees",
+        };
+        "diverges"
+    }
+
+    pub(crate) fn report(&self) -> CanaryReport {
+        CanaryReport {
+            stable: self.stable.load(Ordering::Relaxed),
+            canary: self.canary.load(Ordering::Relaxed),
+            loosened: self.loosened.load(Ordering::Relaxed),
+            tightened: self.tightened.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::flags::SESSION_ATTRIBUTE;
+    use crate::gate::{CapabilityGate, Decision};
+    use crate::policy::{Policy, Rule};
+
+    fn session(id: usize) -> RequestContext {
+        RequestContext::default().with_attribute(SESSION_ATTRIBUTE, id.to_string())
+    }
+
+    fn gate() -> CapabilityGate {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("shell", "Shell"));
+        gate.add_policy(Policy::new("tools", "1").with_rule(Rule::allow("shell")));
+        let mut candidate = PolicyEngine::new();
+        candidate.add_policy(Policy::new("tools", "2").with_rule(Rule::deny("shell")));
+        gate.with_canary(candidate, 0.1)
+    }
+
+    #[test]
+    fn test_canary_routes_a_fraction_and_counts_divergence() {
+        let gate = gate();
+        let denied = (0..2_000)
+            .filter(|&i| !gate.authorize_with("shell", &(), &session(i)).is_allowed())
+            .count();
+        assert!((150..250).contains(&denied), "{}", denied);
+
+        let report = gate.canary_report().unwrap();
+        assert_eq!(report.canary, denied as u64);
+        assert_eq!(report.stable + report.canary, 2_000);
+        assert_eq!((report.tightened, report.loosened), (denied as u64, 0));
+        assert!((report.divergence_rate() - 1.0).abs() < f64::EPSILON);
+
+        let routed = (0..2_000)
+            .map(session)
+            .find(|ctx| !gate.authorize_with("shell", &(), ctx).is_allowed())
+            .unwrap();
+        let record = gate.authorize_record("shell", &(), &routed);
+        assert_eq!(record.details[CANARY_DETAIL], "diverges");
+    }
+
+    #[test]
+    fn test_promote_and_abort() {
+        let mut gate = gate();
+        assert!(gate.abort_canary());
+        assert!(gate.canary_report().is_none());
+        assert!((0..100).all(|i| gate.authorize_with("shell", &(), &session(i)).is_allowed()));
+
+        let mut gate = self::gate();
+        assert!(gate.promote_canary());
+        assert_eq!(
+            gate.authorize_with("shell", &(), &session(0)),
+            Decision::DeniedPolicyViolation
+        );
+        assert!(!gate.promote_canary());
+    }
+
+    #[test]
+    fn test_promotion_clears_cached_decisions() {
+        use crate::cache::DecisionCache;
+        use std::time::Duration;
+
+        let mut gate =
+            gate().with_decision_cache(DecisionCache::new(Vec::new(), Duration::from_secs(60)));
+        let stable = (0..100)
+            .map(session)
+            .find(|ctx| gate.authorize_with("shell", &(), ctx).is_allowed())
+            .unwrap();
+        let record = gate.authorize_record("shell", &(), &stable);
+        assert_eq!(record.details["cached"], "true");
+
+        assert!(gate.promote_canary());
+        assert_eq!(
+            gate.authorize_with("shell", &(), &stable),
+            Decision::DeniedPolicyViolation
+        );
+    }
+}
@@ -1,17 +1,157 @@
 //! Capability Definition and Registry.
 //!
 //! Capability Registry maintains the authoritative list of registered capabilities.
-//! Unknown capabilities MUST be denied by default.
+//! Unknown capabilities MUST be denied by default. Capabilities are kept sorted by
+//! name so listings are stable.
+//!
+//! Renamed capabilities keep working through aliases: an alias maps a deprecated
+//! name to its replacement and is resolved on every lookup.
+//!
+//! Every capability belongs to a [`CapabilityCategory`]; rules can target a whole
+//! category with a `category:<Name>` resource, e.g. `deny category:Process`.
+//!
+//! `register` stores definitions as given; `register_checked` validates them
+//! first, see [`crate::validation`].
+//!
+//! Lookups first check a [`BloomFilter`] of registered names and aliases, so
+//! misses rarely touch the map.
 
+use crate::bloom::BloomFilter;
+use crate::condition::ParamConstraint;
+use crate::policy::Effect;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+
+pub const CATEGORY_PREFIX: &str = "category:";
+
+/// The most aliases [`CapabilityRegistry::resolve`] follows for one name.
+pub const MAX_ALIAS_HOPS: usize = 32;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub enum CapabilityCategory {
+    Filesystem,
+    Network,
+    Process,
+    Memory,
+    Credential,
+    Communication,
+    #[default]
+    Other,
+}
+
+impl CapabilityCategory {
+    pub const ALL: [CapabilityCategory; 7] = [
+        CapabilityCategory::Filesystem,
+        CapabilityCategory::Network,
+        CapabilityCategory::Process,
+        CapabilityCategory::Memory,
+        CapabilityCategory::Credential,
+        CapabilityCategory::Communication,
+        CapabilityCategory::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CapabilityCategory::Filesystem => "Filesystem",
+            CapabilityCategory::Network => "Network",
+            CapabilityCategory::Process => "Process",
+            CapabilityCategory::Memory => "Memory",
+            CapabilityCategory::Credential => "Credential",
+            CapabilityCategory::Communication => "Communication",
+            CapabilityCategory::Other => "Other",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.as_str() == name)
+    }
+
+    /// The category a capability name conventionally belongs to, judged by its
+    /// namespace (`fs.read` is Filesystem, `http.get` is Network).
+    pub fn infer(capability: &str) -> Self {
+        let namespace = capability.split('.').next().unwrap_or(capability);
+        match namespace {
+            "fs" | "file" | "dir" => CapabilityCategory::Filesystem,
+            "http" | "https" | "web" | "net" | "dns" | "socket" => CapabilityCategory::Network,
+            "shell" | "exec" | "process" | "proc" => CapabilityCategory::Process,
+            "memory" | "mem" => CapabilityCategory::Memory,
+            "secret" | "secrets" | "credential" | "credentials" | "keychain" | "vault" => {
+                CapabilityCategory::Credential
+            }
+            "email" | "mail" | "sms" | "chat" | "slack" | "message" | "notify" => {
+                CapabilityCategory::Communication
+            }
+            _ => CapabilityCategory::Other,
+        }
+    }
+
+    /// The rule resource that targets every capability in this category.
+    pub fn selector(&self) -> String {
+        format!("{}{}", CATEGORY_PREFIX, self.as_str())
+    }
+}
+
+impl std::fmt::Display for CapabilityCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
+/// Deserializing infers the category from the name when the field is absent,
+/// as [`Capability::new`] does.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "SerializedCapability")]
 pub struct Capability {
     pub name: String,
     pub description: String,
     pub enabled: bool,
     pub parameters: Vec<CapabilityParam>,
+    pub category: CapabilityCategory,
+    /// The widest scope any policy may grant; see [`crate::scope`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub constraints: Vec<ParamConstraint>,
+    /// JSON Schema for the arguments, beyond the flat `parameters` list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+    /// The effect when no policy rule matches, overriding the engine default;
+    /// see [`crate::policy::PolicyEngine::set_capability_default`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_effect: Option<Effect>,
+}
+
+#[derive(Deserialize)]
+struct SerializedCapability {
+    name: String,
+    description: String,
+    enabled: bool,
+    parameters: Vec<CapabilityParam>,
+    #[serde(default)]
+    category: Option<CapabilityCategory>,
+    #[serde(default)]
+    constraints: Vec<ParamConstraint>,
+    #[serde(default)]
+    schema: Option<serde_json::Value>,
+    #[serde(default)]
+    default_effect: Option<Effect>,
+}
+
+impl From<SerializedCapability> for Capability {
+    fn from(raw: SerializedCapability) -> Self {
+        Self {
+            category: raw
+                .category
+                .unwrap_or_else(|| CapabilityCategory::infer(&raw.name)),
+            name: raw.name,
+            description: raw.description,
+            enabled: raw.enabled,
+            parameters: raw.parameters,
+            constraints: raw.constraints,
+            schema: raw.schema,
+            default_effect: raw.default_effect,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,15 +162,41 @@ pub struct CapabilityParam {
 }
 
 impl Capability {
+    /// Creates an enabled capability whose category is inferred from its name.
     pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        let name = name.into();
         Self {
-            name: name.into(),
+            category: CapabilityCategory::infer(&name),
+            name,
             description: description.into(),
             enabled: true,
             parameters: Vec::new(),
+            constraints: Vec::new(),
+            schema: None,
+            default_effect: None,
         }
     }
 
+    pub fn with_schema(mut self, schema: serde_json::Value) -> Self {
+        self.schema = Some(schema);
+        self
+    }
+
+    pub fn with_constraint(mut self, constraint: ParamConstraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    pub fn with_default_effect(mut self, effect: Effect) -> Self {
+        self.default_effect = Some(effect);
+        self
+    }
+
+    pub fn with_category(mut self, category: CapabilityCategory) -> Self {
+        self.category = category;
+        self
+    }
+
     pub fn with_params(mut self, params: Vec<CapabilityParam>) -> Self {
         self.parameters = params;
         self
@@ -45,9 +211,11 @@ impl Capability {
     }
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, Default)]
 pub struct CapabilityRegistry {
-    capabilities: HashMap<String, Capability>,
+    capabilities: BTreeMap<String, Capability>,
+    aliases: BTreeMap<String, String>,
+    filter: BloomFilter,
 }
 
 impl CapabilityRegistry {
@@ -56,31 +224,106 @@ impl CapabilityRegistry {
     }
 
     pub fn register(&mut self, capability: Capability) {
+        self.remember(&capability.name);
         self.capabilities
             .insert(capability.name.clone(), capability);
     }
 
+    fn remember(&mut self, name: &str) {
+        self.filter.insert(name);
+        if self.filter.is_saturated() {
+            let names = self.capabilities.keys().chain(self.aliases.keys());
+            self.filter = BloomFilter::from_items(names.map(String::as_str).chain([name]));
+        }
+    }
+
+    /// `false` if `name` is certainly neither a capability nor an alias.
+    fn may_know(&self, name: &str) -> bool {
+        self.filter.may_contain(name)
+    }
+
+    /// Registers `old` as a deprecated alias of `new`. Returns `false` if `old` is
+    /// itself a registered capability, or the alias would create a cycle or a
+    /// chain longer than [`MAX_ALIAS_HOPS`]. Re-pointing an alias is checked the
+    /// same way.
+    pub fn register_alias(&mut self, old: impl Into<String>, new: impl Into<String>) -> bool {
+        let (old, new) = (old.into(), new.into());
+        if self.capabilities.contains_key(&old) || self.reaches(&new, &old) {
+            return false;
+        }
+        self.remember(&old);
+        self.aliases.insert(old, new);
+        true
+    }
+
+    /// Whether following aliases from `from` reaches `to`, or runs past
+    /// [`MAX_ALIAS_HOPS`].
+    fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut current = from;
+        for _ in 0..MAX_ALIAS_HOPS {
+            if current == to {
+                return true;
+            }
+            match self.aliases.get(current) {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Follows aliases to the canonical capability name, at most
+    /// [`MAX_ALIAS_HOPS`] of them.
+    pub fn resolve<'a>(&'a self, name: &'a str) -> &'a str {
+        let mut current = name;
+        for _ in 0..MAX_ALIAS_HOPS {
+            match self.aliases.get(current) {
+                Some(next) => current = next,
+                None => break,
+            }
+        }
+        current
+    }
+
+    /// The replacement name if `name` is a deprecated alias.
+    pub fn deprecated(&self, name: &str) -> Option<&str> {
+        let target = self.aliases.get(name)?;
+        Some(self.resolve(target))
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
     pub fn get(&self, name: &str) -> Option<&Capability> {
-        self.capabilities.get(name)
+        if !self.may_know(name) {
+            return None;
+        }
+        self.capabilities.get(self.resolve(name))
     }
 
     pub fn is_registered(&self, name: &str) -> bool {
-        self.capabilities.contains_key(name)
+        self.may_know(name) && self.capabilities.contains_key(self.resolve(name))
     }
 
     pub fn is_enabled(&self, name: &str) -> bool {
-        self.capabilities
-            .get(name)
-            .map(|c| c.enabled)
-            .unwrap_or(false)
+        self.get(name).map(|c| c.enabled).unwrap_or(false)
     }
 
     pub fn list(&self) -> Vec<&Capability> {
         self.capabilities.values().collect()
     }
 
+    pub fn by_category(&self, category: CapabilityCategory) -> Vec<&Capability> {
+        self.capabilities
+            .values()
+            .filter(|c| c.category == category)
+            .collect()
+    }
+
     pub fn enable(&mut self, name: &str) -> bool {
-        if let Some(cap) = self.capabilities.get_mut(name) {
+        let name = self.resolve(name).to_string();
+        if let Some(cap) = self.capabilities.get_mut(&name) {
             cap.enable();
             true
         } else {
@@ -89,7 +332,8 @@ impl CapabilityRegistry {
     }
 
     pub fn disable(&mut self, name: &str) -> bool {
-        if let Some(cap) = self.capabilities.get_mut(name) {
+        let name = self.resolve(name).to_string();
+        if let Some(cap) = self.capabilities.get_mut(&name) {
             cap.disable();
             true
         } else {
@@ -122,6 +366,97 @@ mod tests {
         assert!(!registry.is_enabled("shell"));
     }
 
+    #[test]
+    fn test_list_is_sorted_by_name() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("shell", "Execute shell commands"));
+        registry.register(Capability::new("fs.read", "Read files from filesystem"));
+
+        let names: Vec<_> = registry.list().iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["fs.read", "shell"]);
+    }
+
+    #[test]
+    fn test_alias_resolves_to_renamed_capability() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("http.get", "HTTP GET requests"));
+        assert!(registry.register_alias("web.fetch", "http.get"));
+
+        assert!(registry.is_registered("web.fetch"));
+        assert_eq!(registry.resolve("web.fetch"), "http.get");
+        assert_eq!(registry.deprecated("web.fetch"), Some("http.get"));
+        assert_eq!(registry.deprecated("http.get"), None);
+
+        registry.disable("web.fetch");
+        assert!(!registry.is_enabled("http.get"));
+    }
+
+    #[test]
+    fn test_alias_cycles_rejected() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("http.get", "HTTP GET requests"));
+        assert!(registry.register_alias("a", "b"));
+        assert!(!registry.register_alias("b", "a"));
+        assert!(!registry.register_alias("http.get", "a"));
+        assert!(!registry.register_alias("a", "a"));
+
+        assert!(registry.register_alias("b", "c"));
+        assert!(!registry.register_alias("b", "a"));
+        assert!(!registry.register_alias("c", "a"));
+        assert_eq!(registry.resolve("a"), "c");
+    }
+
+    #[test]
+    fn test_query_by_category() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("fs.read", "Read files from filesystem"));
+        registry.register(Capability::new("shell", "Execute shell commands"));
+        registry.register(
+            Capability::new("browser", "Drive a headless browser")
+                .with_category(CapabilityCategory::Network),
+        );
+
+        let names = |c| -> Vec<String> {
+            registry
+                .by_category(c)
+                .iter()
+                .map(|c| c.name.clone())
+                .collect()
+        };
+        assert_eq!(names(CapabilityCategory::Process), vec!["shell"]);
+        assert_eq!(names(CapabilityCategory::Network), vec!["browser"]);
+        assert_eq!(
+            CapabilityCategory::parse("Filesystem"),
+            Some(CapabilityCategory::Filesystem)
+        );
+
+        let parse = |json: &str| serde_json::from_str::<Capability>(json).unwrap().category;
+        let fields = r#""description": "", "enabled": true, "parameters": []"#;
+        assert_eq!(
+            parse(&format!(r#"{{"name": "shell", {}}}"#, fields)),
+            CapabilityCategory::Process
+        );
+        assert_eq!(
+            parse(&format!(
+                r#"{{"name": "shell", "category": "Other", {}}}"#,
+                fields
+            )),
+            CapabilityCategory::Other
+        );
+    }
+
+    #[test]
+    fn test_many_registrations_stay_visible() {
+        let mut registry = CapabilityRegistry::new();
+        for i in 0..200 {
+            registry.register(Capability::new(format!("tool.{}", i), "generated"));
+        }
+        assert!(registry.register_alias("legacy.tool", "tool.7"));
+        assert!((0..200).all(|i| registry.is_registered(&format!("tool.{}", i))));
+        assert!(registry.is_registered("legacy.tool"));
+        assert!(!registry.is_registered("tool.200"));
+    }
+
     #[test]
     fn test_unknown_capability() {
         let registry = CapabilityRegistry::new();
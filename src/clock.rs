@@ -0,0 +1,337 @@
+//! Clocks and Time Conditions.
+//!
+//! Conditions keyed `time` are evaluated against the engine's [`Clock`], or the
+//! request's `evaluated_at_ms` when it has one, rather than the request
+//! arguments:
+//!
+//! - `after` / `before`: a Unix timestamp in milliseconds
+//! - `between`: `[start_ms, end_ms]`
+//! - `hours`: `[start_hour, end_hour]`, a daily UTC window that may wrap midnight
+//!
+//! Hosts with skewed clocks are handled with a tolerance: the true time is
+//! taken to be anywhere within `now ± tolerance`. An `Allow` rule's time
+//! condition must hold across that whole interval and a `Deny` rule's anywhere
+//! in it, so uncertainty always resolves towards denial.
+
+use crate::lint::Lint;
+use crate::policy::{Condition, Effect, Policy, PolicyEngine};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub const TIME_KEY: &str = "time";
+pub const TIME_OPERATORS: &[&str] = &["after", "before", "between", "hours"];
+
+const HOUR_MS: u64 = 60 * 60 * 1000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+pub trait Clock: Send + Sync {
+    /// Milliseconds since the Unix epoch.
+    fn now_ms(&self) -> u64;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> u64 + Send + Sync,
+{
+    fn now_ms(&self) -> u64 {
+        self()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_ms(&self) -> u64 {
+        crate::audit::now_ms()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedClock(pub u64);
+
+impl Clock for FixedClock {
+    fn now_ms(&self) -> u64 {
+        self.0
+    }
+}
+
+pub(crate) fn system() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
+
+/// The evaluation instant and how far the local clock may be off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeCheck {
+    pub now_ms: u64,
+    pub tolerance_ms: u64,
+}
+
+impl TimeCheck {
+    pub fn now() -> Self {
+        Self {
+            now_ms: crate::audit::now_ms(),
+            tolerance_ms: 0,
+        }
+    }
+
+    /// Whether a time condition holds for a rule with `effect`; `None` if
+    /// `condition` is not a time condition or is malformed.
+    pub fn holds(&self, condition: &Condition, effect: Effect) -> Option<bool> {
+        let lo = self.now_ms.saturating_sub(self.tolerance_ms);
+        let hi = self.now_ms.saturating_add(self.tolerance_ms);
+        let windows = windows(condition, lo, hi)?;
+        Some(match effect {
+            Effect::Deny => windows.iter().any(|&(a, b)| a <= hi && lo < b),
+            _ => windows.iter().any(|&(a, b)| a <= lo && hi < b),
+        })
+    }
+}
+
+/// `ms` since the Unix epoch as an RFC 3339 UTC timestamp, to the second.
+pub fn format_utc(ms: u64) -> String {
+    let secs = ms / 1000;
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days, after Howard Hinnant's date algorithms.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+pub fn is_time_condition(condition: &Condition) -> bool {
+    condition.key == TIME_KEY
+}
+
+impl PolicyEngine {
+    /// Whether any rule that may apply to `resource` depends on the clock,
+    /// through a time condition or a grace period.
+    pub(crate) fn uses_clock(&self, resource: &str) -> bool {
+        self.candidate_rules(resource).any(|rule| {
+            rule.enforce_after_ms.is_some() || rule.conditions.iter().any(is_time_condition)
+        })
+    }
+}
+
+fn pair(condition: &Condition) -> Option<(u64, u64)> {
+    match condition.value.as_array()?.as_slice() {
+        [a, b] => Some((a.as_u64()?, b.as_u64()?)),
+        _ => None,
+    }
+}
+
+/// Half-open `[start, end)` windows in which `condition` holds, covering at
+/// least `lo..=hi`. Adjacent windows are merged.
+fn windows(condition: &Condition, lo: u64, hi: u64) -> Option<Vec<(u64, u64)>> {
+    if !is_time_condition(condition) {
+        return None;
+    }
+    let value = condition.value.as_u64();
+    let mut windows = match condition.operator.as_str() {
+        "after" => vec![(value?.saturating_add(1), u64::MAX)],
+        "before" => vec![(0, value?)],
+        "between" => {
+            let (start, end) = pair(condition)?;
+            vec![(start, end)]
+        }
+        "hours" => {
+            let (start, end) = pair(condition)?;
+            if start > 24 || end > 24 {
+                return None;
+            }
+            let mut windows = Vec::new();
+            let first_day = (lo / DAY_MS).saturating_sub(1);
+            for day in first_day..=hi / DAY_MS + 1 {
+                let base = day * DAY_MS;
+                if start <= end {
+                    windows.push((base + start * HOUR_MS, base + end * HOUR_MS));
+                } else {
+                    windows.push((base, base + end * HOUR_MS));
+                    windows.push((base + start * HOUR_MS, base + DAY_MS));
+                }
+            }
+            windows
+        }
+        _ => return None,
+    };
+    windows.retain(|(a, b)| a < b);
+    let mut merged: Vec<(u64, u64)> = Vec::new();
+    for (a, b) in windows {
+        match merged.last_mut() {
+            Some(last) if last.1 >= a => last.1 = last.1.max(b),
+            _ => merged.push((a, b)),
+        }
+    }
+    Some(merged)
+}
+
+/// Length of the window a time condition describes, if bounded.
+fn window_length(condition: &Condition) -> Option<Duration> {
+    let (start, end) = pair(condition)?;
+    let ms = match condition.operator.as_str() {
+        "between" => end.saturating_sub(start),
+        "hours" if start <= end => (end - start) * HOUR_MS,
+        "hours" => (24 - start + end) * HOUR_MS,
+        _ => return None,
+    };
+    Some(Duration::from_millis(ms))
+}
+
+/// Warns about time windows too short to be evaluated reliably under
+/// `tolerance`: an `Allow` window of at most twice the tolerance can never
+/// match.
+pub fn skew_lints(policy: &Policy, tolerance: Duration) -> Vec<Lint> {
+    if tolerance.is_zero() {
+        return Vec::new();
+    }
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        for condition in rule.conditions.iter().filter(|c| is_time_condition(c)) {
+            match window_length(condition) {
+                Some(length) if length <= tolerance * 2 => lints.push(Lint::warning(
+                    "time-window-within-skew",
+                    &policy.name,
+                    Some(index),
+                    format!(
+                        "`{}` window of {}s is not longer than twice the {}s clock-skew tolerance",
+                        condition.operator,
+                        length.as_secs(),
+                        tolerance.as_secs()
+                    ),
+                )),
+                _ => {}
+            }
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn check(now_ms: u64, tolerance_ms: u64) -> TimeCheck {
+        TimeCheck {
+            now_ms,
+            tolerance_ms,
+        }
+    }
+
+    #[test]
+    fn test_skew_resolves_towards_denial() {
+        let business = Condition::new("time", "hours", json!([9, 17]));
+        let at = |h: u64, m: u64| 3 * DAY_MS + h * HOUR_MS + m * 60_000;
+
+        assert_eq!(
+            check(at(12, 0), 0).holds(&business, Effect::Allow),
+            Some(true)
+        );
+        assert_eq!(
+            check(at(8, 58), 0).holds(&business, Effect::Allow),
+            Some(false)
+        );
+
+        let tolerance = 5 * 60_000;
+        assert_eq!(
+            check(at(9, 2), tolerance).holds(&business, Effect::Allow),
+            Some(false)
+        );
+        assert_eq!(
+            check(at(8, 58), tolerance).holds(&business, Effect::Deny),
+            Some(true)
+        );
+
+        let night = Condition::new("time", "hours", json!([22, 6]));
+        assert_eq!(
+            check(at(23, 59), tolerance).holds(&night, Effect::Allow),
+            Some(true)
+        );
+        assert_eq!(
+            check(at(0, 1), tolerance).holds(&night, Effect::Allow),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_engine_uses_its_clock() {
+        let policy = Policy::new("window", "1.0").with_rule(
+            crate::policy::Rule::allow("deploy").with_conditions(vec![Condition::new(
+                "time",
+                "before",
+                json!(1_000_000),
+            )]),
+        );
+        let mut engine = crate::policy::PolicyEngine::new()
+            .with_clock(Arc::new(FixedClock(999_000)))
+            .with_skew_tolerance(Duration::from_secs(2));
+        engine.add_policy(policy);
+        assert_eq!(
+            engine.evaluate("deploy", "execute", &crate::args::NO_ARGS),
+            Effect::Deny
+        );
+        let engine = engine.with_skew_tolerance(Duration::ZERO);
+        assert_eq!(
+            engine.evaluate("deploy", "execute", &crate::args::NO_ARGS),
+            Effect::Allow
+        );
+    }
+
+    #[test]
+    fn test_request_time_overrides_clock() {
+        let policy = Policy::new("window", "1.0").with_rule(
+            crate::policy::Rule::allow("deploy").with_conditions(vec![Condition::new(
+                "time",
+                "before",
+                json!(1_000_000),
+            )]),
+        );
+        let mut engine =
+            crate::policy::PolicyEngine::new().with_clock(Arc::new(FixedClock(2_000_000)));
+        engine.add_policy(policy);
+        let then = crate::context::RequestContext::new().with_evaluation_time(500_000);
+        assert_eq!(
+            engine.evaluate_with(&then, "deploy", "execute", &crate::args::NO_ARGS),
+            Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate("deploy", "execute", &crate::args::NO_ARGS),
+            Effect::Deny
+        );
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(951_782_400_000), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc(1_790_000_000_999), "2026-09-21T14:13:20Z");
+    }
+
+    #[test]
+    fn test_short_window_lint() {
+        let policy = Policy::new("maintenance", "1.0").with_rule(
+            crate::policy::Rule::allow("shell").with_conditions(vec![Condition::new(
+                "time",
+                "between",
+                json!([0, 60_000]),
+            )]),
+        );
+        assert!(skew_lints(&policy, Duration::from_secs(10)).is_empty());
+        let lints = skew_lints(&policy, Duration::from_secs(30));
+        assert_eq!(lints[0].code, "time-window-within-skew");
+    }
+}
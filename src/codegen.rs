@@ -0,0 +1,407 @@
+//! Typed Argument Builders.
+//!
+//! [`generate`] turns capability definitions into Rust source: one
+//! `<Name>Args` struct per capability with a constructor taking the required
+//! arguments, a `with_<field>` builder per optional one, and a
+//! `From<<Name>Args> for serde_json::Value` impl, so application code builds
+//! tool arguments type-safely and stays in sync with the registry. String
+//! `enum`s become Rust enums.
+//!
+//! Fields come from the capability's `schema` when it has top-level
+//! `properties`, and from its flat `parameters` otherwise. Nested objects and
+//! untyped values are passed through as `serde_json::Value`.
+//!
+//! Call it from a build script and `include!` the output, or run
+//! `femtoclaw-policy codegen <capabilities.json>`.
+
+use crate::capability::Capability;
+use serde_json::Value;
+use std::fmt::Write;
+
+const KEYWORDS: [&str; 38] = [
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use",
+    "where", "while", "yield",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldType {
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Json,
+    Array(Box<FieldType>),
+    Enum { name: String, variants: Vec<String> },
+}
+
+impl FieldType {
+    fn rust(&self) -> String {
+        match self {
+            FieldType::String => "String".to_string(),
+            FieldType::Integer => "i64".to_string(),
+            FieldType::Number => "f64".to_string(),
+            FieldType::Boolean => "bool".to_string(),
+            FieldType::Json => "serde_json::Value".to_string(),
+            FieldType::Array(item) => format!("Vec<{}>", item.rust()),
+            FieldType::Enum { name, .. } => name.clone(),
+        }
+    }
+
+    /// The parameter type for constructors and builders.
+    fn input(&self) -> String {
+        match self {
+            FieldType::String => "impl Into<String>".to_string(),
+            other => other.rust(),
+        }
+    }
+
+    fn enums(&self) -> Vec<(&str, &[String])> {
+        match self {
+            FieldType::Enum { name, variants } => vec![(name.as_str(), variants.as_slice())],
+            FieldType::Array(item) => item.enums(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    json_name: String,
+    rust_name: String,
+    ty: FieldType,
+    required: bool,
+}
+
+/// `github.create-issue` to `GithubCreateIssue`.
+fn pascal_case(name: &str) -> String {
+    let mut out = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.extend(chars);
+        }
+    }
+    match out.chars().next() {
+        None => "Value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("V{}", out),
+        Some(_) => out,
+    }
+}
+
+/// `pageSize` or `page-size` to `page_size`, escaping keywords.
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if c.is_ascii_uppercase() && previous_lower {
+            out.push('_');
+        }
+        previous_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        match c.is_ascii_alphanumeric() {
+            true => out.push(c.to_ascii_lowercase()),
+            false => out.push('_'),
+        }
+    }
+    match out.chars().next() {
+        None => "value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", out),
+        Some(_) if KEYWORDS.contains(&out.as_str()) => format!("r#{}", out),
+        Some(_) => out,
+    }
+}
+
+fn schema_type(schema: &Value, enum_name: &str) -> FieldType {
+    let variants: Option<Vec<String>> =
+        schema
+            .get("enum")
+            .and_then(Value::as_array)
+            .and_then(|values| {
+                values
+                    .iter()
+                    .map(|v| v.as_str().map(String::from))
+                    .collect()
+            });
+    if let Some(variants) = variants.filter(|v| !v.is_empty()) {
+        return FieldType::Enum {
+            name: enum_name.to_string(),
+            variants,
+        };
+    }
+    match schema.get("type").and_then(Value::as_str) {
+        Some("array") => {
+            let item = schema.get("items").unwrap_or(&Value::Null);
+            FieldType::Array(Box::new(schema_type(item, enum_name)))
+        }
+        Some(name) => param_type(name),
+        None => FieldType::Json,
+    }
+}
+
+fn param_type(name: &str) -> FieldType {
+    match name {
+        "string" => FieldType::String,
+        "integer" => FieldType::Integer,
+        "number" => FieldType::Number,
+        "boolean" => FieldType::Boolean,
+        "array" => FieldType::Array(Box::new(FieldType::Json)),
+        _ => FieldType::Json,
+    }
+}
+
+fn fields(capability: &Capability, type_name: &str) -> Vec<Field> {
+    let properties = capability
+        .schema
+        .as_ref()
+        .and_then(|s| s.get("properties"))
+        .and_then(Value::as_object);
+    let Some(properties) = properties else {
+        return capability
+            .parameters
+            .iter()
+            .map(|p| Field {
+                json_name: p.name.clone(),
+                rust_name: snake_case(&p.name),
+                ty: param_type(&p.param_type),
+                required: p.required,
+            })
+            .collect();
+    };
+    let required: Vec<&str> = capability
+        .schema
+        .as_ref()
+        .and_then(|s| s.get("required"))
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    properties
+        .iter()
+        .map(|(name, schema)| Field {
+            json_name: name.clone(),
+            rust_name: snake_case(name),
+            ty: schema_type(schema, &format!("{}{}", type_name, pascal_case(name))),
+            required: required.contains(&name.as_str()),
+        })
+        .collect()
+}
+
+fn field_type(field: &Field) -> String {
+    match field.required {
+        true => field.ty.rust(),
+        false => format!("Option<{}>", field.ty.rust()),
+    }
+}
+
+fn conversion(field: &Field) -> &'static str {
+    match field.ty {
+        FieldType::String => ".into()",
+        _ => "",
+    }
+}
+
+fn emit_enum(out: &mut String, name: &str, variants: &[String]) {
+    let _ = writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq)]");
+    let _ = writeln!(out, "pub enum {} {{", name);
+    for variant in variants {
+        let _ = writeln!(out, "    {},", pascal_case(variant));
+    }
+    let _ = writeln!(out, "}}\n");
+    let _ = writeln!(out, "impl From<{}> for serde_json::Value {{", name);
+    let _ = writeln!(out, "    fn from(value: {}) -> Self {{", name);
+    let _ = writeln!(out, "        let name = match value {{");
+    for variant in variants {
+        let _ = writeln!(
+            out,
+            "            {}::{} => {:?},",
+            name,
+            pascal_case(variant),
+            variant
+        );
+    }
+    let _ = writeln!(out, "        }};");
+    let _ = writeln!(out, "        serde_json::Value::String(name.to_string())");
+    let _ = writeln!(out, "    }}\n}}\n");
+}
+
+fn emit_capability(out: &mut String, capability: &Capability) {
+    let name = format!("{}Args", pascal_case(&capability.name));
+    let fields = fields(capability, &pascal_case(&capability.name));
+    for field in &fields {
+        for (enum_name, variants) in field.ty.enums() {
+            emit_enum(out, enum_name, variants);
+        }
+    }
+
+    let _ = writeln!(
+        out,
+        "/// Arguments for `{}`: {}",
+        capability.name, capability.description
+    );
+    let _ = writeln!(out, "#[derive(Debug, Clone, PartialEq)]");
+    let _ = writeln!(out, "pub struct {} {{", name);
+    for field in &fields {
+        let _ = writeln!(out, "    pub {}: {},", field.rust_name, field_type(field));
+    }
+    let _ = writeln!(out, "}}\n");
+
+    let required: Vec<&Field> = fields.iter().filter(|f| f.required).collect();
+    let params: Vec<String> = required
+        .iter()
+        .map(|f| format!("{}: {}", f.rust_name, f.ty.input()))
+        .collect();
+    let _ = writeln!(out, "impl {} {{", name);
+    let _ = writeln!(
+        out,
+        "    pub const CAPABILITY: &'static str = {:?};\n",
+        capability.name
+    );
+    let _ = writeln!(out, "    pub fn new({}) -> Self {{", params.join(", "));
+    let _ = writeln!(out, "        Self {{");
+    for field in &fields {
+        match field.required {
+            true => {
+                let _ = writeln!(
+                    out,
+                    "            {}: {}{},",
+                    field.rust_name,
+                    field.rust_name,
+                    conversion(field)
+                );
+            }
+            false => {
+                let _ = writeln!(out, "            {}: None,", field.rust_name);
+            }
+        }
+    }
+    let _ = writeln!(out, "        }}\n    }}");
+    for field in fields.iter().filter(|f| !f.required) {
+        let method = format!("with_{}", field.rust_name.trim_start_matches("r#"));
+        let _ = writeln!(
+            out,
+            "\n    pub fn {}(mut self, {}: {}) -> Self {{",
+            method,
+            field.rust_name,
+            field.ty.input()
+        );
+        let _ = writeln!(
+            out,
+            "        self.{} = Some({}{});",
+            field.rust_name,
+            field.rust_name,
+            conversion(field)
+        );
+        let _ = writeln!(out, "        self\n    }}");
+    }
+    let _ = writeln!(out, "}}\n");
+
+    let _ = writeln!(out, "impl From<{}> for serde_json::Value {{", name);
+    let _ = writeln!(out, "    fn from(args: {}) -> Self {{", name);
+    let _ = writeln!(out, "        let mut map = serde_json::Map::new();");
+    for field in &fields {
+        match field.required {
+            true => {
+                let _ = writeln!(
+                    out,
+                    "        map.insert({:?}.to_string(), args.{}.into());",
+                    field.json_name, field.rust_name
+                );
+            }
+            false => {
+                let _ = writeln!(
+                    out,
+                    "        if let Some(value) = args.{} {{",
+                    field.rust_name
+                );
+                let _ = writeln!(
+                    out,
+                    "            map.insert({:?}.to_string(), value.into());",
+                    field.json_name
+                );
+                let _ = writeln!(out, "        }}");
+            }
+        }
+    }
+    let _ = writeln!(out, "        serde_json::Value::Object(map)");
+    let _ = writeln!(out, "    }}\n}}\n");
+}
+
+/// Rust source for typed argument builders, one per capability.
+pub fn generate(capabilities: &[Capability]) -> String {
+    let mut out = String::from("// Generated by femtoclaw-policy codegen; do not edit.\n\n");
+    for capability in capabilities {
+        emit_capability(&mut out, capability);
+    }
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityParam;
+    use serde_json::json;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(pascal_case("github.create-issue"), "GithubCreateIssue");
+        assert_eq!(pascal_case("in_progress"), "InProgress");
+        assert_eq!(snake_case("pageSize"), "page_size");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("2fa"), "_2fa");
+    }
+
+    #[test]
+    fn test_generates_from_schema() {
+        let capability = Capability::new("github.issues", "List issues").with_schema(json!({
+            "type": "object",
+            "properties": {
+                "repo": { "type": "string" },
+                "state": { "enum": ["open", "closed"] },
+                "pageSize": { "type": "integer" },
+                "labels": { "type": "array", "items": { "type": "string" } },
+            },
+            "required": ["repo"],
+        }));
+        let source = generate(&[capability]);
+        for expected in [
+            "pub struct GithubIssuesArgs {",
+            "    pub repo: String,",
+            "    pub state: Option<GithubIssuesState>,",
+            "    pub page_size: Option<i64>,",
+            "    pub labels: Option<Vec<String>>,",
+            "    pub fn new(repo: impl Into<String>) -> Self {",
+            "    pub fn with_state(mut self, state: GithubIssuesState) -> Self {",
+            "            GithubIssuesState::Closed => \"closed\",",
+            "        map.insert(\"pageSize\".to_string(), value.into());",
+        ] {
+            assert!(
+                source.contains(expected),
+                "missing {:?} in\n{}",
+                expected,
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_generates_from_flat_parameters() {
+        let capability = Capability::new("shell", "Run a command").with_params(vec![
+            CapabilityParam {
+                name: "command".to_string(),
+                param_type: "string".to_string(),
+                required: true,
+            },
+            CapabilityParam {
+                name: "timeout".to_string(),
+                param_type: "number".to_string(),
+                required: false,
+            },
+        ]);
+        let source = generate(&[capability]);
+        assert!(source.contains("pub const CAPABILITY: &'static str = \"shell\";"));
+        assert!(source.contains("pub fn with_timeout(mut self, timeout: f64) -> Self {"));
+    }
+}
@@ -0,0 +1,328 @@
+//! Stable Decision and Reason Codes.
+//!
+//! Every [`Decision`] has a stable string code ([`Decision::code`]) and a
+//! stable number ([`Decision::number`]). Denials whose decision has more than
+//! one possible cause also carry a sub-reason [`Reason`] in the record's
+//! `reason` detail. Codes and numbers are never reused or renumbered; wording
+//! in messages and details may change freely.
+//!
+//! [`DECISIONS`] and [`REASONS`] are the registries, [`registry_schema`]
+//! exports them as a JSON Schema for services and dashboards.
+
+use crate::decision::{Decision, DecisionCategory};
+use serde_json::{json, Value};
+
+pub const REASON_DETAIL: &str = "reason";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodeEntry {
+    pub code: &'static str,
+    pub number: u16,
+    pub description: &'static str,
+}
+
+const fn entry(code: &'static str, number: u16, description: &'static str) -> CodeEntry {
+    CodeEntry {
+        code,
+        number,
+        description,
+    }
+}
+
+/// Indexed by [`Decision::number`].
+pub const DECISIONS: [CodeEntry; 12] = [
+    entry("AUTHORIZED", 0, "the request is allowed"),
+    entry(
+        "DENIED_CAPABILITY_NOT_FOUND",
+        1,
+        "the capability is not registered",
+    ),
+    entry(
+        "DENIED_CAPABILITY_DISABLED",
+        2,
+        "the capability is disabled",
+    ),
+    entry(
+        "DENIED_POLICY_VIOLATION",
+        3,
+        "a rule or the default effect denied the request",
+    ),
+    entry(
+        "DENIED_EVALUATION_TIMEOUT",
+        4,
+        "evaluation exceeded its budget",
+    ),
+    entry(
+        "DENIED_RESOLVER_UNAVAILABLE",
+        5,
+        "a resolver or backend was unavailable",
+    ),
+    entry(
+        "DENIED_PREFLIGHT_FAILED",
+        6,
+        "a capability precondition did not hold",
+    ),
+    entry("DENIED_LEASE_EXPIRED", 7, "a lease was not renewed in time"),
+    entry(
+        "DENIED_INVALID_ARGUMENTS",
+        8,
+        "the arguments were malformed or too large",
+    ),
+    entry(
+        "DENIED_CONSENT_REFUSED",
+        9,
+        "the user did not consent to the request",
+    ),
+    entry(
+        "DENIED_PLAN_DEVIATION",
+        10,
+        "the call deviates from its approved plan",
+    ),
+    entry(
+        "DENIED_QUARANTINED",
+        11,
+        "the principal is quarantined after repeated denials",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Reason {
+    DenyRuleMatched,
+    NoMatchingRule,
+    VetoedByMiddleware,
+    FeatureFlagOff,
+    ConsentDeclined,
+    ConsentUnavailable,
+    PlanDeviation,
+    PlanUnknown,
+    ArgsTooDeep,
+    ArgsTooLarge,
+    ArgsMalformedEncoding,
+    ArgsInvalidUtf8,
+    ArgsDoubleEncoded,
+    ArgsSchemaViolation,
+    IdempotencyKeyMismatch,
+}
+
+pub const REASONS: [(Reason, CodeEntry); 15] = [
+    (
+        Reason::DenyRuleMatched,
+        entry("DENY_RULE_MATCHED", 100, "a deny rule matched"),
+    ),
+    (
+        Reason::NoMatchingRule,
+        entry(
+            "NO_MATCHING_RULE",
+            101,
+            "no rule matched; the default effect denied",
+        ),
+    ),
+    (
+        Reason::VetoedByMiddleware,
+        entry(
+            "VETOED_BY_MIDDLEWARE",
+            102,
+            "a gate middleware vetoed the request",
+        ),
+    ),
+    (
+        Reason::FeatureFlagOff,
+        entry(
+            "FEATURE_FLAG_OFF",
+            103,
+            "the capability's feature flag is off or unknown",
+        ),
+    ),
+    (
+        Reason::ConsentDeclined,
+        entry("CONSENT_DECLINED", 104, "the user declined the request"),
+    ),
+    (
+        Reason::ConsentUnavailable,
+        entry(
+            "CONSENT_UNAVAILABLE",
+            105,
+            "consent was required but no consent provider is configured",
+        ),
+    ),
+    (
+        Reason::PlanDeviation,
+        entry(
+            "PLAN_DEVIATION",
+            106,
+            "the call is not the next step of its plan",
+        ),
+    ),
+    (
+        Reason::PlanUnknown,
+        entry(
+            "PLAN_UNKNOWN",
+            107,
+            "the plan token is unknown, void or complete",
+        ),
+    ),
+    (
+        Reason::ArgsTooDeep,
+        entry("ARGS_TOO_DEEP", 200, "arguments nest deeper than the limit"),
+    ),
+    (
+        Reason::ArgsTooLarge,
+        entry("ARGS_TOO_LARGE", 201, "arguments exceed the size limit"),
+    ),
+    (
+        Reason::ArgsMalformedEncoding,
+        entry(
+            "ARGS_MALFORMED_ENCODING",
+            202,
+            "a locator has a malformed percent escape",
+        ),
+    ),
+    (
+        Reason::ArgsInvalidUtf8,
+        entry(
+            "ARGS_INVALID_UTF8",
+            203,
+            "a locator is not UTF-8 once decoded",
+        ),
+    ),
+    (
+        Reason::ArgsDoubleEncoded,
+        entry(
+            "ARGS_DOUBLE_ENCODED",
+            204,
+            "a locator is percent-encoded more than once",
+        ),
+    ),
+    (
+        Reason::ArgsSchemaViolation,
+        entry(
+            "ARGS_SCHEMA_VIOLATION",
+            205,
+            "arguments do not conform to the capability's schema",
+        ),
+    ),
+    (
+        Reason::IdempotencyKeyMismatch,
+        entry(
+            "IDEMPOTENCY_KEY_MISMATCH",
+            206,
+            "the idempotency key was first used with different arguments",
+        ),
+    ),
+];
+
+impl Reason {
+    fn entry(&self) -> &'static CodeEntry {
+        &REASONS
+            .iter()
+            .find(|(reason, _)| reason == self)
+            .expect("every reason is registered")
+            .1
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.entry().code
+    }
+
+    pub fn number(&self) -> u16 {
+        self.entry().number
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        REASONS
+            .iter()
+            .find(|(_, e)| e.code == code)
+            .map(|(r, _)| *r)
+    }
+}
+
+impl std::fmt::Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl Decision {
+    /// Stable numeric code; see [`DECISIONS`].
+    pub fn number(&self) -> u16 {
+        DECISIONS
+            .iter()
+            .find(|e| e.code == self.code())
+            .map(|e| e.number)
+            .expect("every decision is registered")
+    }
+
+    pub fn from_number(number: u16) -> Option<Self> {
+        DECISIONS
+            .get(number as usize)
+            .and_then(|e| Decision::from_code(e.code))
+    }
+}
+
+fn category_name(category: DecisionCategory) -> String {
+    serde_json::to_value(category)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+/// JSON Schema for decision and reason codes, with the full registries under
+/// `x-decisions` and `x-reasons`.
+pub fn registry_schema() -> Value {
+    let decisions: Vec<Value> = DECISIONS
+        .iter()
+        .map(|e| {
+            let category = Decision::from_code(e.code).map(|d| category_name(d.category()));
+            json!({"code": e.code, "number": e.number, "category": category, "description": e.description})
+        })
+        .collect();
+    let reasons: Vec<Value> = REASONS
+        .iter()
+        .map(|(_, e)| json!({"code": e.code, "number": e.number, "description": e.description}))
+        .collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "$id": "https://femtoclaw.io/schemas/decision-codes/v1.json",
+        "title": "femtoclaw-policy decision codes",
+        "$defs": {
+            "decision": {"type": "string", "enum": DECISIONS.iter().map(|e| e.code).collect::<Vec<_>>()},
+            "reason": {"type": "string", "enum": REASONS.iter().map(|(_, e)| e.code).collect::<Vec<_>>()},
+        },
+        "x-decisions": decisions,
+        "x-reasons": reasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_registry_covers_every_decision_uniquely() {
+        for (number, e) in DECISIONS.iter().enumerate() {
+            let decision = Decision::from_code(e.code).expect(e.code);
+            assert_eq!(decision.number() as usize, number);
+            assert_eq!(Decision::from_number(e.number), Some(decision));
+        }
+        let numbers: BTreeSet<u16> = REASONS.iter().map(|(_, e)| e.number).collect();
+        assert_eq!(numbers.len(), REASONS.len());
+        assert_eq!(
+            Reason::from_code("ARGS_TOO_DEEP"),
+            Some(Reason::ArgsTooDeep)
+        );
+        assert_eq!(Reason::ArgsDoubleEncoded.number(), 204);
+    }
+
+    #[test]
+    fn test_schema_lists_codes() {
+        let schema = registry_schema();
+        assert_eq!(
+            schema["$defs"]["decision"]["enum"][8],
+            "DENIED_INVALID_ARGUMENTS"
+        );
+        assert_eq!(schema["x-decisions"][3]["category"], "policy");
+        assert_eq!(schema["x-reasons"][0]["code"], "DENY_RULE_MATCHED");
+    }
+}
@@ -0,0 +1,221 @@
+//! Differential Compatibility Testing.
+//!
+//! Replays a request corpus against two [`Evaluator`]s — typically the same
+//! policies under an old and a new engine configuration — and reports every
+//! request on which they disagree, so matcher rewrites and policy refactors can
+//! be checked for behavioral changes before they land.
+//!
+//! A corpus is JSON Lines, one [`CorpusRequest`] per line.
+
+use crate::context::RequestContext;
+use crate::policy::{Effect, PolicyEngine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorpusRequest {
+    #[serde(default)]
+    pub ctx: RequestContext,
+    pub resource: String,
+    #[serde(default = "default_action")]
+    pub action: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+fn default_action() -> String {
+    "execute".to_string()
+}
+
+impl CorpusRequest {
+    pub fn new(resource: impl Into<String>, args: Value) -> Self {
+        Self {
+            ctx: RequestContext::default(),
+            resource: resource.into(),
+            action: default_action(),
+            args,
+        }
+    }
+
+    pub fn with_context(mut self, ctx: RequestContext) -> Self {
+        self.ctx = ctx;
+        self
+    }
+}
+
+/// Parses a JSON Lines corpus, skipping blank lines.
+pub fn parse_corpus(jsonl: &str) -> Result<Vec<CorpusRequest>, serde_json::Error> {
+    jsonl
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+/// What an evaluator decided for one request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Outcome {
+    pub effect: Effect,
+    /// Id of the deciding rule, `None` when the default effect applied.
+    pub rule: Option<String>,
+}
+
+pub trait Evaluator {
+    fn decide(&self, request: &CorpusRequest) -> Outcome;
+}
+
+impl Evaluator for PolicyEngine {
+    fn decide(&self, request: &CorpusRequest) -> Outcome {
+        let found = self.find_rule(
+            &request.ctx,
+            &request.resource,
+            &request.action,
+            &request.args,
+        );
+        Outcome {
+            effect: found
+                .as_ref()
+                .map(|m| m.rule.effect)
+                .unwrap_or_else(|| self.default_effect_for(&request.resource)),
+            rule: found.map(|m| m.id()),
+        }
+    }
+}
+
+impl<F: Fn(&CorpusRequest) -> Outcome> Evaluator for F {
+    fn decide(&self, request: &CorpusRequest) -> Outcome {
+        self(request)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Divergence {
+    /// Position of the request in the corpus.
+    pub index: usize,
+    pub request: CorpusRequest,
+    pub left: Outcome,
+    pub right: Outcome,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CompatReport {
+    pub evaluated: usize,
+    pub divergences: Vec<Divergence>,
+}
+
+impl CompatReport {
+    pub fn is_compatible(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Compares effects only by default; [`Harness::compare_rules`] also flags
+/// requests decided by a different rule.
+#[derive(Debug, Clone, Default)]
+pub struct Harness {
+    corpus: Vec<CorpusRequest>,
+    compare_rules: bool,
+}
+
+impl Harness {
+    pub fn new(corpus: Vec<CorpusRequest>) -> Self {
+        Self {
+            corpus,
+            compare_rules: false,
+        }
+    }
+
+    pub fn compare_rules(mut self, compare: bool) -> Self {
+        self.compare_rules = compare;
+        self
+    }
+
+    pub fn run(&self, left: &dyn Evaluator, right: &dyn Evaluator) -> CompatReport {
+        let mut report = CompatReport::default();
+        for (index, request) in self.corpus.iter().enumerate() {
+            let (l, r) = (left.decide(request), right.decide(request));
+            report.evaluated += 1;
+            let diverged = if self.compare_rules {
+                l != r
+            } else {
+                l.effect != r.effect
+            };
+            if diverged {
+                report.divergences.push(Divergence {
+                    index,
+                    request: request.clone(),
+                    left: l,
+                    right: r,
+                });
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    fn engine(policy: Policy) -> PolicyEngine {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(policy);
+        engine
+    }
+
+    fn corpus() -> Vec<CorpusRequest> {
+        parse_corpus(
+            r#"{"resource": "git", "args": {"cmd": "status"}}
+
+{"resource": "git", "args": {"cmd": "push"}}
+{"resource": "shell", "ctx": {"principal": "ci"}}
+"#,
+        )
+        .unwrap()
+    }
+
+    fn git(cmd: &str) -> Rule {
+        Rule::allow("git").with_conditions(vec![Condition::new("cmd", "eq", json!(cmd))])
+    }
+
+    #[test]
+    fn test_equivalent_engines_are_compatible() {
+        let old = engine(
+            Policy::new("p", "1.0")
+                .with_rule(git("status"))
+                .with_rule(git("status"))
+                .with_rule(Rule::allow("shell")),
+        );
+        let new = engine(
+            Policy::new("p", "1.0")
+                .with_rule(git("status"))
+                .with_rule(Rule::allow("shell")),
+        );
+
+        let harness = Harness::new(corpus());
+        let report = harness.run(&old, &new);
+        assert_eq!(report.evaluated, 3);
+        assert!(report.is_compatible());
+
+        // shell was decided by rule #2 before and #1 after.
+        let strict = harness.compare_rules(true).run(&old, &new);
+        assert_eq!(strict.divergences.len(), 1);
+        assert_eq!(strict.divergences[0].left.rule.as_deref(), Some("p#2"));
+    }
+
+    #[test]
+    fn test_divergence_reported() {
+        let old = engine(Policy::new("p", "1.0").with_rule(git("status")));
+        let allow_all = |_: &CorpusRequest| Outcome {
+            effect: Effect::Allow,
+            rule: None,
+        };
+
+        let report = Harness::new(corpus()).run(&old, &allow_all);
+        let indexes: Vec<usize> = report.divergences.iter().map(|d| d.index).collect();
+        assert_eq!(indexes, vec![1, 2]);
+        assert_eq!(report.divergences[0].left.effect, Effect::Deny);
+    }
+}
@@ -0,0 +1,412 @@
+//! Condition and Parameter Constraint Evaluation.
+//!
+//! A rule only matches a request when all of its conditions and parameter
+//! constraints hold against the request arguments. Keys are dotted paths into the
+//! argument object (`path`, `args.path` and `options.mode` are all accepted).
+//! Missing keys never match, so a constrained `Allow` rule fails closed.
+//! Unknown operators are rejected when a condition is deserialized; one built
+//! in code never matches on its own, and a rule holding it fails closed: an
+//! `Allow` rule never matches and a `Deny` rule always does. Arguments are read
+//! through an [`ArgView`], so any payload format can be evaluated.
+//!
+//! The `host` operator parses the argument as an absolute URL and compares its
+//! host, case-insensitively, with a domain; a leading `*.` in the domain
+//! matches exactly one DNS label. Unlike a glob over the whole URL, a query,
+//! fragment, path or userinfo can never pass for the host.
+
+use crate::args::{ArgValue, ArgView};
+use crate::clock::{TIME_KEY, TIME_OPERATORS};
+use crate::pattern::{is_pattern_operator, Glob};
+use crate::policy::Condition;
+use crate::session::SESSION_PREFIX;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// Every operator [`Condition::evaluate`] understands, aliases included.
+pub const OPERATORS: &[&str] = &[
+    "eq",
+    "equals",
+    "ne",
+    "not_equals",
+    "in",
+    "not_in",
+    "starts_with",
+    "prefix",
+    "ends_with",
+    "suffix",
+    "contains",
+    "gt",
+    "gte",
+    "lt",
+    "lte",
+    "glob",
+    "matches",
+    "exists",
+    "host",
+];
+
+pub fn lookup<'a>(args: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(args, |value, segment| match value {
+        Value::Object(map) => map.get(segment),
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+pub(crate) fn arg_key(key: &str) -> &str {
+    key.strip_prefix("args.").unwrap_or(key)
+}
+
+/// The value a condition on `key` tests: session state for `session.` keys,
+/// otherwise the argument.
+pub(crate) fn condition_value<'a>(args: &'a dyn ArgView, key: &str) -> Option<ArgValue<'a>> {
+    match key.strip_prefix(SESSION_PREFIX) {
+        Some(key) => args.session_lookup(key),
+        None => args.lookup(arg_key(key)),
+    }
+}
+
+/// The lowercased host of an absolute URL, without userinfo or port; `None`
+/// unless it is a plain DNS name.
+pub fn url_host(url: &str) -> Option<String> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '\\', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = match host.rsplit_once(':') {
+        Some((host, port)) if port.chars().all(|c| c.is_ascii_digit()) => host,
+        _ => host,
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    let valid = host.split('.').all(|label| {
+        !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    });
+    valid.then_some(host)
+}
+
+/// Whether `host` is `domain`, where a leading `*.` stands for one label.
+pub fn host_matches(domain: &str, host: &str) -> bool {
+    let domain = domain.to_ascii_lowercase();
+    match domain.strip_prefix("*.") {
+        Some(parent) => host
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == parent),
+        None => host == domain,
+    }
+}
+
+fn compare(actual: &Value, expected: &Value) -> Option<std::cmp::Ordering> {
+    match (actual, expected) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    }
+}
+
+/// The operators a condition on `key` may use.
+pub fn operators_for(key: &str) -> &'static [&'static str] {
+    match key == TIME_KEY {
+        true => TIME_OPERATORS,
+        false => OPERATORS,
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Raw {
+            key: String,
+            operator: String,
+            value: Value,
+        }
+        let raw = Raw::deserialize(deserializer)?;
+        let known = operators_for(&raw.key);
+        if !known.contains(&raw.operator.as_str()) {
+            return Err(serde::de::Error::unknown_variant(&raw.operator, known));
+        }
+        Ok(Condition {
+            key: raw.key,
+            operator: raw.operator,
+            value: raw.value,
+        })
+    }
+}
+
+impl Condition {
+    pub fn is_known_operator(&self) -> bool {
+        operators_for(&self.key).contains(&self.operator.as_str())
+    }
+
+    pub fn new(key: impl Into<String>, operator: impl Into<String>, value: Value) -> Self {
+        Self {
+            key: key.into(),
+            operator: operator.into(),
+            value,
+        }
+    }
+
+    pub fn evaluate(&self, args: &dyn ArgView) -> bool {
+        self.evaluate_compiled(args, None)
+    }
+
+    /// Like [`Condition::evaluate`], using `glob` when it is this condition's
+    /// precompiled pattern instead of compiling it on the spot.
+    pub fn evaluate_compiled(&self, args: &dyn ArgView, glob: Option<&Glob>) -> bool {
+        let actual = condition_value(args, &self.key);
+        if self.operator == "exists" {
+            return actual.is_some() == self.value.as_bool().unwrap_or(true);
+        }
+        let Some(actual) = actual else {
+            return false;
+        };
+        let actual = actual.to_value();
+        let actual = actual.as_ref();
+
+        use std::cmp::Ordering::*;
+        match self.operator.as_str() {
+            "eq" | "equals" => actual == &self.value,
+            "ne" | "not_equals" => actual != &self.value,
+            "in" => self.value.as_array().is_some_and(|v| v.contains(actual)),
+            "not_in" => self.value.as_array().is_some_and(|v| !v.contains(actual)),
+            "starts_with" | "prefix" => match (actual.as_str(), self.value.as_str()) {
+                (Some(a), Some(p)) => a.starts_with(p),
+                _ => false,
+            },
+            "ends_with" | "suffix" => match (actual.as_str(), self.value.as_str()) {
+                (Some(a), Some(p)) => a.ends_with(p),
+                _ => false,
+            },
+            "contains" => match (actual, &self.value) {
+                (Value::String(a), Value::String(p)) => a.contains(p.as_str()),
+                (Value::Array(items), needle) => items.contains(needle),
+                _ => false,
+            },
+            "gt" => compare(actual, &self.value) == Some(Greater),
+            "gte" => matches!(compare(actual, &self.value), Some(Greater | Equal)),
+            "lt" => compare(actual, &self.value) == Some(Less),
+            "lte" => matches!(compare(actual, &self.value), Some(Less | Equal)),
+            "host" => match (actual.as_str(), self.value.as_str()) {
+                (Some(a), Some(domain)) => {
+                    url_host(a).is_some_and(|host| host_matches(domain, &host))
+                }
+                _ => false,
+            },
+            op if is_pattern_operator(op) => match (actual.as_str(), self.value.as_str()) {
+                (Some(a), Some(p)) => match glob {
+                    Some(glob) => glob.is_match(a),
+                    None => Glob::compile(p).is_match(a),
+                },
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParamRule {
+    OneOf(Vec<Value>),
+    NoneOf(Vec<Value>),
+    Equals(Value),
+    Prefix(String),
+}
+
+/// A first-class restriction on one capability parameter, e.g. allow `git` only
+/// when `subcommand` is one of `status`, `diff` or `log`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamConstraint {
+    pub param: String,
+    #[serde(flatten)]
+    pub rule: ParamRule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParamViolation {
+    pub param: String,
+    pub expected: String,
+    pub actual: Option<Value>,
+}
+
+impl fmt::Display for ParamViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "`{}` must be {}, got {}",
+                self.param, self.expected, actual
+            ),
+            None => write!(
+                f,
+                "`{}` must be {}, but it is missing",
+                self.param, self.expected
+            ),
+        }
+    }
+}
+
+fn list(values: &[Value]) -> String {
+    let items: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+    format!("[{}]", items.join(", "))
+}
+
+impl ParamConstraint {
+    pub fn one_of<I, V>(param: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        Self {
+            param: param.into(),
+            rule: ParamRule::OneOf(values.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    pub fn none_of<I, V>(param: impl Into<String>, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        Self {
+            param: param.into(),
+            rule: ParamRule::NoneOf(values.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    pub fn equals(param: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self {
+            param: param.into(),
+            rule: ParamRule::Equals(value.into()),
+        }
+    }
+
+    pub fn prefix(param: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self {
+            param: param.into(),
+            rule: ParamRule::Prefix(prefix.into()),
+        }
+    }
+
+    /// What the constraint requires, e.g. `one of ["status", "diff"]`.
+    pub fn describe(&self) -> String {
+        match &self.rule {
+            ParamRule::OneOf(values) => format!("one of {}", list(values)),
+            ParamRule::NoneOf(values) => format!("none of {}", list(values)),
+            ParamRule::Equals(value) => format!("equal to {}", value),
+            ParamRule::Prefix(prefix) => format!("a string starting with {:?}", prefix),
+        }
+    }
+
+    pub fn check(&self, args: &dyn ArgView) -> Result<(), ParamViolation> {
+        let looked_up = args.lookup(arg_key(&self.param));
+        let actual = looked_up.as_ref().map(|v| v.to_value());
+        let actual = actual.as_deref();
+        let ok = match (&self.rule, actual) {
+            (_, None) => false,
+            (ParamRule::OneOf(values), Some(a)) => values.contains(a),
+            (ParamRule::NoneOf(values), Some(a)) => !values.contains(a),
+            (ParamRule::Equals(value), Some(a)) => a == value,
+            (ParamRule::Prefix(prefix), Some(a)) => {
+                a.as_str().is_some_and(|s| s.starts_with(prefix.as_str()))
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(ParamViolation {
+                param: self.param.clone(),
+                expected: self.describe(),
+                actual: actual.cloned(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_condition_operators() {
+        let args = json!({ "path": "/work/a.txt", "size": 10, "opts": { "mode": "ro" } });
+
+        assert!(Condition::new("args.path", "starts_with", json!("/work")).evaluate(&args));
+        assert!(Condition::new("opts.mode", "in", json!(["ro", "rw"])).evaluate(&args));
+        assert!(Condition::new("size", "lte", json!(10)).evaluate(&args));
+        assert!(!Condition::new("size", "gt", json!(10)).evaluate(&args));
+        assert!(Condition::new("missing", "exists", json!(false)).evaluate(&args));
+        assert!(!Condition::new("missing", "eq", json!(null)).evaluate(&args));
+        assert!(!Condition::new("path", "frobnicate", json!("/work")).evaluate(&args));
+    }
+
+    #[test]
+    fn test_host_operator_compares_the_parsed_host() {
+        let host = |url: &str| {
+            Condition::new("url", "host", json!("*.example.com")).evaluate(&json!({ "url": url }))
+        };
+        assert!(host("https://www.example.com"));
+        assert!(host("https://WWW.Example.com:8443/a?b#c"));
+        assert!(!host("https://example.com/"));
+        assert!(!host("https://a.b.example.com/"));
+        assert!(!host("https://evil.com?.example.com"));
+        assert!(!host("https://evil.com#.example.com/x"));
+        assert!(!host("https://evil.com/.example.com"));
+        assert!(!host("https://evil.com\\.example.com"));
+        assert!(!host("https://www.example.com@evil.com/"));
+        assert_eq!(
+            url_host("https://user@docs.rs:443/x"),
+            Some("docs.rs".into())
+        );
+    }
+
+    #[test]
+    fn test_unknown_operators_are_rejected_or_fail_closed() {
+        use crate::policy::{Effect, Policy, PolicyEngine, Rule};
+
+        let err = serde_json::from_value::<Condition>(
+            json!({ "key": "command", "operator": "start_with", "value": "rm" }),
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("unknown variant `start_with`"));
+
+        let typo = vec![Condition::new("command", "start_with", json!("rm"))];
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::deny("shell").with_conditions(typo.clone()))
+                .with_rule(Rule::allow("shell"))
+                .with_rule(Rule::allow("fs.read").with_conditions(typo)),
+        );
+        let args = json!({ "command": "ls" });
+        assert_eq!(engine.evaluate("shell", "call", &args), Effect::Deny);
+        assert_eq!(engine.evaluate("fs.read", "call", &args), Effect::Deny);
+    }
+
+    #[test]
+    fn test_param_constraint_messages() {
+        let constraint = ParamConstraint::one_of("subcommand", ["status", "diff", "log"]);
+        assert!(constraint.check(&json!({ "subcommand": "diff" })).is_ok());
+
+        let err = constraint
+            .check(&json!({ "subcommand": "push" }))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"`subcommand` must be one of ["status", "diff", "log"], got "push""#
+        );
+
+        let err = constraint.check(&json!({})).unwrap_err();
+        assert!(err.to_string().ends_with("but it is missing"));
+    }
+
+    #[test]
+    fn test_param_constraint_serde_shape() {
+        let constraint: ParamConstraint =
+            serde_json::from_value(json!({ "param": "subcommand", "one_of": ["status"] })).unwrap();
+        assert_eq!(
+            constraint,
+            ParamConstraint::one_of("subcommand", ["status"])
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Interactive User Consent.
+//!
+//! A rule with `require_user_consent` allows a request only once the user
+//! agrees. The gate asks its [`ConsentProvider`] — a CLI prompt, a desktop
+//! dialog, a chat message — after policy allows the request, and denies with
+//! `DeniedConsentRefused` if the user declines or no provider is configured.
+//!
+//! The user may approve for the rest of the session. The gate then records a
+//! session grant, keyed by the `session` attribute, principal, capability and
+//! a fingerprint of the rule's content, and does not ask again for that
+//! combination. A grant never carries over to another principal in the same
+//! session, or to a different rule that later takes the same `policy#index`.
+//! Adding or replacing policies forgets every grant. Requests without a
+//! `session` attribute are never remembered.
+//!
+//! Consent is asked after the decision cache, so cached allows still ask.
+
+use crate::context::RequestContext;
+use crate::digest::sha256_hex;
+use crate::flags::SESSION_ATTRIBUTE;
+use crate::policy::Rule;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+/// Detail key recording how consent was given: `approved`, `session` for a
+/// remembered approval, or `lease` for a renewed lease (see [`crate::lease`]).
+pub const CONSENT_DETAIL: &str = "consent";
+
+pub struct ConsentRequest<'a> {
+    pub capability: &'a str,
+    /// The `policy#index` of the rule that requires consent.
+    pub rule: &'a str,
+    pub ctx: &'a RequestContext,
+    /// JSON arguments, for showing the user what will run.
+    pub args: Option<&'a Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Consent {
+    pub approved: bool,
+    pub remember_for_session: bool,
+}
+
+impl Consent {
+    pub fn approve() -> Self {
+        Self {
+            approved: true,
+            remember_for_session: false,
+        }
+    }
+
+    pub fn deny() -> Self {
+        Self {
+            approved: false,
+            remember_for_session: false,
+        }
+    }
+
+    /// Approves and remembers the approval for the rest of the session.
+    pub fn approve_for_session() -> Self {
+        Self {
+            approved: true,
+            remember_for_session: true,
+        }
+    }
+}
+
+pub trait ConsentProvider: Send + Sync {
+    fn request_consent(&self, request: &ConsentRequest<'_>) -> Consent;
+}
+
+impl<F> ConsentProvider for F
+where
+    F: Fn(&ConsentRequest<'_>) -> Consent + Send + Sync,
+{
+    fn request_consent(&self, request: &ConsentRequest<'_>) -> Consent {
+        self(request)
+    }
+}
+
+/// Identifies `rule`, of the policy `id` names, by its content rather than
+/// its position.
+pub(crate) fn rule_fingerprint(id: &str, rule: &Rule) -> String {
+    let policy = id.rsplit_once('#').map_or(id, |(policy, _)| policy);
+    sha256_hex(&serde_json::to_vec(&(policy, rule)).unwrap_or_default())
+}
+
+/// Session, principal, capability and rule fingerprint.
+type Grant = (String, Option<String>, String, String);
+
+/// Approvals remembered for the rest of a session.
+#[derive(Debug, Default)]
+pub(crate) struct SessionGrants {
+    grants: Mutex<BTreeSet<Grant>>,
+}
+
+impl SessionGrants {
+    fn key(ctx: &RequestContext, capability: &str, fingerprint: &str) -> Option<Grant> {
+        let session = ctx.attributes.get(SESSION_ATTRIBUTE)?;
+        Some((
+            session.clone(),
+            ctx.principal.clone(),
+            capability.to_string(),
+            fingerprint.to_string(),
+        ))
+    }
+
+    pub(crate) fn contains(
+        &self,
+        ctx: &RequestContext,
+        capability: &str,
+        fingerprint: &str,
+    ) -> bool {
+        Self::key(ctx, capability, fingerprint)
+            .is_some_and(|key| self.grants.lock().unwrap().contains(&key))
+    }
+
+    pub(crate) fn grant(&self, ctx: &RequestContext, capability: &str, fingerprint: &str) {
+        if let Some(key) = Self::key(ctx, capability, fingerprint) {
+            self.grants.lock().unwrap().insert(key);
+        }
+    }
+
+    /// Forgets every grant of `session`, e.g. when the session ends.
+    pub(crate) fn revoke_session(&self, session: &str) {
+        self.grants.lock().unwrap().retain(|(s, ..)| s != session);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.grants.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::codes::{Reason, REASON_DETAIL};
+    use crate::decision::Decision;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Policy, Rule};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn gate() -> CapabilityGate {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("email.send", "Send email"));
+        gate.add_policy(
+            Policy::new("p", "1").with_rule(Rule::allow("email.send").requiring_consent()),
+        );
+        gate
+    }
+
+    #[test]
+    fn test_session_grants_skip_later_prompts() {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let gate = gate().with_consent_provider(Arc::new(move |request: &ConsentRequest<'_>| {
+            assert_eq!(request.rule, "p#0");
+            counter.fetch_add(1, Ordering::SeqCst);
+            Consent::approve_for_session()
+        }));
+
+        let session = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, "s1");
+        for _ in 0..3 {
+            let record = gate.authorize_record("email.send", &(), &session);
+            assert_eq!(record.decision, Decision::Authorized);
+        }
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        let record = gate.authorize_record("email.send", &(), &session);
+        assert_eq!(record.details[CONSENT_DETAIL], "session");
+
+        gate.end_session("s1");
+        gate.authorize_record("email.send", &(), &session);
+        gate.authorize_record("email.send", &(), &RequestContext::new());
+        assert_eq!(asked.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_grants_bind_principal_and_rule_content() {
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let mut gate = gate().with_consent_provider(Arc::new(move |_: &ConsentRequest<'_>| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Consent::approve_for_session()
+        }));
+        let session = |principal: &str| {
+            RequestContext::new()
+                .with_principal(principal)
+                .with_attribute(SESSION_ATTRIBUTE, "s1")
+        };
+
+        gate.authorize_record("email.send", &(), &session("alice"));
+        gate.authorize_record("email.send", &(), &session("alice"));
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        gate.authorize_record("email.send", &(), &session("bob"));
+        assert_eq!(asked.load(Ordering::SeqCst), 2);
+
+        gate.add_policy(
+            Policy::new("p", "2").with_rule(
+                Rule::allow("email.send")
+                    .for_principal("alice")
+                    .requiring_consent(),
+            ),
+        );
+        let record = gate.authorize_record("email.send", &(), &session("alice"));
+        assert_eq!(record.details[CONSENT_DETAIL], "approved");
+        assert_eq!(asked.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_declined_or_unavailable_consent_denies() {
+        let record = gate().authorize_record("email.send", &(), &RequestContext::new());
+        assert_eq!(record.decision, Decision::DeniedConsentRefused);
+        assert_eq!(
+            record.details[REASON_DETAIL],
+            Reason::ConsentUnavailable.code()
+        );
+
+        let gate = gate().with_consent_provider(Arc::new(|_: &ConsentRequest<'_>| Consent::deny()));
+        let record = gate.authorize_record("email.send", &(), &RequestContext::new());
+        assert_eq!(record.decision, Decision::DeniedConsentRefused);
+        assert_eq!(
+            record.details[REASON_DETAIL],
+            Reason::ConsentDeclined.code()
+        );
+    }
+}
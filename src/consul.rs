@@ -0,0 +1,180 @@
+//! Consul-backed Policy Store.
+//!
+//! Implements [`PolicyStore`] over Consul's KV HTTP API: the key's
+//! `ModifyIndex` is the revision, writes use `?cas=<index>` for fencing, and
+//! watches are blocking queries (`?index=<n>&wait=<secs>s`). The crate does not
+//! ship an HTTP client; callers supply a [`ConsulTransport`].
+
+use crate::store::{PolicyStore, StoreError, StoredBundle};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+pub trait ConsulTransport: Send + Sync {
+    /// `GET` a path; `Ok(None)` for a 404.
+    fn get(&self, path: &str) -> Result<Option<Value>, String>;
+    /// `PUT` a raw body and return the JSON response.
+    fn put(&self, path: &str, body: &[u8]) -> Result<Value, String>;
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct KvPair {
+    modify_index: u64,
+    value: Option<String>,
+}
+
+pub struct ConsulStore {
+    key: String,
+    transport: Arc<dyn ConsulTransport>,
+}
+
+impl ConsulStore {
+    pub fn new(key: impl Into<String>, transport: Arc<dyn ConsulTransport>) -> Self {
+        Self {
+            key: key.into().trim_start_matches('/').to_string(),
+            transport,
+        }
+    }
+
+    fn read(&self, query: &str) -> Result<Option<StoredBundle>, StoreError> {
+        let path = format!("/v1/kv/{}{}", self.key, query);
+        let Some(body) = self.transport.get(&path).map_err(StoreError::Unavailable)? else {
+            return Ok(None);
+        };
+        let pairs: Vec<KvPair> = serde_json::from_value(body)
+            .map_err(|e| StoreError::Unavailable(format!("invalid KV response: {}", e)))?;
+        let Some(pair) = pairs.into_iter().next() else {
+            return Ok(None);
+        };
+        let bytes = base64_decode(pair.value.as_deref().unwrap_or(""))
+            .ok_or_else(|| StoreError::Unavailable("KV value is not base64".to_string()))?;
+        let bundle = String::from_utf8(bytes)
+            .map_err(|_| StoreError::Unavailable("KV value is not UTF-8".to_string()))?;
+        Ok(Some(StoredBundle {
+            revision: pair.modify_index,
+            bundle,
+        }))
+    }
+}
+
+impl PolicyStore for ConsulStore {
+    fn get(&self) -> Result<Option<StoredBundle>, StoreError> {
+        self.read("")
+    }
+
+    fn put(&self, bundle: &str, expected: Option<u64>) -> Result<u64, StoreError> {
+        let path = format!("/v1/kv/{}?cas={}", self.key, expected.unwrap_or(0));
+        let written = self
+            .transport
+            .put(&path, bundle.as_bytes())
+            .map_err(StoreError::Unavailable)?;
+        if written != Value::Bool(true) {
+            let actual = self.get()?.map(|b| b.revision);
+            return Err(StoreError::Conflict { expected, actual });
+        }
+        self.get()?
+            .map(|b| b.revision)
+            .ok_or_else(|| StoreError::Unavailable("key vanished after write".to_string()))
+    }
+
+    fn watch(&self, after: u64, timeout: Duration) -> Result<Option<StoredBundle>, StoreError> {
+        let wait = timeout.as_secs().max(1);
+        let stored = self.read(&format!("?index={}&wait={}s", after, wait))?;
+        Ok(stored.filter(|b| b.revision > after))
+    }
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let sextet = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let input = input.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for &c in input {
+        buffer = (buffer << 6) | sextet(c)? as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// A single Consul key with real CAS semantics.
+    #[derive(Default)]
+    struct FakeConsul {
+        kv: Mutex<Option<(u64, Vec<u8>)>>,
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        bytes
+            .chunks(3)
+            .flat_map(|chunk| {
+                let n = chunk
+                    .iter()
+                    .enumerate()
+                    .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+                (0..4).map(move |i| {
+                    if i <= chunk.len() {
+                        ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char
+                    } else {
+                        '='
+                    }
+                })
+            })
+            .collect()
+    }
+
+    impl ConsulTransport for FakeConsul {
+        fn get(&self, path: &str) -> Result<Option<Value>, String> {
+            assert!(path.starts_with("/v1/kv/fleet/bundle"));
+            Ok(self.kv.lock().unwrap().as_ref().map(|(index, value)| {
+                serde_json::json!([{ "ModifyIndex": index, "Value": base64(value) }])
+            }))
+        }
+
+        fn put(&self, path: &str, body: &[u8]) -> Result<Value, String> {
+            let cas: u64 = path.rsplit("cas=").next().unwrap().parse().unwrap();
+            let mut kv = self.kv.lock().unwrap();
+            let current = kv.as_ref().map(|(i, _)| *i).unwrap_or(0);
+            if cas != current {
+                return Ok(Value::Bool(false));
+            }
+            *kv = Some((current + 7, body.to_vec()));
+            Ok(Value::Bool(true))
+        }
+    }
+
+    #[test]
+    fn test_cas_and_watch() {
+        let store = ConsulStore::new("/fleet/bundle", Arc::new(FakeConsul::default()));
+        let revision = store.put(r#"{"name":"fleet"}"#, None).unwrap();
+        assert_eq!(revision, 7);
+        assert!(matches!(
+            store.put("{}", None),
+            Err(StoreError::Conflict {
+                actual: Some(7),
+                ..
+            })
+        ));
+
+        let stored = store.watch(0, Duration::from_secs(1)).unwrap().unwrap();
+        assert_eq!(stored.bundle, r#"{"name":"fleet"}"#);
+        assert!(store.watch(7, Duration::from_secs(1)).unwrap().is_none());
+    }
+}
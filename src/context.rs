@@ -0,0 +1,51 @@
+//! Request Context.
+//!
+//! Describes who is asking for a capability, alongside the tool name and
+//! arguments passed to [`CapabilityGate::authorize_with`](crate::CapabilityGate::authorize_with).
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequestContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// Identifies retries of the same call; see [`crate::idempotency`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+    /// Free-form request dimensions such as `tenant` or `environment`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+    /// Evaluates time conditions at this instant, in Unix milliseconds, rather
+    /// than the engine's clock; for replaying historical requests. The gate
+    /// ignores it unless built
+    /// [`with_request_time`](crate::CapabilityGate::with_request_time).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evaluated_at_ms: Option<u64>,
+}
+
+impl RequestContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = Some(principal.into());
+        self
+    }
+
+    pub fn with_attribute(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.attributes.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_evaluation_time(mut self, at_ms: u64) -> Self {
+        self.evaluated_at_ms = Some(at_ms);
+        self
+    }
+
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+}
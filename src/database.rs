@@ -0,0 +1,410 @@
+//! Database Audit Sink.
+//!
+//! Streams audit events into a Postgres or ClickHouse table for SQL
+//! dashboards. The crate does not link a database driver itself: callers wrap
+//! their sqlx pool or ClickHouse client in an [`AuditDatabase`], and
+//! [`DatabaseSink`] owns the table schema, batching and backpressure.
+//!
+//! The table has one column per [`DECISION_COLUMNS`] entry, so it matches the
+//! CSV and Parquet exports; [`Dialect::create_table`] returns the DDL. For
+//! Postgres:
+//!
+//! ```sql
+//! CREATE TABLE IF NOT EXISTS femtoclaw_audit (
+//!     timestamp_ms BIGINT NOT NULL,
+//!     principal TEXT,
+//!     tool TEXT NOT NULL,
+//!     decision TEXT NOT NULL,
+//!     reason TEXT,
+//!     rule TEXT,
+//!     sample_rate DOUBLE PRECISION,
+//!     degraded BOOLEAN NOT NULL,
+//!     count BIGINT NOT NULL,
+//!     details TEXT NOT NULL
+//! )
+//! ```
+//!
+//! ClickHouse uses `Int64`, `Float64`, `Bool` and `String`, `Nullable(..)` for
+//! nullable columns, and a `MergeTree` ordered by `timestamp_ms`.
+//!
+//! Events are buffered and inserted `batch_size` at a time, with one
+//! multi-row `INSERT`. A batch is taken out of the buffer before it is sent, so
+//! recording never waits on the database; a failed insert puts its batch back
+//! for the next flush. After a failure, recording does not flush again until a
+//! retry delay has passed, doubling from 100ms up to 30s while the database
+//! stays unavailable; [`DatabaseSink::flush`] always tries. While the database
+//! is unavailable the buffer grows up to `max_pending` events; beyond that
+//! [`Backpressure`] decides which events are dropped, and
+//! [`DatabaseSink::stats`] counts them.
+//!
+//! [`DatabaseSink::flush_in_background`] moves flushing off the recording
+//! threads entirely, onto a thread that flushes on an interval.
+
+use crate::audit::{AuditEvent, AuditSink};
+use crate::export::{export_row, Column, ColumnType, ExportValue, DECISION_COLUMNS};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_TABLE: &str = "femtoclaw_audit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    ClickHouse,
+}
+
+impl Dialect {
+    fn column_type(&self, column: &Column) -> String {
+        let name = match (self, column.column_type) {
+            (Dialect::Postgres, ColumnType::Int64) => "BIGINT",
+            (Dialect::Postgres, ColumnType::Float64) => "DOUBLE PRECISION",
+            (Dialect::Postgres, ColumnType::Boolean) => "BOOLEAN",
+            (Dialect::Postgres, ColumnType::Utf8) => "TEXT",
+            (Dialect::ClickHouse, ColumnType::Int64) => "Int64",
+            (Dialect::ClickHouse, ColumnType::Float64) => "Float64",
+            (Dialect::ClickHouse, ColumnType::Boolean) => "Bool",
+            (Dialect::ClickHouse, ColumnType::Utf8) => "String",
+        };
+        match (self, column.nullable) {
+            (Dialect::Postgres, true) => name.to_string(),
+            (Dialect::Postgres, false) => format!("{} NOT NULL", name),
+            (Dialect::ClickHouse, true) => format!("Nullable({})", name),
+            (Dialect::ClickHouse, false) => name.to_string(),
+        }
+    }
+
+    pub fn create_table(&self, table: &str) -> String {
+        let columns: Vec<String> = DECISION_COLUMNS
+            .iter()
+            .map(|c| format!("    {} {}", c.name, self.column_type(c)))
+            .collect();
+        let engine = match self {
+            Dialect::Postgres => "",
+            Dialect::ClickHouse => " ENGINE = MergeTree ORDER BY timestamp_ms",
+        };
+        format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n{}\n){}",
+            table,
+            columns.join(",\n"),
+            engine
+        )
+    }
+
+    /// A multi-row `INSERT` for `rows` rows, with `$n` placeholders for
+    /// Postgres and `?` for ClickHouse.
+    pub fn insert(&self, table: &str, rows: usize) -> String {
+        let width = DECISION_COLUMNS.len();
+        let values: Vec<String> = (0..rows)
+            .map(|row| {
+                let params: Vec<String> = (0..width)
+                    .map(|i| match self {
+                        Dialect::Postgres => format!("${}", row * width + i + 1),
+                        Dialect::ClickHouse => "?".to_string(),
+                    })
+                    .collect();
+                format!("({})", params.join(", "))
+            })
+            .collect();
+        let names: Vec<&str> = DECISION_COLUMNS.iter().map(|c| c.name).collect();
+        format!(
+            "INSERT INTO {} ({}) VALUES {}",
+            table,
+            names.join(", "),
+            values.join(", ")
+        )
+    }
+}
+
+pub trait AuditDatabase: Send + Sync {
+    /// Runs `statement` with `params` bound in order.
+    fn execute(&self, statement: &str, params: &[ExportValue]) -> Result<(), String>;
+}
+
+/// Which events to drop once `max_pending` events are buffered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Keep the backlog and drop incoming events.
+    #[default]
+    DropNewest,
+    /// Make room for incoming events by dropping the oldest buffered ones.
+    DropOldest,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DatabaseSinkStats {
+    pub inserted: u64,
+    pub dropped: u64,
+    pub failed_batches: u64,
+    pub pending: usize,
+}
+
+#[derive(Default)]
+struct Buffer {
+    events: VecDeque<AuditEvent>,
+    stats: DatabaseSinkStats,
+    /// Consecutive failed inserts, and when recording may flush again.
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+impl Buffer {
+    /// Puts a failed batch back in front, within `max_pending`.
+    fn restore(&mut self, batch: Vec<AuditEvent>, max_pending: usize, backpressure: Backpressure) {
+        for event in batch.into_iter().rev() {
+            self.events.push_front(event);
+        }
+        while self.events.len() > max_pending {
+            self.stats.dropped += 1;
+            match backpressure {
+                Backpressure::DropNewest => self.events.pop_back(),
+                Backpressure::DropOldest => self.events.pop_front(),
+            };
+        }
+    }
+}
+
+pub struct DatabaseSink {
+    db: Arc<dyn AuditDatabase>,
+    dialect: Dialect,
+    table: String,
+    batch_size: usize,
+    max_pending: usize,
+    backpressure: Backpressure,
+    retry_base: Duration,
+    retry_max: Duration,
+    background: AtomicBool,
+    buffer: Mutex<Buffer>,
+}
+
+impl DatabaseSink {
+    /// A sink inserting batches of 100 into [`DEFAULT_TABLE`], buffering up to
+    /// 10,000 events.
+    pub fn new(db: Arc<dyn AuditDatabase>, dialect: Dialect) -> Self {
+        Self {
+            db,
+            dialect,
+            table: DEFAULT_TABLE.to_string(),
+            batch_size: 100,
+            max_pending: 10_000,
+            backpressure: Backpressure::default(),
+            retry_base: Duration::from_millis(100),
+            retry_max: Duration::from_secs(30),
+            background: AtomicBool::new(false),
+            buffer: Mutex::new(Buffer::default()),
+        }
+    }
+
+    pub fn with_table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    pub fn with_max_pending(mut self, max_pending: usize, backpressure: Backpressure) -> Self {
+        self.max_pending = max_pending.max(1);
+        self.backpressure = backpressure;
+        self
+    }
+
+    /// The retry delay after the first failed insert, doubling up to `max`.
+    pub fn with_retry_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.retry_base = base;
+        self.retry_max = max.max(base);
+        self
+    }
+
+    /// Creates the table if it does not exist.
+    pub fn create_table(&self) -> Result<(), String> {
+        self.db
+            .execute(&self.dialect.create_table(&self.table), &[])
+    }
+
+    /// Inserts buffered events batch by batch, stopping at the first failure.
+    pub fn flush(&self) -> Result<(), String> {
+        loop {
+            let batch: Vec<AuditEvent> = {
+                let mut buffer = self.buffer.lock().unwrap();
+                let rows = buffer.events.len().min(self.batch_size);
+                buffer.events.drain(..rows).collect()
+            };
+            if batch.is_empty() {
+                return Ok(());
+            }
+            let params: Vec<ExportValue> = batch.iter().flat_map(export_row).collect();
+            let statement = self.dialect.insert(&self.table, batch.len());
+            let result = self.db.execute(&statement, &params);
+            let mut buffer = self.buffer.lock().unwrap();
+            match result {
+                Ok(()) => {
+                    buffer.stats.inserted += batch.len() as u64;
+                    buffer.failures = 0;
+                    buffer.retry_at = None;
+                }
+                Err(e) => {
+                    buffer.stats.failed_batches += 1;
+                    buffer.failures = buffer.failures.saturating_add(1);
+                    buffer.retry_at = Some(Instant::now() + self.retry_delay(buffer.failures));
+                    buffer.restore(batch, self.max_pending, self.backpressure);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    fn retry_delay(&self, failures: u32) -> Duration {
+        let doublings = failures.saturating_sub(1).min(31);
+        self.retry_base
+            .saturating_mul(1 << doublings)
+            .min(self.retry_max)
+    }
+
+    /// Flushes every `interval` on a new thread, instead of on the recording
+    /// thread once a batch fills. The thread stops once the sink is dropped.
+    pub fn flush_in_background(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        self.background.store(true, Ordering::SeqCst);
+        let sink: Weak<Self> = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            match sink.upgrade() {
+                Some(sink) => {
+                    let _ = sink.flush();
+                }
+                None => return,
+            }
+        })
+    }
+
+    pub fn stats(&self) -> DatabaseSinkStats {
+        let buffer = self.buffer.lock().unwrap();
+        DatabaseSinkStats {
+            pending: buffer.events.len(),
+            ..buffer.stats
+        }
+    }
+}
+
+impl AuditSink for DatabaseSink {
+    fn record(&self, event: &AuditEvent) {
+        let full = {
+            let mut buffer = self.buffer.lock().unwrap();
+            if buffer.events.len() >= self.max_pending {
+                buffer.stats.dropped += 1;
+                match self.backpressure {
+                    Backpressure::DropNewest => return,
+                    Backpressure::DropOldest => {
+                        buffer.events.pop_front();
+                    }
+                }
+            }
+            buffer.events.push_back(event.clone());
+            let waiting = buffer.retry_at.is_some_and(|at| Instant::now() < at);
+            buffer.events.len() >= self.batch_size
+                && !waiting
+                && !self.background.load(Ordering::SeqCst)
+        };
+        if full {
+            let _ = self.flush();
+        }
+    }
+}
+
+impl Drop for DatabaseSink {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Recorder {
+        down: AtomicBool,
+        statements: Mutex<Vec<(String, usize)>>,
+    }
+
+    impl AuditDatabase for Recorder {
+        fn execute(&self, statement: &str, params: &[ExportValue]) -> Result<(), String> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err("connection refused".into());
+            }
+            let mut statements = self.statements.lock().unwrap();
+            statements.push((statement.to_string(), params.len()));
+            Ok(())
+        }
+    }
+
+    fn event(tool: &str) -> AuditEvent {
+        AuditEvent::new(tool, "AUTHORIZED")
+    }
+
+    #[test]
+    fn test_events_are_inserted_in_batches() {
+        let db = Arc::new(Recorder::default());
+        let sink = DatabaseSink::new(db.clone(), Dialect::Postgres).with_batch_size(2);
+        sink.create_table().unwrap();
+        for tool in ["a", "b", "c"] {
+            sink.record(&event(tool));
+        }
+        assert_eq!(sink.stats().pending, 1);
+        sink.flush().unwrap();
+
+        let statements = db.statements.lock().unwrap();
+        assert!(statements[0].0.starts_with(
+            "CREATE TABLE IF NOT EXISTS femtoclaw_audit (\n    timestamp_ms BIGINT NOT NULL,\n    principal TEXT,"
+        ));
+        assert!(statements[1].0.ends_with(
+            "VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10), ($11, $12, $13, $14, $15, $16, $17, $18, $19, $20)"
+        ));
+        assert_eq!(statements[1].1, 20);
+        assert_eq!(statements[2].1, 10);
+        assert_eq!(sink.stats().inserted, 3);
+        assert!(Dialect::ClickHouse
+            .create_table("t")
+            .contains("principal Nullable(String)"));
+    }
+
+    #[test]
+    fn test_outage_buffers_then_drops_under_backpressure() {
+        let db = Arc::new(Recorder::default());
+        db.down.store(true, Ordering::SeqCst);
+        let sink = DatabaseSink::new(db.clone(), Dialect::ClickHouse)
+            .with_batch_size(2)
+            .with_max_pending(3, Backpressure::DropOldest);
+        for tool in ["a", "b", "c", "d"] {
+            sink.record(&event(tool));
+        }
+        let stats = sink.stats();
+        assert_eq!((stats.pending, stats.dropped), (3, 1));
+        // Events recorded after the failure wait for the retry delay.
+        assert_eq!(stats.failed_batches, 1);
+
+        db.down.store(false, Ordering::SeqCst);
+        sink.flush().unwrap();
+        assert_eq!(sink.stats().inserted, 3);
+        assert_eq!(sink.stats().pending, 0);
+    }
+
+    #[test]
+    fn test_background_flush() {
+        let db = Arc::new(Recorder::default());
+        let sink = Arc::new(DatabaseSink::new(db.clone(), Dialect::Postgres));
+        let flusher = sink.flush_in_background(Duration::from_millis(1));
+        sink.record(&event("a"));
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while sink.stats().inserted == 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(sink.stats().inserted, 1);
+        drop(sink);
+        flusher.join().unwrap();
+    }
+}
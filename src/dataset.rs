@@ -0,0 +1,203 @@
+//! Decision Sampling for Offline Training.
+//!
+//! A [`DecisionSampler`] installed with `CapabilityGate::with_decision_sampler`
+//! captures a fraction of the gate's decisions as [`DecisionSample`]s and hands
+//! them to a [`DatasetWriter`], so anomaly models can be trained on real
+//! traffic without instrumenting call sites.
+//!
+//! Samples are anonymized before they leave the gate. The principal and every
+//! string argument are replaced by a salted HMAC tag, so equal values still
+//! share a tag and repetition patterns survive. The salt is required and must
+//! not be empty: without a secret, common values could be recovered by
+//! tagging guesses. Numbers, booleans and the
+//! shape of the arguments are kept. Arguments under a redacted key are dropped.
+//! Non-JSON arguments are recorded as `null`.
+//!
+//! Sampling is deterministic per capability, like audit sampling: with rate
+//! `r`, one in every `1/r` decisions for a capability is kept.
+
+use crate::audit::Sampler;
+use crate::decision::DecisionRecord;
+use crate::digest::{hmac_sha256, to_hex};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionSample {
+    pub timestamp_ms: u64,
+    pub capability: String,
+    /// Tag of the principal, if there was one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    pub args: Value,
+    pub decision: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+}
+
+pub trait DatasetWriter: Send + Sync {
+    fn write(&self, sample: &DecisionSample);
+}
+
+/// Keeps samples in memory.
+#[derive(Default)]
+pub struct MemoryDataset {
+    samples: Mutex<Vec<DecisionSample>>,
+}
+
+impl MemoryDataset {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn samples(&self) -> Vec<DecisionSample> {
+        self.samples.lock().unwrap().clone()
+    }
+}
+
+impl DatasetWriter for MemoryDataset {
+    fn write(&self, sample: &DecisionSample) {
+        self.samples.lock().unwrap().push(sample.clone());
+    }
+}
+
+/// Writes one JSON sample per line. Write errors drop the sample.
+pub struct JsonlDataset<W> {
+    out: Mutex<W>,
+}
+
+impl<W: Write + Send> JsonlDataset<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Mutex::new(out),
+        }
+    }
+}
+
+impl<W: Write + Send> DatasetWriter for JsonlDataset<W> {
+    fn write(&self, sample: &DecisionSample) {
+        if let Ok(line) = serde_json::to_string(sample) {
+            let _ = writeln!(self.out.lock().unwrap(), "{}", line);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("decision sampler salt must not be empty")]
+pub struct EmptySalt;
+
+pub struct DecisionSampler {
+    writer: Arc<dyn DatasetWriter>,
+    rate: f64,
+    salt: Vec<u8>,
+    redacted: BTreeSet<String>,
+    sampler: Sampler,
+}
+
+impl DecisionSampler {
+    /// Keeps `rate` (0.0 to 1.0) of decisions, tagging values with an HMAC
+    /// keyed by the secret `salt`.
+    pub fn new(
+        writer: Arc<dyn DatasetWriter>,
+        rate: f64,
+        salt: impl AsRef<[u8]>,
+    ) -> Result<Self, EmptySalt> {
+        let salt = salt.as_ref();
+        if salt.is_empty() {
+            return Err(EmptySalt);
+        }
+        Ok(Self {
+            writer,
+            rate,
+            salt: salt.to_vec(),
+            redacted: BTreeSet::new(),
+            sampler: Sampler::new(),
+        })
+    }
+
+    /// Drops the argument `key`, at any depth, from samples.
+    pub fn with_redacted_key(mut self, key: impl Into<String>) -> Self {
+        self.redacted.insert(key.into());
+        self
+    }
+
+    /// The tag that stands in for `value`.
+    pub fn tag(&self, value: &str) -> String {
+        let mac = hmac_sha256(&self.salt, value.as_bytes());
+        format!("h:{}", &to_hex(&mac)[..16])
+    }
+
+    pub fn redact(&self, args: &Value) -> Value {
+        match args {
+            Value::String(s) => Value::String(self.tag(s)),
+            Value::Array(items) => items.iter().map(|v| self.redact(v)).collect(),
+            Value::Object(map) => map
+                .iter()
+                .filter(|(key, _)| !self.redacted.contains(key.as_str()))
+                .map(|(key, value)| (key.clone(), self.redact(value)))
+                .collect(),
+            other => other.clone(),
+        }
+    }
+
+    /// Writes an anonymized sample of `record` if it is selected.
+    pub fn observe(&self, record: &DecisionRecord, args: Option<&Value>) {
+        if !self.sampler.keep(&record.capability, self.rate) {
+            return;
+        }
+        self.writer.write(&DecisionSample {
+            timestamp_ms: record.timestamp_ms,
+            capability: record.capability.clone(),
+            principal: record.principal.as_deref().map(|p| self.tag(p)),
+            args: args.map(|args| self.redact(args)).unwrap_or(Value::Null),
+            decision: record.decision.code().to_string(),
+            rule: record.rule.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_samples_are_redacted_and_rate_limited() {
+        let dataset = Arc::new(MemoryDataset::new());
+        assert_eq!(
+            DecisionSampler::new(dataset.clone(), 0.5, "").err(),
+            Some(EmptySalt)
+        );
+        let sampler = DecisionSampler::new(dataset.clone(), 0.5, "s3cret")
+            .unwrap()
+            .with_redacted_key("token");
+        let mut gate = CapabilityGate::new().with_decision_sampler(sampler);
+        gate.register_capability(Capability::new("http.get", "Fetch URLs"));
+        gate.add_policy(Policy::new("p", "1").with_rule(Rule::allow("http.get")));
+
+        let ctx = RequestContext::new().with_principal("alice");
+        let args = json!({ "url": "https://example.com", "retries": 3, "token": "abc" });
+        for _ in 0..4 {
+            gate.authorize_record("http.get", &args, &ctx);
+        }
+
+        let samples = dataset.samples();
+        assert_eq!(samples.len(), 2);
+        let sample = &samples[0];
+        assert_eq!(sample.decision, "AUTHORIZED");
+        assert_eq!(sample.rule.as_deref(), Some("p#0"));
+        assert_ne!(sample.principal.as_deref(), Some("alice"));
+        assert_eq!(sample.principal, samples[1].principal);
+        assert_eq!(sample.args["retries"], 3);
+        assert!(sample.args["url"].as_str().unwrap().starts_with("h:"));
+        assert!(sample.args.get("token").is_none());
+    }
+}
@@ -0,0 +1,224 @@
+//! Audit Event De-bouncing.
+//!
+//! Agents stuck in retry loops can emit thousands of identical decisions a
+//! second. [`DebouncingSink`] sits in front of another [`AuditSink`] and
+//! coalesces events with the same principal, tool, decision, rule and
+//! arguments fingerprint (see [`crate::fingerprint`]) that arrive within a
+//! window into one event carrying a `count` and, in its details, the
+//! `last_timestamp_ms` of the burst. Events for different rules or different
+//! arguments are never merged.
+//!
+//! Like [`crate::filesink`], the sink reads time from event timestamps. A burst
+//! is forwarded once an event arrives after its window closed, on
+//! [`DebouncingSink::flush_expired`], or on [`DebouncingSink::flush`], which
+//! also runs on drop. [`DebouncingSink::flush_in_background`] forwards closed
+//! bursts on an interval, so a burst is not held until the next event.
+
+use crate::audit::{now_ms, AuditEvent, AuditSink};
+use crate::fingerprint::FINGERPRINT_DETAIL;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+type Key = (
+    Option<String>,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+);
+
+fn key(event: &AuditEvent) -> Key {
+    (
+        event.principal.clone(),
+        event.tool.clone(),
+        event.decision.clone(),
+        event.rule.clone(),
+        event.details.get(FINGERPRINT_DETAIL).cloned(),
+    )
+}
+
+struct Burst {
+    event: AuditEvent,
+    count: u64,
+    last_ms: u64,
+}
+
+impl Burst {
+    fn into_event(self) -> AuditEvent {
+        let mut event = self.event;
+        if self.count > 1 {
+            event.count = Some(self.count);
+            event
+                .details
+                .insert("last_timestamp_ms".to_string(), self.last_ms.to_string());
+        }
+        event
+    }
+}
+
+pub struct DebouncingSink {
+    inner: Arc<dyn AuditSink>,
+    window_ms: u64,
+    bursts: Mutex<HashMap<Key, Burst>>,
+}
+
+impl DebouncingSink {
+    pub fn new(inner: Arc<dyn AuditSink>, window: Duration) -> Self {
+        Self {
+            inner,
+            window_ms: window.as_millis() as u64,
+            bursts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Forwards every pending burst, oldest first.
+    pub fn flush(&self) {
+        let drained: Vec<Burst> = self
+            .bursts
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, b)| b)
+            .collect();
+        self.forward(drained);
+    }
+
+    /// Forwards the bursts whose window closed by `now_ms`.
+    pub fn flush_expired(&self, now_ms: u64) {
+        let expired = self.take_expired(&mut self.bursts.lock().unwrap(), now_ms);
+        self.forward(expired);
+    }
+
+    /// Calls [`DebouncingSink::flush_expired`] with the current time every
+    /// `interval`, until the sink is dropped.
+    pub fn flush_in_background(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let sink: Weak<Self> = Arc::downgrade(self);
+        std::thread::spawn(move || loop {
+            std::thread::sleep(interval);
+            match sink.upgrade() {
+                Some(sink) => sink.flush_expired(now_ms()),
+                None => return,
+            }
+        })
+    }
+
+    fn take_expired(&self, bursts: &mut HashMap<Key, Burst>, now_ms: u64) -> Vec<Burst> {
+        let closed: Vec<Key> = bursts
+            .iter()
+            .filter(|(_, b)| now_ms.saturating_sub(b.event.timestamp_ms) >= self.window_ms)
+            .map(|(k, _)| k.clone())
+            .collect();
+        closed.iter().filter_map(|k| bursts.remove(k)).collect()
+    }
+
+    pub fn pending(&self) -> usize {
+        self.bursts.lock().unwrap().len()
+    }
+
+    fn forward(&self, mut bursts: Vec<Burst>) {
+        bursts.sort_by_key(|b| b.event.timestamp_ms);
+        for burst in bursts {
+            self.inner.record(&burst.into_event());
+        }
+    }
+}
+
+impl AuditSink for DebouncingSink {
+    fn record(&self, event: &AuditEvent) {
+        let now = event.timestamp_ms;
+        let key = key(event);
+        let mut bursts = self.bursts.lock().unwrap();
+        let expired = self.take_expired(&mut bursts, now);
+
+        match bursts.get_mut(&key) {
+            Some(burst) => {
+                burst.count += 1;
+                burst.last_ms = burst.last_ms.max(now);
+            }
+            None => {
+                let burst = Burst {
+                    event: event.clone(),
+                    count: 1,
+                    last_ms: now,
+                };
+                bursts.insert(key, burst);
+            }
+        }
+        drop(bursts);
+        self.forward(expired);
+    }
+}
+
+impl Drop for DebouncingSink {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+
+    fn event(timestamp_ms: u64, tool: &str) -> AuditEvent {
+        AuditEvent {
+            timestamp_ms,
+            ..AuditEvent::new(tool, "DENIED_POLICY_VIOLATION")
+        }
+    }
+
+    #[test]
+    fn test_retry_loop_coalesced() {
+        let log = Arc::new(AuditLog::new());
+        let sink = DebouncingSink::new(log.clone(), Duration::from_secs(1));
+        for ms in 0..1000 {
+            sink.record(&event(ms, "shell"));
+        }
+        sink.record(&event(500, "fs.read"));
+        assert!(log.records().is_empty());
+
+        sink.record(&event(1_000, "shell"));
+        let records = log.records();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].event.count, Some(1000));
+        assert_eq!(records[0].event.details["last_timestamp_ms"], "999");
+
+        sink.flush();
+        let records = log.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].event.tool, "fs.read");
+        assert_eq!(records[1].event.count, None);
+        assert_eq!(sink.pending(), 0);
+    }
+
+    #[test]
+    fn test_bursts_split_by_rule_and_fingerprint_and_close_on_time() {
+        let log = Arc::new(AuditLog::new());
+        let sink = DebouncingSink::new(log.clone(), Duration::from_secs(1));
+        for (rule, fingerprint) in [("p#0", "a"), ("p#0", "a"), ("p#1", "a"), ("p#0", "b")] {
+            let mut event = event(0, "shell");
+            event.rule = Some(rule.into());
+            event
+                .details
+                .insert(FINGERPRINT_DETAIL.into(), fingerprint.into());
+            sink.record(&event);
+        }
+        assert_eq!(sink.pending(), 3);
+
+        sink.flush_expired(999);
+        assert!(log.records().is_empty());
+        sink.flush_expired(1_000);
+        let records = log.records();
+        assert_eq!(records.len(), 3);
+        assert_eq!(
+            records
+                .iter()
+                .filter_map(|r| r.event.count)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert_eq!(sink.pending(), 0);
+    }
+}
@@ -0,0 +1,379 @@
+//! Evaluation Step Debugger.
+//!
+//! [`DebugEvaluator`] replays a decision as the sequence of [`Step`]s the
+//! engine took: each policy considered, each candidate rule, every condition
+//! and constraint result, and the final decision. Steps come from the same
+//! scan that answers [`PolicyEngine::find_rule`], so they follow its layer
+//! order, `extends` chains and short-circuiting exactly.
+//!
+//! A [`DebugSession`] is an iterator over those steps; with [`Breakpoint`]s
+//! set, [`DebugSession::resume`] skips ahead to the next step that hits one,
+//! which is how to find the rule that denies something in a large bundle.
+
+use crate::args::ArgView;
+use crate::capability::CapabilityCategory;
+use crate::clock::TimeCheck;
+use crate::condition::ParamViolation;
+use crate::context::RequestContext;
+use crate::layer::Layer;
+use crate::pattern::Glob;
+use crate::policy::{Condition, Effect, PolicyEngine, Rule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Principal,
+    Grace,
+    Resource,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// `layer` is `None` for the baseline policy.
+    Policy {
+        name: String,
+        layer: Option<Layer>,
+    },
+    Rule {
+        rule: String,
+        effect: Effect,
+    },
+    Skipped {
+        rule: String,
+        reason: SkipReason,
+    },
+    Condition {
+        rule: String,
+        condition: Condition,
+        holds: bool,
+    },
+    Constraint {
+        rule: String,
+        param: String,
+        violation: Option<ParamViolation>,
+    },
+    Matched {
+        rule: String,
+        effect: Effect,
+    },
+    /// No rule matched; `capability` tells whether the capability's own default
+    /// applied rather than the engine default.
+    Defaulted {
+        effect: Effect,
+        capability: bool,
+    },
+    /// `rule` is `None` when no rule matched and the default effect applied.
+    Decided {
+        effect: Effect,
+        rule: Option<String>,
+    },
+}
+
+impl Step {
+    /// The `policy#index` id of the rule this step concerns, if any.
+    pub fn rule(&self) -> Option<&str> {
+        match self {
+            Step::Rule { rule, .. }
+            | Step::Skipped { rule, .. }
+            | Step::Condition { rule, .. }
+            | Step::Constraint { rule, .. }
+            | Step::Matched { rule, .. } => Some(rule),
+            Step::Decided { rule, .. } => rule.as_deref(),
+            Step::Policy { .. } | Step::Defaulted { .. } => None,
+        }
+    }
+
+    fn failed(&self) -> bool {
+        match self {
+            Step::Condition { holds, .. } => !holds,
+            Step::Constraint { violation, .. } => violation.is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Policy { name, layer } => match layer {
+                Some(layer) => write!(f, "policy {} ({:?} layer)", name, layer),
+                None => write!(f, "policy {} (baseline)", name),
+            },
+            Step::Rule { rule, effect } => write!(f, "  rule {} {:?}", rule, effect),
+            Step::Skipped { rule, reason } => {
+                let reason = match reason {
+                    SkipReason::Principal => "principal does not match",
+                    SkipReason::Grace => "deny is in its grace period",
+                    SkipReason::Resource => "resource does not apply",
+                };
+                write!(f, "    {} skipped: {}", rule, reason)
+            }
+            Step::Condition {
+                condition, holds, ..
+            } => write!(
+                f,
+                "    `{} {} {}` {}",
+                condition.key,
+                condition.operator,
+                condition.value,
+                if *holds { "holds" } else { "fails" }
+            ),
+            Step::Constraint {
+                param, violation, ..
+            } => match violation {
+                Some(violation) => write!(f, "    constraint failed: {}", violation),
+                None => write!(f, "    constraint on `{}` holds", param),
+            },
+            Step::Matched { rule, effect } => write!(f, "  {} matched: {:?}", rule, effect),
+            Step::Defaulted { effect, capability } => match capability {
+                true => write!(f, "no rule matched: capability default {:?}", effect),
+                false => write!(f, "no rule matched: engine default {:?}", effect),
+            },
+            Step::Decided { effect, rule } => match rule {
+                Some(rule) => write!(f, "decided {:?} by {}", effect, rule),
+                None => write!(f, "decided {:?} by default", effect),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops when the named policy is considered.
+    Policy(String),
+    /// Stops at every step for the rule with this `policy#index` id.
+    Rule(String),
+    /// Stops at any condition or constraint that does not hold.
+    FailedCondition,
+    /// Stops when a rule matches.
+    Match,
+}
+
+impl Breakpoint {
+    pub fn hits(&self, step: &Step) -> bool {
+        match self {
+            Breakpoint::Policy(name) => {
+                matches!(step, Step::Policy { name: n, .. } if n == name)
+            }
+            Breakpoint::Rule(id) => {
+                !matches!(step, Step::Decided { .. }) && step.rule() == Some(id.as_str())
+            }
+            Breakpoint::FailedCondition => step.failed(),
+            Breakpoint::Match => matches!(step, Step::Matched { .. }),
+        }
+    }
+}
+
+pub struct DebugEvaluator<'a> {
+    engine: &'a PolicyEngine,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl<'a> DebugEvaluator<'a> {
+    pub fn new(engine: &'a PolicyEngine) -> Self {
+        Self {
+            engine,
+            breakpoints: Vec::new(),
+        }
+    }
+
+    pub fn with_breakpoint(mut self, breakpoint: Breakpoint) -> Self {
+        self.breakpoints.push(breakpoint);
+        self
+    }
+
+    /// Evaluates `resource` and returns the steps taken, ending with
+    /// [`Step::Decided`]. Group resolver failures are resolved fail-closed, as
+    /// in [`PolicyEngine::find_rule`].
+    pub fn steps(&self, ctx: &RequestContext, resource: &str, args: &dyn ArgView) -> DebugSession {
+        let mut steps = Vec::new();
+        let found = self.engine.debug_scan(ctx, resource, args, &mut steps);
+        let decided = match found {
+            Some((effect, rule)) => Step::Decided {
+                effect,
+                rule: Some(rule),
+            },
+            None => {
+                let effect = self.engine.default_effect_for(resource);
+                steps.push(Step::Defaulted {
+                    effect,
+                    capability: self.engine.capability_default(resource).is_some(),
+                });
+                Step::Decided { effect, rule: None }
+            }
+        };
+        steps.push(decided);
+        DebugSession {
+            steps: steps.into_iter(),
+            breakpoints: self.breakpoints.clone(),
+        }
+    }
+}
+
+pub struct DebugSession {
+    steps: std::vec::IntoIter<Step>,
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl DebugSession {
+    /// Advances to the next step that hits a breakpoint, or to the decision
+    /// if none does; `None` once the session is exhausted.
+    pub fn resume(&mut self) -> Option<Step> {
+        for step in self.steps.by_ref() {
+            let stop = matches!(step, Step::Decided { .. })
+                || self.breakpoints.iter().any(|b| b.hits(&step));
+            if stop {
+                return Some(step);
+            }
+        }
+        None
+    }
+}
+
+impl Iterator for DebugSession {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        self.steps.next()
+    }
+}
+
+/// Records the checks `Rule::args_match_compiled` makes for `rule`, stopping
+/// where it would, and returns whether the rule matched.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn trace_rule(
+    steps: &mut Vec<Step>,
+    id: String,
+    rule: &Rule,
+    principal_matches: bool,
+    resource: &str,
+    category: CapabilityCategory,
+    args: &dyn ArgView,
+    time: &TimeCheck,
+    patterns: &[Option<Arc<Glob>>],
+) -> bool {
+    steps.push(Step::Rule {
+        rule: id.clone(),
+        effect: rule.effect,
+    });
+    let skipped = if !principal_matches {
+        Some(SkipReason::Principal)
+    } else if rule.in_grace(time.now_ms) {
+        Some(SkipReason::Grace)
+    } else if !rule.applies_in(resource, category) {
+        Some(SkipReason::Resource)
+    } else {
+        None
+    };
+    if let Some(reason) = skipped {
+        steps.push(Step::Skipped { rule: id, reason });
+        return false;
+    }
+    for (i, condition) in rule.conditions.iter().enumerate() {
+        let holds = rule.condition_holds(i, args, time, patterns);
+        steps.push(Step::Condition {
+            rule: id.clone(),
+            condition: condition.clone(),
+            holds,
+        });
+        if !holds {
+            return false;
+        }
+    }
+    for constraint in &rule.param_constraints {
+        let violation = constraint.check(args).err();
+        let failed = violation.is_some();
+        steps.push(Step::Constraint {
+            rule: id.clone(),
+            param: constraint.param.clone(),
+            violation,
+        });
+        if failed {
+            return false;
+        }
+    }
+    steps.push(Step::Matched {
+        rule: id,
+        effect: rule.effect,
+    });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Policy;
+    use serde_json::json;
+
+    fn engine() -> PolicyEngine {
+        let policy: Policy = serde_json::from_value(json!({
+            "name": "p", "version": "1", "rules": [
+                {"effect": "Deny", "principal": "*", "resource": "shell", "action": "execute",
+                 "conditions": [{"key": "cmd", "operator": "eq", "value": "rm"}]},
+                {"effect": "Allow", "principal": "alice", "resource": "shell", "action": "execute",
+                 "conditions": []},
+                {"effect": "Allow", "principal": "*", "resource": "shell", "action": "execute",
+                 "conditions": [{"key": "cmd", "operator": "eq", "value": "ls"}]}
+            ]
+        }))
+        .unwrap();
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(policy);
+        engine
+    }
+
+    #[test]
+    fn test_steps_follow_the_scan() {
+        let engine = engine();
+        let steps: Vec<String> = DebugEvaluator::new(&engine)
+            .steps(&RequestContext::new(), "shell", &json!({"cmd": "ls"}))
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                "policy p (Org layer)",
+                "  rule p#0 Deny",
+                "    `cmd eq \"rm\"` fails",
+                "  rule p#1 Allow",
+                "    p#1 skipped: principal does not match",
+                "  rule p#2 Allow",
+                "    `cmd eq \"ls\"` holds",
+                "  p#2 matched: Allow",
+                "decided Allow by p#2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resume_stops_at_breakpoints() {
+        let engine = engine();
+        let mut session = DebugEvaluator::new(&engine)
+            .with_breakpoint(Breakpoint::FailedCondition)
+            .with_breakpoint(Breakpoint::Rule("p#2".into()))
+            .steps(&RequestContext::new(), "shell", &json!({"cmd": "cat"}));
+
+        let first = session.resume().unwrap();
+        assert!(matches!(first, Step::Condition { holds: false, .. }));
+        assert_eq!(first.rule(), Some("p#0"));
+        assert_eq!(
+            session.resume().unwrap(),
+            Step::Rule {
+                rule: "p#2".into(),
+                effect: Effect::Allow
+            }
+        );
+        assert!(session.resume().unwrap().failed());
+        assert_eq!(
+            session.resume(),
+            Some(Step::Decided {
+                effect: Effect::Deny,
+                rule: None
+            })
+        );
+        assert_eq!(session.resume(), None);
+    }
+}
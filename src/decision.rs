@@ -0,0 +1,336 @@
+//! Authorization Decisions.
+//!
+//! [`Decision`] is `#[non_exhaustive]` so new denial reasons can be added without
+//! breaking downstream matches; callers should branch on [`Decision::category`]
+//! or [`Decision::is_allowed`] and treat unknown denials as denials.
+//! [`DecisionRecord`] carries the decision along with how it was reached.
+//!
+//! Decisions serialize as their stable [`Decision::code`] strings, so they can be
+//! shipped over IPC (JSON or the [`crate::wire`] formats) and parsed back.
+//!
+//! [`Authorization`] and [`Denial`] are the `Result` form returned by
+//! `CapabilityGate::try_authorize`; `Denial` is a `std::error::Error`.
+
+use crate::condition::{ParamConstraint, ParamViolation};
+use crate::outcome::Ticket;
+use crate::policy::Condition;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+/// Record detail naming a matched `AllowWithWarning` or `Audit` effect.
+pub const EFFECT_DETAIL: &str = "effect";
+pub const WARNING_EFFECT: &str = "allow_with_warning";
+pub const AUDIT_EFFECT: &str = "audit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[non_exhaustive]
+pub enum Decision {
+    Authorized,
+    DeniedCapabilityNotFound,
+    DeniedCapabilityDisabled,
+    DeniedPolicyViolation,
+    DeniedEvaluationTimeout,
+    DeniedResolverUnavailable,
+    DeniedPreflightFailed,
+    DeniedLeaseExpired,
+    DeniedInvalidArguments,
+    DeniedConsentRefused,
+    DeniedPlanDeviation,
+    DeniedQuarantined,
+}
+
+/// Coarse grouping of decisions that stays stable as variants are added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum DecisionCategory {
+    Allowed,
+    /// The capability is unknown or switched off in the registry.
+    Registry,
+    /// A policy rule, or the default effect, denied the request.
+    Policy,
+    /// Evaluation could not complete; retrying later may succeed.
+    Unavailable,
+    /// Policy allowed the request but a capability precondition, the user's
+    /// consent or an approved plan did not hold.
+    Precondition,
+    /// A lease was not renewed in time; the operation must be re-authorized.
+    Expired,
+    /// The request itself was malformed or exceeded the gate's argument limits.
+    Invalid,
+    /// The principal is quarantined after repeated denials.
+    Quarantined,
+}
+
+impl Decision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Decision::Authorized)
+    }
+
+    /// Stable machine-readable code, e.g. `DENIED_POLICY_VIOLATION`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Decision::Authorized => "AUTHORIZED",
+            Decision::DeniedCapabilityNotFound => "DENIED_CAPABILITY_NOT_FOUND",
+            Decision::DeniedCapabilityDisabled => "DENIED_CAPABILITY_DISABLED",
+            Decision::DeniedPolicyViolation => "DENIED_POLICY_VIOLATION",
+            Decision::DeniedEvaluationTimeout => "DENIED_EVALUATION_TIMEOUT",
+            Decision::DeniedResolverUnavailable => "DENIED_RESOLVER_UNAVAILABLE",
+            Decision::DeniedPreflightFailed => "DENIED_PREFLIGHT_FAILED",
+            Decision::DeniedLeaseExpired => "DENIED_LEASE_EXPIRED",
+            Decision::DeniedInvalidArguments => "DENIED_INVALID_ARGUMENTS",
+            Decision::DeniedConsentRefused => "DENIED_CONSENT_REFUSED",
+            Decision::DeniedPlanDeviation => "DENIED_PLAN_DEVIATION",
+            Decision::DeniedQuarantined => "DENIED_QUARANTINED",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        [
+            Decision::Authorized,
+            Decision::DeniedCapabilityNotFound,
+            Decision::DeniedCapabilityDisabled,
+            Decision::DeniedPolicyViolation,
+            Decision::DeniedEvaluationTimeout,
+            Decision::DeniedResolverUnavailable,
+            Decision::DeniedPreflightFailed,
+            Decision::DeniedLeaseExpired,
+            Decision::DeniedInvalidArguments,
+            Decision::DeniedConsentRefused,
+            Decision::DeniedPlanDeviation,
+            Decision::DeniedQuarantined,
+        ]
+        .into_iter()
+        .find(|d| d.code() == code)
+    }
+
+    /// Same as [`Decision::code`].
+    pub fn as_str(&self) -> &'static str {
+        self.code()
+    }
+
+    pub fn category(&self) -> DecisionCategory {
+        match self {
+            Decision::Authorized => DecisionCategory::Allowed,
+            Decision::DeniedCapabilityNotFound | Decision::DeniedCapabilityDisabled => {
+                DecisionCategory::Registry
+            }
+            Decision::DeniedPolicyViolation => DecisionCategory::Policy,
+            Decision::DeniedEvaluationTimeout | Decision::DeniedResolverUnavailable => {
+                DecisionCategory::Unavailable
+            }
+            Decision::DeniedPreflightFailed
+            | Decision::DeniedConsentRefused
+            | Decision::DeniedPlanDeviation => DecisionCategory::Precondition,
+            Decision::DeniedLeaseExpired => DecisionCategory::Expired,
+            Decision::DeniedInvalidArguments => DecisionCategory::Invalid,
+            Decision::DeniedQuarantined => DecisionCategory::Quarantined,
+        }
+    }
+}
+
+impl std::fmt::Display for Decision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+/// A decision plus the context it was reached in. Fields may be added in minor
+/// releases; free-form extras go in `details`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct DecisionRecord {
+    pub decision: Decision,
+    /// The capability name as requested.
+    pub tool: String,
+    /// The canonical capability name after alias resolution.
+    pub capability: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    /// The matching rule as `policy#index`, if any rule matched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degraded: bool,
+    /// Set when this is the stored decision for an earlier call with the same
+    /// idempotency key; the call was already performed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub replayed: bool,
+    pub timestamp_ms: u64,
+    /// How long after `timestamp_ms` the decision remains valid. Callers holding
+    /// long-running operations should re-authorize once it lapses.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_for_ms: Option<u64>,
+    /// Suggested delay before retrying a repeatedly denied call; see
+    /// [`crate::backoff`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+impl DecisionRecord {
+    pub fn new(decision: Decision, tool: impl Into<String>) -> Self {
+        let tool = tool.into();
+        Self {
+            decision,
+            capability: tool.clone(),
+            tool,
+            principal: None,
+            rule: None,
+            degraded: false,
+            replayed: false,
+            timestamp_ms: crate::audit::now_ms(),
+            valid_for_ms: None,
+            retry_after_ms: None,
+            details: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_detail(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.details.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn is_allowed(&self) -> bool {
+        self.decision.is_allowed()
+    }
+
+    /// Whether reports should count this decision as a denial: it was denied,
+    /// or allowed only by an [`Effect::Audit`](crate::policy::Effect::Audit) rule.
+    pub fn counts_as_denied(&self) -> bool {
+        !self.is_allowed()
+            || self.details.get(EFFECT_DETAIL).map(String::as_str) == Some(AUDIT_EFFECT)
+    }
+
+    pub fn valid_for(&self) -> Option<std::time::Duration> {
+        self.valid_for_ms.map(std::time::Duration::from_millis)
+    }
+
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        self.retry_after_ms.map(std::time::Duration::from_millis)
+    }
+
+    /// Whether the decision has lapsed at `now_ms`. Decisions without a TTL
+    /// never expire.
+    pub fn is_expired_at(&self, now_ms: u64) -> bool {
+        self.valid_for_ms
+            .is_some_and(|ttl| now_ms >= self.timestamp_ms.saturating_add(ttl))
+    }
+}
+
+/// Something the caller must honor when acting on an [`Authorization`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Obligation {
+    /// Decided in degraded mode; re-check once the resolver recovers.
+    Recheck,
+    /// The capability was requested under a deprecated name.
+    UseCapability { replacement: String },
+}
+
+/// A granted request plus the constraints the matched rule placed on it, which
+/// executors can re-enforce against the arguments they actually run with.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Authorization {
+    pub record: DecisionRecord,
+    pub conditions: Vec<Condition>,
+    pub constraints: Vec<ParamConstraint>,
+    pub obligations: Vec<Obligation>,
+    /// Identifies the call for `CapabilityGate::report_outcome`.
+    pub ticket: Option<Ticket>,
+}
+
+impl Authorization {
+    pub fn new(record: DecisionRecord) -> Self {
+        Self {
+            record,
+            conditions: Vec::new(),
+            constraints: Vec::new(),
+            obligations: Vec::new(),
+            ticket: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error(
+    "`{}` denied: {}{}{}",
+    record.tool,
+    record.decision,
+    violations_suffix(violations),
+    summary.as_ref().map(|s| format!("; {}", s)).unwrap_or_default()
+)]
+#[non_exhaustive]
+pub struct Denial {
+    /// Boxed to keep `Result<_, Denial>` small on the success path.
+    pub record: Box<DecisionRecord>,
+    /// Parameter constraints that kept an `Allow` rule from matching.
+    pub violations: Vec<ParamViolation>,
+    /// The deciding deny rule in plain English; see [`crate::summary`].
+    pub summary: Option<String>,
+}
+
+impl Denial {
+    pub fn new(record: DecisionRecord) -> Self {
+        Self {
+            record: Box::new(record),
+            violations: Vec::new(),
+            summary: None,
+        }
+    }
+
+    pub fn decision(&self) -> Decision {
+        self.record.decision
+    }
+}
+
+fn violations_suffix(violations: &[ParamViolation]) -> String {
+    violations.iter().map(|v| format!("; {}", v)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes_and_categories() {
+        assert_eq!(Decision::Authorized.code(), "AUTHORIZED");
+        assert_eq!(
+            Decision::DeniedCapabilityDisabled.category(),
+            DecisionCategory::Registry
+        );
+        assert_eq!(
+            Decision::DeniedResolverUnavailable.category(),
+            DecisionCategory::Unavailable
+        );
+        assert_eq!(
+            Decision::DeniedPolicyViolation.to_string(),
+            Decision::DeniedPolicyViolation.code()
+        );
+    }
+
+    #[test]
+    fn test_serde_uses_stable_codes() {
+        let decision = Decision::DeniedEvaluationTimeout;
+        let json = serde_json::to_string(&decision).unwrap();
+        assert_eq!(json, format!("\"{}\"", decision.code()));
+        assert_eq!(serde_json::from_str::<Decision>(&json).unwrap(), decision);
+        assert_eq!(
+            Decision::from_code("AUTHORIZED"),
+            Some(Decision::Authorized)
+        );
+
+        let record = DecisionRecord::new(Decision::DeniedPolicyViolation, "shell")
+            .with_detail("reason", "default deny");
+        let value = serde_json::to_value(&record).unwrap();
+        assert_eq!(value["decision"], "DENIED_POLICY_VIOLATION");
+        assert!(value.get("principal").is_none());
+        let back: DecisionRecord = serde_json::from_value(value).unwrap();
+        assert_eq!(back, record);
+    }
+}
@@ -0,0 +1,179 @@
+//! Category Defaults.
+//!
+//! Category-level defaults synthesize a baseline policy, so small deployments get
+//! sane behavior without writing policies. The baseline is installed with
+//! [`PolicyEngine::set_baseline`] and is consulted only after every regular
+//! policy, so any explicit rule overrides it.
+
+use crate::capability::CapabilityCategory;
+use crate::policy::{Condition, Effect, Policy, PolicyEngine, Rule};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+pub const BASELINE_POLICY: &str = "category-defaults";
+
+/// Read-only filesystem capabilities allowed inside the workspace.
+const WORKSPACE_READ: [&str; 3] = ["fs.read", "fs.list", "fs.stat"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CategoryDefaults {
+    defaults: BTreeMap<CapabilityCategory, Effect>,
+    workspace: Option<String>,
+}
+
+impl CategoryDefaults {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Network, Process, Credential and Communication are denied unless explicitly
+    /// allowed; filesystem reads are allowed under `workspace`.
+    pub fn recommended(workspace: impl Into<String>) -> Self {
+        Self::new()
+            .with_default(CapabilityCategory::Network, Effect::Deny)
+            .with_default(CapabilityCategory::Process, Effect::Deny)
+            .with_default(CapabilityCategory::Credential, Effect::Deny)
+            .with_default(CapabilityCategory::Communication, Effect::Deny)
+            .with_default(CapabilityCategory::Filesystem, Effect::Deny)
+            .with_workspace(workspace)
+    }
+
+    pub fn with_default(mut self, category: CapabilityCategory, effect: Effect) -> Self {
+        self.defaults.insert(category, effect);
+        self
+    }
+
+    /// Allows read-only filesystem capabilities for `path` and paths below it.
+    /// `/work` covers `/work/src` but not `/workspace-secrets`, and paths with a
+    /// `..` segment are denied rather than resolved.
+    pub fn with_workspace(mut self, path: impl Into<String>) -> Self {
+        self.workspace = Some(path.into());
+        self
+    }
+
+    pub fn default_for(&self, category: CapabilityCategory) -> Option<Effect> {
+        self.defaults.get(&category).copied()
+    }
+
+    /// The synthesized baseline: workspace reads first, then one rule per category.
+    pub fn to_policy(&self) -> Policy {
+        let mut policy = Policy::new(BASELINE_POLICY, "1.0");
+        if let Some(workspace) = &self.workspace {
+            let root = workspace.trim_end_matches('/');
+            let below = Condition::new("path", "starts_with", json!(format!("{}/", root)));
+            for capability in WORKSPACE_READ {
+                policy = policy
+                    .with_rule(Rule::deny(capability).with_conditions(vec![
+                        below.clone(),
+                        Condition::new("path", "contains", json!("..")),
+                    ]))
+                    .with_rule(Rule::allow(capability).with_conditions(vec![Condition::new(
+                        "path",
+                        "eq",
+                        json!(root),
+                    )]))
+                    .with_rule(Rule::allow(capability).with_conditions(vec![below.clone()]));
+            }
+        }
+        for (category, effect) in &self.defaults {
+            let rule = Rule {
+                effect: *effect,
+                ..Rule::deny(category.selector())
+            };
+            policy = policy.with_rule(rule);
+        }
+        policy
+    }
+}
+
+impl PolicyEngine {
+    pub fn with_category_defaults(mut self, defaults: &CategoryDefaults) -> Self {
+        self.set_baseline(defaults.to_policy());
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommended_defaults() {
+        let engine = PolicyEngine::new()
+            .with_default_effect(Effect::Allow)
+            .with_category_defaults(&CategoryDefaults::recommended("/work"));
+
+        let inside = json!({ "path": "/work/src/main.rs" });
+        let outside = json!({ "path": "/etc/passwd" });
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &inside),
+            Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &outside),
+            Effect::Deny
+        );
+        for path in ["/work/../etc/shadow", "/workspace-secrets/x"] {
+            let args = json!({ "path": path });
+            assert_eq!(engine.evaluate("fs.read", "execute", &args), Effect::Deny);
+        }
+        let root = json!({ "path": "/work" });
+        assert_eq!(engine.evaluate("fs.list", "execute", &root), Effect::Allow);
+        assert_eq!(
+            engine.evaluate("fs.write", "execute", &inside),
+            Effect::Deny
+        );
+        assert_eq!(
+            engine.evaluate("http.get", "execute", &json!({})),
+            Effect::Deny
+        );
+        assert_eq!(
+            engine.evaluate("memory.store", "execute", &json!({})),
+            Effect::Allow
+        );
+    }
+
+    #[test]
+    fn test_explicit_policies_override_baseline() {
+        let mut engine =
+            PolicyEngine::new().with_category_defaults(&CategoryDefaults::recommended("/work"));
+        engine.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("http.get")));
+
+        assert_eq!(
+            engine.evaluate("http.get", "execute", &json!({})),
+            Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate("http.post", "execute", &json!({})),
+            Effect::Deny
+        );
+        let names: Vec<_> = engine.policies().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["default", BASELINE_POLICY]);
+    }
+
+    #[test]
+    fn test_capability_defaults_take_precedence_over_baseline() {
+        let mut engine =
+            PolicyEngine::new().with_category_defaults(&CategoryDefaults::recommended("/work"));
+        engine.set_capability_default("fs.write", Some(Effect::Allow));
+        engine.add_policy(Policy::new("default", "1.0").with_rule(
+            Rule::deny("fs.write").with_conditions(vec![Condition::new(
+                "path",
+                "starts_with",
+                json!("/etc"),
+            )]),
+        ));
+
+        let args = json!({ "path": "/tmp/out" });
+        assert_eq!(engine.evaluate("fs.write", "execute", &args), Effect::Allow);
+        let etc = json!({ "path": "/etc/hosts" });
+        assert_eq!(engine.evaluate("fs.write", "execute", &etc), Effect::Deny);
+        assert_eq!(engine.evaluate("fs.delete", "execute", &args), Effect::Deny);
+
+        let steps: Vec<_> = crate::debug::DebugEvaluator::new(&engine)
+            .steps(&Default::default(), "fs.write", &args)
+            .map(|step| step.to_string())
+            .collect();
+        assert!(steps.contains(&"no rule matched: capability default Allow".to_string()));
+    }
+}
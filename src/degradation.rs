@@ -0,0 +1,256 @@
+//! Degraded-mode Behaviour.
+//!
+//! When an external dependency of evaluation (such as a group resolver) is
+//! unavailable, the gate consults the capability's [`DegradationMode`]. The
+//! default is to fail closed; low-risk capabilities may fail open, or be served
+//! the last decision computed for the same request within a staleness bound.
+//!
+//! The [`StaleDecisionCache`] keeps at most `max_entries` decisions, evicting
+//! the least recently stored first, and drops decisions older than its maximum
+//! age. The gate sets that age to the largest staleness bound it serves.
+//!
+//! Entries are keyed by principal, capability and arguments, and by the same
+//! context [`Dimension`]s as the gate's decision cache, so a decision is never
+//! served across tenants. Without a decision cache, every context attribute is
+//! part of the key. The gate clears the store whenever it clears the decision
+//! cache, so decisions from revoked policies never come back during an outage.
+
+use crate::args::ArgView;
+use crate::cache::Dimension;
+use crate::context::RequestContext;
+use crate::gate::Decision;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DegradationMode {
+    #[default]
+    FailClosed,
+    FailOpen,
+    ServeCached {
+        max_staleness: Duration,
+    },
+}
+
+pub const DEFAULT_MAX_STALE_ENTRIES: usize = 10_000;
+
+#[derive(Default)]
+struct Entries {
+    /// Each decision with when it was stored and its store sequence number.
+    decisions: HashMap<String, (Instant, Decision, u64)>,
+    /// Keys in store order; a key stored again is superseded by its later entry.
+    order: VecDeque<(String, u64)>,
+    stored: u64,
+}
+
+impl Entries {
+    fn is_current(&self, key: &str, seq: u64) -> bool {
+        self.decisions.get(key).is_some_and(|(_, _, s)| *s == seq)
+    }
+}
+
+/// Last healthy decision per request key, kept for `ServeCached` capabilities.
+pub struct StaleDecisionCache {
+    /// `None` keys entries by every context attribute.
+    dimensions: Option<Vec<Dimension>>,
+    max_entries: usize,
+    max_age: Option<Duration>,
+    entries: Mutex<Entries>,
+}
+
+impl Default for StaleDecisionCache {
+    fn default() -> Self {
+        Self {
+            dimensions: None,
+            max_entries: DEFAULT_MAX_STALE_ENTRIES,
+            max_age: None,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+}
+
+impl StaleDecisionCache {
+    /// A cache of up to [`DEFAULT_MAX_STALE_ENTRIES`] decisions of any age.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keys entries by `dimensions` rather than every context attribute.
+    pub fn with_dimensions(mut self, dimensions: Vec<Dimension>) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().decisions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// `None` when the arguments have no stable identity and cannot be cached.
+    pub fn key(&self, ctx: &RequestContext, tool: &str, args: &dyn ArgView) -> Option<String> {
+        let args = args.cache_key()?;
+        let partition: Vec<(&str, Option<&str>)> = match &self.dimensions {
+            Some(dimensions) => dimensions
+                .iter()
+                .map(|d| (d.name(), d.value(ctx)))
+                .collect(),
+            None => ctx
+                .attributes
+                .iter()
+                .map(|(name, value)| (name.as_str(), Some(value.as_str())))
+                .collect(),
+        };
+        serde_json::to_string(&(ctx.principal.as_deref(), partition, tool, args)).ok()
+    }
+
+    pub fn store(&self, key: String, decision: Decision) {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.stored += 1;
+        let seq = entries.stored;
+        entries.decisions.insert(key.clone(), (now, decision, seq));
+        entries.order.push_back((key, seq));
+        while let Some((oldest, seq)) = entries.order.front().cloned() {
+            if !entries.is_current(&oldest, seq) {
+                entries.order.pop_front();
+                continue;
+            }
+            let expired = self
+                .max_age
+                .is_some_and(|age| now.duration_since(entries.decisions[&oldest].0) > age);
+            if !expired && entries.decisions.len() <= self.max_entries {
+                break;
+            }
+            entries.order.pop_front();
+            entries.decisions.remove(&oldest);
+        }
+        // Superseded keys are skipped lazily; compact before they pile up.
+        if entries.order.len() > 2 * entries.decisions.len() + 16 {
+            let Entries {
+                decisions, order, ..
+            } = &mut *entries;
+            order.retain(|(key, seq)| decisions.get(key).is_some_and(|(_, _, s)| s == seq));
+        }
+    }
+
+    pub fn get(&self, key: &str, max_staleness: Duration) -> Option<Decision> {
+        let max_staleness = self
+            .max_age
+            .map_or(max_staleness, |age| age.min(max_staleness));
+        self.entries
+            .lock()
+            .unwrap()
+            .decisions
+            .get(key)
+            .filter(|(at, _, _)| at.elapsed() <= max_staleness)
+            .map(|(_, decision, _)| *decision)
+    }
+
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.decisions.clear();
+        entries.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staleness_bound() {
+        let cache = StaleDecisionCache::new();
+        let alice = RequestContext::new().with_principal("alice");
+        let key = cache
+            .key(&alice, "web.get", &serde_json::json!({}))
+            .unwrap();
+        cache.store(key.clone(), Decision::Authorized);
+
+        assert_eq!(
+            cache.get(&key, Duration::from_secs(60)),
+            Some(Decision::Authorized)
+        );
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(cache.get(&key, Duration::from_millis(1)), None);
+    }
+
+    #[test]
+    fn test_keys_are_partitioned() {
+        let args = serde_json::json!({});
+        let in_tenant = |tenant: &str| {
+            RequestContext::new()
+                .with_principal("a")
+                .with_attribute("tenant", tenant)
+                .with_attribute("request", tenant)
+        };
+        let cache = StaleDecisionCache::new();
+        assert_ne!(
+            cache.key(&in_tenant("t1"), "web.get", &args),
+            cache.key(&in_tenant("t2"), "web.get", &args)
+        );
+        assert_ne!(
+            cache.key(&RequestContext::new(), "web.get", &args),
+            cache.key(&RequestContext::new().with_principal(""), "web.get", &args)
+        );
+
+        let cache = cache.with_dimensions(vec![Dimension::attribute("tenant")]);
+        let mut other_request = in_tenant("t1");
+        other_request
+            .attributes
+            .insert("request".into(), "r2".into());
+        assert_eq!(
+            cache.key(&in_tenant("t1"), "web.get", &args),
+            cache.key(&other_request, "web.get", &args)
+        );
+        assert_ne!(
+            cache.key(&in_tenant("t1"), "web.get", &args),
+            cache.key(&in_tenant("t2"), "web.get", &args)
+        );
+    }
+
+    #[test]
+    fn test_least_recently_stored_entries_are_evicted() {
+        let cache = StaleDecisionCache::new().with_max_entries(2);
+        let minute = Duration::from_secs(60);
+        cache.store("a".into(), Decision::Authorized);
+        cache.store("b".into(), Decision::Authorized);
+        cache.store("a".into(), Decision::DeniedPolicyViolation);
+        cache.store("c".into(), Decision::Authorized);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("b", minute), None);
+        assert_eq!(
+            cache.get("a", minute),
+            Some(Decision::DeniedPolicyViolation)
+        );
+
+        for _ in 0..1_000 {
+            cache.store("c".into(), Decision::Authorized);
+        }
+        assert!(cache.entries.lock().unwrap().order.len() < 32);
+
+        let aged = StaleDecisionCache::new().with_max_age(Duration::from_millis(1));
+        aged.store("a".into(), Decision::Authorized);
+        std::thread::sleep(Duration::from_millis(2));
+        aged.store("b".into(), Decision::Authorized);
+        assert_eq!(aged.len(), 1);
+        assert_eq!(aged.get("a", minute), None);
+    }
+}
@@ -0,0 +1,269 @@
+//! Friendly Policy Parse Errors.
+//!
+//! [`PolicyEngine::load_from_json`] reports failures as a [`ParseDiagnostic`]:
+//! the serde message with its line and column, the name of the policy being
+//! parsed, and, for misspellings, the nearest valid name — `effect` for an
+//! object with `efect` but no `effect`, `Allow` for the variant `Alow`.
+//!
+//! [`nearest`] is also used by the `unknown-operator` lint, since unknown
+//! operators parse fine and only ever fail to match.
+
+use crate::policy::Rule;
+use crate::strict::{CONDITION_FIELDS, CONSTRAINT_FIELDS, POLICY_FIELDS, RULE_FIELDS};
+use crate::template::expand_rule;
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::fmt;
+
+/// The candidate closest to `word` by edit distance, if it is close enough to
+/// be a likely typo.
+pub fn nearest<'a>(word: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (word.chars().count() / 3).clamp(1, 3);
+    candidates
+        .into_iter()
+        .map(|c| (distance(word, c), c))
+        .filter(|&(d, c)| d <= limit && c != word)
+        .min_by_key(|&(d, _)| d)
+        .map(|(_, c)| c)
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous + usize::from(ca != cb);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    pub line: usize,
+    pub column: usize,
+    /// The policy being parsed, when its name was read before the error.
+    pub policy: Option<String>,
+    pub message: String,
+    pub suggestion: Option<String>,
+    /// The key that was probably meant to be `suggestion`.
+    pub misspelled: Option<String>,
+    source: serde_json::Error,
+}
+
+impl fmt::Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(policy) = &self.policy {
+            write!(f, "policy `{}`, ", policy)?;
+        }
+        write!(
+            f,
+            "line {} column {}: {}",
+            self.line, self.column, self.message
+        )?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean `{}`", suggestion)?;
+            if let Some(misspelled) = &self.misspelled {
+                write!(f, " instead of `{}`", misspelled)?;
+            }
+            f.write_str("?")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseDiagnostic {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+fn offset(json: &str, line: usize, column: usize) -> usize {
+    let start: usize = json
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (start + column.saturating_sub(1)).min(json.len())
+}
+
+/// The first backquoted word in `message` after `marker`.
+fn quoted<'a>(message: &'a str, marker: &str) -> Option<&'a str> {
+    let rest = &message[message.find(marker)? + marker.len()..];
+    rest.split('`').next()
+}
+
+/// Every object key in `value`, depth first.
+fn keys(value: &Value, out: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                out.push(key.clone());
+                keys(value, out);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| keys(v, out)),
+        _ => {}
+    }
+}
+
+fn policy_name(json: &str, at: usize) -> Option<String> {
+    let before = &json[..at];
+    let key = before.rfind("\"name\"")?;
+    let value = before[key + 6..]
+        .trim_start()
+        .strip_prefix(':')?
+        .trim_start();
+    let value = value.strip_prefix('"')?;
+    Some(value[..value.find('"')?].to_string())
+}
+
+fn line_column(json: &str, at: usize) -> (usize, usize) {
+    let before = &json[..at];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |n| n + 1) + 1;
+    (line, column)
+}
+
+#[derive(Deserialize)]
+struct RawPolicy<'a> {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default, borrow)]
+    rules: Vec<&'a RawValue>,
+}
+
+/// Rules are deserialized through template expansion, which loses positions,
+/// so a failing rule is re-parsed on its own to find the error within it. A
+/// template rule is reported with the error of the rules it expands into, at
+/// the start of the template.
+fn locate_rule(json: &str) -> Option<(Option<String>, usize, serde_json::Error)> {
+    let policies: Vec<RawPolicy> = serde_json::from_str(json).ok()?;
+    for policy in policies {
+        for raw in policy.rules {
+            let text = raw.get();
+            let Err(error) = serde_json::from_str::<Rule>(text) else {
+                continue;
+            };
+            let start = text.as_ptr() as usize - json.as_ptr() as usize;
+            let expanded = serde_json::from_str::<Value>(text)
+                .ok()
+                .and_then(|v| expand_rule(v.clone()).ok().filter(|rules| rules != &[v]));
+            if let Some(rules) = expanded {
+                match rules.into_iter().find_map(|r| Rule::deserialize(r).err()) {
+                    Some(error) => return Some((policy.name, start, error)),
+                    None => continue,
+                }
+            }
+            let at = start + offset(text, error.line(), error.column());
+            return Some((policy.name, at, error));
+        }
+    }
+    None
+}
+
+pub fn diagnose(json: &str, source: serde_json::Error) -> ParseDiagnostic {
+    let (policy, at, message) = match locate_rule(json) {
+        Some((policy, at, error)) => (policy, at, error.to_string()),
+        None => {
+            let at = offset(json, source.line(), source.column());
+            (policy_name(json, at), at, source.to_string())
+        }
+    };
+    let (line, column) = line_column(json, at);
+    let message = match message.rfind(" at line ") {
+        Some(end) => message[..end].to_string(),
+        None => message,
+    };
+
+    let known = || {
+        POLICY_FIELDS
+            .iter()
+            .chain(RULE_FIELDS)
+            .chain(CONDITION_FIELDS)
+            .chain(CONSTRAINT_FIELDS)
+            .copied()
+    };
+    let mut misspelled = None;
+    let suggestion = if let Some(missing) = quoted(&message, "missing field `") {
+        // The intended field is usually present under a misspelled key.
+        let mut found = Vec::new();
+        if let Ok(value) = serde_json::from_str::<Value>(json) {
+            keys(&value, &mut found);
+        }
+        misspelled = found
+            .into_iter()
+            .filter(|key| !known().any(|k| k == key))
+            .find(|key| nearest(key, [missing]).is_some());
+        misspelled.as_ref().map(|_| missing.to_string())
+    } else if let Some(variant) = quoted(&message, "unknown variant `") {
+        let expected = message.split("expected ").nth(1).unwrap_or("");
+        nearest(variant, expected.split('`').skip(1).step_by(2)).map(String::from)
+    } else if let Some(field) = quoted(&message, "unknown field `") {
+        nearest(field, known()).map(String::from)
+    } else {
+        None
+    };
+
+    ParseDiagnostic {
+        line,
+        column,
+        policy,
+        message,
+        suggestion,
+        misspelled,
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::PolicyEngine;
+
+    #[test]
+    fn test_nearest() {
+        assert_eq!(
+            nearest("start_with", crate::condition::OPERATORS.iter().copied()),
+            Some("starts_with")
+        );
+        assert_eq!(nearest("regex", ["eq", "glob", "in"]), None);
+    }
+
+    #[test]
+    fn test_misspelled_field_and_variant() {
+        let json = r#"[{"name": "p", "version": "1", "rules": [
+            {"efect": "Deny", "principal": "*", "resource": "shell", "action": "execute",
+             "conditions": []}
+        ]}]"#;
+        let err = PolicyEngine::new().load_from_json(json).unwrap_err();
+        assert_eq!(err.policy.as_deref(), Some("p"));
+        assert_eq!(err.line, 3);
+        assert_eq!(err.suggestion.as_deref(), Some("effect"));
+        assert_eq!(
+            err.to_string(),
+            "policy `p`, line 3 column 30: missing field `effect`; did you mean `effect` instead of `efect`?"
+        );
+
+        let json = json.replace(r#""efect": "Deny""#, r#""effect": "Alow""#);
+        let err = PolicyEngine::new().load_from_json(&json).unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("Allow"));
+    }
+
+    #[test]
+    fn test_template_rules_report_their_own_missing_field() {
+        let json = r#"[{"name": "p", "version": "1", "rules": [
+            {"effect": "Deny", "principal": "*", "resources_matching": "db.{drop,truncate}",
+             "action": "execute"}
+        ]}]"#;
+        let err = PolicyEngine::new().load_from_json(json).unwrap_err();
+        assert_eq!(err.policy.as_deref(), Some("p"));
+        assert_eq!((err.line, err.column), (2, 13));
+        assert_eq!(err.message, "missing field `conditions`");
+    }
+}
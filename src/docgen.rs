@@ -0,0 +1,335 @@
+//! Capability Documentation.
+//!
+//! [`CapabilityRegistry::generate_docs`] renders security documentation for
+//! every registered capability as Markdown or HTML: its description,
+//! category and risk level, whether it is enabled, its default effect and
+//! deprecated aliases, its parameters and constraints, and the loaded policy
+//! rules that can decide it, each summarized in plain English by a
+//! [`PolicySummarizer`]. Generated from the registry and the engine, the
+//! documentation is as current as the policies themselves.
+//!
+//! The risk level follows the category: `Process` and `Credential`
+//! capabilities are high risk, as in the default invariants (see
+//! [`crate::invariant`]), `Memory` and `Other` are low, and the rest medium.
+
+use crate::capability::{Capability, CapabilityCategory, CapabilityRegistry};
+use crate::policy::{Effect, PolicyEngine};
+use crate::summary::{PolicySummarizer, TemplateSummarizer};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+impl RiskLevel {
+    pub fn of(category: CapabilityCategory) -> Self {
+        match category {
+            CapabilityCategory::Process | CapabilityCategory::Credential => RiskLevel::High,
+            CapabilityCategory::Memory | CapabilityCategory::Other => RiskLevel::Low,
+            _ => RiskLevel::Medium,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskLevel::Low => "low",
+            RiskLevel::Medium => "medium",
+            RiskLevel::High => "high",
+        }
+    }
+}
+
+struct RuleDoc {
+    id: String,
+    effect: Effect,
+    principal: String,
+    summary: String,
+}
+
+struct CapabilityDoc<'a> {
+    capability: &'a Capability,
+    default_effect: Effect,
+    aliases: Vec<&'a str>,
+    rules: Vec<RuleDoc>,
+}
+
+fn capability_doc<'a>(
+    registry: &'a CapabilityRegistry,
+    engine: &PolicyEngine,
+    summarizer: &dyn PolicySummarizer,
+    capability: &'a Capability,
+) -> CapabilityDoc<'a> {
+    let name = capability.name.as_str();
+    let mut rules = Vec::new();
+    for policy in engine.policies() {
+        for (index, rule) in policy.rules.iter().enumerate() {
+            if !rule.applies_in(name, capability.category) {
+                continue;
+            }
+            rules.push(RuleDoc {
+                id: format!("{}#{}", policy.name, index),
+                effect: rule.effect,
+                principal: rule.principal.clone(),
+                summary: summarizer.summarize_rule(rule),
+            });
+        }
+    }
+    CapabilityDoc {
+        capability,
+        default_effect: engine.default_effect_for(name),
+        aliases: registry
+            .aliases()
+            .filter(|(old, _)| registry.resolve(old) == name)
+            .map(|(old, _)| old)
+            .collect(),
+        rules,
+    }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    match value {
+        true => "yes",
+        false => "no",
+    }
+}
+
+fn markdown(doc: &CapabilityDoc, out: &mut String) {
+    let capability = doc.capability;
+    let _ = writeln!(out, "## `{}`\n", capability.name);
+    if !capability.description.is_empty() {
+        let _ = writeln!(out, "{}\n", capability.description);
+    }
+    let _ = writeln!(out, "- Category: {}", capability.category);
+    let _ = writeln!(
+        out,
+        "- Risk: {}",
+        RiskLevel::of(capability.category).as_str()
+    );
+    let _ = writeln!(out, "- Enabled: {}", yes_no(capability.enabled));
+    let _ = writeln!(out, "- Default effect: {:?}", doc.default_effect);
+    if !doc.aliases.is_empty() {
+        let aliases: Vec<String> = doc.aliases.iter().map(|a| format!("`{}`", a)).collect();
+        let _ = writeln!(out, "- Deprecated aliases: {}", aliases.join(", "));
+    }
+    if !capability.parameters.is_empty() {
+        out.push_str("\n### Parameters\n\n| Name | Type | Required |\n|---|---|---|\n");
+        for param in &capability.parameters {
+            let _ = writeln!(
+                out,
+                "| `{}` | {} | {} |",
+                param.name,
+                param.param_type,
+                yes_no(param.required)
+            );
+        }
+    }
+    if !capability.constraints.is_empty() {
+        out.push_str("\n### Constraints\n\n");
+        for constraint in &capability.constraints {
+            let _ = writeln!(
+                out,
+                "- `{}` must be {}",
+                constraint.param,
+                constraint.describe()
+            );
+        }
+    }
+    out.push_str("\n### Rules\n\n");
+    if doc.rules.is_empty() {
+        out.push_str("No rule applies; the default effect decides.\n");
+    } else {
+        out.push_str("| Rule | Effect | Principal | Summary |\n|---|---|---|---|\n");
+        for rule in &doc.rules {
+            let _ = writeln!(
+                out,
+                "| `{}` | {:?} | `{}` | {} |",
+                rule.id,
+                rule.effect,
+                rule.principal,
+                rule.summary.replace('|', "\\|")
+            );
+        }
+    }
+    out.push('\n');
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn html(doc: &CapabilityDoc, out: &mut String) {
+    let capability = doc.capability;
+    let name = escape(&capability.name);
+    let _ = writeln!(out, "<section id=\"{}\">", name);
+    let _ = writeln!(out, "<h2><code>{}</code></h2>", name);
+    if !capability.description.is_empty() {
+        let _ = writeln!(out, "<p>{}</p>", escape(&capability.description));
+    }
+    let _ = writeln!(
+        out,
+        "<ul>\n<li>Category: {}</li>\n<li>Risk: {}</li>\n<li>Enabled: {}</li>\n<li>Default effect: {:?}</li>",
+        capability.category,
+        RiskLevel::of(capability.category).as_str(),
+        yes_no(capability.enabled),
+        doc.default_effect
+    );
+    if !doc.aliases.is_empty() {
+        let aliases: Vec<String> = doc
+            .aliases
+            .iter()
+            .map(|a| format!("<code>{}</code>", escape(a)))
+            .collect();
+        let _ = writeln!(out, "<li>Deprecated aliases: {}</li>", aliases.join(", "));
+    }
+    out.push_str("</ul>\n");
+    if !capability.parameters.is_empty() {
+        out.push_str(
+            "<h3>Parameters</h3>\n<table>\n<tr><th>Name</th><th>Type</th><th>Required</th></tr>\n",
+        );
+        for param in &capability.parameters {
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                escape(&param.name),
+                escape(&param.param_type),
+                yes_no(param.required)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    if !capability.constraints.is_empty() {
+        out.push_str("<h3>Constraints</h3>\n<ul>\n");
+        for constraint in &capability.constraints {
+            let _ = writeln!(
+                out,
+                "<li><code>{}</code> must be {}</li>",
+                escape(&constraint.param),
+                escape(&constraint.describe())
+            );
+        }
+        out.push_str("</ul>\n");
+    }
+    out.push_str("<h3>Rules</h3>\n");
+    if doc.rules.is_empty() {
+        out.push_str("<p>No rule applies; the default effect decides.</p>\n");
+    } else {
+        out.push_str(
+            "<table>\n<tr><th>Rule</th><th>Effect</th><th>Principal</th><th>Summary</th></tr>\n",
+        );
+        for rule in &doc.rules {
+            let _ = writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td>{:?}</td><td><code>{}</code></td><td>{}</td></tr>",
+                escape(&rule.id),
+                rule.effect,
+                escape(&rule.principal),
+                escape(&rule.summary)
+            );
+        }
+        out.push_str("</table>\n");
+    }
+    out.push_str("</section>\n");
+}
+
+impl CapabilityRegistry {
+    /// Documents every registered capability, by name, against the rules
+    /// loaded in `engine`; see the module docs.
+    pub fn generate_docs(&self, engine: &PolicyEngine, format: DocFormat) -> String {
+        self.generate_docs_with(engine, format, &TemplateSummarizer)
+    }
+
+    /// Like [`CapabilityRegistry::generate_docs`], with rules summarized by
+    /// `summarizer`.
+    pub fn generate_docs_with(
+        &self,
+        engine: &PolicyEngine,
+        format: DocFormat,
+        summarizer: &dyn PolicySummarizer,
+    ) -> String {
+        let mut out = String::new();
+        match format {
+            DocFormat::Markdown => out.push_str("# Capabilities\n\n"),
+            DocFormat::Html => out.push_str("<h1>Capabilities</h1>\n"),
+        }
+        for capability in self.list() {
+            let doc = capability_doc(self, engine, summarizer, capability);
+            match format {
+                DocFormat::Markdown => markdown(&doc, &mut out),
+                DocFormat::Html => html(&doc, &mut out),
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityParam;
+    use crate::condition::ParamConstraint;
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    fn setup() -> (CapabilityRegistry, PolicyEngine) {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(
+            Capability::new("shell", "Run <shell> commands")
+                .with_category(CapabilityCategory::Process)
+                .with_params(vec![CapabilityParam {
+                    name: "cmd".into(),
+                    param_type: "string".into(),
+                    required: true,
+                }])
+                .with_constraint(ParamConstraint::prefix("cwd", "/work/")),
+        );
+        registry.register(Capability::new("memory.get", "Recall a note"));
+        registry.register_alias("exec", "shell");
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                    "cmd",
+                    "eq",
+                    json!("rm"),
+                )]))
+                .with_rule(Rule::allow("fs.read")),
+        );
+        (registry, engine)
+    }
+
+    #[test]
+    fn test_markdown_docs() {
+        let (registry, engine) = setup();
+        let docs = registry.generate_docs(&engine, DocFormat::Markdown);
+        let shell = &docs[docs.find("## `shell`").unwrap()..];
+        assert!(shell.contains("- Risk: high\n"));
+        assert!(shell.contains("- Deprecated aliases: `exec`\n"));
+        assert!(shell.contains("| `cmd` | string | yes |\n"));
+        assert!(shell.contains("- `cwd` must be a string starting with \"/work/\"\n"));
+        assert!(shell.contains("| `p#0` | Deny | `*` | shell is denied when cmd is rm |\n"));
+        let memory = &docs[docs.find("## `memory.get`").unwrap()..docs.find("## `shell`").unwrap()];
+        assert!(memory.contains("- Risk: low\n"));
+        assert!(memory.contains("No rule applies; the default effect decides."));
+    }
+
+    #[test]
+    fn test_html_docs_are_escaped() {
+        let (registry, engine) = setup();
+        let docs = registry.generate_docs(&engine, DocFormat::Html);
+        assert!(docs.contains("<p>Run &lt;shell&gt; commands</p>"));
+        assert!(docs.contains("<td>shell is denied when cmd is rm</td>"));
+        assert_eq!(docs.matches("<section").count(), 2);
+    }
+}
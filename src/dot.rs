@@ -0,0 +1,181 @@
+//! GraphViz Export.
+//!
+//! Renders the policy structure as a DOT digraph for security reviews:
+//! capabilities clustered by namespace, principals and roles with their
+//! effective access (from [`PolicyEngine::permission_matrix`]), role
+//! membership, policy inheritance and capability aliases. Piping the output
+//! through `dot -Tsvg` answers questions like "who can reach `shell`".
+
+use crate::capability::CapabilityRegistry;
+use crate::group::GROUP_PREFIX;
+use crate::matrix::Cell;
+use crate::policy::PolicyEngine;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn node(kind: &str, name: &str) -> String {
+    quote(&format!("{}:{}", kind, name))
+}
+
+pub fn to_dot(engine: &PolicyEngine, registry: &CapabilityRegistry) -> String {
+    let mut out =
+        String::from("digraph policy {\n  rankdir=LR;\n  node [fontname=\"Helvetica\"];\n");
+
+    let mut namespaces: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for capability in registry.list() {
+        let namespace = capability
+            .name
+            .split('.')
+            .next()
+            .unwrap_or(&capability.name);
+        namespaces
+            .entry(namespace)
+            .or_default()
+            .push(&capability.name);
+    }
+    for (namespace, capabilities) in &namespaces {
+        let _ = writeln!(
+            out,
+            "  subgraph {} {{",
+            quote(&format!("cluster_{}", namespace))
+        );
+        let _ = writeln!(out, "    label={};", quote(namespace));
+        for capability in capabilities {
+            let _ = writeln!(
+                out,
+                "    {} [label={}, shape=box];",
+                node("cap", capability),
+                quote(capability)
+            );
+        }
+        out.push_str("  }\n");
+    }
+
+    let matrix = engine.permission_matrix(registry);
+    for principal in &matrix.principals {
+        let shape = if principal.starts_with(GROUP_PREFIX) {
+            "hexagon"
+        } else {
+            "ellipse"
+        };
+        let _ = writeln!(
+            out,
+            "  {} [label={}, shape={}];",
+            node("principal", principal),
+            quote(principal),
+            shape
+        );
+    }
+    for (principal, row) in matrix.principals.iter().zip(&matrix.cells) {
+        for (capability, cell) in matrix.capabilities.iter().zip(row) {
+            let style = match cell {
+                Cell::Allow => "color=darkgreen",
+                Cell::Conditional => "color=darkorange, style=dashed",
+                Cell::Deny => continue,
+            };
+            let _ = writeln!(
+                out,
+                "  {} -> {} [{}];",
+                node("principal", principal),
+                node("cap", capability),
+                style
+            );
+        }
+    }
+
+    if let Some(resolver) = engine.group_resolver() {
+        let mut pending: Vec<String> = matrix
+            .principals
+            .iter()
+            .filter_map(|p| p.strip_prefix(GROUP_PREFIX).map(String::from))
+            .collect();
+        let mut seen = BTreeSet::new();
+        while let Some(group) = pending.pop() {
+            if !seen.insert(group.clone()) {
+                continue;
+            }
+            for member in resolver.members(&group).ok().flatten().unwrap_or_default() {
+                let _ = writeln!(
+                    out,
+                    "  {} -> {} [label=\"member of\", style=dotted];",
+                    node("principal", &member),
+                    node("principal", &format!("{}{}", GROUP_PREFIX, group))
+                );
+                if let Some(nested) = member.strip_prefix(GROUP_PREFIX) {
+                    pending.push(nested.to_string());
+                }
+            }
+        }
+    }
+
+    for policy in engine.policies() {
+        let _ = writeln!(
+            out,
+            "  {} [label={}, shape=note];",
+            node("policy", &policy.name),
+            quote(&policy.name)
+        );
+        if let Some(base) = &policy.extends {
+            let _ = writeln!(
+                out,
+                "  {} -> {} [label=\"extends\"];",
+                node("policy", &policy.name),
+                node("policy", base)
+            );
+        }
+    }
+
+    for (alias, target) in registry.aliases() {
+        let _ = writeln!(
+            out,
+            "  {} [label={}, shape=box, style=dashed];",
+            node("cap", alias),
+            quote(alias)
+        );
+        let _ = writeln!(
+            out,
+            "  {} -> {} [label=\"renamed to\", style=dashed];",
+            node("cap", alias),
+            node("cap", target)
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::group::StaticGroups;
+    use crate::policy::{Policy, Rule};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_dot_shows_who_reaches_shell() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("shell", "Execute shell commands"));
+        registry.register(Capability::new("fs.read", "Read files"));
+        registry.register_alias("exec", "shell");
+
+        let mut ops = Rule::allow("shell");
+        ops.principal = "group:ops".to_string();
+        let mut engine = PolicyEngine::new()
+            .with_group_resolver(Arc::new(StaticGroups::new().with_group("ops", ["alice"])));
+        engine.add_policy(Policy::new("base", "1.0").with_rule(Rule::allow("fs.read")));
+        engine.add_policy(Policy::new("prod", "1.0").extending("base").with_rule(ops));
+
+        let dot = to_dot(&engine, &registry);
+        assert!(dot.starts_with("digraph policy {"));
+        assert!(dot.contains("\"principal:group:ops\" -> \"cap:shell\" [color=darkgreen];"));
+        assert!(dot.contains("\"principal:alice\" -> \"principal:group:ops\""));
+        assert!(dot.contains("\"policy:prod\" -> \"policy:base\" [label=\"extends\"];"));
+        assert!(dot.contains("\"cap:exec\" -> \"cap:shell\""));
+        assert!(dot.contains("subgraph \"cluster_fs\""));
+    }
+}
@@ -0,0 +1,222 @@
+//! Encrypted Policy Loading.
+//!
+//! Policies can be stored on agent hosts as an encrypted envelope so embedded
+//! hostnames and paths are not kept in plaintext. The crate does not ship a
+//! cipher: callers supply a [`PolicyDecryptor`] (AES-GCM, age, a KMS client) and
+//! a [`KeyProvider`] (environment variable, KMS callback), and the plaintext only
+//! ever exists in memory while it is parsed.
+
+use crate::digest::from_hex;
+use crate::policy::PolicyEngine;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+pub const ENVELOPE_FORMAT: &str = "femtoclaw-encrypted-policy/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptedPolicy {
+    pub format: String,
+    pub alg: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    /// Hex-encoded nonce/IV.
+    pub nonce: String,
+    /// Hex-encoded ciphertext including any authentication tag.
+    pub ciphertext: String,
+}
+
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("invalid encrypted policy envelope: {0}")]
+    Envelope(String),
+    #[error("unsupported envelope format {0:?}")]
+    UnsupportedFormat(String),
+    #[error("unsupported algorithm {0:?}")]
+    UnsupportedAlgorithm(String),
+    #[error("key unavailable: {0}")]
+    Key(String),
+    #[error("decryption failed: {0}")]
+    Decrypt(String),
+    #[error("decrypted policy is invalid: {0}")]
+    Policy(#[from] crate::diagnostic::ParseDiagnostic),
+}
+
+pub trait KeyProvider {
+    fn key(&self, key_id: Option<&str>) -> Result<Vec<u8>, String>;
+}
+
+impl<F> KeyProvider for F
+where
+    F: Fn(Option<&str>) -> Result<Vec<u8>, String>,
+{
+    fn key(&self, key_id: Option<&str>) -> Result<Vec<u8>, String> {
+        self(key_id)
+    }
+}
+
+/// Reads a hex-encoded key from an environment variable.
+pub struct EnvKey {
+    var: String,
+}
+
+impl EnvKey {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl KeyProvider for EnvKey {
+    fn key(&self, _key_id: Option<&str>) -> Result<Vec<u8>, String> {
+        let hex = std::env::var(&self.var).map_err(|_| format!("{} is not set", self.var))?;
+        from_hex(hex.trim()).ok_or_else(|| format!("{} is not valid hex", self.var))
+    }
+}
+
+pub trait PolicyDecryptor {
+    fn supports(&self, alg: &str) -> bool;
+    fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+impl EncryptedPolicy {
+    pub fn parse(json: &[u8]) -> Result<Self, EncryptionError> {
+        let envelope: EncryptedPolicy =
+            serde_json::from_slice(json).map_err(|e| EncryptionError::Envelope(e.to_string()))?;
+        if envelope.format != ENVELOPE_FORMAT {
+            return Err(EncryptionError::UnsupportedFormat(envelope.format));
+        }
+        Ok(envelope)
+    }
+
+    pub fn decrypt(
+        &self,
+        keys: &dyn KeyProvider,
+        decryptor: &dyn PolicyDecryptor,
+    ) -> Result<Vec<u8>, EncryptionError> {
+        if !decryptor.supports(&self.alg) {
+            return Err(EncryptionError::UnsupportedAlgorithm(self.alg.clone()));
+        }
+        let nonce = from_hex(&self.nonce)
+            .ok_or_else(|| EncryptionError::Envelope("nonce is not valid hex".to_string()))?;
+        let ciphertext = from_hex(&self.ciphertext)
+            .ok_or_else(|| EncryptionError::Envelope("ciphertext is not valid hex".to_string()))?;
+
+        let mut key = keys
+            .key(self.key_id.as_deref())
+            .map_err(EncryptionError::Key)?;
+        let plaintext = decryptor.decrypt(&key, &nonce, &ciphertext);
+        key.iter_mut().for_each(|b| *b = 0);
+        plaintext.map_err(EncryptionError::Decrypt)
+    }
+}
+
+impl PolicyEngine {
+    pub fn load_encrypted(
+        &mut self,
+        envelope: &[u8],
+        keys: &dyn KeyProvider,
+        decryptor: &dyn PolicyDecryptor,
+    ) -> Result<(), EncryptionError> {
+        let mut plaintext = EncryptedPolicy::parse(envelope)?.decrypt(keys, decryptor)?;
+        let result = std::str::from_utf8(&plaintext)
+            .map_err(|e| EncryptionError::Decrypt(e.to_string()))
+            .and_then(|json| self.load_from_json(json).map_err(EncryptionError::from));
+        plaintext.iter_mut().for_each(|b| *b = 0);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::to_hex;
+    use crate::policy::Effect;
+
+    /// Toy cipher for tests only.
+    struct XorDecryptor;
+
+    impl PolicyDecryptor for XorDecryptor {
+        fn supports(&self, alg: &str) -> bool {
+            alg == "xor-test"
+        }
+
+        fn decrypt(&self, key: &[u8], nonce: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+            Ok(ciphertext
+                .iter()
+                .enumerate()
+                .map(|(i, b)| b ^ key[i % key.len()] ^ nonce[i % nonce.len()])
+                .collect())
+        }
+    }
+
+    fn envelope(plaintext: &str, key: &[u8], alg: &str) -> Vec<u8> {
+        let nonce = [7u8, 9, 11];
+        let ciphertext = XorDecryptor
+            .decrypt(key, &nonce, plaintext.as_bytes())
+            .unwrap();
+        serde_json::to_vec(&EncryptedPolicy {
+            format: ENVELOPE_FORMAT.to_string(),
+            alg: alg.to_string(),
+            key_id: Some("k1".to_string()),
+            nonce: to_hex(&nonce),
+            ciphertext: to_hex(&ciphertext),
+        })
+        .unwrap()
+    }
+
+    const POLICIES: &str = r#"[{"name":"p","version":"1","rules":[{"effect":"Allow","principal":"*","resource":"net.internal","action":"execute","conditions":[]}]}]"#;
+
+    #[test]
+    fn test_load_encrypted_policy() {
+        let keys = |key_id: Option<&str>| -> Result<Vec<u8>, String> {
+            assert_eq!(key_id, Some("k1"));
+            Ok(b"secret".to_vec())
+        };
+        let mut engine = PolicyEngine::new();
+        engine
+            .load_encrypted(
+                &envelope(POLICIES, b"secret", "xor-test"),
+                &keys,
+                &XorDecryptor,
+            )
+            .unwrap();
+        assert_eq!(
+            engine.evaluate("net.internal", "execute", &serde_json::json!({})),
+            Effect::Allow
+        );
+    }
+
+    #[test]
+    fn test_wrong_key_and_algorithm_rejected() {
+        let keys = |_: Option<&str>| -> Result<Vec<u8>, String> { Ok(b"wrong".to_vec()) };
+        let mut engine = PolicyEngine::new();
+
+        let err = engine
+            .load_encrypted(
+                &envelope(POLICIES, b"secret", "xor-test"),
+                &keys,
+                &XorDecryptor,
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            EncryptionError::Policy(_) | EncryptionError::Decrypt(_)
+        ));
+
+        let err = engine
+            .load_encrypted(
+                &envelope(POLICIES, b"secret", "aes-256-gcm"),
+                &keys,
+                &XorDecryptor,
+            )
+            .unwrap_err();
+        assert!(matches!(err, EncryptionError::UnsupportedAlgorithm(_)));
+    }
+
+    #[test]
+    fn test_env_key_provider() {
+        std::env::set_var("FEMTOCLAW_TEST_POLICY_KEY", "00ff10");
+        let key = EnvKey::new("FEMTOCLAW_TEST_POLICY_KEY").key(None).unwrap();
+        assert_eq!(key, vec![0x00, 0xff, 0x10]);
+        assert!(EnvKey::new("FEMTOCLAW_TEST_UNSET_KEY").key(None).is_err());
+    }
+}
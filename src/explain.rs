@@ -0,0 +1,281 @@
+//! Structured Decision Explanations.
+//!
+//! [`PolicyEngine::explain`] folds the [`DebugEvaluator`] steps for a call into
+//! an [`Explanation`], a tree that serializes to a stable JSON shape for
+//! frontends rendering a "why was this blocked" view:
+//!
+//! ```json
+//! {
+//!   "capability": "shell",
+//!   "effect": "Deny",
+//!   "rule": "p#0",
+//!   "policies": [{
+//!     "name": "p",
+//!     "layer": "org",
+//!     "rules": [{
+//!       "id": "p#0",
+//!       "effect": "Deny",
+//!       "matched": true,
+//!       "conditions": [
+//!         {"key": "cmd", "operator": "eq", "expected": "rm", "actual": "rm", "passed": true}
+//!       ],
+//!       "constraints": []
+//!     }]
+//!   }]
+//! }
+//! ```
+//!
+//! `default` is `"capability"` or `"engine"` when no rule matched, `layer` is
+//! `null` for the baseline policy, and a rule that was never checked against
+//! the arguments carries `skipped` (`principal`, `grace` or `resource`).
+//! `actual` is the argument value compared, `null` when it is absent; for time
+//! conditions it is the evaluation time in milliseconds. Rules after the
+//! deciding one are not listed, since evaluation never reached them.
+
+use crate::args::ArgView;
+use crate::clock::is_time_condition;
+use crate::condition::condition_value;
+use crate::context::RequestContext;
+use crate::debug::{DebugEvaluator, SkipReason, Step};
+use crate::layer::Layer;
+use crate::policy::{Effect, PolicyEngine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DefaultSource {
+    Capability,
+    Engine,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionExplanation {
+    pub key: String,
+    pub operator: String,
+    pub expected: Value,
+    pub actual: Value,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintExplanation {
+    pub param: String,
+    pub actual: Value,
+    pub passed: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleExplanation {
+    /// The `policy#index` id.
+    pub id: String,
+    pub effect: Effect,
+    pub matched: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<SkipReason>,
+    pub conditions: Vec<ConditionExplanation>,
+    pub constraints: Vec<ConstraintExplanation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyExplanation {
+    pub name: String,
+    pub layer: Option<Layer>,
+    pub rules: Vec<RuleExplanation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub capability: String,
+    pub effect: Effect,
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<DefaultSource>,
+    pub policies: Vec<PolicyExplanation>,
+}
+
+fn actual(args: &dyn ArgView, path: &str) -> Value {
+    condition_value(args, path)
+        .map(|v| v.to_value().into_owned())
+        .unwrap_or(Value::Null)
+}
+
+impl Explanation {
+    /// The rule the latest steps belong to.
+    fn current_rule(&mut self) -> Option<&mut RuleExplanation> {
+        self.policies.last_mut()?.rules.last_mut()
+    }
+}
+
+impl PolicyEngine {
+    /// Explains the decision for `resource` as a serializable tree; see the
+    /// module docs for its JSON shape.
+    pub fn explain(&self, ctx: &RequestContext, resource: &str, args: &dyn ArgView) -> Explanation {
+        let now_ms = self.time_check_for(ctx).now_ms;
+        let mut explanation = Explanation {
+            capability: resource.to_string(),
+            effect: self.default_effect_for(resource),
+            rule: None,
+            default: None,
+            policies: Vec::new(),
+        };
+        for step in DebugEvaluator::new(self).steps(ctx, resource, args) {
+            match step {
+                Step::Policy { name, layer } => explanation.policies.push(PolicyExplanation {
+                    name,
+                    layer,
+                    rules: Vec::new(),
+                }),
+                Step::Rule { rule, effect } => {
+                    if explanation.policies.is_empty() {
+                        let name = rule.split('#').next().unwrap_or_default().to_string();
+                        explanation.policies.push(PolicyExplanation {
+                            name,
+                            layer: None,
+                            rules: Vec::new(),
+                        });
+                    }
+                    if let Some(policy) = explanation.policies.last_mut() {
+                        policy.rules.push(RuleExplanation {
+                            id: rule,
+                            effect,
+                            matched: false,
+                            skipped: None,
+                            conditions: Vec::new(),
+                            constraints: Vec::new(),
+                        });
+                    }
+                }
+                Step::Skipped { reason, .. } => {
+                    if let Some(rule) = explanation.current_rule() {
+                        rule.skipped = Some(reason);
+                    }
+                }
+                Step::Condition {
+                    condition, holds, ..
+                } => {
+                    let actual = match is_time_condition(&condition) {
+                        true => Value::from(now_ms),
+                        false => actual(args, &condition.key),
+                    };
+                    if let Some(rule) = explanation.current_rule() {
+                        rule.conditions.push(ConditionExplanation {
+                            key: condition.key,
+                            operator: condition.operator,
+                            expected: condition.value,
+                            actual,
+                            passed: holds,
+                        });
+                    }
+                }
+                Step::Constraint {
+                    param, violation, ..
+                } => {
+                    let actual = actual(args, &param);
+                    if let Some(rule) = explanation.current_rule() {
+                        rule.constraints.push(ConstraintExplanation {
+                            param,
+                            actual,
+                            passed: violation.is_none(),
+                            message: violation.map(|v| v.to_string()),
+                        });
+                    }
+                }
+                Step::Matched { .. } => {
+                    if let Some(rule) = explanation.current_rule() {
+                        rule.matched = true;
+                    }
+                }
+                Step::Defaulted { capability, .. } => {
+                    explanation.default = Some(match capability {
+                        true => DefaultSource::Capability,
+                        false => DefaultSource::Engine,
+                    });
+                }
+                Step::Decided { effect, rule } => {
+                    explanation.effect = effect;
+                    explanation.rule = rule;
+                }
+            }
+        }
+        explanation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{ParamConstraint, ParamRule};
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_explanation_shape() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                    "cmd",
+                    "eq",
+                    json!("rm"),
+                )]))
+                .with_rule({
+                    let mut rule = Rule::allow("shell");
+                    rule.principal = "alice".into();
+                    rule
+                }),
+        );
+        let explanation = engine.explain(&RequestContext::new(), "shell", &json!({"cmd": "ls"}));
+
+        assert_eq!(
+            serde_json::to_value(&explanation).unwrap(),
+            json!({
+                "capability": "shell",
+                "effect": "Deny",
+                "rule": null,
+                "default": "engine",
+                "policies": [{
+                    "name": "p",
+                    "layer": "org",
+                    "rules": [
+                        {"id": "p#0", "effect": "Deny", "matched": false,
+                         "conditions": [{"key": "cmd", "operator": "eq", "expected": "rm",
+                                         "actual": "ls", "passed": false}],
+                         "constraints": []},
+                        {"id": "p#1", "effect": "Allow", "matched": false, "skipped": "principal",
+                         "conditions": [], "constraints": []}
+                    ]
+                }]
+            })
+        );
+        let json = serde_json::to_string(&explanation).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Explanation>(&json).unwrap(),
+            explanation
+        );
+    }
+
+    #[test]
+    fn test_failed_constraint_is_explained() {
+        let mut rule = Rule::allow("fs.read");
+        rule.param_constraints.push(ParamConstraint {
+            param: "path".into(),
+            rule: ParamRule::Prefix("/tmp/".into()),
+        });
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(Policy::new("p", "1").with_rule(rule));
+
+        let explanation = engine.explain(
+            &RequestContext::new(),
+            "fs.read",
+            &json!({"path": "/etc/passwd"}),
+        );
+        let constraint = &explanation.policies[0].rules[0].constraints[0];
+        assert_eq!(constraint.actual, json!("/etc/passwd"));
+        assert!(!constraint.passed);
+        assert!(constraint.message.is_some());
+        assert_eq!(explanation.effect, Effect::Deny);
+    }
+}
@@ -0,0 +1,197 @@
+//! Decision Export for Offline Analysis.
+//!
+//! Audit events exported with a stable column schema, [`DECISION_COLUMNS`],
+//! so warehouses can load them without custom ETL. Columns are only ever
+//! appended; existing names, types and positions do not change.
+//!
+//! | column | type | nullable |
+//! |---|---|---|
+//! | `timestamp_ms` | int64 | no |
+//! | `principal` | utf8 | yes |
+//! | `tool` | utf8 | no |
+//! | `decision` | utf8 | no |
+//! | `reason` | utf8 | yes |
+//! | `rule` | utf8 | yes |
+//! | `sample_rate` | float64 | yes |
+//! | `degraded` | boolean | no |
+//! | `count` | int64 | no |
+//! | `details` | utf8 | no |
+//!
+//! `reason` is the event's reason code, `count` is 1 unless the event stands
+//! for coalesced events, and `details` is the remaining details as a JSON
+//! object. [`CsvExporter`] writes RFC 4180 CSV with a header row; with the
+//! `parquet` feature, [`crate::parquet`] writes row groups in the same schema.
+
+use crate::audit::{AuditEvent, AuditSink};
+use crate::codes::REASON_DETAIL;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Int64,
+    Float64,
+    Boolean,
+    Utf8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+}
+
+const fn column(name: &'static str, column_type: ColumnType, nullable: bool) -> Column {
+    Column {
+        name,
+        column_type,
+        nullable,
+    }
+}
+
+pub const DECISION_COLUMNS: [Column; 10] = [
+    column("timestamp_ms", ColumnType::Int64, false),
+    column("principal", ColumnType::Utf8, true),
+    column("tool", ColumnType::Utf8, false),
+    column("decision", ColumnType::Utf8, false),
+    column("reason", ColumnType::Utf8, true),
+    column("rule", ColumnType::Utf8, true),
+    column("sample_rate", ColumnType::Float64, true),
+    column("degraded", ColumnType::Boolean, false),
+    column("count", ColumnType::Int64, false),
+    column("details", ColumnType::Utf8, false),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportValue {
+    Null,
+    Int64(i64),
+    Float64(f64),
+    Boolean(bool),
+    Utf8(String),
+}
+
+impl ExportValue {
+    fn csv(&self) -> String {
+        match self {
+            ExportValue::Null => String::new(),
+            ExportValue::Int64(n) => n.to_string(),
+            ExportValue::Float64(x) => x.to_string(),
+            ExportValue::Boolean(b) => b.to_string(),
+            ExportValue::Utf8(s) => csv_field(s),
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    match s.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", s.replace('"', "\"\"")),
+        false => s.to_string(),
+    }
+}
+
+fn utf8(value: Option<&String>) -> ExportValue {
+    value.map_or(ExportValue::Null, |s| ExportValue::Utf8(s.clone()))
+}
+
+/// `event` as one value per [`DECISION_COLUMNS`] entry.
+pub fn export_row(event: &AuditEvent) -> Vec<ExportValue> {
+    let mut details = event.details.clone();
+    let reason = details.remove(REASON_DETAIL);
+    vec![
+        ExportValue::Int64(event.timestamp_ms as i64),
+        utf8(event.principal.as_ref()),
+        ExportValue::Utf8(event.tool.clone()),
+        ExportValue::Utf8(event.decision.clone()),
+        utf8(reason.as_ref()),
+        utf8(event.rule.as_ref()),
+        event
+            .sample_rate
+            .map_or(ExportValue::Null, ExportValue::Float64),
+        ExportValue::Boolean(event.degraded),
+        ExportValue::Int64(event.count.unwrap_or(1) as i64),
+        ExportValue::Utf8(serde_json::to_string(&details).unwrap_or_default()),
+    ]
+}
+
+struct CsvState<W> {
+    out: W,
+    header: bool,
+}
+
+/// Writes events as CSV rows, after a header row. As an [`AuditSink`], write
+/// errors drop the event.
+pub struct CsvExporter<W> {
+    state: Mutex<CsvState<W>>,
+}
+
+impl<W: Write + Send> CsvExporter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            state: Mutex::new(CsvState { out, header: false }),
+        }
+    }
+
+    pub fn write_event(&self, event: &AuditEvent) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if !state.header {
+            let names: Vec<&str> = DECISION_COLUMNS.iter().map(|c| c.name).collect();
+            writeln!(state.out, "{}", names.join(","))?;
+            state.header = true;
+        }
+        let fields: Vec<String> = export_row(event).iter().map(ExportValue::csv).collect();
+        writeln!(state.out, "{}", fields.join(","))
+    }
+
+    pub fn write_all<'a>(
+        &self,
+        events: impl IntoIterator<Item = &'a AuditEvent>,
+    ) -> io::Result<()> {
+        events
+            .into_iter()
+            .try_for_each(|event| self.write_event(event))
+    }
+
+    pub fn into_inner(self) -> W {
+        self.state.into_inner().unwrap().out
+    }
+}
+
+impl<W: Write + Send> AuditSink for CsvExporter<W> {
+    fn record(&self, event: &AuditEvent) {
+        let _ = self.write_event(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_rows_follow_the_column_schema() {
+        let mut denied = AuditEvent::new("shell", "DENIED_POLICY_VIOLATION");
+        denied.timestamp_ms = 1_000;
+        denied.principal = Some("agent, the \"first\"".into());
+        denied.rule = Some("p#0".into());
+        denied
+            .details
+            .insert(REASON_DETAIL.into(), "DENY_RULE_MATCHED".into());
+        denied.details.insert("region".into(), "eu".into());
+        let mut allowed = AuditEvent::new("fs.read", "AUTHORIZED");
+        allowed.timestamp_ms = 2_000;
+        allowed.sample_rate = Some(0.5);
+
+        let exporter = CsvExporter::new(Vec::new());
+        exporter.write_all([&denied, &allowed]).unwrap();
+        let csv = String::from_utf8(exporter.into_inner()).unwrap();
+        assert_eq!(
+            csv.lines().collect::<Vec<_>>(),
+            vec![
+                "timestamp_ms,principal,tool,decision,reason,rule,sample_rate,degraded,count,details",
+                r#"1000,"agent, the ""first""",shell,DENIED_POLICY_VIOLATION,DENY_RULE_MATCHED,p#0,,false,1,"{""region"":""eu""}""#,
+                "2000,,fs.read,AUTHORIZED,,,0.5,false,1,{}",
+            ]
+        );
+    }
+}
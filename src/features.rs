@@ -0,0 +1,139 @@
+//! Self-Describing Engine Features.
+//!
+//! [`PolicyEngine::features`] reports what this build of the engine supports —
+//! condition operators, load formats, resolution strategies and versioned
+//! schemas — as a serializable [`EngineFeatures`], so a management plane can
+//! check compatibility before pushing a bundle that relies on newer features.
+//! [`EngineFeatures::unsupported`] does that check for a set of policies.
+
+use crate::clock::{is_time_condition, TIME_OPERATORS};
+use crate::condition::OPERATORS;
+use crate::image::IMAGE_VERSION;
+use crate::policy::{Policy, PolicyEngine};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How decisions are resolved, in evaluation order.
+pub const RESOLUTION: &[&str] = &[
+    "layers",
+    "first-match-within-layer",
+    "deny-final-across-layers",
+    "extends",
+    "group-principals",
+    "capability-defaults",
+    "category-defaults",
+    "default-effect",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineFeatures {
+    pub version: String,
+    pub operators: Vec<String>,
+    pub time_operators: Vec<String>,
+    pub param_rules: Vec<String>,
+    pub formats: Vec<String>,
+    pub resolution: Vec<String>,
+    pub layers: Vec<String>,
+    pub backend_combinations: Vec<String>,
+    /// Versions of the versioned encodings, keyed by encoding.
+    pub schema_versions: BTreeMap<String, u32>,
+}
+
+fn owned(items: &[&str]) -> Vec<String> {
+    items.iter().map(|s| s.to_string()).collect()
+}
+
+impl EngineFeatures {
+    pub fn supports_operator(&self, operator: &str) -> bool {
+        self.operators.iter().any(|o| o == operator)
+    }
+
+    /// Every feature `policies` use that this engine lacks, e.g.
+    /// `operator regex in p#2`; empty if the bundle is compatible.
+    pub fn unsupported(&self, policies: &[Policy]) -> Vec<String> {
+        let mut missing = Vec::new();
+        for policy in policies {
+            for (index, rule) in policy.rules.iter().enumerate() {
+                for condition in &rule.conditions {
+                    let known = match is_time_condition(condition) {
+                        true => self.time_operators.contains(&condition.operator),
+                        false => self.supports_operator(&condition.operator),
+                    };
+                    if !known {
+                        missing.push(format!(
+                            "operator {} in {}#{}",
+                            condition.operator, policy.name, index
+                        ));
+                    }
+                }
+            }
+        }
+        missing
+    }
+}
+
+impl PolicyEngine {
+    pub fn features(&self) -> EngineFeatures {
+        let mut formats = vec!["json", "bundle", "encrypted", "image", "lazy"];
+        if cfg!(feature = "cbor") {
+            formats.push("cbor");
+        }
+        if cfg!(feature = "msgpack") {
+            formats.push("msgpack");
+        }
+        if cfg!(feature = "hcl") {
+            formats.push("hcl");
+        }
+        EngineFeatures {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            operators: owned(OPERATORS),
+            time_operators: owned(TIME_OPERATORS),
+            param_rules: owned(&["one_of", "none_of", "equals", "prefix"]),
+            formats: owned(&formats),
+            resolution: owned(RESOLUTION),
+            layers: owned(&["org", "project", "session"]),
+            backend_combinations: owned(&["backend_only", "local_first", "both_must_allow"]),
+            schema_versions: BTreeMap::from([("image".to_string(), IMAGE_VERSION)]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_unsupported_operators_are_reported() {
+        let features = PolicyEngine::new().features();
+        assert!(features.supports_operator("starts_with"));
+
+        let policy = Policy::new("p", "1")
+            .with_rule(Rule::allow("fs.read").with_conditions(vec![Condition::new(
+                "path",
+                "glob",
+                json!("/tmp/*"),
+            )]))
+            .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                "cmd",
+                "regex",
+                json!("^rm"),
+            )]));
+        assert_eq!(
+            features.unsupported(&[policy]),
+            vec!["operator regex in p#1"]
+        );
+    }
+
+    #[test]
+    fn test_features_round_trip() {
+        let features = PolicyEngine::new().features();
+        let json = serde_json::to_value(&features).unwrap();
+        assert_eq!(json["schema_versions"]["image"], IMAGE_VERSION);
+        assert_eq!(
+            serde_json::from_value::<EngineFeatures>(json).unwrap(),
+            features
+        );
+    }
+}
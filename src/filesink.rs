@@ -0,0 +1,257 @@
+//! Rotating JSONL Audit Files.
+//!
+//! [`JsonlFileSink`] appends one audit event per line to a file and rotates it
+//! once it exceeds a size or age limit. Rotated files are renamed to
+//! `<name>.<timestamp_ms>`, optionally gzip-compressed, and purged once they
+//! are older than the retention period or exceed the maximum file count, so
+//! long-running agents do not fill their disks.
+
+use crate::audit::{AuditEvent, AuditSink};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RotationPolicy {
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+    pub compress: bool,
+    /// Rotated files older than this are deleted.
+    pub retention: Option<Duration>,
+    /// At most this many rotated files are kept.
+    pub max_files: Option<usize>,
+}
+
+struct Current {
+    file: File,
+    bytes: u64,
+    opened_ms: Option<u64>,
+}
+
+pub struct JsonlFileSink {
+    path: PathBuf,
+    policy: RotationPolicy,
+    current: Mutex<Current>,
+}
+
+impl JsonlFileSink {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let bytes = file.metadata()?.len();
+        Ok(Self {
+            path,
+            policy: RotationPolicy::default(),
+            current: Mutex::new(Current {
+                file,
+                bytes,
+                opened_ms: None,
+            }),
+        })
+    }
+
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.policy.max_bytes = Some(max_bytes);
+        self
+    }
+
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.policy.max_age = Some(max_age);
+        self
+    }
+
+    pub fn with_compression(mut self) -> Self {
+        self.policy.compress = true;
+        self
+    }
+
+    pub fn with_retention(mut self, retention: Duration) -> Self {
+        self.policy.retention = Some(retention);
+        self
+    }
+
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.policy.max_files = Some(max_files);
+        self
+    }
+
+    pub fn policy(&self) -> &RotationPolicy {
+        &self.policy
+    }
+
+    /// Appends `event`, rotating first if the current file is over its limits.
+    /// The event's timestamp is the clock for age-based rotation and retention.
+    pub fn append(&self, event: &AuditEvent) -> io::Result<()> {
+        let mut line = serde_json::to_vec(event).map_err(io::Error::other)?;
+        line.push(b'\n');
+        let now_ms = event.timestamp_ms;
+
+        let mut current = self.current.lock().unwrap();
+        let too_big = self
+            .policy
+            .max_bytes
+            .is_some_and(|max| current.bytes > 0 && current.bytes + line.len() as u64 > max);
+        let too_old = match (self.policy.max_age, current.opened_ms) {
+            (Some(age), Some(opened)) => now_ms.saturating_sub(opened) >= age.as_millis() as u64,
+            _ => false,
+        };
+        if too_big || too_old {
+            self.rotate(&mut current, now_ms)?;
+        }
+
+        current.file.write_all(&line)?;
+        current.bytes += line.len() as u64;
+        current.opened_ms.get_or_insert(now_ms);
+        Ok(())
+    }
+
+    fn rotate(&self, current: &mut Current, now_ms: u64) -> io::Result<()> {
+        current.file.flush()?;
+        let mut rotated = self.rotated_name(now_ms, 0);
+        let mut n = 0;
+        while rotated.exists() || gz_path(&rotated).exists() {
+            n += 1;
+            rotated = self.rotated_name(now_ms, n);
+        }
+        fs::rename(&self.path, &rotated)?;
+        if self.policy.compress {
+            let data = fs::read(&rotated)?;
+            fs::write(gz_path(&rotated), crate::gzip::compress(&data))?;
+            fs::remove_file(&rotated)?;
+        }
+
+        current.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        current.bytes = 0;
+        current.opened_ms = None;
+        self.purge(now_ms)
+    }
+
+    fn rotated_name(&self, now_ms: u64, n: u32) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", now_ms));
+        if n > 0 {
+            name.push(format!("-{}", n));
+        }
+        PathBuf::from(name)
+    }
+
+    /// Rotated files, oldest first, with their rotation timestamps.
+    pub fn rotated_files(&self) -> io::Result<Vec<(u64, PathBuf)>> {
+        let dir = match self.path.parent() {
+            Some(p) if !p.as_os_str().is_empty() => p.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let prefix = format!(
+            "{}.",
+            self.path.file_name().unwrap_or_default().to_string_lossy()
+        );
+        let mut files = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let Some(rest) = name.strip_prefix(&prefix) else {
+                continue;
+            };
+            let stamp = rest.trim_end_matches(".gz");
+            let stamp = stamp.split('-').next().unwrap_or(stamp);
+            if let Ok(ms) = stamp.parse::<u64>() {
+                files.push((ms, path));
+            }
+        }
+        files.sort();
+        Ok(files)
+    }
+
+    fn purge(&self, now_ms: u64) -> io::Result<()> {
+        let mut files = self.rotated_files()?;
+        if let Some(retention) = self.policy.retention {
+            let cutoff = now_ms.saturating_sub(retention.as_millis() as u64);
+            for (_, path) in files.iter().filter(|(ms, _)| *ms < cutoff) {
+                fs::remove_file(path)?;
+            }
+            files.retain(|(ms, _)| *ms >= cutoff);
+        }
+        if let Some(max) = self.policy.max_files {
+            let excess = files.len().saturating_sub(max);
+            for (_, path) in &files[..excess] {
+                fs::remove_file(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+impl AuditSink for JsonlFileSink {
+    fn record(&self, event: &AuditEvent) {
+        let _ = self.append(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp_ms: u64) -> AuditEvent {
+        AuditEvent {
+            timestamp_ms,
+            ..AuditEvent::new("fs.read", "AUTHORIZED")
+        }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("femtoclaw-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.join("audit.jsonl")
+    }
+
+    #[test]
+    fn test_size_rotation_with_compression_and_max_files() {
+        let path = scratch("size");
+        let sink = JsonlFileSink::open(&path)
+            .unwrap()
+            .with_max_bytes(200)
+            .with_compression()
+            .with_max_files(2);
+        for ms in 0..20 {
+            sink.append(&event(ms)).unwrap();
+        }
+
+        let rotated = sink.rotated_files().unwrap();
+        assert_eq!(rotated.len(), 2);
+        assert!(rotated.iter().all(|(_, p)| p.extension().unwrap() == "gz"));
+        assert!(fs::metadata(&path).unwrap().len() <= 200);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_age_rotation_and_retention() {
+        let path = scratch("age");
+        let sink = JsonlFileSink::open(&path)
+            .unwrap()
+            .with_max_age(Duration::from_secs(60))
+            .with_retention(Duration::from_secs(300));
+        sink.append(&event(0)).unwrap();
+        sink.append(&event(61_000)).unwrap();
+        assert_eq!(sink.rotated_files().unwrap().len(), 1);
+
+        sink.append(&event(400_000)).unwrap();
+        let rotated = sink.rotated_files().unwrap();
+        assert_eq!(
+            rotated.iter().map(|(ms, _)| *ms).collect::<Vec<_>>(),
+            vec![400_000]
+        );
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+//! Argument Fingerprints.
+//!
+//! A fingerprint identifies a request's arguments for audit records and decision
+//! caches. It hashes the canonical JSON form, so payloads that differ only in
+//! key order or number spelling (`1`, `1.0`, `1e0`) fingerprint the same:
+//! object keys are sorted, integral numbers are written as integers and there
+//! is no whitespace.
+//!
+//! Volatile fields such as timestamps or request ids would make every payload
+//! unique; a [`Fingerprinter`] leaves out excluded fields, named by dotted path
+//! from the root (`meta.sent_at`). Array elements share their array's path, so
+//! `items.ts` excludes `ts` from every element of `items`.
+//!
+//! Fingerprints carry their algorithm, e.g. `blake3:6437b3…`, so values from
+//! differently configured gates never compare equal.
+
+use crate::digest::{blake3, sha256, to_hex};
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::BTreeSet;
+
+/// Detail key under which the gate records the arguments' fingerprint.
+pub const FINGERPRINT_DETAIL: &str = "args_fingerprint";
+
+/// Integral floats beyond this are not exactly representable as integers.
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    pub fn digest(&self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlgorithm::Sha256 => sha256(data),
+            HashAlgorithm::Blake3 => blake3(data),
+        }
+    }
+}
+
+fn number(n: &Number, out: &mut String) {
+    match n.as_f64() {
+        Some(f) if n.is_f64() && f.fract() == 0.0 && f.abs() < MAX_SAFE_INTEGER => {
+            out.push_str(&(f as i64).to_string())
+        }
+        _ => out.push_str(&n.to_string()),
+    }
+}
+
+fn write(value: &Value, path: &str, excluded: &BTreeSet<String>, out: &mut String) {
+    match value {
+        Value::Number(n) => number(n, out),
+        Value::String(s) => out.push_str(&Value::String(s.clone()).to_string()),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write(item, path, excluded, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            let mut first = true;
+            for key in keys {
+                let child = match path.is_empty() {
+                    true => key.clone(),
+                    false => format!("{}.{}", path, key),
+                };
+                if excluded.contains(&child) {
+                    continue;
+                }
+                if !first {
+                    out.push(',');
+                }
+                first = false;
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write(&map[key], &child, excluded, out);
+            }
+            out.push('}');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+/// The canonical JSON form of `value`.
+pub fn canonical_json(value: &Value) -> String {
+    let mut out = String::new();
+    write(value, "", &BTreeSet::new(), &mut out);
+    out
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprinter {
+    algorithm: HashAlgorithm,
+    excluded: BTreeSet<String>,
+}
+
+impl Fingerprinter {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        Self {
+            algorithm,
+            excluded: BTreeSet::new(),
+        }
+    }
+
+    /// Leaves the field at dotted `path` out of fingerprints.
+    pub fn excluding(mut self, path: impl Into<String>) -> Self {
+        self.excluded.insert(path.into());
+        self
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    /// The canonical JSON that is hashed, without excluded fields.
+    pub fn canonical(&self, args: &Value) -> String {
+        let mut out = String::new();
+        write(args, "", &self.excluded, &mut out);
+        out
+    }
+
+    pub fn fingerprint(&self, args: &Value) -> String {
+        let digest = self.algorithm.digest(self.canonical(args).as_bytes());
+        format!("{}:{}", self.algorithm.as_str(), to_hex(&digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_form() {
+        let a: Value = serde_json::from_str(r#"{"b": [1.0, -0.0, 2.5], "a": "x\"y"}"#).unwrap();
+        assert_eq!(canonical_json(&a), r#"{"a":"x\"y","b":[1,0,2.5]}"#);
+        let b: Value = serde_json::from_str(r#"{"a": "x\"y", "b": [1, 0, 25e-1]}"#).unwrap();
+        let fingerprinter = Fingerprinter::default();
+        assert_eq!(fingerprinter.fingerprint(&a), fingerprinter.fingerprint(&b));
+        assert!(fingerprinter.fingerprint(&a).starts_with("sha256:"));
+    }
+
+    #[test]
+    fn test_excluded_fields_are_ignored() {
+        let fingerprinter = Fingerprinter::new(HashAlgorithm::Blake3)
+            .excluding("meta.sent_at")
+            .excluding("items.ts");
+        let at = |t: u64| json!({ "path": "/tmp", "meta": { "sent_at": t, "id": 1 }, "items": [{ "ts": t }] });
+        assert_eq!(
+            fingerprinter.fingerprint(&at(1)),
+            fingerprinter.fingerprint(&at(2))
+        );
+        assert_eq!(
+            fingerprinter.canonical(&at(1)),
+            r#"{"items":[{}],"meta":{"id":1},"path":"/tmp"}"#
+        );
+        assert_ne!(
+            fingerprinter.fingerprint(&json!({ "path": "/tmp" })),
+            Fingerprinter::default().fingerprint(&json!({ "path": "/tmp" }))
+        );
+    }
+}
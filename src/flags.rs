@@ -0,0 +1,135 @@
+//! Feature-Flagged Capabilities.
+//!
+//! A capability can be tied to a feature flag with
+//! `CapabilityGate::with_feature_flag`, so it can be rolled out gradually. The
+//! gate asks its [`FeatureFlagProvider`] after policy allows the call; a flag
+//! that is off — or unknown to the provider — denies it with
+//! `DeniedCapabilityDisabled`. The outcome is recorded in the decision's
+//! `feature_flag` detail either way.
+//!
+//! Providers wrap LaunchDarkly, Unleash or similar clients; [`PercentageRollout`]
+//! is a self-contained provider that enables a flag for a stable fraction of
+//! sessions.
+
+use crate::context::RequestContext;
+use crate::digest::sha256;
+use std::collections::BTreeMap;
+
+pub const SESSION_ATTRIBUTE: &str = "session";
+
+pub trait FeatureFlagProvider: Send + Sync {
+    /// Whether `flag` is on for this request; `None` if the flag is unknown.
+    fn is_enabled(&self, flag: &str, ctx: &RequestContext) -> Option<bool>;
+}
+
+impl<F> FeatureFlagProvider for F
+where
+    F: Fn(&str, &RequestContext) -> Option<bool> + Send + Sync,
+{
+    fn is_enabled(&self, flag: &str, ctx: &RequestContext) -> Option<bool> {
+        self(flag, ctx)
+    }
+}
+
+/// Enables each flag for a fixed fraction of sessions. Sessions are identified
+/// by the `session` attribute, falling back to the principal; requests with
+/// neither are bucketed together. Bucketing hashes flag and session, so a
+/// session keeps its bucket and raising the fraction only adds sessions.
+#[derive(Debug, Clone, Default)]
+pub struct PercentageRollout {
+    fractions: BTreeMap<String, f64>,
+}
+
+impl PercentageRollout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables `flag` for `fraction` (0.0 to 1.0) of sessions.
+    pub fn with_flag(mut self, flag: impl Into<String>, fraction: f64) -> Self {
+        self.fractions.insert(flag.into(), fraction.clamp(0.0, 1.0));
+        self
+    }
+
+    /// The session's position in `[0, 1)` for `flag`.
+    pub fn bucket(flag: &str, ctx: &RequestContext) -> f64 {
+        let session = ctx
+            .attributes
+            .get(SESSION_ATTRIBUTE)
+            .or(ctx.principal.as_ref())
+            .map(String::as_str)
+            .unwrap_or("");
+        let digest = sha256(format!("{}\n{}", flag, session).as_bytes());
+        let top = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+        top as f64 / (u32::MAX as f64 + 1.0)
+    }
+}
+
+impl FeatureFlagProvider for PercentageRollout {
+    fn is_enabled(&self, flag: &str, ctx: &RequestContext) -> Option<bool> {
+        let fraction = self.fractions.get(flag)?;
+        Some(Self::bucket(flag, ctx) < *fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::gate::{CapabilityGate, Decision};
+    use crate::policy::{Policy, Rule};
+    use std::sync::Arc;
+
+    fn session(id: usize) -> RequestContext {
+        RequestContext::default().with_attribute(SESSION_ATTRIBUTE, id.to_string())
+    }
+
+    #[test]
+    fn test_rollout_fraction_is_stable() {
+        let rollout = PercentageRollout::new().with_flag("browser-v2", 0.05);
+        let enabled = (0..10_000)
+            .filter(|&i| rollout.is_enabled("browser-v2", &session(i)) == Some(true))
+            .count();
+        assert!((400..600).contains(&enabled), "{}", enabled);
+        assert_eq!(rollout.is_enabled("other", &session(1)), None);
+
+        let wider = PercentageRollout::new().with_flag("browser-v2", 0.5);
+        assert!((0..1000)
+            .filter(|&i| rollout.is_enabled("browser-v2", &session(i)) == Some(true))
+            .all(|i| wider.is_enabled("browser-v2", &session(i)) == Some(true)));
+    }
+
+    #[test]
+    fn test_gate_consults_flag_after_policy() {
+        let provider = |flag: &str, ctx: &RequestContext| match flag {
+            "browser" => Some(ctx.principal.as_deref() == Some("beta")),
+            _ => None,
+        };
+        let mut gate = CapabilityGate::new()
+            .with_flag_provider(Arc::new(provider))
+            .with_feature_flag("web.browse", "browser")
+            .with_feature_flag("web.search", "search");
+        gate.register_capability(Capability::new("web.browse", "Browse"));
+        gate.register_capability(Capability::new("web.search", "Search"));
+        gate.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::allow("web.browse"))
+                .with_rule(Rule::allow("web.search")),
+        );
+
+        let beta = RequestContext::default().with_principal("beta");
+        let record = gate.authorize_record("web.browse", &(), &beta);
+        assert_eq!(record.decision, Decision::Authorized);
+        assert_eq!(record.details["feature_flag"], "browser=on");
+
+        let other = RequestContext::default().with_principal("other");
+        let record = gate.authorize_record("web.browse", &(), &other);
+        assert_eq!(record.decision, Decision::DeniedCapabilityDisabled);
+        assert_eq!(record.details["feature_flag"], "browser=off");
+        assert_eq!(record.details["reason"], "FEATURE_FLAG_OFF");
+
+        let record = gate.authorize_record("web.search", &(), &beta);
+        assert_eq!(record.details["feature_flag"], "search=unknown");
+        assert!(!record.is_allowed());
+    }
+}
@@ -0,0 +1,84 @@
+//! Canonical Policy Formatting.
+//!
+//! [`Policy::format`] renders a stable layout so policy diffs in review stay
+//! minimal: operator aliases are normalized (`equals` becomes `eq`, `prefix`
+//! becomes `starts_with`), the redundant `args.` key prefix is dropped, and each
+//! rule's conditions and parameter constraints are sorted. Rule order is kept,
+//! because the first matching rule decides.
+
+use crate::condition::arg_key;
+use crate::policy::Policy;
+
+/// The canonical spelling of a condition operator.
+pub fn canonical_operator(operator: &str) -> &str {
+    match operator {
+        "equals" => "eq",
+        "not_equals" => "ne",
+        "prefix" => "starts_with",
+        "suffix" => "ends_with",
+        other => other,
+    }
+}
+
+impl Policy {
+    /// Normalizes the policy in place without changing what it decides.
+    pub fn canonicalize(&mut self) {
+        for rule in &mut self.rules {
+            for condition in &mut rule.conditions {
+                condition.operator = canonical_operator(&condition.operator).to_string();
+                condition.key = arg_key(&condition.key).to_string();
+            }
+            rule.conditions.sort_by(|a, b| {
+                (&a.key, &a.operator, a.value.to_string()).cmp(&(
+                    &b.key,
+                    &b.operator,
+                    b.value.to_string(),
+                ))
+            });
+            rule.param_constraints.sort_by(|a, b| a.param.cmp(&b.param));
+        }
+    }
+
+    /// Pretty-printed canonical JSON, ending in a newline.
+    pub fn format(&self) -> String {
+        format_policies(std::slice::from_ref(self))
+    }
+}
+
+/// Formats a policy file: a JSON array of policies in canonical form.
+pub fn format_policies(policies: &[Policy]) -> String {
+    let canonical: Vec<Policy> = policies
+        .iter()
+        .cloned()
+        .map(|mut p| {
+            p.canonicalize();
+            p
+        })
+        .collect();
+    let mut out = serde_json::to_string_pretty(&canonical).expect("policies serialize");
+    out.push('\n');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_format_is_canonical_and_stable() {
+        let policy =
+            Policy::new("p", "1.0").with_rule(Rule::allow("fs.read").with_conditions(vec![
+                Condition::new("size", "lt", json!(10)),
+                Condition::new("args.path", "prefix", json!("/work")),
+            ]));
+
+        let formatted = policy.format();
+        let parsed: Vec<Policy> = serde_json::from_str(&formatted).unwrap();
+        let conditions = &parsed[0].rules[0].conditions;
+        assert_eq!(conditions[0].key, "path");
+        assert_eq!(conditions[0].operator, "starts_with");
+        assert_eq!(format_policies(&parsed), formatted);
+    }
+}
@@ -0,0 +1,129 @@
+//! Fuzzing Entry Points.
+//!
+//! With the `fuzzing` feature these functions take raw fuzzer input and drive
+//! the parser and evaluator, panicking only when an invariant fails; the
+//! `cargo fuzz` targets in `fuzz/` (and an OSS-Fuzz build) are thin wrappers
+//! around them, so the harness logic is versioned and unit-tested with the
+//! crate.
+//!
+//! - [`policy_json`]: parsed policies survive a serialize/parse round trip
+//!   and can be loaded and evaluated.
+//! - [`evaluate`]: a condition and JSON arguments, split at the first NUL,
+//!   evaluate without panicking, through the gate as well.
+//! - [`pattern`]: glob matching of arbitrary pattern and text, checking that
+//!   a literal pattern matches itself.
+
+use crate::args::NO_ARGS;
+use crate::capability::Capability;
+use crate::context::RequestContext;
+use crate::gate::CapabilityGate;
+use crate::pattern::Glob;
+use crate::policy::{Condition, Policy, PolicyEngine, Rule};
+use serde_json::Value;
+
+fn split(data: &[u8]) -> (&[u8], &[u8]) {
+    match data.iter().position(|&b| b == 0) {
+        Some(at) => (&data[..at], &data[at + 1..]),
+        None => (data, &[]),
+    }
+}
+
+pub fn policy_json(data: &[u8]) {
+    let policies: Vec<Policy> = match serde_json::from_slice::<Vec<Policy>>(data) {
+        Ok(policies) => policies,
+        Err(_) => match serde_json::from_slice::<Policy>(data) {
+            Ok(policy) => vec![policy],
+            Err(_) => return,
+        },
+    };
+    let json = serde_json::to_string(&policies).expect("parsed policies serialize");
+    let reparsed: Vec<Policy> = serde_json::from_str(&json).expect("serialized policies parse");
+    assert_eq!(
+        serde_json::to_string(&reparsed).expect("reparsed policies serialize"),
+        json,
+        "round trip changed the policies"
+    );
+
+    let mut engine = PolicyEngine::new();
+    for policy in policies {
+        let resources: Vec<String> = policy.rules.iter().map(|r| r.resource.clone()).collect();
+        engine.add_policy(policy);
+        for resource in resources {
+            engine.evaluate(&resource, "execute", &NO_ARGS);
+        }
+    }
+}
+
+pub fn evaluate(data: &[u8]) {
+    let (condition, args) = split(data);
+    let Ok(condition) = serde_json::from_slice::<Condition>(condition) else {
+        return;
+    };
+    let args: Value = serde_json::from_slice(args).unwrap_or(Value::Null);
+    condition.evaluate(&args);
+    condition.evaluate_folded(&args);
+
+    let mut gate = CapabilityGate::new().with_hardened_matching(true);
+    gate.register_capability(Capability::new("tool", "fuzzed"));
+    gate.add_policy(
+        Policy::new("fuzz", "1")
+            .with_rule(Rule::deny("tool").with_conditions(vec![condition.clone()]))
+            .with_rule(Rule::allow("tool").with_conditions(vec![condition])),
+    );
+    gate.authorize_record("tool", &args, &RequestContext::default());
+}
+
+pub fn pattern(data: &[u8]) {
+    let (pattern, text) = split(data);
+    let (pattern, text) = (
+        String::from_utf8_lossy(pattern),
+        String::from_utf8_lossy(text),
+    );
+    Glob::compile(&pattern).is_match(&text);
+    if !pattern.contains(['*', '?']) {
+        assert!(
+            Glob::compile(&pattern).is_match(&pattern),
+            "literal {:?}",
+            pattern
+        );
+    }
+    #[cfg(feature = "json-schema")]
+    if let Ok(regex) = crate::regex::Regex::new(&pattern) {
+        regex.is_match(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mutates seeds with a fixed xorshift stream, standing in for a short fuzz run.
+    #[test]
+    fn test_targets_survive_mutated_seeds() {
+        let seeds: [&[u8]; 4] = [
+            include_bytes!("builtin/baseline-v1.json"),
+            br#"{"key":"path","operator":"glob","value":"/etc/**"}"#,
+            b"{\"key\":\"cmd\",\"operator\":\"contains\",\"value\":\"rm\"}\0{\"cmd\":\"rm -rf\"}",
+            b"**/*.rs\0src/fuzz.rs",
+        ];
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for seed in seeds {
+            for _ in 0..200 {
+                let mut input = seed.to_vec();
+                for _ in 0..3 {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    let at = state as usize % input.len();
+                    input[at] = (state >> 32) as u8;
+                }
+                policy_json(&input);
+                evaluate(&input);
+                pattern(&input);
+            }
+            policy_json(seed);
+            evaluate(seed);
+            pattern(seed);
+        }
+    }
+}
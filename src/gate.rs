@@ -6,41 +6,123 @@
 //! 2. Capability is enabled
 //! 3. Policy engine permits execution
 
+use crate::args::{view_of, ArgView, Args};
+use crate::audit::{AuditEvent, AuditMode, AuditSink, Sampler};
+use crate::backend::{BackendRequest, Combination, DecisionBackend};
+use crate::backoff::DenialBackoff;
+use crate::breaker::CircuitBreaker;
+use crate::budget::{EvaluationBudget, Meter};
+use crate::cache::{projected_args_key, CachedDecision, DecisionCache};
+use crate::canary::{Canary, CanaryReport, CANARY_DETAIL};
 use crate::capability::{Capability, CapabilityRegistry};
-use crate::policy::{Effect, PolicyEngine};
+use crate::clock::skew_lints;
+use crate::codes::{Reason, REASON_DETAIL};
+use crate::consent::{
+    rule_fingerprint, ConsentProvider, ConsentRequest, SessionGrants, CONSENT_DETAIL,
+};
+use crate::context::RequestContext;
+use crate::dataset::DecisionSampler;
+pub use crate::decision::{
+    Authorization, Decision, DecisionCategory, DecisionRecord, Denial, Obligation,
+};
+use crate::decision::{AUDIT_EFFECT, EFFECT_DETAIL, WARNING_EFFECT};
+use crate::degradation::{DegradationMode, StaleDecisionCache};
+use crate::fingerprint::{Fingerprinter, FINGERPRINT_DETAIL};
+use crate::flags::FeatureFlagProvider;
+use crate::group::GroupError;
+use crate::idempotency::{IdempotencyCache, Replay};
+use crate::keys::declared_keys;
+use crate::limits::ArgLimits;
+use crate::lint::{lint_policy, locate, Lint, Severity};
+use crate::middleware::GateMiddleware;
+use crate::outcome::{OutcomeObserver, Ticket};
+use crate::percent;
+use crate::plan::PlanLedger;
+use crate::policy::{Effect, EvaluationError, Policy, PolicyEngine};
+use crate::preflight::{Preflight, PreflightResult};
+#[cfg(feature = "json-schema")]
+use crate::schema::{CompiledSchema, SchemaError};
+use crate::session::{SessionHistory, SessionView};
+use crate::summary::{PolicySummarizer, TemplateSummarizer};
+use crate::validation::CapabilityError;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Decision {
-    Authorized,
-    DeniedCapabilityNotFound,
-    DeniedCapabilityDisabled,
-    DeniedPolicyViolation,
+pub struct CapabilityGate {
+    registry: CapabilityRegistry,
+    engine: PolicyEngine,
+    audit: Option<Arc<dyn AuditSink>>,
+    sampler: Sampler,
+    budget: EvaluationBudget,
+    degradation: BTreeMap<String, DegradationMode>,
+    stale: StaleDecisionCache,
+    lints: Vec<Lint>,
+    middleware: Vec<Arc<dyn GateMiddleware>>,
+    backend: Option<(Arc<dyn DecisionBackend>, Combination)>,
+    preflight: BTreeMap<String, Arc<dyn Preflight>>,
+    idempotency: Option<IdempotencyCache>,
+    decision_ttl: Option<std::time::Duration>,
+    cache: Option<DecisionCache>,
+    limits: ArgLimits,
+    hardened: bool,
+    request_time: bool,
+    flags: Option<Arc<dyn FeatureFlagProvider>>,
+    flagged: BTreeMap<String, String>,
+    canary: Option<Canary>,
+    observers: Vec<Arc<dyn OutcomeObserver>>,
+    dataset: Option<DecisionSampler>,
+    fingerprinter: Option<Fingerprinter>,
+    consent: Option<Arc<dyn ConsentProvider>>,
+    grants: SessionGrants,
+    plans: PlanLedger,
+    sessions: Option<SessionHistory>,
+    backoff: Option<DenialBackoff>,
+    breaker: Option<CircuitBreaker>,
+    summarizer: Arc<dyn PolicySummarizer>,
+    tickets: AtomicU64,
+    #[cfg(feature = "json-schema")]
+    schemas: BTreeMap<String, Result<CompiledSchema, SchemaError>>,
 }
 
-impl Decision {
-    pub fn is_allowed(&self) -> bool {
-        matches!(self, Decision::Authorized)
-    }
+struct Outcome {
+    decision: Decision,
+    rule: Option<(String, AuditMode)>,
+    degraded: bool,
+}
 
-    pub fn as_str(&self) -> &'static str {
-        match self {
-            Decision::Authorized => "AUTHORIZED",
-            Decision::DeniedCapabilityNotFound => "DENIED_CAPABILITY_NOT_FOUND",
-            Decision::DeniedCapabilityDisabled => "DENIED_CAPABILITY_DISABLED",
-            Decision::DeniedPolicyViolation => "DENIED_POLICY_VIOLATION",
+impl From<Decision> for Outcome {
+    fn from(decision: Decision) -> Self {
+        Self {
+            decision,
+            rule: None,
+            degraded: false,
         }
     }
 }
 
-impl std::fmt::Display for Decision {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_str())
-    }
+fn sync_capability(engine: &mut PolicyEngine, capability: &Capability) {
+    engine.set_category(capability.name.clone(), capability.category);
+    engine.set_capability_default(capability.name.clone(), capability.default_effect);
+    engine.set_declared_keys(capability.name.clone(), declared_keys(capability));
 }
 
-pub struct CapabilityGate {
-    registry: CapabilityRegistry,
-    engine: PolicyEngine,
+/// How the gate evaluates a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Evaluation {
+    Live,
+    /// A lease renewal: live, except that the call is not counted again toward
+    /// session history, consent given for the lease stands and no ticket is
+    /// issued; see [`crate::lease`].
+    Renewal,
+    /// No side effects; see [`CapabilityGate::preview_record`].
+    Preview,
+}
+
+impl Evaluation {
+    fn is_live(self) -> bool {
+        self != Evaluation::Preview
+    }
 }
 
 impl CapabilityGate {
@@ -48,46 +130,1087 @@ impl CapabilityGate {
         Self {
             registry: CapabilityRegistry::new(),
             engine: PolicyEngine::new().with_default_effect(Effect::Deny),
+            audit: None,
+            sampler: Sampler::new(),
+            budget: EvaluationBudget::unlimited(),
+            degradation: BTreeMap::new(),
+            stale: StaleDecisionCache::new(),
+            lints: Vec::new(),
+            middleware: Vec::new(),
+            backend: None,
+            preflight: BTreeMap::new(),
+            idempotency: None,
+            decision_ttl: None,
+            cache: None,
+            limits: ArgLimits::default(),
+            hardened: false,
+            request_time: false,
+            flags: None,
+            flagged: BTreeMap::new(),
+            canary: None,
+            observers: Vec::new(),
+            dataset: None,
+            fingerprinter: None,
+            consent: None,
+            grants: SessionGrants::default(),
+            plans: PlanLedger::default(),
+            sessions: None,
+            backoff: None,
+            breaker: None,
+            summarizer: Arc::new(TemplateSummarizer),
+            tickets: AtomicU64::new(0),
+            #[cfg(feature = "json-schema")]
+            schemas: BTreeMap::new(),
         }
     }
 
     pub fn with_registry(mut self, registry: CapabilityRegistry) -> Self {
         self.registry = registry;
+        self.sync_categories();
+        self.clear_cached_decisions();
         self
     }
 
     pub fn with_engine(mut self, engine: PolicyEngine) -> Self {
         self.engine = engine;
+        self.grants.clear();
+        self.sync_categories();
+        self.clear_cached_decisions();
+        self
+    }
+
+    /// Drops cached decisions after a change that can alter them.
+    fn clear_cached_decisions(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        self.stale.clear();
+    }
+
+    pub fn engine(&self) -> &PolicyEngine {
+        &self.engine
+    }
+
+    pub fn registry(&self) -> &CapabilityRegistry {
+        &self.registry
+    }
+
+    /// Tells the engine the registered category, default effect and declared
+    /// argument keys of every capability, so `category:` rules follow the
+    /// registry rather than name inference.
+    fn sync_categories(&mut self) {
+        #[cfg(feature = "json-schema")]
+        for capability in self.registry.list() {
+            if let Some(schema) = &capability.schema {
+                let compiled = CompiledSchema::compile(schema);
+                self.schemas.insert(capability.name.clone(), compiled);
+            }
+        }
+        for capability in self.registry.list() {
+            sync_capability(&mut self.engine, capability);
+            if let Some(canary) = &mut self.canary {
+                sync_capability(&mut canary.engine, capability);
+            }
+        }
+    }
+
+    pub fn with_audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit = Some(sink);
+        self
+    }
+
+    /// Appends a middleware; see [`GateMiddleware`] for ordering.
+    pub fn with_middleware(mut self, middleware: Arc<dyn GateMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// Consults an external decision backend, combined with the local engine
+    /// according to `combination`.
+    pub fn with_backend(
+        mut self,
+        backend: Arc<dyn DecisionBackend>,
+        combination: Combination,
+    ) -> Self {
+        self.backend = Some((backend, combination));
+        self
+    }
+
+    pub fn with_flag_provider(mut self, provider: Arc<dyn FeatureFlagProvider>) -> Self {
+        self.flags = Some(provider);
+        self
+    }
+
+    /// Decides `fraction` of sessions with `engine` instead; see [`crate::canary`].
+    pub fn with_canary(mut self, engine: PolicyEngine, fraction: f64) -> Self {
+        self.canary = Some(Canary::new(engine, fraction));
+        self.sync_categories();
+        self
+    }
+
+    pub fn canary_report(&self) -> Option<CanaryReport> {
+        self.canary.as_ref().map(Canary::report)
+    }
+
+    /// Makes the canary engine the stable one. Returns `false` if no canary is running.
+    pub fn promote_canary(&mut self) -> bool {
+        match self.canary.take() {
+            Some(canary) => {
+                self.engine = canary.engine;
+                self.grants.clear();
+                self.clear_cached_decisions();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stops routing requests to the canary engine and discards it.
+    pub fn abort_canary(&mut self) -> bool {
+        self.canary.take().is_some()
+    }
+
+    /// Sends reported outcomes to `observer`; see [`crate::outcome`].
+    pub fn with_outcome_observer(mut self, observer: Arc<dyn OutcomeObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Samples anonymized decisions into a dataset; see [`crate::dataset`].
+    pub fn with_decision_sampler(mut self, sampler: DecisionSampler) -> Self {
+        self.dataset = Some(sampler);
+        self
+    }
+
+    /// Records a fingerprint of JSON arguments on every decision and keys the
+    /// decision cache by it; see [`crate::fingerprint`]. Cached decisions are
+    /// shared across excluded fields, so policies must not condition on them.
+    pub fn with_args_fingerprint(mut self, fingerprinter: Fingerprinter) -> Self {
+        self.fingerprinter = Some(fingerprinter);
+        self
+    }
+
+    /// Asks `provider` before allowing requests whose rule requires consent;
+    /// see [`crate::consent`].
+    pub fn with_consent_provider(mut self, provider: Arc<dyn ConsentProvider>) -> Self {
+        self.consent = Some(provider);
+        self
+    }
+
+    /// Keeps per-session history for `session.` conditions; see
+    /// [`crate::session`].
+    pub fn with_session_state(mut self) -> Self {
+        self.sessions = Some(SessionHistory::default());
+        self
+    }
+
+    /// Adds retry hints to repeated denials; see [`crate::backoff`].
+    pub fn with_denial_backoff(mut self, backoff: DenialBackoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Quarantines principals after repeated denials; see [`crate::breaker`].
+    pub fn with_circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.breaker = Some(breaker);
+        self
+    }
+
+    /// Phrases the deny rule behind a [`Denial`]; defaults to
+    /// [`TemplateSummarizer`].
+    pub fn with_summarizer(mut self, summarizer: Arc<dyn PolicySummarizer>) -> Self {
+        self.summarizer = summarizer;
+        self
+    }
+
+    pub(crate) fn breaker(&self) -> Option<&CircuitBreaker> {
+        self.breaker.as_ref()
+    }
+
+    pub(crate) fn sessions(&self) -> Option<&SessionHistory> {
+        self.sessions.as_ref()
+    }
+
+    /// Forgets the consent `session` gave for the rest of the session, and its
+    /// history.
+    pub fn end_session(&self, session: &str) {
+        self.grants.revoke_session(session);
+        if let Some(sessions) = &self.sessions {
+            sessions.end(session);
+        }
+    }
+
+    pub(crate) fn plans(&self) -> &PlanLedger {
+        &self.plans
+    }
+
+    /// Reports how the call `ticket` was issued for went.
+    pub fn report_outcome(&self, ticket: &Ticket, outcome: crate::outcome::Outcome) {
+        for observer in &self.observers {
+            observer.outcome(ticket, outcome);
+        }
+    }
+
+    /// Gates `capability` behind `flag`; see [`crate::flags`].
+    pub fn with_feature_flag(
+        mut self,
+        capability: impl Into<String>,
+        flag: impl Into<String>,
+    ) -> Self {
+        self.flagged.insert(capability.into(), flag.into());
+        self
+    }
+
+    /// Runs `check` before `capability` is authorized; see [`Preflight`].
+    pub fn with_preflight(
+        mut self,
+        capability: impl Into<String>,
+        check: Arc<dyn Preflight>,
+    ) -> Self {
+        self.preflight.insert(capability.into(), check);
+        self
+    }
+
+    /// Replays decisions for requests that reuse an idempotency key within `window`.
+    pub fn with_idempotency(self, window: std::time::Duration) -> Self {
+        self.with_idempotency_cache(IdempotencyCache::new(window))
+    }
+
+    /// Like [`CapabilityGate::with_idempotency`], with the scope and bound of
+    /// `cache`; see [`crate::idempotency`].
+    pub fn with_idempotency_cache(mut self, cache: IdempotencyCache) -> Self {
+        self.idempotency = Some(cache);
+        self
+    }
+
+    /// Caps how long any `Authorized` decision stays valid; rules may set a
+    /// shorter TTL with [`Rule::with_ttl`](crate::Rule::with_ttl).
+    pub fn with_decision_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.decision_ttl = Some(ttl);
+        self
+    }
+
+    /// Caches engine decisions; see [`crate::cache`].
+    pub fn with_decision_cache(mut self, cache: DecisionCache) -> Self {
+        self.stale = std::mem::take(&mut self.stale).with_dimensions(cache.dimensions().to_vec());
+        self.cache = Some(cache);
+        self
+    }
+
+    pub fn decision_cache(&self) -> Option<&DecisionCache> {
+        self.cache.as_ref()
+    }
+
+    /// Depth and size limits for JSON arguments; see [`crate::limits`].
+    pub fn with_arg_limits(mut self, limits: ArgLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Decodes percent-encoded path and URL arguments once before matching and
+    /// denies malformed or double-encoded ones; see [`crate::percent`].
+    pub fn with_hardened_matching(mut self, hardened: bool) -> Self {
+        self.hardened = hardened;
+        self
+    }
+
+    /// Honors [`RequestContext::evaluated_at_ms`] when authorizing. Off by
+    /// default: live requests are judged by the engine's clock, since the
+    /// override would let a caller step outside time windows and grace periods.
+    /// Replays and what-if evaluations use the engine or
+    /// [`crate::archive`] directly, which always honor it.
+    pub fn with_request_time(mut self, honored: bool) -> Self {
+        self.request_time = honored;
+        self
+    }
+
+    /// Bounds per-request evaluation work; exceeding it yields
+    /// [`Decision::DeniedEvaluationTimeout`].
+    pub fn with_budget(mut self, budget: EvaluationBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// How `capability` is decided while a resolver it depends on is unavailable.
+    pub fn with_degradation(
+        mut self,
+        capability: impl Into<String>,
+        mode: DegradationMode,
+    ) -> Self {
+        if let DegradationMode::ServeCached { max_staleness } = mode {
+            let age = self
+                .stale
+                .max_age()
+                .map_or(max_staleness, |a| a.max(max_staleness));
+            self.stale = std::mem::take(&mut self.stale).with_max_age(age);
+        }
+        self.degradation.insert(capability.into(), mode);
         self
     }
 
     pub fn register_capability(&mut self, capability: Capability) {
+        sync_capability(&mut self.engine, &capability);
+        if let Some(canary) = &mut self.canary {
+            sync_capability(&mut canary.engine, &capability);
+        }
+        #[cfg(feature = "json-schema")]
+        match &capability.schema {
+            Some(schema) => {
+                let compiled = CompiledSchema::compile(schema);
+                self.schemas.insert(capability.name.clone(), compiled);
+            }
+            None => {
+                self.schemas.remove(&capability.name);
+            }
+        }
         self.registry.register(capability);
+        self.clear_cached_decisions();
+    }
+
+    /// Enables or disables a registered capability, dropping cached decisions
+    /// so the change applies to the next request. `false` if it is unknown.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        let changed = match enabled {
+            true => self.registry.enable(name),
+            false => self.registry.disable(name),
+        };
+        if changed {
+            self.clear_cached_decisions();
+        }
+        changed
+    }
+
+    /// Like [`CapabilityGate::register_capability`], but refuses malformed
+    /// definitions; see [`crate::validation`].
+    pub fn register_capability_checked(
+        &mut self,
+        capability: Capability,
+    ) -> Result<(), Vec<CapabilityError>> {
+        let errors = capability.validate();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.register_capability(capability);
+        Ok(())
     }
 
-    pub fn add_policy(&mut self, policy: crate::policy::Policy) {
+    /// Adds a policy, rewriting deprecated capability names to their replacements.
+    /// Each rewrite is recorded as a lint, see [`CapabilityGate::lints`].
+    pub fn add_policy(&mut self, policy: Policy) {
+        let policy = self.prepare(policy);
         self.engine.add_policy(policy);
+        self.grants.clear();
+        self.clear_cached_decisions();
+    }
+
+    /// Adds a policy whose `Deny` rules are consulted before every other
+    /// policy; see [`PolicyEngine::add_guard`].
+    pub fn add_guard(&mut self, policy: Policy) {
+        let policy = self.prepare(policy);
+        self.engine.add_guard(policy);
+        self.grants.clear();
+        self.clear_cached_decisions();
+    }
+
+    /// Lints `policy` and applies capability renames to it.
+    fn prepare(&mut self, mut policy: Policy) -> Policy {
+        self.lints.retain(|l| l.policy != policy.name);
+        self.lints.extend(lint_policy(&policy, &self.registry));
+        let mut skew = skew_lints(&policy, self.engine.skew_tolerance());
+        locate(&policy, &mut skew);
+        self.lints.extend(skew);
+        policy.apply_renames(&self.registry);
+        policy
+    }
+
+    /// Like [`CapabilityGate::add_policy`], but refuses the policy if it has any
+    /// error-severity lint, such as a rule widening a capability's declared scope.
+    pub fn add_policy_checked(&mut self, policy: Policy) -> Result<(), Vec<Lint>> {
+        let errors: Vec<Lint> = lint_policy(&policy, &self.registry)
+            .into_iter()
+            .filter(|l| l.severity == Severity::Error)
+            .collect();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.add_policy(policy);
+        Ok(())
+    }
+
+    pub fn lints(&self) -> &[Lint] {
+        &self.lints
     }
 
-    pub fn authorize(&self, tool: &str, args: &serde_json::Value) -> Decision {
+    pub fn authorize<A: Args + ?Sized>(&self, tool: &str, args: &A) -> Decision {
+        self.authorize_with(tool, args, &RequestContext::default())
+    }
+
+    pub fn authorize_with(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+    ) -> Decision {
+        self.authorize_record(tool, args, ctx).decision
+    }
+
+    /// Authorizes like [`CapabilityGate::authorize_with`], returning the full
+    /// [`DecisionRecord`] instead of just the decision.
+    pub fn authorize_record(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+    ) -> DecisionRecord {
+        self.authorize_as(tool, args, ctx, Evaluation::Live)
+    }
+
+    /// The decision [`CapabilityGate::authorize_record`] would return, without
+    /// its side effects: nothing is audited, sampled, cached or counted toward
+    /// session history, breakers or backoff, and no consent is asked. A rule
+    /// needing consent the session has not granted is reported as `pending`.
+    pub(crate) fn preview_record(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+    ) -> DecisionRecord {
+        self.authorize_as(tool, args, ctx, Evaluation::Preview)
+    }
+
+    fn authorize_as(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+        evaluation: Evaluation,
+    ) -> DecisionRecord {
+        let live = evaluation.is_live();
+        let request;
+        let ctx = match ctx.evaluated_at_ms.is_some() && !self.request_time {
+            true => {
+                request = RequestContext {
+                    evaluated_at_ms: None,
+                    ..ctx.clone()
+                };
+                &request
+            }
+            false => ctx,
+        };
+        let invalid = args
+            .as_json()
+            .and_then(|json| match self.limits.check(json) {
+                Err(e) => Some((e.reason(), e.to_string())),
+                Ok(()) if self.hardened => percent::validate(json)
+                    .err()
+                    .map(|e| (e.reason(), e.to_string())),
+                Ok(()) => None,
+            })
+            .or_else(|| self.schema_violation(tool, args.as_json()));
+        let fingerprint = self
+            .fingerprinter
+            .as_ref()
+            .zip(args.as_json())
+            .map(|(fingerprinter, json)| fingerprinter.fingerprint(json));
+        if let Some((reason, message)) = invalid {
+            let mut record = DecisionRecord::new(Decision::DeniedInvalidArguments, tool)
+                .with_detail("invalid_arguments", message)
+                .with_detail(REASON_DETAIL, reason.code());
+            if let Some(fingerprint) = fingerprint {
+                record = record.with_detail(FINGERPRINT_DETAIL, fingerprint);
+            }
+            record.capability = self.registry.resolve(tool).to_string();
+            record.principal = ctx.principal.clone();
+            if live {
+                self.record(&record, None);
+                self.sample(&record, args.as_json());
+            }
+            return record;
+        }
+
+        let replay_key = match (&self.idempotency, &ctx.idempotency_key) {
+            (Some(cache), Some(key)) if live => IdempotencyCache::args_digest(args)
+                .map(|digest| (cache.key(ctx, self.registry.resolve(tool), key), digest)),
+            _ => None,
+        };
+        if let (Some(cache), Some((key, digest))) = (&self.idempotency, &replay_key) {
+            match cache.get(key, digest) {
+                Some(Replay::Record(mut record)) => {
+                    record.replayed = true;
+                    self.record(&record, None);
+                    return record;
+                }
+                Some(Replay::Mismatch) => {
+                    let reason = Reason::IdempotencyKeyMismatch;
+                    let mut record = DecisionRecord::new(Decision::DeniedInvalidArguments, tool)
+                        .with_detail(
+                            "invalid_arguments",
+                            "idempotency key reused with other arguments",
+                        )
+                        .with_detail(REASON_DETAIL, reason.code());
+                    record.capability = self.registry.resolve(tool).to_string();
+                    record.principal = ctx.principal.clone();
+                    self.record(&record, None);
+                    return record;
+                }
+                None => {}
+            }
+        }
+
+        let record = match self.hardened {
+            true => self.evaluate_record(
+                tool,
+                &percent::Decoded(view_of(args)),
+                ctx,
+                args.as_json(),
+                fingerprint.as_deref(),
+                evaluation,
+            ),
+            false => self.evaluate_record(
+                tool,
+                view_of(args),
+                ctx,
+                args.as_json(),
+                fingerprint.as_deref(),
+                evaluation,
+            ),
+        };
+        if let (Some(cache), Some((key, digest))) = (&self.idempotency, replay_key) {
+            cache.insert(key, digest, record.clone());
+        }
+        if live {
+            self.sample(&record, args.as_json());
+        }
+        record
+    }
+
+    /// Whether the user consents, unless the session already granted it.
+    fn consent(
+        &self,
+        capability: &str,
+        rule: &str,
+        fingerprint: &str,
+        ctx: &RequestContext,
+        args: Option<&serde_json::Value>,
+    ) -> Result<&'static str, Reason> {
+        if self.grants.contains(ctx, capability, fingerprint) {
+            return Ok("session");
+        }
+        let provider = self.consent.as_ref().ok_or(Reason::ConsentUnavailable)?;
+        let request = ConsentRequest {
+            capability,
+            rule,
+            ctx,
+            args,
+        };
+        let answer = provider.request_consent(&request);
+        if !answer.approved {
+            return Err(Reason::ConsentDeclined);
+        }
+        if answer.remember_for_session {
+            self.grants.grant(ctx, capability, fingerprint);
+        }
+        Ok("approved")
+    }
+
+    fn sample(&self, record: &DecisionRecord, args: Option<&serde_json::Value>) {
+        if let Some(sampler) = &self.dataset {
+            sampler.observe(record, args);
+        }
+    }
+
+    /// Why `json` fails the capability's schema; see [`crate::schema`].
+    #[cfg(feature = "json-schema")]
+    fn schema_violation(
+        &self,
+        tool: &str,
+        json: Option<&serde_json::Value>,
+    ) -> Option<(Reason, String)> {
+        let message = match (self.schemas.get(self.registry.resolve(tool))?, json) {
+            // Arguments the schema cannot see fail closed.
+            (Ok(_), None) => "arguments are not JSON; the capability schema requires JSON".into(),
+            (Ok(schema), Some(json)) => {
+                let violations = schema.validate(json);
+                if violations.is_empty() {
+                    return None;
+                }
+                let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+                messages.join("; ")
+            }
+            // An unusable schema fails closed.
+            (Err(e), _) => format!("capability schema is invalid: {}", e),
+        };
+        Some((Reason::ArgsSchemaViolation, message))
+    }
+
+    #[cfg(not(feature = "json-schema"))]
+    fn schema_violation(
+        &self,
+        _tool: &str,
+        _json: Option<&serde_json::Value>,
+    ) -> Option<(Reason, String)> {
+        None
+    }
+
+    fn evaluate_record(
+        &self,
+        tool: &str,
+        args: &dyn ArgView,
+        ctx: &RequestContext,
+        json: Option<&serde_json::Value>,
+        fingerprint: Option<&str>,
+        evaluation: Evaluation,
+    ) -> DecisionRecord {
+        let live = evaluation.is_live();
+        let mut ctx = std::borrow::Cow::Borrowed(ctx);
+        let mut veto = None;
+        for middleware in &self.middleware {
+            match middleware.before(tool, args, ctx.to_mut()) {
+                Some(decision) if !decision.is_allowed() => {
+                    veto = Some((decision, middleware.name().to_string()));
+                    break;
+                }
+                _ => {}
+            }
+        }
+
+        let state = self.sessions.as_ref().and_then(|s| s.state(&ctx));
+        let session_view;
+        let args = match &state {
+            Some(state) => {
+                session_view = SessionView { args, state };
+                &session_view as &dyn ArgView
+            }
+            None => args,
+        };
+
+        let capability = self.registry.resolve(tool);
+        let canary = self.canary.as_ref().filter(|c| c.routes(&ctx));
+        let engine = canary.map_or(&self.engine, |c| &c.engine);
+        let quarantined = self
+            .breaker
+            .as_ref()
+            .is_some_and(|b| b.blocks(ctx.principal.as_deref(), capability));
+        let cache_key = self
+            .cache
+            .as_ref()
+            .filter(|_| {
+                canary.is_none()
+                    && !quarantined
+                    && !engine.uses_session_state(capability)
+                    && !engine.uses_clock(capability)
+            })
+            .and_then(|_| {
+                let projected = engine
+                    .cache_key_fields(capability)
+                    .filter(|_| self.backend.is_none())
+                    .map(|fields| projected_args_key(args, &fields));
+                let args_key = projected
+                    .or_else(|| fingerprint.map(String::from))
+                    .or_else(|| args.cache_key());
+                DecisionCache::key(&ctx, capability, args_key)
+            });
+        let cached = match (&self.cache, &cache_key, &veto) {
+            (Some(cache), Some(key), None) => cache.get(&ctx, key),
+            _ => None,
+        };
+        let from_cache = cached.is_some();
+        let mut outcome = match (&veto, cached) {
+            (Some((decision, _)), _) => Outcome::from(*decision),
+            (None, _) if quarantined => Outcome::from(Decision::DeniedQuarantined),
+            (None, Some(cached)) => Outcome {
+                decision: cached.decision,
+                rule: cached.rule.map(|id| {
+                    let mode = engine.rule(&id).map(|r| r.audit).unwrap_or_default();
+                    (id, mode)
+                }),
+                degraded: false,
+            },
+            (None, None) => self.decide(engine, tool, args, &ctx, live),
+        };
+        if let (Some(cache), Some(key), None, false, false, true) = (
+            &self.cache,
+            cache_key,
+            &veto,
+            from_cache,
+            outcome.degraded,
+            live,
+        ) {
+            let cached = CachedDecision {
+                decision: outcome.decision,
+                rule: outcome.rule.as_ref().map(|(id, _)| id.clone()),
+            };
+            cache.insert(&ctx, key, cached);
+        }
+        let divergence = match (canary, outcome.decision, &veto) {
+            (Some(canary), Decision::Authorized | Decision::DeniedPolicyViolation, None)
+                if live =>
+            {
+                let stable = self.engine.evaluate_with(&ctx, capability, "execute", args);
+                let candidate = match outcome.decision.is_allowed() {
+                    true => Effect::Allow,
+                    false => Effect::Deny,
+                };
+                Some(canary.observe(stable, candidate))
+            }
+            _ => None,
+        };
+        let mut flag = None;
+        if let (true, Some(name)) = (outcome.decision.is_allowed(), self.flagged.get(capability)) {
+            let enabled = self
+                .flags
+                .as_ref()
+                .and_then(|provider| provider.is_enabled(name, &ctx));
+            let state = match enabled {
+                Some(true) => "on",
+                Some(false) => "off",
+                None => "unknown",
+            };
+            if enabled != Some(true) {
+                outcome.decision = Decision::DeniedCapabilityDisabled;
+            }
+            flag = Some(format!("{}={}", name, state));
+        }
+        let mut preflight = None;
+        if let (true, Some(check)) = (
+            outcome.decision.is_allowed(),
+            self.preflight.get(capability),
+        ) {
+            if let PreflightResult::Failed(reason) = check.check(args) {
+                outcome.decision = Decision::DeniedPreflightFailed;
+                preflight = Some(reason);
+            }
+        }
+        let mut consent = None;
+        if let (true, Some((id, _))) = (outcome.decision.is_allowed(), &outcome.rule) {
+            if let Some(rule) = engine.rule(id).filter(|rule| rule.require_user_consent) {
+                let fingerprint = rule_fingerprint(id, rule);
+                let given = match evaluation {
+                    Evaluation::Live => self.consent(capability, id, &fingerprint, &ctx, json),
+                    Evaluation::Renewal => Ok("lease"),
+                    _ if self.grants.contains(&ctx, capability, &fingerprint) => Ok("session"),
+                    Evaluation::Preview => Ok("pending"),
+                };
+                if given.is_err() {
+                    outcome.decision = Decision::DeniedConsentRefused;
+                }
+                consent = Some(given);
+            }
+        }
+        let mut record = DecisionRecord::new(outcome.decision, tool);
+        record.capability = capability.to_string();
+        record.principal = ctx.principal.clone();
+        record.degraded = outcome.degraded;
+        let mut mode = outcome.rule.map(|(id, mode)| {
+            record.rule = Some(id);
+            mode
+        });
+        let provenance = record
+            .rule
+            .as_deref()
+            .and_then(|id| engine.rule(id))
+            .and_then(|rule| rule.provenance.as_ref());
+        if let Some(provenance) = provenance {
+            record = record.with_detail("source", provenance.to_string());
+        }
+        let matched = record.rule.as_deref().and_then(|id| engine.rule(id));
+        let flagged = match matched.map(|rule| rule.effect) {
+            Some(Effect::AllowWithWarning) => Some(WARNING_EFFECT),
+            Some(Effect::Audit) => Some(AUDIT_EFFECT),
+            _ => None,
+        };
+        if let (true, Some(effect)) = (record.is_allowed(), flagged) {
+            record = record.with_detail(EFFECT_DETAIL, effect);
+            mode = Some(AuditMode::Always);
+        }
+        if record.is_allowed() {
+            let rule_ttl = record
+                .rule
+                .as_deref()
+                .and_then(|id| engine.rule(id))
+                .and_then(|rule| rule.ttl_secs)
+                .map(|secs| secs.saturating_mul(1000));
+            let gate_ttl = self.decision_ttl.map(|ttl| ttl.as_millis() as u64);
+            record.valid_for_ms = match (rule_ttl, gate_ttl) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+        }
+        if let Some(replacement) = self.registry.deprecated(tool) {
+            record = record.with_detail("deprecated_alias", replacement);
+        }
+        if let Some((_, name)) = veto {
+            record = record
+                .with_detail("vetoed_by", name)
+                .with_detail(REASON_DETAIL, Reason::VetoedByMiddleware.code());
+        } else if record.decision == Decision::DeniedPolicyViolation && self.backend.is_none() {
+            let reason = match record.rule {
+                Some(_) => Reason::DenyRuleMatched,
+                None => Reason::NoMatchingRule,
+            };
+            record = record.with_detail(REASON_DETAIL, reason.code());
+        }
+        if record.is_allowed() {
+            let pending = engine.grace_denials(&ctx, capability, args);
+            if !pending.is_empty() {
+                let pending: Vec<String> = pending.iter().map(ToString::to_string).collect();
+                record = record.with_detail("would_deny", pending.join(", "));
+            }
+        }
+        if let Some(divergence) = divergence {
+            record = record.with_detail(CANARY_DETAIL, divergence);
+        }
+        if let Some(flag) = flag {
+            if !record.is_allowed() {
+                record = record.with_detail(REASON_DETAIL, Reason::FeatureFlagOff.code());
+            }
+            record = record.with_detail("feature_flag", flag);
+        }
+        if let Some(reason) = preflight {
+            record = record.with_detail("preflight", reason);
+        }
+        match consent {
+            Some(Ok(how)) => record = record.with_detail(CONSENT_DETAIL, how),
+            Some(Err(reason)) => record = record.with_detail(REASON_DETAIL, reason.code()),
+            None => {}
+        }
+        if from_cache {
+            record = record.with_detail("cached", "true");
+        }
+        if let Some(fingerprint) = fingerprint {
+            record = record.with_detail(FINGERPRINT_DETAIL, fingerprint);
+        }
+        if !live {
+            return record;
+        }
+        for middleware in &self.middleware {
+            let mut details = std::mem::take(&mut record.details);
+            middleware.after(&record, &mut details);
+            record.details = details;
+        }
+        if let Some(breaker) = &self.breaker {
+            breaker.observe(&record);
+        }
+        if let Some(backoff) = &self.backoff {
+            record.retry_after_ms = backoff.observe(&record);
+        }
+        if let (true, Some(sessions), Evaluation::Live) =
+            (record.is_allowed(), &self.sessions, evaluation)
+        {
+            sessions.record(&ctx, capability);
+        }
+        self.record(&record, mode);
+        record
+    }
+
+    /// The engine that decided `record`: the canary's if it was routed there.
+    pub(crate) fn deciding_engine(&self, record: &DecisionRecord) -> &PolicyEngine {
+        match (&self.canary, record.details.contains_key(CANARY_DETAIL)) {
+            (Some(canary), true) => &canary.engine,
+            _ => &self.engine,
+        }
+    }
+
+    /// Authorizes as a `Result`, so callers can propagate denials with `?`.
+    pub fn try_authorize(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+    ) -> Result<Authorization, Denial> {
+        self.try_authorize_as(tool, args, ctx, Evaluation::Live)
+    }
+
+    /// Issues a ticket only for live requests: a renewed lease keeps its own.
+    pub(crate) fn try_authorize_as(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+        evaluation: Evaluation,
+    ) -> Result<Authorization, Denial> {
+        let record = self.authorize_as(tool, args, ctx, evaluation);
+        let engine = self.deciding_engine(&record);
+        if !record.is_allowed() {
+            let mut denial = Denial::new(record);
+            if denial.decision() == Decision::DeniedPolicyViolation {
+                let capability = &denial.record.capability;
+                denial.violations = engine.param_violations(capability, view_of(args));
+                let rule = denial.record.rule.as_deref().and_then(|id| engine.rule(id));
+                denial.summary = rule.map(|rule| self.summarizer.summarize_rule(rule));
+            }
+            return Err(denial);
+        }
+
+        let mut authorization = Authorization::new(record);
+        if evaluation == Evaluation::Live {
+            let ticket = Ticket {
+                id: self.tickets.fetch_add(1, Ordering::Relaxed),
+                capability: authorization.record.capability.clone(),
+                principal: authorization.record.principal.clone(),
+                issued_at_ms: authorization.record.timestamp_ms,
+            };
+            for observer in &self.observers {
+                observer.started(&ticket);
+            }
+            authorization.ticket = Some(ticket);
+        }
+        let record = &authorization.record;
+        if let Some(rule) = record.rule.as_deref().and_then(|id| engine.rule(id)) {
+            authorization.conditions = rule.conditions.clone();
+            authorization.constraints = rule.param_constraints.clone();
+        }
+        if record.degraded {
+            authorization.obligations.push(Obligation::Recheck);
+        }
+        if let Some(replacement) = record.details.get("deprecated_alias") {
+            authorization.obligations.push(Obligation::UseCapability {
+                replacement: replacement.clone(),
+            });
+        }
+        Ok(authorization)
+    }
+
+    fn decide(
+        &self,
+        engine: &PolicyEngine,
+        tool: &str,
+        args: &dyn ArgView,
+        ctx: &RequestContext,
+        live: bool,
+    ) -> Outcome {
+        let tool = self.registry.resolve(tool);
         if !self.registry.is_registered(tool) {
-            return Decision::DeniedCapabilityNotFound;
+            return Decision::DeniedCapabilityNotFound.into();
         }
 
         if !self.registry.is_enabled(tool) {
-            return Decision::DeniedCapabilityDisabled;
+            return Decision::DeniedCapabilityDisabled.into();
         }
 
-        let effect = self.engine.evaluate(tool, "execute", args);
+        let mut meter = Meter::new(self.budget);
+        let mode = self.degradation.get(tool).copied().unwrap_or_default();
+        let backend_only = matches!(self.backend, Some((_, Combination::BackendOnly)));
+        let evaluated = match backend_only {
+            true => Ok(None),
+            false => engine.find_rule_metered(ctx, tool, "execute", args, &mut meter),
+        };
+        let matched = match evaluated {
+            Ok(matched) => matched,
+            Err(EvaluationError::Budget(_)) => return Decision::DeniedEvaluationTimeout.into(),
+            Err(EvaluationError::Resolver(GroupError::Unavailable(_))) => {
+                return self.degrade(mode, tool, args, ctx);
+            }
+            Err(EvaluationError::Resolver(_) | EvaluationError::Inheritance(_)) => {
+                return Decision::DeniedPolicyViolation.into()
+            }
+        };
+        let local = matched.map(|m| m.rule.effect);
+        // A local deny is final: the backend cannot lift it, so it is not asked,
+        // and an unavailable backend cannot degrade it into an allow.
+        let locally_denied = local == Some(Effect::Deny);
+        let effect = match &self.backend {
+            Some((_, Combination::LocalFirst)) if local.is_some() => local,
+            Some(_) if locally_denied => local,
+            Some((backend, combination)) => {
+                let request = BackendRequest {
+                    ctx,
+                    capability: tool,
+                    action: "execute",
+                    args,
+                };
+                match backend.decide(&request) {
+                    Ok(effect) if effect.allows() && *combination == Combination::BothMustAllow => {
+                        local.or(Some(engine.default_effect_for(tool)))
+                    }
+                    Ok(effect) => Some(effect),
+                    Err(_) => return self.degrade(mode, tool, args, ctx),
+                }
+            }
+            None => local,
+        }
+        .unwrap_or_else(|| engine.default_effect_for(tool));
+
+        let decision = match effect.allows() {
+            true => Decision::Authorized,
+            false => Decision::DeniedPolicyViolation,
+        };
+        if let (DegradationMode::ServeCached { .. }, true) = (mode, live) {
+            if let Some(key) = self.stale.key(ctx, tool, args) {
+                self.stale.store(key, decision);
+            }
+        }
+        Outcome {
+            decision,
+            rule: matched.map(|m| (m.id(), m.rule.audit)),
+            degraded: false,
+        }
+    }
+
+    fn degrade(
+        &self,
+        mode: DegradationMode,
+        tool: &str,
+        args: &dyn ArgView,
+        ctx: &RequestContext,
+    ) -> Outcome {
+        let decision = match mode {
+            DegradationMode::FailClosed => Decision::DeniedResolverUnavailable,
+            DegradationMode::FailOpen => Decision::Authorized,
+            DegradationMode::ServeCached { max_staleness } => self
+                .stale
+                .key(ctx, tool, args)
+                .and_then(|key| self.stale.get(&key, max_staleness))
+                .unwrap_or(Decision::DeniedResolverUnavailable),
+        };
+        Outcome {
+            decision,
+            rule: None,
+            degraded: true,
+        }
+    }
+
+    pub(crate) fn record(&self, record: &DecisionRecord, mode: Option<AuditMode>) {
+        let Some(sink) = &self.audit else {
+            return;
+        };
+
+        let mut sample_rate = None;
+        if let (true, Some(id), Some(mode)) = (record.is_allowed(), &record.rule, mode) {
+            match mode {
+                AuditMode::Always => {}
+                AuditMode::Never => return,
+                AuditMode::Sampled(rate) => {
+                    if !self.sampler.keep(id, rate) {
+                        return;
+                    }
+                    sample_rate = Some(rate);
+                }
+            }
+        }
 
-        match effect {
-            Effect::Allow => Decision::Authorized,
-            Effect::Deny => Decision::DeniedPolicyViolation,
+        let mut event = AuditEvent::new(&record.tool, record.decision.code());
+        event.timestamp_ms = record.timestamp_ms;
+        event.principal = record.principal.clone();
+        event.rule = record.rule.clone();
+        event.sample_rate = sample_rate;
+        event.degraded = record.degraded;
+        event.details = record.details.clone();
+        if let Some(retry_after) = record.retry_after_ms {
+            event
+                .details
+                .insert("retry_after_ms".to_string(), retry_after.to_string());
+        }
+        if record.replayed {
+            event
+                .details
+                .insert("replayed".to_string(), "true".to_string());
         }
+        sink.record(&event);
     }
 
     pub fn check(&self, tool: &str) -> bool {
-        self.authorize(tool, &serde_json::json!({})).is_allowed()
+        self.authorize(tool, &()).is_allowed()
     }
 }
 
@@ -103,6 +1226,58 @@ mod tests {
     use crate::capability::Capability;
     use crate::policy::{Policy, Rule};
 
+    #[test]
+    fn test_deeply_nested_args_denied() {
+        let mut gate =
+            CapabilityGate::new().with_arg_limits(ArgLimits::default().with_max_depth(8));
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("shell")));
+
+        let deep = (0..9).fold(
+            serde_json::json!("x"),
+            |inner, _| serde_json::json!({ "a": inner }),
+        );
+        let record = gate.authorize_record("shell", &deep, &RequestContext::default());
+        assert_eq!(record.decision, Decision::DeniedInvalidArguments);
+        assert_eq!(record.decision.category(), DecisionCategory::Invalid);
+        assert_eq!(
+            record.details.get("invalid_arguments").map(String::as_str),
+            Some("arguments nest deeper than 8 levels")
+        );
+        assert_eq!(record.details[REASON_DETAIL], "ARGS_TOO_DEEP");
+        assert!(gate
+            .authorize("shell", &serde_json::json!({"a": {"b": 1}}))
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_hardened_matching_decodes_paths() {
+        let policy =
+            Policy::new("default", "1.0")
+                .with_rule(Rule::deny("fs.read").with_conditions(vec![
+                    crate::policy::Condition::new("path", "contains", serde_json::json!("..")),
+                ]))
+                .with_rule(Rule::allow("fs.read"));
+        let gate = |hardened| {
+            let mut gate = CapabilityGate::new().with_hardened_matching(hardened);
+            gate.register_capability(Capability::new("fs.read", "Read files"));
+            gate.add_policy(policy.clone());
+            gate
+        };
+
+        let encoded = serde_json::json!({"path": "/work/%2e%2e/etc/passwd"});
+        assert!(gate(false).authorize("fs.read", &encoded).is_allowed());
+        assert_eq!(
+            gate(true).authorize("fs.read", &encoded),
+            Decision::DeniedPolicyViolation
+        );
+        let double = serde_json::json!({"path": "/work/%252e%252e/etc"});
+        assert_eq!(
+            gate(true).authorize("fs.read", &double),
+            Decision::DeniedInvalidArguments
+        );
+    }
+
     #[test]
     fn test_unknown_capability() {
         let gate = CapabilityGate::new();
@@ -143,4 +1318,368 @@ mod tests {
         let result = gate.authorize("shell", &serde_json::json!({}));
         assert_eq!(result, Decision::DeniedPolicyViolation);
     }
+
+    #[test]
+    fn test_renamed_capability_authorized_under_old_name() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("http.get", "HTTP GET"));
+        gate.registry.register_alias("web.fetch", "http.get");
+
+        gate.add_policy(Policy::new("legacy", "1.0").with_rule(Rule::allow("web.fetch")));
+        assert_eq!(gate.lints().len(), 1);
+
+        assert_eq!(
+            gate.authorize("web.fetch", &serde_json::json!({})),
+            Decision::Authorized
+        );
+        assert_eq!(
+            gate.authorize("http.get", &serde_json::json!({})),
+            Decision::Authorized
+        );
+
+        let record = gate.authorize_record("web.fetch", &(), &RequestContext::default());
+        assert_eq!(record.capability, "http.get");
+        assert_eq!(record.rule.as_deref(), Some("legacy#0"));
+        assert_eq!(record.details["deprecated_alias"], "http.get");
+    }
+
+    #[test]
+    fn test_category_rules_follow_registry() {
+        use crate::capability::CapabilityCategory;
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        gate.register_capability(
+            Capability::new("browser", "Headless browser")
+                .with_category(CapabilityCategory::Process),
+        );
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::deny("category:Process"))
+                .with_rule(Rule::allow("*")),
+        );
+
+        let args = serde_json::json!({});
+        assert_eq!(
+            gate.authorize("shell", &args),
+            Decision::DeniedPolicyViolation
+        );
+        assert_eq!(
+            gate.authorize("browser", &args),
+            Decision::DeniedPolicyViolation
+        );
+        assert_eq!(gate.authorize("fs.read", &args), Decision::Authorized);
+    }
+
+    #[test]
+    fn test_try_authorize() {
+        use crate::condition::ParamConstraint;
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("git", "Run git"));
+        gate.add_policy(
+            Policy::new("git", "1.0").with_rule(
+                Rule::allow("git").with_param_constraint(ParamConstraint::one_of(
+                    "subcommand",
+                    ["status", "diff"],
+                )),
+            ),
+        );
+        let ctx = RequestContext::default();
+
+        let granted = gate
+            .try_authorize("git", &serde_json::json!({ "subcommand": "status" }), &ctx)
+            .unwrap();
+        assert_eq!(granted.constraints.len(), 1);
+        assert!(granted.obligations.is_empty());
+
+        let denied = gate
+            .try_authorize("git", &serde_json::json!({ "subcommand": "push" }), &ctx)
+            .unwrap_err();
+        assert_eq!(denied.decision(), Decision::DeniedPolicyViolation);
+        assert_eq!(
+            denied.to_string(),
+            "`git` denied: DENIED_POLICY_VIOLATION; `subcommand` must be one of [\"status\", \"diff\"], got \"push\""
+        );
+    }
+
+    #[test]
+    fn test_middleware_can_veto_and_enrich() {
+        use crate::audit::AuditLog;
+        use crate::middleware::GateMiddleware;
+
+        struct RateLimit;
+        impl GateMiddleware for RateLimit {
+            fn name(&self) -> &str {
+                "rate-limit"
+            }
+            fn before(
+                &self,
+                tool: &str,
+                _args: &dyn ArgView,
+                ctx: &mut RequestContext,
+            ) -> Option<Decision> {
+                ctx.principal.get_or_insert_with(|| "anonymous".to_string());
+                (tool == "shell").then_some(Decision::DeniedPolicyViolation)
+            }
+            fn after(&self, _record: &DecisionRecord, details: &mut BTreeMap<String, String>) {
+                details.insert("region".into(), "eu".into());
+            }
+        }
+
+        let log = Arc::new(AuditLog::new());
+        let mut gate = CapabilityGate::new()
+            .with_audit_sink(log.clone())
+            .with_middleware(Arc::new(RateLimit));
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("*")));
+
+        let ctx = RequestContext::default();
+        let vetoed = gate.authorize_record("shell", &(), &ctx);
+        assert_eq!(vetoed.decision, Decision::DeniedPolicyViolation);
+        assert_eq!(vetoed.details["vetoed_by"], "rate-limit");
+        assert_eq!(vetoed.principal.as_deref(), Some("anonymous"));
+        assert!(gate.authorize("fs.read", &()).is_allowed());
+
+        let records = log.records();
+        assert_eq!(records[1].event.details["region"], "eu");
+    }
+
+    #[test]
+    fn test_decision_ttl() {
+        use std::time::Duration;
+        let mut gate = CapabilityGate::new().with_decision_ttl(Duration::from_secs(300));
+        gate.register_capability(Capability::new("fs.tail", "Tail a file"));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("fs.tail").with_ttl(Duration::from_secs(30)))
+                .with_rule(Rule::allow("fs.read")),
+        );
+
+        let ctx = RequestContext::default();
+        let tail = gate.authorize_record("fs.tail", &(), &ctx);
+        assert_eq!(tail.valid_for(), Some(Duration::from_secs(30)));
+        assert!(tail.is_expired_at(tail.timestamp_ms + 30_000));
+        assert!(!tail.is_expired_at(tail.timestamp_ms + 29_999));
+        let read = gate.authorize_record("fs.read", &(), &ctx);
+        assert_eq!(read.valid_for(), Some(Duration::from_secs(300)));
+        assert_eq!(gate.authorize_record("shell", &(), &ctx).valid_for(), None);
+    }
+
+    #[test]
+    fn test_decisions_are_audited() {
+        let log = Arc::new(crate::audit::AuditLog::new());
+        let mut gate = CapabilityGate::new().with_audit_sink(log.clone());
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+
+        gate.authorize("fs.read", &serde_json::json!({}));
+        gate.authorize("unknown", &serde_json::json!({}));
+
+        let records = log.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].event.decision, "DENIED_CAPABILITY_NOT_FOUND");
+        assert!(log.verify_chain().is_ok());
+    }
+
+    #[test]
+    fn test_capability_default_effects() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(
+            Capability::new("fs.read", "Read files").with_default_effect(Effect::Allow),
+        );
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(
+            Rule::deny("fs.read").with_conditions(vec![crate::policy::Condition::new(
+                "path",
+                "starts_with",
+                serde_json::json!("/etc"),
+            )]),
+        ));
+
+        let tmp = serde_json::json!({ "path": "/tmp/a" });
+        assert_eq!(gate.authorize("fs.read", &tmp), Decision::Authorized);
+        let etc = serde_json::json!({ "path": "/etc/shadow" });
+        assert_eq!(
+            gate.authorize("fs.read", &etc),
+            Decision::DeniedPolicyViolation
+        );
+        assert_eq!(
+            gate.authorize("shell", &tmp),
+            Decision::DeniedPolicyViolation
+        );
+    }
+
+    #[test]
+    fn test_watch_mode_effects_allow_and_are_always_audited() {
+        let log = Arc::new(crate::audit::AuditLog::new());
+        let mut gate = CapabilityGate::new().with_audit_sink(log.clone());
+        gate.register_capability(Capability::new("fs.write", "Write files"));
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        let watched = |resource, effect| Rule {
+            effect,
+            ..Rule::allow(resource).with_audit(AuditMode::Never)
+        };
+        gate.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(watched("fs.write", Effect::AllowWithWarning))
+                .with_rule(watched("shell", Effect::Audit)),
+        );
+
+        let write = gate.authorize_record("fs.write", &(), &RequestContext::new());
+        let shell = gate.authorize_record("shell", &(), &RequestContext::new());
+        assert!(write.is_allowed() && shell.is_allowed());
+        assert_eq!(write.details[EFFECT_DETAIL], WARNING_EFFECT);
+        assert!(!write.counts_as_denied());
+        assert!(shell.counts_as_denied());
+        assert_eq!(log.records().len(), 2);
+    }
+
+    #[test]
+    fn test_per_rule_audit_modes() {
+        let log = Arc::new(crate::audit::AuditLog::new());
+        let mut gate = CapabilityGate::new().with_audit_sink(log.clone());
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.register_capability(Capability::new("fs.stat", "Stat files"));
+        gate.register_capability(Capability::new("shell", "Shell commands"));
+        gate.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("fs.read").with_audit(AuditMode::Never))
+                .with_rule(Rule::allow("fs.stat").with_audit(AuditMode::Sampled(0.5)))
+                .with_rule(Rule::deny("shell").with_audit(AuditMode::Never)),
+        );
+
+        let args = serde_json::json!({});
+        for _ in 0..4 {
+            gate.authorize("fs.read", &args);
+            gate.authorize("fs.stat", &args);
+        }
+        gate.authorize("shell", &args);
+
+        let records = log.records();
+        assert_eq!(
+            records.iter().filter(|r| r.event.tool == "fs.read").count(),
+            0
+        );
+        assert_eq!(
+            records.iter().filter(|r| r.event.tool == "fs.stat").count(),
+            2
+        );
+        assert_eq!(
+            records.last().unwrap().event.rule.as_deref(),
+            Some("default#2")
+        );
+        assert_eq!(records[0].event.sample_rate, Some(0.5));
+    }
+
+    #[test]
+    fn test_evaluation_budget_fails_closed() {
+        let log = Arc::new(crate::audit::AuditLog::new());
+        let mut gate = CapabilityGate::new()
+            .with_audit_sink(log.clone())
+            .with_budget(EvaluationBudget::unlimited().with_max_steps(5));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+
+        let mut policy = Policy::new("large", "1.0");
+        for i in 0..10 {
+            policy = policy.with_rule(Rule::deny("fs.read").with_conditions(vec![
+                crate::policy::Condition::new("path", "eq", serde_json::json!(format!("/{}", i))),
+            ]));
+        }
+        gate.add_policy(policy.with_rule(Rule::allow("fs.read")));
+
+        assert_eq!(
+            gate.authorize("fs.read", &serde_json::json!({})),
+            Decision::DeniedEvaluationTimeout
+        );
+        assert_eq!(log.records()[0].event.decision, "DENIED_EVALUATION_TIMEOUT");
+    }
+
+    #[test]
+    fn test_degradation_modes() {
+        use crate::group::GroupResolver;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
+        struct Flaky(AtomicBool);
+        impl GroupResolver for Flaky {
+            fn members(&self, _: &str) -> Result<Option<Vec<String>>, GroupError> {
+                if self.0.load(Ordering::SeqCst) {
+                    Ok(Some(vec!["alice".to_string()]))
+                } else {
+                    Err(GroupError::Unavailable("ldap down".to_string()))
+                }
+            }
+        }
+
+        let resolver = Arc::new(Flaky(AtomicBool::new(true)));
+        let engine = PolicyEngine::new().with_group_resolver(resolver.clone());
+        let mut gate = CapabilityGate::new()
+            .with_engine(engine)
+            .with_degradation("web.get", DegradationMode::FailOpen)
+            .with_degradation(
+                "fs.read",
+                DegradationMode::ServeCached {
+                    max_staleness: Duration::from_secs(60),
+                },
+            );
+        for tool in ["web.get", "fs.read", "shell"] {
+            gate.register_capability(Capability::new(tool, tool));
+        }
+        let mut policy = Policy::new("default", "1.0");
+        for tool in ["web.get", "fs.read", "shell"] {
+            policy = policy.with_rule(Rule::allow(tool).for_principal("group:staff"));
+        }
+        gate.add_policy(policy);
+
+        let alice = RequestContext::new().with_principal("alice");
+        let args = serde_json::json!({});
+        assert!(gate.authorize_with("fs.read", &args, &alice).is_allowed());
+
+        resolver.0.store(false, Ordering::SeqCst);
+        assert_eq!(
+            gate.authorize_with("web.get", &args, &alice),
+            Decision::Authorized
+        );
+        assert_eq!(
+            gate.authorize_with("fs.read", &args, &alice),
+            Decision::Authorized
+        );
+        assert_eq!(
+            gate.authorize_with("shell", &args, &alice),
+            Decision::DeniedResolverUnavailable
+        );
+        assert_eq!(
+            gate.authorize_with(
+                "fs.read",
+                &args,
+                &alice.clone().with_attribute("tenant", "t2")
+            ),
+            Decision::DeniedResolverUnavailable
+        );
+
+        gate.add_policy(Policy::new("extra", "1.0").with_rule(Rule::deny("shell")));
+        assert_eq!(
+            gate.authorize_with("fs.read", &args, &alice),
+            Decision::DeniedResolverUnavailable
+        );
+    }
+
+    #[test]
+    fn test_authorize_without_args() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.register_capability(Capability::new("git", "Git"));
+        gate.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("git").with_param_constraint(
+                    crate::condition::ParamConstraint::one_of("subcommand", ["status"]),
+                )),
+        );
+
+        assert_eq!(gate.authorize("fs.read", &()), Decision::Authorized);
+        assert!(gate.check("fs.read"));
+        assert_eq!(gate.authorize("git", &()), Decision::DeniedPolicyViolation);
+    }
 }
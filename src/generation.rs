@@ -0,0 +1,217 @@
+//! Retained Policy Generations.
+//!
+//! Every successful `activate_bundle` records the resulting policy set as a new
+//! generation, keeping the last [`DEFAULT_RETAINED`] (see
+//! `PolicyEngine::with_retained_generations`). `rollback(1)` restores the
+//! previous generation from memory, so a bad push can be reverted without
+//! refetching or reparsing anything. The state before the first activation is
+//! retained as generation 0.
+//!
+//! A generation covers the policies, their layers and the baseline; the
+//! clock, group resolver and categories are engine configuration and are left
+//! alone. Policies added outside a bundle after the last activation are lost on
+//! rollback.
+
+use crate::digest::sha256_hex;
+use crate::index::PolicyIndex;
+use crate::layer::Layer;
+use crate::policy::{Policy, PolicyEngine};
+use serde::Serialize;
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Arc;
+use thiserror::Error;
+
+pub const DEFAULT_RETAINED: usize = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Generation {
+    pub number: u64,
+    /// SHA-256 of the generation's policies and baseline as JSON.
+    pub digest: String,
+    pub activated_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<String>,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RollbackError {
+    #[error(
+        "cannot roll back {requested} generation(s): only {retained} older generation(s) retained"
+    )]
+    NotRetained { requested: usize, retained: usize },
+}
+
+#[derive(Clone)]
+pub(crate) struct PolicySnapshot {
+    pub(crate) policies: Vec<Policy>,
+    pub(crate) indexes: Vec<PolicyIndex>,
+    pub(crate) layers: Vec<Layer>,
+    pub(crate) baseline: Option<(Policy, PolicyIndex)>,
+    pub(crate) guards: BTreeSet<String>,
+}
+
+impl PolicySnapshot {
+    fn digest(&self) -> String {
+        let baseline = self.baseline.as_ref().map(|(policy, _)| policy);
+        let json =
+            serde_json::to_vec(&(&self.policies, baseline, &self.guards)).unwrap_or_default();
+        sha256_hex(&json)
+    }
+}
+
+/// Newest generation first; the front is the active one.
+#[derive(Clone)]
+pub(crate) struct Generations {
+    retain: usize,
+    next: u64,
+    entries: VecDeque<(Generation, Arc<PolicySnapshot>)>,
+}
+
+impl Default for Generations {
+    fn default() -> Self {
+        Self {
+            retain: DEFAULT_RETAINED,
+            next: 0,
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl Generations {
+    fn push(&mut self, snapshot: PolicySnapshot, at_ms: u64, bundle: Option<String>) {
+        let generation = Generation {
+            number: self.next,
+            digest: snapshot.digest(),
+            activated_at_ms: at_ms,
+            bundle,
+        };
+        self.next += 1;
+        self.entries.push_front((generation, Arc::new(snapshot)));
+        self.entries.truncate(self.retain.max(1));
+    }
+
+    /// Retained generations, oldest first.
+    pub(crate) fn oldest_first(&self) -> impl Iterator<Item = &(Generation, Arc<PolicySnapshot>)> {
+        self.entries.iter().rev()
+    }
+}
+
+impl PolicyEngine {
+    /// Keeps the last `count` generations, including the active one.
+    pub fn with_retained_generations(mut self, count: usize) -> Self {
+        self.generations_mut().retain = count.max(1);
+        self.generations_mut().entries.truncate(count.max(1));
+        self
+    }
+
+    /// SHA-256 of the current policies and baseline, in the form of
+    /// [`Generation::digest`], so it names the active generation when no
+    /// policies were added since the last activation.
+    pub fn policy_digest(&self) -> String {
+        self.snapshot().digest()
+    }
+
+    /// Retained generations, newest (active) first.
+    pub fn generations(&self) -> Vec<Generation> {
+        self.generations_ref()
+            .entries
+            .iter()
+            .map(|(generation, _)| generation.clone())
+            .collect()
+    }
+
+    /// Records the current policy set as a new generation. Called by
+    /// `activate_bundle`; the pre-activation state becomes generation 0.
+    pub(crate) fn record_generation(&mut self, previous: PolicySnapshot, bundle: &str) {
+        let now_ms = self.time_check().now_ms;
+        let current = self.snapshot();
+        let generations = self.generations_mut();
+        if generations.entries.is_empty() {
+            generations.push(previous, now_ms, None);
+        }
+        generations.push(current, now_ms, Some(bundle.to_string()));
+    }
+
+    /// Restores the generation `steps` activations back, discarding newer ones.
+    pub fn rollback(&mut self, steps: usize) -> Result<Generation, RollbackError> {
+        let retained = self.generations_ref().entries.len().saturating_sub(1);
+        if steps == 0 || steps > retained {
+            return Err(RollbackError::NotRetained {
+                requested: steps,
+                retained,
+            });
+        }
+        let generations = self.generations_mut();
+        generations.entries.drain(..steps);
+        let (generation, snapshot) = generations.entries[0].clone();
+        self.restore(PolicySnapshot::clone(&snapshot));
+        Ok(generation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::PolicyBundle;
+    use crate::clock::FixedClock;
+    use crate::policy::{Effect, Rule};
+
+    fn bundle(version: &str, effect: Effect) -> PolicyBundle {
+        let rule = Rule {
+            effect,
+            ..Rule::deny("shell")
+        };
+        PolicyBundle::new("prod", version)
+            .with_policy(Policy::new("tools", version).with_rule(rule))
+    }
+
+    fn shell(engine: &PolicyEngine) -> Effect {
+        engine.evaluate("shell", "execute", &crate::args::NO_ARGS)
+    }
+
+    #[test]
+    fn test_rollback_restores_previous_generation() {
+        let mut engine = PolicyEngine::new().with_clock(Arc::new(FixedClock(42)));
+        engine.activate_bundle(bundle("1", Effect::Allow)).unwrap();
+        engine.activate_bundle(bundle("2", Effect::Deny)).unwrap();
+        assert_eq!(shell(&engine), Effect::Deny);
+
+        let generations = engine.generations();
+        let numbers: Vec<u64> = generations.iter().map(|g| g.number).collect();
+        assert_eq!(numbers, vec![2, 1, 0]);
+        assert_eq!(generations[0].activated_at_ms, 42);
+        assert_eq!(generations[0].bundle.as_deref(), Some("prod"));
+        assert_eq!(generations[2].bundle, None);
+
+        let restored = engine.rollback(1).unwrap();
+        assert_eq!(restored, generations[1]);
+        assert_eq!(shell(&engine), Effect::Allow);
+        assert_eq!(engine.generations()[0].number, 1);
+
+        engine.rollback(1).unwrap();
+        assert!(engine.policies().next().is_none());
+        assert_eq!(
+            engine.rollback(1),
+            Err(RollbackError::NotRetained {
+                requested: 1,
+                retained: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_retention_limit_and_digests() {
+        let mut engine = PolicyEngine::new().with_retained_generations(2);
+        engine.activate_bundle(bundle("1", Effect::Allow)).unwrap();
+        engine.activate_bundle(bundle("2", Effect::Deny)).unwrap();
+        engine.activate_bundle(bundle("1", Effect::Allow)).unwrap();
+
+        let generations = engine.generations();
+        assert_eq!(generations.len(), 2);
+        assert_ne!(generations[0].digest, generations[1].digest);
+        assert_eq!(generations[0].digest.len(), 64);
+        assert!(engine.rollback(2).is_err());
+        engine.rollback(1).unwrap();
+        assert_eq!(shell(&engine), Effect::Deny);
+    }
+}
@@ -0,0 +1,203 @@
+//! Golden-File Decision Snapshots.
+//!
+//! [`trace`] replays a request corpus (see [`crate::compat`]) through any
+//! [`Authorizer`] and [`render`] serializes the decisions deterministically:
+//! pretty JSON with sorted keys, no timestamps. Teams snapshot their full
+//! policy behavior for a representative corpus and fail CI when it changes.
+//!
+//! [`assert_golden`] compares against a file, rewriting it instead when
+//! `UPDATE_GOLDEN=1` (or insta's `INSTA_UPDATE=always`) is set. It accepts
+//! insta `.snap` files, whose `---` front matter is skipped, and the rendered
+//! string can be handed to `insta::assert_snapshot!` directly.
+
+use crate::authorizer::{AuthorizationRequest, Authorizer};
+use crate::compat::CorpusRequest;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecisionTrace {
+    pub request: CorpusRequest,
+    pub decision: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub valid_for_ms: Option<u64>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+pub enum GoldenError {
+    #[error("golden file {} does not exist; rerun with UPDATE_GOLDEN=1 to create it", .path.display())]
+    Missing { path: PathBuf },
+    #[error("{}:{line}: expected `{expected}`, got `{actual}`; rerun with UPDATE_GOLDEN=1 to accept", .path.display())]
+    Mismatch {
+        path: PathBuf,
+        line: usize,
+        expected: String,
+        actual: String,
+    },
+    #[error("golden file {}: {source}", .path.display())]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+pub fn trace(authorizer: &dyn Authorizer, corpus: &[CorpusRequest]) -> Vec<DecisionTrace> {
+    corpus
+        .iter()
+        .map(|request| {
+            let record = authorizer.authorize(&AuthorizationRequest::new(
+                &request.resource,
+                &request.args,
+                &request.ctx,
+            ));
+            DecisionTrace {
+                request: request.clone(),
+                decision: record.decision.code().to_string(),
+                rule: record.rule,
+                valid_for_ms: record.valid_for_ms,
+                details: record.details,
+            }
+        })
+        .collect()
+}
+
+pub fn render(traces: &[DecisionTrace]) -> String {
+    let mut out = serde_json::to_string_pretty(traces).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// Drops insta's `---`-delimited front matter, if any.
+fn snapshot_body(contents: &str) -> &str {
+    let Some(rest) = contents.strip_prefix("---\n") else {
+        return contents;
+    };
+    match rest.find("\n---\n") {
+        Some(end) => &rest[end + 5..],
+        None => contents,
+    }
+}
+
+/// Compares `actual` with the golden file at `path`, or writes it when `update`.
+pub fn check_golden(path: &Path, actual: &str, update: bool) -> Result<(), GoldenError> {
+    let io = |source| GoldenError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    if update {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io)?;
+        }
+        return std::fs::write(path, actual).map_err(io);
+    }
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(GoldenError::Missing {
+                path: path.to_path_buf(),
+            })
+        }
+        Err(e) => return Err(io(e)),
+    };
+    let expected = snapshot_body(&contents);
+    if expected.trim_end() == actual.trim_end() {
+        return Ok(());
+    }
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    for line in 1.. {
+        let (e, a) = (expected_lines.next(), actual_lines.next());
+        if e != a {
+            return Err(GoldenError::Mismatch {
+                path: path.to_path_buf(),
+                line,
+                expected: e.unwrap_or("<end of file>").to_string(),
+                actual: a.unwrap_or("<end of output>").to_string(),
+            });
+        }
+    }
+    unreachable!("differing texts differ on some line")
+}
+
+fn update_requested() -> bool {
+    let var = |name| std::env::var(name).ok();
+    var("UPDATE_GOLDEN").is_some_and(|v| v != "0")
+        || var("INSTA_UPDATE").as_deref() == Some("always")
+}
+
+/// Panics unless `actual` matches the golden file at `path`.
+#[track_caller]
+pub fn assert_golden(path: impl AsRef<Path>, actual: &str) {
+    if let Err(e) = check_golden(path.as_ref(), actual, update_requested()) {
+        panic!("{}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    fn snapshot() -> String {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("fs.read")));
+        let corpus = vec![
+            CorpusRequest::new("fs.read", json!({ "path": "/tmp/a" }))
+                .with_context(RequestContext::default().with_principal("alice")),
+            CorpusRequest::new("shell", json!({ "cmd": "ls" })),
+        ];
+        render(&trace(&gate, &corpus))
+    }
+
+    #[test]
+    fn test_render_is_deterministic() {
+        let rendered = snapshot();
+        assert_eq!(rendered, snapshot());
+        assert!(!rendered.contains("timestamp"));
+        let value: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(value[0]["rule"], "default#0");
+        assert_eq!(value[1]["decision"], "DENIED_CAPABILITY_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_golden_round_trip() {
+        let dir = std::env::temp_dir().join(format!("femtoclaw-golden-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("decisions.json");
+        let rendered = snapshot();
+
+        assert!(matches!(
+            check_golden(&path, &rendered, false),
+            Err(GoldenError::Missing { .. })
+        ));
+        check_golden(&path, &rendered, true).unwrap();
+        check_golden(&path, &rendered, false).unwrap();
+
+        let snap = dir.join("decisions.snap");
+        std::fs::write(
+            &snap,
+            format!("---\nsource: tests/policy.rs\n---\n{}", rendered),
+        )
+        .unwrap();
+        check_golden(&snap, &rendered, false).unwrap();
+
+        let changed = rendered.replace("AUTHORIZED", "DENIED_POLICY_VIOLATION");
+        let err = check_golden(&path, &changed, false).unwrap_err();
+        let GoldenError::Mismatch { expected, .. } = &err else {
+            panic!("{}", err);
+        };
+        assert!(expected.contains("\"AUTHORIZED\""), "{}", err);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Grace Periods for New Deny Rules.
+//!
+//! A `Deny` rule with `enforce_after_ms` set is in its grace period until then:
+//! it does not decide requests, but requests it would deny are still allowed
+//! and flagged, and the gate records a `would_deny` detail such as
+//! `tools#3 after 2026-11-01T00:00:00Z` in the decision and audit event.
+//!
+//! A bundle with `grace_period_secs` puts every deny rule it adds — one not
+//! already present in the policy it replaces — into a grace period of that
+//! length on activation, giving teams time to adapt before enforcement flips.
+
+use crate::args::ArgView;
+use crate::clock::format_utc;
+use crate::context::RequestContext;
+use crate::policy::{Effect, Policy, PolicyEngine, Rule};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraceDenial {
+    pub rule: String,
+    pub enforce_after_ms: u64,
+}
+
+impl fmt::Display for GraceDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} after {}",
+            self.rule,
+            format_utc(self.enforce_after_ms)
+        )
+    }
+}
+
+impl Rule {
+    /// Defers enforcement of a `Deny` rule until `ms` since the Unix epoch.
+    pub fn with_grace_until(mut self, ms: u64) -> Self {
+        self.enforce_after_ms = Some(ms);
+        self
+    }
+
+    pub fn in_grace(&self, now_ms: u64) -> bool {
+        self.effect == Effect::Deny && self.enforce_after_ms.is_some_and(|t| now_ms < t)
+    }
+}
+
+/// The rule as written, ignoring grace and where it was loaded from.
+fn same_rule(a: &Rule, b: &Rule) -> bool {
+    a.effect == b.effect
+        && a.principal == b.principal
+        && a.resource == b.resource
+        && a.action == b.action
+        && a.conditions == b.conditions
+        && a.param_constraints == b.param_constraints
+}
+
+/// Starts a grace period ending at `until_ms` for the deny rules in `policy`
+/// that the engine's current version of it does not have. Rules carried over
+/// keep their existing grace, if any.
+pub(crate) fn apply_grace(engine: &PolicyEngine, policy: &mut Policy, until_ms: u64) {
+    let current = engine.get_policy(&policy.name);
+    for rule in policy.rules.iter_mut().filter(|r| r.effect == Effect::Deny) {
+        let existing = current.and_then(|p| p.rules.iter().find(|old| same_rule(old, rule)));
+        match existing {
+            Some(old) => rule.enforce_after_ms = old.enforce_after_ms,
+            None => rule.enforce_after_ms = rule.enforce_after_ms.or(Some(until_ms)),
+        }
+    }
+}
+
+impl PolicyEngine {
+    /// Deny rules in their grace period that would otherwise match the request.
+    pub fn grace_denials(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        args: &dyn ArgView,
+    ) -> Vec<GraceDenial> {
+        let time = self.time_check_for(ctx);
+        let category = self.category_of(resource);
+        let mut denials = Vec::new();
+        for policy in self.policies() {
+            for (index, rule) in policy.rules.iter().enumerate() {
+                if rule.in_grace(time.now_ms)
+                    && self.principal_matches(rule, ctx).unwrap_or(true)
+                    && rule.applies_in(resource, category)
+                    && rule.args_match_at(args, &time)
+                {
+                    denials.push(GraceDenial {
+                        rule: format!("{}#{}", policy.name, index),
+                        enforce_after_ms: rule.enforce_after_ms.unwrap_or_default(),
+                    });
+                }
+            }
+        }
+        denials
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bundle::PolicyBundle;
+    use crate::capability::Capability;
+    use crate::clock::FixedClock;
+    use crate::context::RequestContext;
+    use crate::gate::{CapabilityGate, Decision};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const DAY_MS: u64 = 86_400_000;
+
+    #[test]
+    fn test_grace_rule_flags_then_enforces() {
+        let policy = Policy::new("tools", "2")
+            .with_rule(Rule::deny("shell").with_grace_until(20_000 * DAY_MS))
+            .with_rule(Rule::allow("shell"));
+        let gate = |now_ms| {
+            let engine = PolicyEngine::new()
+                .with_default_effect(Effect::Deny)
+                .with_clock(Arc::new(FixedClock(now_ms)));
+            let mut gate = CapabilityGate::new().with_engine(engine);
+            gate.register_capability(Capability::new("shell", "Shell"));
+            gate.add_policy(policy.clone());
+            gate
+        };
+
+        let record = gate(20_000 * DAY_MS - 1).authorize_record("shell", &(), &Default::default());
+        assert_eq!(record.decision, Decision::Authorized);
+        assert_eq!(
+            record.details["would_deny"],
+            "tools#0 after 2024-10-04T00:00:00Z"
+        );
+
+        let record = gate(20_000 * DAY_MS).authorize_record("shell", &(), &Default::default());
+        assert_eq!(record.decision, Decision::DeniedPolicyViolation);
+        assert!(!record.details.contains_key("would_deny"));
+
+        // Callers cannot move the clock back into the grace period.
+        let back_then = RequestContext::new().with_evaluation_time(0);
+        let enforced = gate(20_000 * DAY_MS);
+        assert_eq!(
+            enforced.authorize_with("shell", &(), &back_then),
+            Decision::DeniedPolicyViolation
+        );
+        let replaying = gate(20_000 * DAY_MS).with_request_time(true);
+        assert_eq!(
+            replaying.authorize_with("shell", &(), &back_then),
+            Decision::Authorized
+        );
+    }
+
+    #[test]
+    fn test_bundle_grace_applies_to_new_deny_rules_only() {
+        let mut engine = PolicyEngine::new().with_clock(Arc::new(FixedClock(1_000)));
+        engine.add_policy(Policy::new("tools", "1").with_rule(Rule::deny("fs.delete")));
+
+        let bundle = PolicyBundle::new("b", "2")
+            .with_grace_period(Duration::from_secs(7 * 86_400))
+            .with_policy(
+                Policy::new("tools", "2")
+                    .with_rule(Rule::deny("fs.delete"))
+                    .with_rule(Rule::deny("shell")),
+            );
+        engine.activate_bundle(bundle).unwrap();
+
+        let rules = &engine.get_policy("tools").unwrap().rules;
+        assert_eq!(rules[0].enforce_after_ms, None);
+        assert_eq!(rules[1].enforce_after_ms, Some(1_000 + 7 * DAY_MS));
+        assert_eq!(
+            engine.evaluate("fs.delete", "execute", &crate::args::NO_ARGS),
+            Effect::Deny
+        );
+        let ctx = RequestContext::default();
+        assert_eq!(
+            engine
+                .grace_denials(&ctx, "shell", &crate::args::NO_ARGS)
+                .len(),
+            1
+        );
+    }
+}
@@ -0,0 +1,183 @@
+//! Principal Groups.
+//!
+//! Rule principals of the form `group:<name>` are expanded at evaluation time
+//! through a [`GroupResolver`]. Group members may themselves be groups
+//! (`group:<name>`); nested groups are followed with cycle detection.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+pub const GROUP_PREFIX: &str = "group:";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GroupError {
+    #[error("group {0:?} is part of a membership cycle")]
+    Cycle(String),
+    #[error("group resolver unavailable: {0}")]
+    Unavailable(String),
+}
+
+pub trait GroupResolver: Send + Sync {
+    /// Direct members of `group`, or `None` if the group does not exist.
+    fn members(&self, group: &str) -> Result<Option<Vec<String>>, GroupError>;
+}
+
+impl<F> GroupResolver for F
+where
+    F: Fn(&str) -> Result<Option<Vec<String>>, GroupError> + Send + Sync,
+{
+    fn members(&self, group: &str) -> Result<Option<Vec<String>>, GroupError> {
+        self(group)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct StaticGroups {
+    groups: BTreeMap<String, Vec<String>>,
+}
+
+impl StaticGroups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_group<I, S>(mut self, group: impl Into<String>, members: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.groups
+            .insert(group.into(), members.into_iter().map(Into::into).collect());
+        self
+    }
+}
+
+impl GroupResolver for StaticGroups {
+    fn members(&self, group: &str) -> Result<Option<Vec<String>>, GroupError> {
+        Ok(self.groups.get(group).cloned())
+    }
+}
+
+type CacheEntry = (Instant, Option<Vec<String>>);
+
+/// Caches another resolver's answers for `ttl`.
+pub struct CachedGroups<R> {
+    inner: R,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+}
+
+impl<R: GroupResolver> CachedGroups<R> {
+    pub fn new(inner: R, ttl: Duration) -> Self {
+        Self {
+            inner,
+            ttl,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn invalidate(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+}
+
+impl<R: GroupResolver> GroupResolver for CachedGroups<R> {
+    fn members(&self, group: &str) -> Result<Option<Vec<String>>, GroupError> {
+        if let Some((at, members)) = self.cache.lock().unwrap().get(group) {
+            if at.elapsed() < self.ttl {
+                return Ok(members.clone());
+            }
+        }
+        let members = self.inner.members(group)?;
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(group.to_string(), (Instant::now(), members.clone()));
+        Ok(members)
+    }
+}
+
+/// Whether `principal` is a direct or transitive member of `group`.
+pub fn is_member(
+    resolver: &dyn GroupResolver,
+    principal: &str,
+    group: &str,
+) -> Result<bool, GroupError> {
+    let mut path = BTreeSet::new();
+    let mut done = BTreeSet::new();
+    visit(resolver, principal, group, &mut path, &mut done)
+}
+
+fn visit(
+    resolver: &dyn GroupResolver,
+    principal: &str,
+    group: &str,
+    path: &mut BTreeSet<String>,
+    done: &mut BTreeSet<String>,
+) -> Result<bool, GroupError> {
+    if !path.insert(group.to_string()) {
+        return Err(GroupError::Cycle(group.to_string()));
+    }
+    let members = resolver.members(group)?.unwrap_or_default();
+    if members.iter().any(|m| m == principal) {
+        return Ok(true);
+    }
+    for member in &members {
+        if let Some(nested) = member.strip_prefix(GROUP_PREFIX) {
+            if !done.contains(nested) && visit(resolver, principal, nested, path, done)? {
+                return Ok(true);
+            }
+        }
+    }
+    path.remove(group);
+    done.insert(group.to_string());
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_membership() {
+        let groups = StaticGroups::new()
+            .with_group("engineers", ["alice", "group:sre"])
+            .with_group("sre", ["bob"]);
+
+        assert!(is_member(&groups, "alice", "engineers").unwrap());
+        assert!(is_member(&groups, "bob", "engineers").unwrap());
+        assert!(!is_member(&groups, "mallory", "engineers").unwrap());
+        assert!(!is_member(&groups, "alice", "missing").unwrap());
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let groups = StaticGroups::new()
+            .with_group("a", ["group:b"])
+            .with_group("b", ["group:a"]);
+        assert_eq!(
+            is_member(&groups, "alice", "a"),
+            Err(GroupError::Cycle("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_cached_resolver() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let resolver = move |_: &str| -> Result<Option<Vec<String>>, GroupError> {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Some(vec!["alice".to_string()]))
+        };
+        let cached = CachedGroups::new(resolver, Duration::from_secs(60));
+
+        assert!(is_member(&cached, "alice", "engineers").unwrap());
+        assert!(is_member(&cached, "alice", "engineers").unwrap());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
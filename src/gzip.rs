@@ -0,0 +1,281 @@
+//! Gzip Compression.
+//!
+//! Dependency-free gzip (RFC 1952) writer used to compress rotated audit files.
+//! Data is encoded as a single DEFLATE block with the fixed Huffman code and a
+//! hash-chain LZ77 matcher, which suits repetitive JSONL well.
+
+const WINDOW: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u32) {
+        self.buffer |= (value as u64) << self.bits;
+        self.bits += bits;
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Huffman codes are packed most-significant bit first.
+    fn write_code(&mut self, code: u32, bits: u32) {
+        self.write(code.reverse_bits() >> (32 - bits), bits);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+fn write_symbol(w: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => w.write_code(0x30 + symbol, 8),
+        144..=255 => w.write_code(0x190 + symbol - 144, 9),
+        256..=279 => w.write_code(symbol - 256, 7),
+        _ => w.write_code(0xc0 + symbol - 280, 8),
+    }
+}
+
+fn write_match(w: &mut BitWriter, length: usize, distance: usize) {
+    let li = LENGTH_BASE
+        .iter()
+        .rposition(|&b| b as usize <= length)
+        .unwrap();
+    write_symbol(w, 257 + li as u16);
+    w.write(
+        (length - LENGTH_BASE[li] as usize) as u32,
+        LENGTH_EXTRA[li] as u32,
+    );
+    let di = DIST_BASE
+        .iter()
+        .rposition(|&b| b as usize <= distance)
+        .unwrap();
+    w.write_code(di as u32, 5);
+    w.write(
+        (distance - DIST_BASE[di] as usize) as u32,
+        DIST_EXTRA[di] as u32,
+    );
+}
+
+fn hash(data: &[u8]) -> usize {
+    let v = (data[0] as u32) << 16 | (data[1] as u32) << 8 | data[2] as u32;
+    (v.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+}
+
+/// Raw DEFLATE stream (RFC 1951) of `data`.
+pub fn deflate(data: &[u8]) -> Vec<u8> {
+    let mut w = BitWriter {
+        out: Vec::with_capacity(data.len() / 2),
+        buffer: 0,
+        bits: 0,
+    };
+    w.write(1, 1); // BFINAL
+    w.write(1, 2); // BTYPE = fixed Huffman
+
+    let mut head = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev = vec![usize::MAX; data.len()];
+    let insert = |pos: usize, head: &mut [usize], prev: &mut [usize]| {
+        if pos + MIN_MATCH <= data.len() {
+            let h = hash(&data[pos..]);
+            prev[pos] = head[h];
+            head[h] = pos;
+        }
+    };
+
+    let mut pos = 0;
+    while pos < data.len() {
+        let (mut best_len, mut best_dist) = (0, 0);
+        if pos + MIN_MATCH <= data.len() {
+            let max = MAX_MATCH.min(data.len() - pos);
+            let mut candidate = head[hash(&data[pos..])];
+            let mut chain = 0;
+            while candidate != usize::MAX && pos - candidate <= WINDOW && chain < MAX_CHAIN {
+                let len = data[candidate..]
+                    .iter()
+                    .zip(&data[pos..pos + max])
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                if len > best_len {
+                    best_len = len;
+                    best_dist = pos - candidate;
+                    if len == max {
+                        break;
+                    }
+                }
+                candidate = prev[candidate];
+                chain += 1;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            write_match(&mut w, best_len, best_dist);
+            for p in pos..pos + best_len {
+                insert(p, &mut head, &mut prev);
+            }
+            pos += best_len;
+        } else {
+            write_symbol(&mut w, data[pos] as u16);
+            insert(pos, &mut head, &mut prev);
+            pos += 1;
+        }
+    }
+    write_symbol(&mut w, 256);
+    w.finish()
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+/// Gzip member containing `data`.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+    out.extend(deflate(data));
+    out.extend(crc32(data).to_le_bytes());
+    out.extend((data.len() as u32).to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal RFC 1951 inflater for a single fixed-Huffman block, returning
+    /// the data and the longest distance referenced.
+    fn inflate(stream: &[u8]) -> (Vec<u8>, usize) {
+        let mut bit = 0;
+        let mut read = |bits: u32| {
+            let mut value = 0;
+            for i in 0..bits {
+                value |= ((stream[bit / 8] >> (bit % 8)) as u32 & 1) << i;
+                bit += 1;
+            }
+            value
+        };
+        assert_eq!(read(3), 0b011, "BFINAL with BTYPE = fixed Huffman");
+        let (mut out, mut longest) = (Vec::new(), 0);
+        loop {
+            let mut code = 0;
+            for _ in 0..7 {
+                code = code << 1 | read(1);
+            }
+            let symbol = if code <= 0x17 {
+                256 + code
+            } else {
+                code = code << 1 | read(1);
+                match code {
+                    0x30..=0xbf => code - 0x30,
+                    0xc0..=0xc7 => 280 + code - 0xc0,
+                    _ => 144 + (code << 1 | read(1)) - 0x190,
+                }
+            } as usize;
+            match symbol {
+                0..=255 => out.push(symbol as u8),
+                256 => return (out, longest),
+                _ => {
+                    let li = symbol - 257;
+                    let length = LENGTH_BASE[li] as usize + read(LENGTH_EXTRA[li] as u32) as usize;
+                    let mut di = 0;
+                    for _ in 0..5 {
+                        di = di << 1 | read(1) as usize;
+                    }
+                    let distance = DIST_BASE[di] as usize + read(DIST_EXTRA[di] as u32) as usize;
+                    assert!(distance <= out.len() && distance <= WINDOW);
+                    longest = longest.max(distance);
+                    for _ in 0..length {
+                        out.push(out[out.len() - distance]);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_deflate_round_trips() {
+        assert_eq!(deflate(b""), [0x03, 0x00]);
+        assert_eq!(inflate(&deflate(b"")).0, b"");
+
+        let run = vec![b'a'; 3 * MAX_MATCH + 7];
+        let (out, longest) = inflate(&deflate(&run));
+        assert_eq!(out, run);
+        assert_eq!(longest, 1);
+
+        let mut seed = 0x2545_f491u32;
+        let mut noise = |n: usize| -> Vec<u8> {
+            (0..n)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                    (seed >> 16) as u8
+                })
+                .collect()
+        };
+        let block = noise(300);
+        let mut data = block.clone();
+        data.extend(noise(WINDOW - block.len()));
+        data.extend(&block);
+        let (out, longest) = inflate(&deflate(&data));
+        assert_eq!(out, data);
+        assert_eq!(longest, WINDOW);
+    }
+
+    #[test]
+    fn test_crc32_vector() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_repetitive_input_shrinks() {
+        let line = br#"{"timestamp_ms":1,"tool":"fs.read","decision":"AUTHORIZED"}"#;
+        let data: Vec<u8> = line
+            .iter()
+            .copied()
+            .cycle()
+            .take(line.len() * 100)
+            .collect();
+        let gz = compress(&data);
+        assert_eq!(&gz[..3], &[0x1f, 0x8b, 8]);
+        assert!(gz.len() < data.len() / 10);
+        assert_eq!(&gz[gz.len() - 4..], &(data.len() as u32).to_le_bytes());
+    }
+}
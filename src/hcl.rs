@@ -0,0 +1,436 @@
+//! HCL Policy Frontend.
+//!
+//! Parses `policy` blocks written in HCL (enabled with the `hcl` feature) into
+//! [`Policy`] values. Supported is the declarative subset policies need:
+//! attributes, labelled blocks, strings, numbers, booleans, lists and objects,
+//! plus `#`, `//` and `/* */` comments. Expressions and interpolation are not.
+//!
+//! ```hcl
+//! policy "workspace" {
+//!   version = "1.0"
+//!   rule {
+//!     effect   = "allow"
+//!     resource = "fs.read"
+//!     condition {
+//!       key      = "path"
+//!       operator = "starts_with"
+//!       value    = "/work"
+//!     }
+//!   }
+//! }
+//! ```
+//!
+//! `condition` and `param_constraint` blocks collect into a rule's `conditions`
+//! and `param_constraints`; `principal` and `action` default to `*` and
+//! `execute`. Errors carry the line and column of the offending token or block.
+
+use crate::policy::Policy;
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HclError {
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for HclError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+impl std::error::Error for HclError {}
+
+fn error<T>(span: Span, message: impl Into<String>) -> Result<T, HclError> {
+    Err(HclError {
+        span,
+        message: message.into(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(Number),
+    Punct(char),
+    Eof,
+}
+
+struct Lexer<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    span: Span,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            chars: src.chars().peekable(),
+            span: Span { line: 1, column: 1 },
+        }
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.span.line += 1;
+            self.span.column = 1;
+        } else {
+            self.span.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), HclError> {
+        loop {
+            match self.chars.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.bump();
+                }
+                Some('#') => self.skip_line(),
+                Some('/') => {
+                    let start = self.span;
+                    self.bump();
+                    match self.bump() {
+                        Some('/') => self.skip_line(),
+                        Some('*') => loop {
+                            match self.bump() {
+                                Some('*') if self.chars.peek() == Some(&'/') => {
+                                    self.bump();
+                                    break;
+                                }
+                                Some(_) => {}
+                                None => return error(start, "unterminated comment"),
+                            }
+                        },
+                        _ => return error(start, "unexpected `/`"),
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while self.chars.peek().is_some_and(|c| *c != '\n') {
+            self.bump();
+        }
+    }
+
+    fn next(&mut self) -> Result<(Token, Span), HclError> {
+        self.skip_trivia()?;
+        let span = self.span;
+        let Some(&c) = self.chars.peek() else {
+            return Ok((Token::Eof, span));
+        };
+        let token = if c.is_alphabetic() || c == '_' {
+            let mut ident = String::new();
+            while let Some(&c) = self.chars.peek() {
+                if !(c.is_alphanumeric() || c == '_' || c == '-') {
+                    break;
+                }
+                ident.push(c);
+                self.bump();
+            }
+            Token::Ident(ident)
+        } else if c == '"' {
+            self.bump();
+            Token::Str(self.string(span)?)
+        } else if c.is_ascii_digit() || c == '-' {
+            let mut text = String::new();
+            while let Some(&c) = self.chars.peek() {
+                if !(c.is_ascii_digit() || matches!(c, '-' | '.' | 'e' | 'E' | '+')) {
+                    break;
+                }
+                text.push(c);
+                self.bump();
+            }
+            match serde_json::from_str::<Number>(&text) {
+                Ok(n) => Token::Num(n),
+                Err(_) => return error(span, format!("invalid number `{}`", text)),
+            }
+        } else if "={}[],:".contains(c) {
+            self.bump();
+            Token::Punct(c)
+        } else {
+            return error(span, format!("unexpected character `{}`", c));
+        };
+        Ok((token, span))
+    }
+
+    fn string(&mut self, start: Span) -> Result<String, HclError> {
+        let mut out = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => return Ok(out),
+                Some('\\') => match self.bump() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some(c @ ('"' | '\\')) => out.push(c),
+                    _ => return error(self.span, "invalid escape"),
+                },
+                Some('$') if self.chars.peek() == Some(&'{') => {
+                    return error(self.span, "interpolation is not supported")
+                }
+                Some('\n') | None => return error(start, "unterminated string"),
+                Some(c) => out.push(c),
+            }
+        }
+    }
+}
+
+struct Block {
+    kind: String,
+    labels: Vec<String>,
+    span: Span,
+    attrs: Vec<(String, Value, Span)>,
+    blocks: Vec<Block>,
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<(Token, Span)>,
+}
+
+impl Parser<'_> {
+    fn peek(&mut self) -> Result<&(Token, Span), HclError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next()?);
+        }
+        Ok(self.peeked.as_ref().expect("peeked token"))
+    }
+
+    fn next(&mut self) -> Result<(Token, Span), HclError> {
+        self.peek()?;
+        Ok(self.peeked.take().expect("peeked token"))
+    }
+
+    /// Items until `}` (or end of input at the top level).
+    fn body(
+        &mut self,
+        kind: String,
+        labels: Vec<String>,
+        span: Span,
+        top: bool,
+    ) -> Result<Block, HclError> {
+        let mut block = Block {
+            kind,
+            labels,
+            span,
+            attrs: Vec::new(),
+            blocks: Vec::new(),
+        };
+        loop {
+            let (token, at) = self.next()?;
+            let name = match token {
+                Token::Eof if top => return Ok(block),
+                Token::Punct('}') if !top => return Ok(block),
+                Token::Ident(name) => name,
+                _ => return error(at, "expected an attribute or block"),
+            };
+            if let (Token::Punct('='), _) = self.peek()? {
+                self.next()?;
+                let value = self.value()?;
+                block.attrs.push((name, value, at));
+                continue;
+            }
+            let mut labels = Vec::new();
+            loop {
+                match self.next()? {
+                    (Token::Str(label), _) => labels.push(label),
+                    (Token::Punct('{'), _) => break,
+                    (_, span) => return error(span, format!("expected `{{` after `{}`", name)),
+                }
+            }
+            block.blocks.push(self.body(name, labels, at, false)?);
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, HclError> {
+        match self.next()? {
+            (Token::Str(s), _) => Ok(Value::String(s)),
+            (Token::Num(n), _) => Ok(Value::Number(n)),
+            (Token::Ident(i), _) if i == "true" => Ok(Value::Bool(true)),
+            (Token::Ident(i), _) if i == "false" => Ok(Value::Bool(false)),
+            (Token::Ident(i), _) if i == "null" => Ok(Value::Null),
+            (Token::Punct('['), _) => {
+                let mut items = Vec::new();
+                loop {
+                    if let (Token::Punct(']'), _) = self.peek()? {
+                        self.next()?;
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.value()?);
+                    if let (Token::Punct(','), _) = self.peek()? {
+                        self.next()?;
+                    }
+                }
+            }
+            (Token::Punct('{'), _) => {
+                let mut map = Map::new();
+                loop {
+                    let key = match self.next()? {
+                        (Token::Punct('}'), _) => return Ok(Value::Object(map)),
+                        (Token::Ident(k) | Token::Str(k), _) => k,
+                        (_, span) => return error(span, "expected an object key"),
+                    };
+                    match self.next()? {
+                        (Token::Punct('=' | ':'), _) => {}
+                        (_, span) => return error(span, "expected `=`"),
+                    }
+                    map.insert(key, self.value()?);
+                    if let (Token::Punct(','), _) = self.peek()? {
+                        self.next()?;
+                    }
+                }
+            }
+            (_, span) => error(span, "expected a value"),
+        }
+    }
+}
+
+fn attrs_object(block: &Block) -> Map<String, Value> {
+    block
+        .attrs
+        .iter()
+        .map(|(k, v, _)| (k.clone(), v.clone()))
+        .collect()
+}
+
+fn rule_value(block: &Block) -> Result<Value, HclError> {
+    let mut rule = attrs_object(block);
+    if let Some(Value::String(effect)) = rule.get_mut("effect") {
+        *effect = match effect.to_ascii_lowercase().as_str() {
+            "allow" => "Allow".to_string(),
+            "deny" => "Deny".to_string(),
+            _ => return error(block.span, format!("unknown effect `{}`", effect)),
+        };
+    }
+    rule.entry("principal").or_insert_with(|| "*".into());
+    rule.entry("action").or_insert_with(|| "execute".into());
+    let mut conditions = Vec::new();
+    let mut constraints = Vec::new();
+    for child in &block.blocks {
+        match child.kind.as_str() {
+            "condition" => conditions.push(Value::Object(attrs_object(child))),
+            "param_constraint" => constraints.push(Value::Object(attrs_object(child))),
+            other => return error(child.span, format!("unexpected `{}` block in rule", other)),
+        }
+    }
+    rule.insert("conditions".into(), Value::Array(conditions));
+    if !constraints.is_empty() {
+        rule.insert("param_constraints".into(), Value::Array(constraints));
+    }
+    Ok(Value::Object(rule))
+}
+
+fn policy_from(block: &Block) -> Result<Policy, HclError> {
+    let [name] = block.labels.as_slice() else {
+        return error(block.span, "`policy` block needs exactly one name label");
+    };
+    let mut policy = attrs_object(block);
+    policy.insert("name".into(), Value::String(name.clone()));
+    let mut rules = Vec::new();
+    for child in &block.blocks {
+        match child.kind.as_str() {
+            "rule" => rules.push(rule_value(child)?),
+            other => {
+                return error(
+                    child.span,
+                    format!("unexpected `{}` block in policy", other),
+                )
+            }
+        }
+    }
+    policy.insert("rules".into(), Value::Array(rules));
+    serde_json::from_value(Value::Object(policy))
+        .or_else(|e| error(block.span, format!("invalid policy `{}`: {}", name, e)))
+}
+
+/// Every `policy` block in `src`, in order.
+pub fn parse_policies(src: &str) -> Result<Vec<Policy>, HclError> {
+    let mut parser = Parser {
+        lexer: Lexer::new(src),
+        peeked: None,
+    };
+    let start = Span { line: 1, column: 1 };
+    let root = parser.body(String::new(), Vec::new(), start, true)?;
+    if let Some((name, _, span)) = root.attrs.first() {
+        return error(*span, format!("unexpected top-level attribute `{}`", name));
+    }
+    root.blocks
+        .iter()
+        .map(|block| match block.kind.as_str() {
+            "policy" => policy_from(block),
+            other => error(block.span, format!("unexpected `{}` block", other)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Effect;
+
+    #[test]
+    fn test_parse_policy_blocks() {
+        let src = r#"
+            # workspace access
+            policy "workspace" {
+              version = "1.0"
+              rule {
+                effect   = "allow"
+                resource = "fs.read"
+                condition {
+                  key      = "path"
+                  operator = "starts_with"
+                  value    = "/work"
+                }
+              }
+              rule {
+                effect   = "deny"
+                resource = "git"
+                param_constraint {
+                  param  = "subcommand"
+                  one_of = ["push", "reset"] // destructive
+                }
+              }
+            }
+        "#;
+        let policies = parse_policies(src).unwrap();
+        assert_eq!(policies[0].name, "workspace");
+        let rules = &policies[0].rules;
+        assert_eq!(rules[0].effect, Effect::Allow);
+        assert_eq!(rules[0].principal, "*");
+        assert_eq!(rules[0].conditions[0].value, "/work");
+        assert_eq!(rules[1].param_constraints.len(), 1);
+    }
+
+    #[test]
+    fn test_errors_carry_spans() {
+        let err = parse_policies(
+            "policy \"p\" {\n  version = \"1.0\"\n  rule {\n    effect = \"maybe\"\n  }\n}",
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "3:3: unknown effect `maybe`");
+        let err = parse_policies("policy \"p\" {\n  version = @\n}").unwrap_err();
+        assert_eq!(
+            err.span,
+            Span {
+                line: 2,
+                column: 13
+            }
+        );
+    }
+}
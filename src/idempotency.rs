@@ -0,0 +1,250 @@
+//! Request Idempotency.
+//!
+//! Agents sometimes retry identical tool calls. A request carrying
+//! [`RequestContext::idempotency_key`](crate::RequestContext) is decided once per
+//! window; retries with the same key get the original [`DecisionRecord`] back
+//! with `replayed` set, telling the executor the call was already performed.
+//! Keys are scoped by principal, capability and context [`Dimension`]s, by
+//! default the `tenant` and `session` attributes, so anonymous callers and a
+//! principal's other tenants or sessions never share a key. They are bound to
+//! a digest of the arguments they were first decided with: a retry that
+//! reuses a key with different arguments is denied with
+//! `IDEMPOTENCY_KEY_MISMATCH` instead of replaying a decision made for another
+//! call. Arguments without a stable identity, see
+//! [`ArgView::cache_key`](crate::args::ArgView::cache_key), are never replayed.
+//!
+//! The cache holds at most `max_entries` keys; beyond that, the oldest are
+//! dropped before they leave the window.
+
+use crate::args::{view_of, Args};
+use crate::cache::Dimension;
+use crate::context::RequestContext;
+use crate::decision::DecisionRecord;
+use crate::digest::sha256_hex;
+use crate::fingerprint::canonical_json;
+use crate::flags::SESSION_ATTRIBUTE;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// What a stored key gives back for a retry.
+#[derive(Debug, Clone)]
+pub enum Replay {
+    Record(DecisionRecord),
+    /// The key was first used with different arguments.
+    Mismatch,
+}
+
+pub const DEFAULT_MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+struct Entry {
+    at: Instant,
+    args: String,
+    record: DecisionRecord,
+    seq: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, Entry>,
+    /// Keys in insertion order; a key inserted again is superseded by its later entry.
+    order: VecDeque<(String, u64)>,
+    inserted: u64,
+}
+
+pub struct IdempotencyCache {
+    window: Duration,
+    dimensions: Vec<Dimension>,
+    max_entries: usize,
+    entries: Mutex<Entries>,
+}
+
+impl IdempotencyCache {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            dimensions: vec![
+                Dimension::attribute("tenant"),
+                Dimension::attribute(SESSION_ATTRIBUTE),
+            ],
+            max_entries: DEFAULT_MAX_IDEMPOTENCY_KEYS,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Scopes keys by `dimensions` instead of the `tenant` and `session`
+    /// attributes.
+    pub fn with_dimensions(mut self, dimensions: Vec<Dimension>) -> Self {
+        self.dimensions = dimensions;
+        self
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn key(&self, ctx: &RequestContext, tool: &str, key: &str) -> String {
+        let scope: Vec<(&str, Option<&str>)> = self
+            .dimensions
+            .iter()
+            .map(|d| (d.name(), d.value(ctx)))
+            .collect();
+        serde_json::to_string(&(ctx.principal.as_deref(), scope, tool, key)).unwrap_or_default()
+    }
+
+    /// SHA-256 of the canonical JSON of `args`, or of their cache key when they
+    /// are not JSON; `None` when they have no stable identity.
+    pub fn args_digest<A: Args + ?Sized>(args: &A) -> Option<String> {
+        let identity = match args.as_json() {
+            Some(json) => canonical_json(json),
+            None => view_of(args).cache_key()?,
+        };
+        Some(sha256_hex(identity.as_bytes()))
+    }
+
+    /// The stored decision for `key` if it was made for the same `args` digest.
+    pub fn get(&self, key: &str, args: &str) -> Option<Replay> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries
+            .by_key
+            .get(key)
+            .filter(|entry| entry.at.elapsed() <= self.window)?;
+        Some(match entry.args == args {
+            true => Replay::Record(entry.record.clone()),
+            false => Replay::Mismatch,
+        })
+    }
+
+    /// Stores `record`, dropping entries that have left the window and, past
+    /// `max_entries`, the oldest.
+    pub fn insert(&self, key: String, args: String, record: DecisionRecord) {
+        let mut entries = self.entries.lock().unwrap();
+        let entries = &mut *entries;
+        while let Some((oldest, seq)) = entries.order.front() {
+            let evict = match entries.by_key.get(oldest) {
+                Some(entry) if entry.seq == *seq => {
+                    entry.at.elapsed() > self.window || entries.by_key.len() >= self.max_entries
+                }
+                // Superseded by a later insert, or already dropped.
+                _ => {
+                    entries.order.pop_front();
+                    continue;
+                }
+            };
+            if !evict {
+                break;
+            }
+            entries.by_key.remove(oldest);
+            entries.order.pop_front();
+        }
+        entries.inserted += 1;
+        let seq = entries.inserted;
+        entries.order.push_back((key.clone(), seq));
+        entries.by_key.insert(
+            key,
+            Entry {
+                at: Instant::now(),
+                args,
+                record,
+                seq,
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditLog;
+    use crate::capability::Capability;
+    use crate::codes::{Reason, REASON_DETAIL};
+    use crate::context::RequestContext;
+    use crate::decision::Decision;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_retries_replay_the_first_decision() {
+        let log = Arc::new(AuditLog::new());
+        let mut gate = CapabilityGate::new()
+            .with_audit_sink(log.clone())
+            .with_idempotency(Duration::from_secs(60));
+        gate.register_capability(Capability::new("email.send", "Send email"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("email.send")));
+
+        let ctx = RequestContext::new()
+            .with_principal("agent")
+            .with_idempotency_key("msg-1");
+        let first = gate.authorize_record("email.send", &(), &ctx);
+        let retry = gate.authorize_record("email.send", &(), &ctx);
+        assert!(!first.replayed);
+        assert!(retry.replayed);
+        assert_eq!(retry.timestamp_ms, first.timestamp_ms);
+
+        let other = ctx.clone().with_idempotency_key("msg-2");
+        assert!(!gate.authorize_record("email.send", &(), &other).replayed);
+        assert_eq!(log.records()[1].event.details["replayed"], "true");
+    }
+
+    #[test]
+    fn test_reused_key_with_other_arguments_is_denied() {
+        let mut gate = CapabilityGate::new().with_idempotency(Duration::from_secs(60));
+        gate.register_capability(Capability::new("payment.send", "Send money"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("payment.send")));
+
+        let ctx = RequestContext::new()
+            .with_principal("agent")
+            .with_idempotency_key("pay-1");
+        let first = gate.authorize_record("payment.send", &json!({"to": "a", "n": 1}), &ctx);
+        assert_eq!(first.decision, Decision::Authorized);
+        let same = gate.authorize_record("payment.send", &json!({"n": 1, "to": "a"}), &ctx);
+        assert!(same.replayed);
+
+        let other = gate.authorize_record("payment.send", &json!({"to": "b", "n": 1}), &ctx);
+        assert!(!other.replayed);
+        assert_eq!(other.decision, Decision::DeniedInvalidArguments);
+        assert_eq!(
+            other.details[REASON_DETAIL],
+            Reason::IdempotencyKeyMismatch.code()
+        );
+    }
+
+    #[test]
+    fn test_keys_are_scoped_and_bounded() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60)).with_max_entries(2);
+        let agent = RequestContext::new().with_principal("agent");
+        let in_tenant = agent.clone().with_attribute("tenant", "t2");
+        let in_session = agent.clone().with_attribute(SESSION_ATTRIBUTE, "s2");
+        let keys: Vec<String> = [
+            &agent,
+            &in_tenant,
+            &in_session,
+            &RequestContext::new(),
+            &RequestContext::new().with_principal(""),
+        ]
+        .iter()
+        .map(|ctx| cache.key(ctx, "email.send", "msg-1"))
+        .collect();
+        let distinct: std::collections::BTreeSet<&String> = keys.iter().collect();
+        assert_eq!(distinct.len(), keys.len());
+
+        let record = DecisionRecord::new(Decision::Authorized, "email.send");
+        for key in &keys[..3] {
+            cache.insert(key.clone(), "args".into(), record.clone());
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&keys[0], "args").is_none());
+        assert!(cache.get(&keys[2], "args").is_some());
+    }
+}
@@ -0,0 +1,426 @@
+//! Read-only Policy Images.
+//!
+//! A policy image is a flat, position-independent binary encoding of an engine's
+//! policy set. [`PolicyImage::parse`] validates the layout once and then serves
+//! every name and pattern as a `&str` borrowed from the input buffer, so a file
+//! mapped into memory by many processes (for example with `memmap2`) is shared
+//! rather than copied into per-process heaps.
+//!
+//! Layout (all integers little-endian `u32`):
+//!
+//! ```text
+//! header   "FCPI" version default_effect policy_count rule_count
+//! policies (name, version, extra, first_rule, rule_count) per policy
+//! rules    (effect, principal, resource, action, extra)   per rule
+//! strings  UTF-8 heap referenced by (offset, len) pairs
+//! ```
+//!
+//! `extra` holds the JSON encoding of every other serialized field of the
+//! policy or rule (`extends`, conditions, parameter constraints, TTLs, consent
+//! and so on), empty when they all have their defaults. A rule's `extra` is only
+//! decoded for rules that apply to the requested resource. The header version
+//! changes whenever the layout does; images of another version are rejected.
+
+use crate::context::RequestContext;
+use crate::policy::{resource_covers, Effect, Policy, PolicyEngine, Rule};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+pub const IMAGE_MAGIC: &[u8; 4] = b"FCPI";
+pub const IMAGE_VERSION: u32 = 2;
+
+const HEADER_LEN: usize = 20;
+const POLICY_LEN: usize = 32;
+const RULE_LEN: usize = 36;
+
+/// The fields stored in the policy and rule tables rather than in `extra`.
+const POLICY_TABLE_FIELDS: &[&str] = &["name", "version", "rules"];
+const RULE_TABLE_FIELDS: &[&str] = &["effect", "principal", "resource", "action"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ImageError {
+    #[error("not a policy image")]
+    BadMagic,
+    #[error("unsupported policy image version {0}")]
+    UnsupportedVersion(u32),
+    #[error("policy image truncated or out of bounds at byte {0}")]
+    OutOfBounds(usize),
+    #[error("policy image contains invalid UTF-8 at byte {0}")]
+    InvalidUtf8(usize),
+    #[error("policy image contains an invalid rule: {0}")]
+    InvalidRule(String),
+}
+
+/// `value`'s fields other than `table`, as JSON; empty if there are none.
+fn extra(value: Value, table: &[&str]) -> String {
+    let Value::Object(mut object) = value else {
+        return String::new();
+    };
+    let empty = |k: &str, v: &Value| k == "conditions" && v == &json!([]);
+    object.retain(|k, v| !(table.contains(&k.as_str()) || empty(k, v)));
+    match object.is_empty() {
+        true => String::new(),
+        false => Value::Object(object).to_string(),
+    }
+}
+
+fn parse_extra(extra: &str) -> Result<Map<String, Value>, ImageError> {
+    if extra.is_empty() {
+        return Ok(Map::new());
+    }
+    serde_json::from_str(extra).map_err(|e| ImageError::InvalidRule(e.to_string()))
+}
+
+fn effect_code(effect: Effect) -> u32 {
+    match effect {
+        Effect::Allow => 0,
+        Effect::Deny => 1,
+        Effect::AllowWithWarning => 2,
+        Effect::Audit => 3,
+    }
+}
+
+fn effect_from_code(code: u32) -> Option<Effect> {
+    match code {
+        0 => Some(Effect::Allow),
+        1 => Some(Effect::Deny),
+        2 => Some(Effect::AllowWithWarning),
+        3 => Some(Effect::Audit),
+        _ => None,
+    }
+}
+
+struct Writer {
+    tables: Vec<u8>,
+    strings: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.tables.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        let offset = self.strings.len() as u32;
+        self.strings.extend_from_slice(value.as_bytes());
+        self.u32(offset);
+        self.u32(value.len() as u32);
+    }
+}
+
+impl PolicyEngine {
+    pub fn to_image(&self) -> Vec<u8> {
+        let policies: Vec<&Policy> = self.policies().collect();
+        let rule_count: usize = policies.iter().map(|p| p.rules.len()).sum();
+        let mut w = Writer {
+            tables: Vec::new(),
+            strings: Vec::new(),
+        };
+
+        w.tables.extend_from_slice(IMAGE_MAGIC);
+        w.u32(IMAGE_VERSION);
+        w.u32(effect_code(self.default_effect()));
+        w.u32(policies.len() as u32);
+        w.u32(rule_count as u32);
+
+        let mut first_rule = 0;
+        for policy in &policies {
+            w.str(&policy.name);
+            w.str(&policy.version);
+            let value = serde_json::to_value(policy).expect("policies serialize");
+            w.str(&extra(value, POLICY_TABLE_FIELDS));
+            w.u32(first_rule);
+            w.u32(policy.rules.len() as u32);
+            first_rule += policy.rules.len() as u32;
+        }
+
+        for rule in policies.iter().flat_map(|p| &p.rules) {
+            w.u32(effect_code(rule.effect));
+            w.str(&rule.principal);
+            w.str(&rule.resource);
+            w.str(&rule.action);
+            let value = serde_json::to_value(rule).expect("rules serialize");
+            w.str(&extra(value, RULE_TABLE_FIELDS));
+        }
+
+        let mut image = w.tables;
+        image.extend_from_slice(&w.strings);
+        image
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImagePolicy<'a> {
+    pub name: &'a str,
+    pub version: &'a str,
+    extra: &'a str,
+    first_rule: usize,
+    rule_count: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageRule<'a> {
+    pub effect: Effect,
+    pub principal: &'a str,
+    pub resource: &'a str,
+    pub action: &'a str,
+    extra: &'a str,
+}
+
+impl ImageRule<'_> {
+    fn to_rule(self) -> Result<Rule, ImageError> {
+        let mut fields = parse_extra(self.extra)?;
+        fields.entry("conditions").or_insert_with(|| json!([]));
+        fields.insert("effect".into(), json!(self.effect));
+        fields.insert("principal".into(), json!(self.principal));
+        fields.insert("resource".into(), json!(self.resource));
+        fields.insert("action".into(), json!(self.action));
+        Rule::deserialize(Value::Object(fields)).map_err(|e| ImageError::InvalidRule(e.to_string()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PolicyImage<'a> {
+    default_effect: Effect,
+    policies: Vec<ImagePolicy<'a>>,
+    rules: Vec<ImageRule<'a>>,
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    strings: usize,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn u32(&mut self) -> Result<u32, ImageError> {
+        let raw = self
+            .bytes
+            .get(self.pos..self.pos + 4)
+            .ok_or(ImageError::OutOfBounds(self.pos))?;
+        self.pos += 4;
+        Ok(u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+    }
+
+    fn str(&mut self) -> Result<&'a str, ImageError> {
+        let offset = self.strings + self.u32()? as usize;
+        let len = self.u32()? as usize;
+        let raw = self
+            .bytes
+            .get(offset..offset + len)
+            .ok_or(ImageError::OutOfBounds(offset))?;
+        std::str::from_utf8(raw).map_err(|_| ImageError::InvalidUtf8(offset))
+    }
+}
+
+impl<'a> PolicyImage<'a> {
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ImageError> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != IMAGE_MAGIC {
+            return Err(ImageError::BadMagic);
+        }
+        let mut r = Reader {
+            bytes,
+            strings: 0,
+            pos: 4,
+        };
+        let version = r.u32()?;
+        if version != IMAGE_VERSION {
+            return Err(ImageError::UnsupportedVersion(version));
+        }
+        let default_effect = effect_from_code(r.u32()?)
+            .ok_or_else(|| ImageError::InvalidRule("unknown default effect".to_string()))?;
+        let policy_count = r.u32()? as usize;
+        let rule_count = r.u32()? as usize;
+        r.strings = policy_count
+            .checked_mul(POLICY_LEN)
+            .and_then(|p| rule_count.checked_mul(RULE_LEN).map(|q| HEADER_LEN + p + q))
+            .filter(|&end| end <= bytes.len())
+            .ok_or(ImageError::OutOfBounds(bytes.len()))?;
+
+        let mut policies = Vec::with_capacity(policy_count);
+        for _ in 0..policy_count {
+            let policy = ImagePolicy {
+                name: r.str()?,
+                version: r.str()?,
+                extra: r.str()?,
+                first_rule: r.u32()? as usize,
+                rule_count: r.u32()? as usize,
+            };
+            if policy.first_rule + policy.rule_count > rule_count {
+                return Err(ImageError::InvalidRule(format!(
+                    "policy {} references missing rules",
+                    policy.name
+                )));
+            }
+            policies.push(policy);
+        }
+
+        let mut rules = Vec::with_capacity(rule_count);
+        for _ in 0..rule_count {
+            let effect = effect_from_code(r.u32()?)
+                .ok_or_else(|| ImageError::InvalidRule("unknown effect".to_string()))?;
+            rules.push(ImageRule {
+                effect,
+                principal: r.str()?,
+                resource: r.str()?,
+                action: r.str()?,
+                extra: r.str()?,
+            });
+        }
+
+        Ok(Self {
+            default_effect,
+            policies,
+            rules,
+        })
+    }
+
+    pub fn policies(&self) -> &[ImagePolicy<'a>] {
+        &self.policies
+    }
+
+    pub fn rules(&self, policy: &ImagePolicy<'a>) -> &[ImageRule<'a>] {
+        &self.rules[policy.first_rule..policy.first_rule + policy.rule_count]
+    }
+
+    /// Evaluates against the image without materializing it. Only `*` and exact
+    /// principals are supported; group principals never match `Allow` rules, and
+    /// category rules use the category inferred from the resource name.
+    pub fn evaluate(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        args: &dyn crate::args::ArgView,
+    ) -> Result<Effect, ImageError> {
+        let selector = crate::capability::CapabilityCategory::infer(resource).selector();
+        for rule in &self.rules {
+            if !resource_covers(rule.resource, resource) && rule.resource != selector {
+                continue;
+            }
+            let principal = rule.principal == "*"
+                || ctx.principal.as_deref() == Some(rule.principal)
+                || (rule.principal.starts_with("group:") && rule.effect == Effect::Deny);
+            if principal && rule.to_rule()?.args_match(args) {
+                return Ok(rule.effect);
+            }
+        }
+        Ok(self.default_effect)
+    }
+
+    pub fn to_engine(&self) -> Result<PolicyEngine, ImageError> {
+        let mut engine = PolicyEngine::new().with_default_effect(self.default_effect);
+        for policy in &self.policies {
+            let mut fields = parse_extra(policy.extra)?;
+            fields.insert("name".into(), json!(policy.name));
+            fields.insert("version".into(), json!(policy.version));
+            fields.insert("rules".into(), json!([]));
+            let mut owned = Policy::deserialize(Value::Object(fields))
+                .map_err(|e| ImageError::InvalidRule(e.to_string()))?;
+            for rule in self.rules(policy) {
+                owned.rules.push(rule.to_rule()?);
+            }
+            engine.add_policy(owned);
+        }
+        Ok(engine)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditMode;
+    use crate::condition::ParamConstraint;
+    use crate::policy::Condition;
+
+    fn engine() -> PolicyEngine {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::deny("fs.write").with_conditions(vec![Condition::new(
+                    "path",
+                    "starts_with",
+                    json!("/etc"),
+                )]))
+                .with_rule(Rule::allow("fs.write"))
+                .with_rule(Rule::allow("shell").for_principal("alice")),
+        );
+        engine.add_policy(Policy::new("web", "2.0").with_rule(Rule::allow("web.get")));
+        engine
+    }
+
+    #[test]
+    fn test_image_matches_engine_decisions() {
+        let engine = engine();
+        let bytes = engine.to_image();
+        let image = PolicyImage::parse(&bytes).unwrap();
+        assert_eq!(image.policies().len(), 2);
+
+        let alice = RequestContext::new().with_principal("alice");
+        for (resource, args) in [
+            ("fs.write", json!({ "path": "/etc/passwd" })),
+            ("fs.write", json!({ "path": "/tmp/x" })),
+            ("shell", json!({})),
+            ("web.get", json!({})),
+            ("unknown", json!({})),
+        ] {
+            assert_eq!(
+                image.evaluate(&alice, resource, &args).unwrap(),
+                engine.evaluate_with(&alice, resource, "execute", &args)
+            );
+        }
+    }
+
+    #[test]
+    fn test_image_roundtrip_to_engine() {
+        let bytes = engine().to_image();
+        let restored = PolicyImage::parse(&bytes).unwrap().to_engine().unwrap();
+        assert_eq!(restored.to_image(), bytes);
+    }
+
+    #[test]
+    fn test_image_roundtrip_keeps_every_field() {
+        let mut rule = Rule::allow("fs.read")
+            .for_principal("alice")
+            .with_conditions(vec![Condition::new("path", "starts_with", json!("/work/"))])
+            .with_param_constraint(ParamConstraint::prefix("path", "/work/"))
+            .requiring_consent();
+        rule.action = "read".into();
+        rule.audit = AuditMode::Never;
+        rule.ttl_secs = Some(60);
+        rule.enforce_after_ms = Some(1_000);
+        rule.cache_key = vec!["path".into(), "principal".into()];
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(Policy::new("base", "1").with_rule(Rule::deny("shell")));
+        engine.add_policy(
+            Policy::new("p", "2")
+                .extending("base")
+                .allowing_unicode()
+                .with_rule(rule),
+        );
+
+        let bytes = engine.to_image();
+        let restored = PolicyImage::parse(&bytes).unwrap().to_engine().unwrap();
+        let policies = |engine: &PolicyEngine| {
+            engine
+                .policies()
+                .map(|p| serde_json::to_value(p).unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(policies(&restored), policies(&engine));
+        assert_eq!(restored.to_image(), bytes);
+    }
+
+    #[test]
+    fn test_corrupt_images_rejected() {
+        let bytes = engine().to_image();
+        assert!(matches!(
+            PolicyImage::parse(b"nope"),
+            Err(ImageError::BadMagic)
+        ));
+        assert!(matches!(
+            PolicyImage::parse(&bytes[..bytes.len() - 3]),
+            Err(ImageError::OutOfBounds(_))
+        ));
+    }
+}
@@ -0,0 +1,238 @@
+//! Compiled Rule Index.
+//!
+//! Each policy is compiled into a [`PolicyIndex`] mapping resource names,
+//! categories and `<namespace>.*` namespaces to the positions of the rules that
+//! can apply to them, so evaluation only considers
+//! candidate rules. Indexes are kept per policy: adding or replacing one policy
+//! recompiles only that policy's index.
+//!
+//! Most resources are named by one or two rules, so position lists keep up to
+//! two `u32` positions inline and only allocate beyond that.
+//!
+//! The index also caches each rule's compiled glob patterns, filled in the
+//! first time the rule is evaluated; see [`crate::pattern`].
+
+use crate::capability::{CapabilityCategory, CATEGORY_PREFIX};
+use crate::pattern::{compile_rule, Glob, RulePatterns};
+use crate::policy::{resource_namespace, Policy, Rule};
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::{Arc, OnceLock};
+
+const INLINE: usize = 2;
+
+/// Ascending rule positions, stored inline while there are at most two.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Positions {
+    Inline { len: u8, slots: [u32; INLINE] },
+    Heap(Vec<u32>),
+}
+
+impl Default for Positions {
+    fn default() -> Self {
+        Positions::Inline {
+            len: 0,
+            slots: [0; INLINE],
+        }
+    }
+}
+
+impl Positions {
+    fn push(&mut self, position: usize) {
+        let position = u32::try_from(position).expect("policy has more than u32::MAX rules");
+        match self {
+            Positions::Inline { len, slots } if (*len as usize) < INLINE => {
+                slots[*len as usize] = position;
+                *len += 1;
+            }
+            Positions::Inline { slots, .. } => {
+                let mut heap = slots.to_vec();
+                heap.push(position);
+                *self = Positions::Heap(heap);
+            }
+            Positions::Heap(heap) => heap.push(position),
+        }
+    }
+
+    fn as_slice(&self) -> &[u32] {
+        match self {
+            Positions::Inline { len, slots } => &slots[..*len as usize],
+            Positions::Heap(heap) => heap,
+        }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Positions::Inline { .. } => 0,
+            Positions::Heap(heap) => heap.capacity() * size_of::<u32>(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PolicyIndex {
+    by_resource: HashMap<Box<str>, Positions>,
+    by_category: HashMap<CapabilityCategory, Positions>,
+    /// Keyed by namespace with its trailing dot, e.g. `db.read.`.
+    by_namespace: HashMap<Box<str>, Positions>,
+    wildcard: Positions,
+    patterns: Vec<OnceLock<RulePatterns>>,
+}
+
+impl PolicyIndex {
+    pub fn compile(policy: &Policy) -> Self {
+        let mut index = Self {
+            patterns: vec![OnceLock::new(); policy.rules.len()],
+            ..Self::default()
+        };
+        for (i, rule) in policy.rules.iter().enumerate() {
+            let category = rule
+                .resource
+                .strip_prefix(CATEGORY_PREFIX)
+                .and_then(CapabilityCategory::parse);
+            if rule.resource == "*" {
+                index.wildcard.push(i);
+            } else if let Some(category) = category {
+                index.by_category.entry(category).or_default().push(i);
+            } else if let Some(namespace) = resource_namespace(&rule.resource) {
+                index
+                    .by_namespace
+                    .entry(namespace.into())
+                    .or_default()
+                    .push(i);
+            } else {
+                index
+                    .by_resource
+                    .entry(rule.resource.as_str().into())
+                    .or_default()
+                    .push(i);
+            }
+        }
+        index
+    }
+
+    /// Positions of rules that may apply to `resource` in `category`, in rule order.
+    pub fn candidates(&self, resource: &str, category: CapabilityCategory) -> Vec<usize> {
+        let exact = self.by_resource.get(resource).map(Positions::as_slice);
+        let grouped = self.by_category.get(&category).map(Positions::as_slice);
+        let mut merged = merge(exact.unwrap_or(&[]), grouped.unwrap_or(&[]));
+        if !self.by_namespace.is_empty() {
+            for (dot, _) in resource.match_indices('.') {
+                if let Some(positions) = self.by_namespace.get(&resource[..=dot]) {
+                    merged = merge(&merged, positions.as_slice());
+                }
+            }
+        }
+        merge(&merged, self.wildcard.as_slice())
+            .into_iter()
+            .map(|p| p as usize)
+            .collect()
+    }
+
+    /// Compiled patterns of the rule at `position`, compiling them on first use.
+    pub fn patterns(&self, position: usize, rule: &Rule) -> &[Option<Arc<Glob>>] {
+        match self.patterns.get(position) {
+            Some(cell) => cell.get_or_init(|| compile_rule(rule)),
+            None => &[],
+        }
+    }
+
+    /// How many rules have had their patterns compiled so far.
+    pub fn compiled_rules(&self) -> usize {
+        self.patterns.iter().filter(|c| c.get().is_some()).count()
+    }
+
+    /// Approximate heap bytes held by the index, counting hash table slots,
+    /// resource names and spilled position lists.
+    pub fn heap_bytes(&self) -> usize {
+        let resources: usize = self
+            .by_resource
+            .iter()
+            .map(|(name, positions)| name.len() + positions.heap_bytes())
+            .sum();
+        let categories: usize = self.by_category.values().map(Positions::heap_bytes).sum();
+        let namespaces: usize = self
+            .by_namespace
+            .iter()
+            .map(|(name, positions)| name.len() + positions.heap_bytes())
+            .sum();
+        resources
+            + categories
+            + namespaces
+            + self.wildcard.heap_bytes()
+            + self.by_resource.capacity() * size_of::<(Box<str>, Positions)>()
+            + self.by_category.capacity() * size_of::<(CapabilityCategory, Positions)>()
+            + self.by_namespace.capacity() * size_of::<(Box<str>, Positions)>()
+    }
+}
+
+fn merge(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let (mut a, mut b) = (0, 0);
+    while a < left.len() || b < right.len() {
+        if b == right.len() || (a < left.len() && left[a] < right[b]) {
+            merged.push(left[a]);
+            a += 1;
+        } else {
+            merged.push(right[b]);
+            b += 1;
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Rule;
+
+    #[test]
+    fn test_candidates_preserve_rule_order() {
+        let policy = Policy::new("p", "1.0")
+            .with_rule(Rule::allow("fs.read"))
+            .with_rule(Rule::deny("*"))
+            .with_rule(Rule::allow("shell"))
+            .with_rule(Rule::deny("fs.read"))
+            .with_rule(Rule::deny("category:Process"));
+
+        let index = PolicyIndex::compile(&policy);
+        let fs = CapabilityCategory::Filesystem;
+        assert_eq!(index.candidates("fs.read", fs), vec![0, 1, 3]);
+        assert_eq!(
+            index.candidates("shell", CapabilityCategory::Process),
+            vec![1, 2, 4]
+        );
+        assert_eq!(
+            index.candidates("web.get", CapabilityCategory::Network),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn test_compact_footprint_on_large_corpus() {
+        let mut policy = Policy::new("large", "1.0");
+        for i in 0..50_000 {
+            policy = policy.with_rule(Rule::allow(format!("tool.{}", i / 2)));
+        }
+        let index = PolicyIndex::compile(&policy);
+
+        // The previous layout: `String` keys plus a `Vec<usize>` per resource
+        // that grows to a capacity of four on first push.
+        let resources = index.by_resource.len();
+        let previous = index.by_resource.capacity() * size_of::<(String, Vec<usize>)>()
+            + index.by_resource.keys().map(|k| k.len()).sum::<usize>()
+            + resources * 4 * size_of::<usize>();
+        assert_eq!(resources, 25_000);
+        assert!(index.by_resource.values().all(|p| p.heap_bytes() == 0));
+        assert!(
+            index.heap_bytes() * 3 < previous * 2,
+            "{} vs {}",
+            index.heap_bytes(),
+            previous
+        );
+        assert_eq!(
+            index.candidates("tool.7", CapabilityCategory::Other),
+            vec![14, 15]
+        );
+    }
+}
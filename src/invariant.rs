@@ -0,0 +1,272 @@
+//! Policy Invariants.
+//!
+//! Built-in properties CI can assert against a policy set, e.g. that nothing
+//! is allowed unconditionally for everyone. Each check is static: it inspects
+//! the rules themselves, so a violation names the rule to fix.
+
+use crate::capability::{CapabilityCategory, CapabilityRegistry, CATEGORY_PREFIX};
+use crate::clock::is_time_condition;
+use crate::condition::arg_key;
+use crate::policy::{Effect, PolicyEngine, Rule};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Condition key that marks a rule as gated on human approval.
+pub const APPROVAL_KEY: &str = "approved";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Invariant {
+    /// Every `Allow` rule targets a registered capability, `*`, or a category
+    /// with registered capabilities.
+    NoAllowWithoutRegisteredCapability,
+    /// No `Allow` rule matches every capability for every principal without
+    /// conditions.
+    NoUnconditionalWildcardAllow,
+    /// At least one `Deny` rule covers the category.
+    DenyRulesExistFor(CapabilityCategory),
+    /// Every `Allow` rule that can match a capability in these categories has
+    /// an `approved` condition.
+    HighRiskRequiresApproval(Vec<CapabilityCategory>),
+}
+
+impl Invariant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Invariant::NoAllowWithoutRegisteredCapability => {
+                "no-allow-without-registered-capability"
+            }
+            Invariant::NoUnconditionalWildcardAllow => "no-unconditional-wildcard-allow",
+            Invariant::DenyRulesExistFor(_) => "deny-rules-exist",
+            Invariant::HighRiskRequiresApproval(_) => "high-risk-requires-approval",
+        }
+    }
+
+    /// The built-in set: the first two invariants, plus approval for
+    /// `Process` and `Credential` capabilities.
+    pub fn defaults() -> Vec<Invariant> {
+        vec![
+            Invariant::NoAllowWithoutRegisteredCapability,
+            Invariant::NoUnconditionalWildcardAllow,
+            Invariant::HighRiskRequiresApproval(vec![
+                CapabilityCategory::Process,
+                CapabilityCategory::Credential,
+            ]),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InvariantViolation {
+    pub invariant: String,
+    pub policy: Option<String>,
+    pub rule: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]", self.invariant)?;
+        if let Some(policy) = &self.policy {
+            write!(f, " {}", policy)?;
+        }
+        if let Some(rule) = self.rule {
+            write!(f, " rule {}", rule)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+fn category_of_selector(resource: &str) -> Option<CapabilityCategory> {
+    CapabilityCategory::parse(resource.strip_prefix(CATEGORY_PREFIX)?)
+}
+
+fn is_unconditional(rule: &Rule) -> bool {
+    rule.conditions.is_empty() && rule.param_constraints.is_empty()
+}
+
+fn requires_approval(rule: &Rule) -> bool {
+    rule.conditions
+        .iter()
+        .any(|c| !is_time_condition(c) && arg_key(&c.key) == APPROVAL_KEY)
+}
+
+impl PolicyEngine {
+    pub fn verify_invariant(
+        &self,
+        invariant: &Invariant,
+        registry: &CapabilityRegistry,
+    ) -> Result<(), Vec<InvariantViolation>> {
+        let mut violations = Vec::new();
+        let mut violation = |policy: &str, rule: Option<usize>, message: String| {
+            violations.push(InvariantViolation {
+                invariant: invariant.name().to_string(),
+                policy: Some(policy.to_string()),
+                rule,
+                message,
+            });
+        };
+        let rules = || {
+            self.policies()
+                .flat_map(|p| p.rules.iter().enumerate().map(move |(i, r)| (p, i, r)))
+        };
+
+        match invariant {
+            Invariant::NoAllowWithoutRegisteredCapability => {
+                for (policy, index, rule) in rules().filter(|(_, _, r)| r.effect.allows()) {
+                    let known = match category_of_selector(&rule.resource) {
+                        Some(category) => !registry.by_category(category).is_empty(),
+                        None => {
+                            rule.resource == "*"
+                                || registry.is_registered(&rule.resource)
+                                || registry.list().iter().any(|c| rule.applies_to(&c.name))
+                        }
+                    };
+                    if !known {
+                        violation(
+                            &policy.name,
+                            Some(index),
+                            format!("allows unregistered capability `{}`", rule.resource),
+                        );
+                    }
+                }
+            }
+            Invariant::NoUnconditionalWildcardAllow => {
+                for (policy, index, _) in rules().filter(|(_, _, r)| {
+                    r.effect.allows()
+                        && r.resource == "*"
+                        && r.principal == "*"
+                        && is_unconditional(r)
+                }) {
+                    violation(
+                        &policy.name,
+                        Some(index),
+                        "allows every capability to everyone unconditionally".to_string(),
+                    );
+                }
+            }
+            Invariant::DenyRulesExistFor(category) => {
+                let covered = rules().any(|(_, _, r)| {
+                    r.effect == Effect::Deny
+                        && (r.resource == "*"
+                            || category_of_selector(&r.resource) == Some(*category)
+                            || registry
+                                .get(&r.resource)
+                                .is_some_and(|c| c.category == *category))
+                });
+                if !covered {
+                    violations.push(InvariantViolation {
+                        invariant: invariant.name().to_string(),
+                        policy: None,
+                        rule: None,
+                        message: format!("no deny rule covers category {}", category),
+                    });
+                }
+            }
+            Invariant::HighRiskRequiresApproval(categories) => {
+                for (policy, index, rule) in rules().filter(|(_, _, r)| r.effect.allows()) {
+                    let high_risk = categories.iter().find(|category| {
+                        registry
+                            .by_category(**category)
+                            .iter()
+                            .any(|c| rule.applies_in(&c.name, c.category))
+                    });
+                    if let (Some(category), false) = (high_risk, requires_approval(rule)) {
+                        violation(
+                            &policy.name,
+                            Some(index),
+                            format!(
+                                "allows {} capabilities without an `{}` condition",
+                                category, APPROVAL_KEY
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Checks every invariant, collecting all violations.
+    pub fn verify_invariants(
+        &self,
+        invariants: &[Invariant],
+        registry: &CapabilityRegistry,
+    ) -> Result<(), Vec<InvariantViolation>> {
+        let violations: Vec<InvariantViolation> = invariants
+            .iter()
+            .filter_map(|i| self.verify_invariant(i, registry).err())
+            .flatten()
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Condition, Policy};
+    use serde_json::json;
+
+    fn registry() -> CapabilityRegistry {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("fs.read", "Read files"));
+        registry.register(Capability::new("shell", "Execute shell commands"));
+        registry
+    }
+
+    #[test]
+    fn test_defaults_hold_for_a_careful_policy() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("shell").with_conditions(vec![Condition::new(
+                    "approved",
+                    "eq",
+                    json!(true),
+                )]))
+                .with_rule(Rule::deny("category:Process")),
+        );
+        let mut invariants = Invariant::defaults();
+        invariants.push(Invariant::DenyRulesExistFor(CapabilityCategory::Process));
+        assert!(engine.verify_invariants(&invariants, &registry()).is_ok());
+    }
+
+    #[test]
+    fn test_violations_name_the_rule() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("loose", "1.0")
+                .with_rule(Rule::allow("*"))
+                .with_rule(Rule::allow("web.fetch")),
+        );
+        let violations = engine
+            .verify_invariants(&Invariant::defaults(), &registry())
+            .unwrap_err();
+        let names: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "[no-allow-without-registered-capability] loose rule 1: allows unregistered capability `web.fetch`",
+                "[no-unconditional-wildcard-allow] loose rule 0: allows every capability to everyone unconditionally",
+                "[high-risk-requires-approval] loose rule 0: allows Process capabilities without an `approved` condition",
+            ]
+        );
+        assert!(engine
+            .verify_invariant(
+                &Invariant::DenyRulesExistFor(CapabilityCategory::Network),
+                &registry()
+            )
+            .is_err());
+    }
+}
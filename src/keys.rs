@@ -0,0 +1,124 @@
+//! Condition Key Discovery.
+//!
+//! [`PolicyEngine::known_condition_keys`] lists the argument keys a condition
+//! on a capability can usefully test, for editors and the CLI to autocomplete
+//! while a rule is being written. Keys come from two places:
+//!
+//! - declared: the capability's `parameters`, parameter constraints and JSON
+//!   Schema `properties` (nested objects as dotted paths), which the gate
+//!   passes to the engine on registration;
+//! - observed: the keys that loaded rules applying to the capability already
+//!   guard with conditions or parameter constraints.
+//!
+//! [`TIME_KEY`] is always included, since time conditions apply everywhere.
+
+use crate::capability::Capability;
+use crate::clock::TIME_KEY;
+use crate::condition::arg_key;
+use crate::policy::PolicyEngine;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+fn schema_keys(schema: &Value, prefix: &str, out: &mut BTreeSet<String>) {
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (name, property) in properties {
+        let key = match prefix {
+            "" => name.clone(),
+            prefix => format!("{}.{}", prefix, name),
+        };
+        schema_keys(property, &key, out);
+        out.insert(key);
+    }
+}
+
+/// The argument keys `capability` declares.
+pub fn declared_keys(capability: &Capability) -> BTreeSet<String> {
+    let mut keys: BTreeSet<String> = capability
+        .parameters
+        .iter()
+        .map(|p| p.name.clone())
+        .chain(capability.constraints.iter().map(|c| c.param.clone()))
+        .collect();
+    if let Some(schema) = &capability.schema {
+        schema_keys(schema, "", &mut keys);
+    }
+    keys
+}
+
+impl PolicyEngine {
+    /// Every argument key known for `resource`, sorted; see the module docs.
+    pub fn known_condition_keys(&self, resource: &str) -> Vec<String> {
+        let category = self.category_of(resource);
+        let mut keys = self.declared_keys(resource).cloned().unwrap_or_default();
+        for policy in self.policies() {
+            for rule in policy
+                .rules
+                .iter()
+                .filter(|r| r.applies_in(resource, category))
+            {
+                let conditions = rule.conditions.iter().map(|c| c.key.as_str());
+                let params = rule.param_constraints.iter().map(|c| c.param.as_str());
+                keys.extend(conditions.chain(params).map(|k| arg_key(k).to_string()));
+            }
+        }
+        keys.insert(TIME_KEY.to_string());
+        keys.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityParam;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_keys_are_declared_and_observed() {
+        let mut capability = Capability::new("http.fetch", "Fetch a URL").with_schema(json!({
+            "type": "object",
+            "properties": {
+                "url": {"type": "string"},
+                "headers": {"type": "object", "properties": {"accept": {"type": "string"}}}
+            }
+        }));
+        capability.parameters.push(CapabilityParam {
+            name: "method".into(),
+            param_type: "string".into(),
+            required: false,
+        });
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(capability);
+        gate.add_policy(
+            Policy::new("p", "1")
+                .with_rule(
+                    Rule::deny("http.fetch").with_conditions(vec![Condition::new(
+                        "args.body.size",
+                        "gt",
+                        json!(1024),
+                    )]),
+                )
+                .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                    "cmd",
+                    "eq",
+                    json!("rm"),
+                )])),
+        );
+
+        assert_eq!(
+            gate.engine().known_condition_keys("http.fetch"),
+            vec![
+                "body.size",
+                "headers",
+                "headers.accept",
+                "method",
+                "time",
+                "url"
+            ]
+        );
+        assert_eq!(gate.engine().known_condition_keys("fs.read"), vec!["time"]);
+    }
+}
@@ -0,0 +1,283 @@
+//! Kubernetes Policy Source.
+//!
+//! Cluster-native deployments keep the policy bundle in a ConfigMap or a
+//! `FemtoClawPolicy` custom resource and manage it with kubectl or GitOps. The
+//! crate does not ship a Kubernetes client: callers supply a [`KubeClient`]
+//! (kube-rs, a plain HTTPS client against the API server) that performs `GET`s,
+//! and stream the lines of a watch response into [`KubeWatcher::handle`], which
+//! activates each new revision on the engine.
+
+use crate::policy::PolicyEngine;
+use crate::source::{PolicySource, SourceError};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+pub const CRD_GROUP: &str = "femtoclaw.io";
+pub const CRD_VERSION: &str = "v1";
+pub const CRD_PLURAL: &str = "femtoclawpolicies";
+pub const DEFAULT_CONFIGMAP_KEY: &str = "bundle.json";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KubeObject {
+    /// A ConfigMap holding the bundle JSON under `key`.
+    ConfigMap {
+        namespace: String,
+        name: String,
+        key: String,
+    },
+    /// A `FemtoClawPolicy` resource whose `spec` is the bundle.
+    Crd { namespace: String, name: String },
+}
+
+impl KubeObject {
+    pub fn config_map(namespace: impl Into<String>, name: impl Into<String>) -> Self {
+        KubeObject::ConfigMap {
+            namespace: namespace.into(),
+            name: name.into(),
+            key: DEFAULT_CONFIGMAP_KEY.to_string(),
+        }
+    }
+
+    pub fn crd(namespace: impl Into<String>, name: impl Into<String>) -> Self {
+        KubeObject::Crd {
+            namespace: namespace.into(),
+            name: name.into(),
+        }
+    }
+
+    fn collection(&self) -> String {
+        match self {
+            KubeObject::ConfigMap { namespace, .. } => {
+                format!("/api/v1/namespaces/{}/configmaps", namespace)
+            }
+            KubeObject::Crd { namespace, .. } => format!(
+                "/apis/{}/{}/namespaces/{}/{}",
+                CRD_GROUP, CRD_VERSION, namespace, CRD_PLURAL
+            ),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            KubeObject::ConfigMap { name, .. } | KubeObject::Crd { name, .. } => name,
+        }
+    }
+
+    /// API server path of the object.
+    pub fn path(&self) -> String {
+        format!("{}/{}", self.collection(), self.name())
+    }
+
+    /// API server path that watches the object from `resource_version` on.
+    pub fn watch_path(&self, resource_version: Option<&str>) -> String {
+        let mut path = format!(
+            "{}?watch=1&fieldSelector=metadata.name%3D{}",
+            self.collection(),
+            self.name()
+        );
+        if let Some(version) = resource_version {
+            path.push_str("&resourceVersion=");
+            path.push_str(version);
+        }
+        path
+    }
+
+    /// Extracts the bundle JSON from a ConfigMap or custom resource object.
+    pub fn bundle(&self, object: &Value) -> Result<String, SourceError> {
+        let missing = |what: &str| SourceError::Fetch {
+            source_name: self.to_string(),
+            message: format!("object has no {}", what),
+        };
+        match self {
+            KubeObject::ConfigMap { key, .. } => object
+                .pointer(&format!(
+                    "/data/{}",
+                    key.replace('~', "~0").replace('/', "~1")
+                ))
+                .and_then(Value::as_str)
+                .map(String::from)
+                .ok_or_else(|| missing(&format!("data key {:?}", key))),
+            KubeObject::Crd { .. } => object
+                .get("spec")
+                .map(Value::to_string)
+                .ok_or_else(|| missing("spec")),
+        }
+    }
+}
+
+impl std::fmt::Display for KubeObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            KubeObject::ConfigMap {
+                namespace,
+                name,
+                key,
+            } => write!(f, "configmap/{}/{}#{}", namespace, name, key),
+            KubeObject::Crd { namespace, name } => {
+                write!(f, "{}/{}/{}", CRD_PLURAL, namespace, name)
+            }
+        }
+    }
+}
+
+pub trait KubeClient: Send + Sync {
+    /// Performs a `GET` against the API server and returns the JSON body.
+    fn get(&self, path: &str) -> Result<Value, String>;
+}
+
+impl<F> KubeClient for F
+where
+    F: Fn(&str) -> Result<Value, String> + Send + Sync,
+{
+    fn get(&self, path: &str) -> Result<Value, String> {
+        self(path)
+    }
+}
+
+pub struct KubeSource {
+    object: KubeObject,
+    client: Arc<dyn KubeClient>,
+}
+
+impl KubeSource {
+    pub fn new(object: KubeObject, client: Arc<dyn KubeClient>) -> Self {
+        Self { object, client }
+    }
+}
+
+impl PolicySource for KubeSource {
+    fn name(&self) -> String {
+        self.object.to_string()
+    }
+
+    fn fetch(&self) -> Result<Vec<u8>, SourceError> {
+        let object =
+            self.client
+                .get(&self.object.path())
+                .map_err(|message| SourceError::Fetch {
+                    source_name: self.name(),
+                    message,
+                })?;
+        Ok(self.object.bundle(&object)?.into_bytes())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct WatchEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    object: Value,
+}
+
+/// Applies watch events for one object to an engine, skipping revisions it
+/// has already seen. A deleted object leaves the last good policy in place.
+#[derive(Debug, Clone)]
+pub struct KubeWatcher {
+    object: KubeObject,
+    resource_version: Option<String>,
+}
+
+impl KubeWatcher {
+    pub fn new(object: KubeObject) -> Self {
+        Self {
+            object,
+            resource_version: None,
+        }
+    }
+
+    /// The last applied revision, to resume a watch after reconnecting.
+    pub fn resource_version(&self) -> Option<&str> {
+        self.resource_version.as_deref()
+    }
+
+    pub fn watch_path(&self) -> String {
+        self.object.watch_path(self.resource_version())
+    }
+
+    /// Handles one line of a watch stream. Returns `true` if a new revision
+    /// was activated.
+    pub fn handle(&mut self, engine: &mut PolicyEngine, line: &str) -> Result<bool, SourceError> {
+        let event: WatchEvent = serde_json::from_str(line).map_err(|e| SourceError::Fetch {
+            source_name: self.object.to_string(),
+            message: format!("invalid watch event: {}", e),
+        })?;
+        if !matches!(event.kind.as_str(), "ADDED" | "MODIFIED") {
+            return Ok(false);
+        }
+        let version = event
+            .object
+            .pointer("/metadata/resourceVersion")
+            .and_then(Value::as_str)
+            .map(String::from);
+        if version.is_some() && version == self.resource_version {
+            return Ok(false);
+        }
+        let bundle = self.object.bundle(&event.object)?;
+        engine.load_bundle(&bundle)?;
+        self.resource_version = version;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Effect;
+    use serde_json::json;
+
+    fn event(kind: &str, version: &str, effect: &str) -> String {
+        let bundle = json!({
+            "name": "cluster", "version": version,
+            "policies": [{"name": "cluster", "version": version, "rules": [
+                {"effect": effect, "principal": "*", "resource": "shell", "action": "execute", "conditions": []}
+            ]}]
+        });
+        json!({
+            "type": kind,
+            "object": {
+                "kind": "ConfigMap",
+                "metadata": {"name": "policy", "namespace": "agents", "resourceVersion": version},
+                "data": {"bundle.json": bundle.to_string()}
+            }
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_watch_applies_new_revisions() {
+        let mut engine = PolicyEngine::new();
+        let mut watcher = KubeWatcher::new(KubeObject::config_map("agents", "policy"));
+        let shell = |engine: &PolicyEngine| engine.evaluate("shell", "execute", &json!({}));
+
+        assert!(watcher
+            .handle(&mut engine, &event("ADDED", "1", "Allow"))
+            .unwrap());
+        assert_eq!(shell(&engine), Effect::Allow);
+        assert!(!watcher
+            .handle(&mut engine, &event("MODIFIED", "1", "Deny"))
+            .unwrap());
+        assert!(watcher
+            .handle(&mut engine, &event("MODIFIED", "2", "Deny"))
+            .unwrap());
+        assert_eq!(shell(&engine), Effect::Deny);
+        assert!(!watcher
+            .handle(&mut engine, &event("DELETED", "3", "Allow"))
+            .unwrap());
+        assert_eq!(shell(&engine), Effect::Deny);
+        assert!(watcher.watch_path().ends_with("&resourceVersion=2"));
+    }
+
+    #[test]
+    fn test_crd_source() {
+        let client: Arc<dyn KubeClient> = Arc::new(|path: &str| {
+            assert_eq!(
+                path,
+                "/apis/femtoclaw.io/v1/namespaces/agents/femtoclawpolicies/prod"
+            );
+            Ok(json!({"spec": {"name": "prod", "version": "1", "policies": []}}))
+        });
+        let source = KubeSource::new(KubeObject::crd("agents", "prod"), client);
+        PolicyEngine::new().load_from_source(&source).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+//! Policy Layers.
+//!
+//! Policies belong to one of three tiers with fixed precedence: organization,
+//! project and session. Every tier is consulted in that order; a `Deny` from any
+//! tier is final, and an `Allow` stands only if no later tier denies and the
+//! highest tier with policies allowed the request too: an unmatched request
+//! there falls to the default effect whatever lower tiers say. Lower tiers can
+//! therefore only further restrict, and [`PolicyEngine::add_policy_at`] rejects
+//! lower-tier `Allow` rules that a higher tier unconditionally denies, or that
+//! no higher tier allows while the default effect denies, so the mistake is
+//! caught at load time rather than silently ignored.
+//!
+//! Policies added with [`PolicyEngine::add_policy`] are organization policies.
+
+use crate::policy::{Effect, Policy, PolicyEngine, Rule};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Layer {
+    #[default]
+    Org,
+    Project,
+    Session,
+}
+
+impl Layer {
+    pub const ALL: [Layer; 3] = [Layer::Org, Layer::Project, Layer::Session];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LayerError {
+    #[error(
+        "{policy} rule {rule} allows `{resource}`, which {layer:?} policy `{denied_by}` denies"
+    )]
+    Loosening {
+        policy: String,
+        rule: usize,
+        resource: String,
+        layer: Layer,
+        denied_by: String,
+    },
+    #[error("{policy} rule {rule} allows `{resource}`, which no higher layer allows")]
+    NotGranted {
+        policy: String,
+        rule: usize,
+        resource: String,
+    },
+}
+
+/// Whether `deny` refuses everything `allow` could grant: same or wider resource,
+/// every principal, and no conditions.
+fn covers(engine: &PolicyEngine, deny: &Rule, allow: &Rule) -> bool {
+    let unconditional = deny.principal == "*"
+        && deny.conditions.is_empty()
+        && deny.param_constraints.is_empty()
+        && deny.enforce_after_ms.is_none();
+    let category = engine.category_of(&allow.resource);
+    unconditional && (deny.resource == "*" || deny.applies_in(&allow.resource, category))
+}
+
+/// Whether `granting` may allow something `allow` targets.
+fn grants(engine: &PolicyEngine, granting: &Rule, allow: &Rule) -> bool {
+    let category = engine.category_of(&allow.resource);
+    granting.effect.allows() && granting.applies_in(&allow.resource, category)
+}
+
+/// Rejects `policy` at `layer` if one of its `Allow` rules would loosen a higher
+/// layer's unconditional `Deny`, or grant what no higher layer allows.
+pub fn check_narrowing(
+    engine: &PolicyEngine,
+    layer: Layer,
+    policy: &Policy,
+) -> Result<(), LayerError> {
+    let higher_policies: Vec<&Policy> = engine
+        .policies()
+        .filter(|p| p.name != policy.name && engine.layer_of(&p.name).is_some_and(|l| l < layer))
+        .collect();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        if !rule.effect.allows() {
+            continue;
+        }
+        // Guards only deny, so they neither grant nor count as a granting layer.
+        let mut granting = higher_policies
+            .iter()
+            .filter(|higher| !engine.is_guard(&higher.name))
+            .peekable();
+        let populated = granting.peek().is_some();
+        let granted = granting.any(|higher| higher.rules.iter().any(|g| grants(engine, g, rule)));
+        if populated && !granted && !engine.default_effect_for(&rule.resource).allows() {
+            return Err(LayerError::NotGranted {
+                policy: policy.name.clone(),
+                rule: index,
+                resource: rule.resource.clone(),
+            });
+        }
+        for higher in &higher_policies {
+            let denied = higher
+                .rules
+                .iter()
+                .any(|d| d.effect == Effect::Deny && covers(engine, d, rule));
+            if denied {
+                return Err(LayerError::Loosening {
+                    policy: policy.name.clone(),
+                    rule: index,
+                    resource: rule.resource.clone(),
+                    layer: engine.layer_of(&higher.name).unwrap_or_default(),
+                    denied_by: higher.name.clone(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::NO_ARGS;
+
+    #[test]
+    fn test_lower_layers_only_restrict() {
+        let mut engine = PolicyEngine::new();
+        engine
+            .add_policy_at(
+                Layer::Org,
+                Policy::new("org", "1.0")
+                    .with_rule(Rule::deny("category:Credential"))
+                    .with_rule(Rule::allow("*")),
+            )
+            .unwrap();
+        engine
+            .add_policy_at(
+                Layer::Session,
+                Policy::new("session", "1.0").with_rule(Rule::deny("shell")),
+            )
+            .unwrap();
+        engine
+            .add_policy_at(
+                Layer::Project,
+                Policy::new("project", "1.0").with_rule(Rule::allow("fs.read")),
+            )
+            .unwrap();
+
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &NO_ARGS),
+            Effect::Allow
+        );
+        assert_eq!(engine.evaluate("shell", "execute", &NO_ARGS), Effect::Deny);
+
+        let loosening = Policy::new("project", "2.0").with_rule(Rule::allow("secret.read"));
+        assert!(matches!(
+            engine.add_policy_at(Layer::Project, loosening),
+            Err(LayerError::Loosening { denied_by, .. }) if denied_by == "org"
+        ));
+        assert_eq!(engine.get_policy("project").unwrap().version, "1.0");
+    }
+
+    #[test]
+    fn test_lower_layers_cannot_grant_what_higher_layers_do_not() {
+        let mut engine = PolicyEngine::new().with_default_effect(Effect::Deny);
+        engine.add_policy(Policy::new("org", "1.0").with_rule(Rule::allow("fs.read")));
+
+        let shell = Policy::new("session", "1.0").with_rule(Rule::allow("shell"));
+        assert!(matches!(
+            engine.add_policy_at(Layer::Session, shell.clone()),
+            Err(LayerError::NotGranted { resource, .. }) if resource == "shell"
+        ));
+        assert_eq!(engine.evaluate("shell", "execute", &NO_ARGS), Effect::Deny);
+
+        // Even an allow that slips past the load-time check does not stand.
+        engine.add_policy(Policy::new("org", "1.0").with_rule(
+            Rule::allow("fs.read").with_conditions(vec![crate::policy::Condition::new(
+                "path",
+                "eq",
+                serde_json::json!("/a"),
+            )]),
+        ));
+        engine
+            .add_policy_at(
+                Layer::Session,
+                Policy::new("session", "1.0").with_rule(Rule::allow("fs.read")),
+            )
+            .unwrap();
+        let other = serde_json::json!({"path": "/b"});
+        assert_eq!(engine.evaluate("fs.read", "execute", &other), Effect::Deny);
+    }
+}
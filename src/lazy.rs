@@ -0,0 +1,122 @@
+//! Zero-copy, Lazily Materialized Policy Loading.
+//!
+//! [`LazyPolicySet::parse`] scans a JSON policy array once, borrowing policy
+//! names and versions from the input and keeping each policy's rules as an
+//! unparsed slice of the source text. Rules are only deserialized into owned
+//! [`Policy`] values when a policy is actually added to an engine, so large
+//! bundles of which a process needs only a few policies load quickly.
+
+use crate::policy::{Policy, PolicyEngine};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::borrow::Cow;
+
+#[derive(Debug, Deserialize)]
+pub struct LazyPolicy<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+    #[serde(default, borrow)]
+    pub extends: Option<Cow<'a, str>>,
+    #[serde(default)]
+    pub allow_unicode: bool,
+    #[serde(borrow)]
+    rules: &'a RawValue,
+}
+
+impl LazyPolicy<'_> {
+    /// The unparsed JSON text of this policy's rules.
+    pub fn raw_rules(&self) -> &str {
+        self.rules.get()
+    }
+
+    pub fn materialize(&self) -> Result<Policy, serde_json::Error> {
+        let mut rules = serde_json::Deserializer::from_str(self.rules.get());
+        let rules = crate::template::deserialize_rules(&mut rules)?;
+        let mut policy = Policy::new(self.name.as_ref(), self.version.as_ref());
+        policy.extends = self.extends.as_deref().map(String::from);
+        policy.allow_unicode = self.allow_unicode;
+        policy.rules = rules;
+        Ok(policy)
+    }
+}
+
+#[derive(Debug)]
+pub struct LazyPolicySet<'a> {
+    policies: Vec<LazyPolicy<'a>>,
+}
+
+impl<'a> LazyPolicySet<'a> {
+    pub fn parse(json: &'a str) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            policies: serde_json::from_str(json)?,
+        })
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.policies.iter().map(|p| p.name.as_ref())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LazyPolicy<'a>> {
+        self.policies.iter().find(|p| p.name == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.policies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.policies.is_empty()
+    }
+}
+
+impl PolicyEngine {
+    /// Materializes and adds only the named policies from `set`, in the order of
+    /// `names`. Unknown names are ignored.
+    pub fn add_lazy(
+        &mut self,
+        set: &LazyPolicySet<'_>,
+        names: &[&str],
+    ) -> Result<(), serde_json::Error> {
+        for name in names {
+            if let Some(policy) = set.get(name) {
+                self.add_policy(policy.materialize()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Effect;
+
+    const JSON: &str = r#"[
+        {"name": "web", "version": "1.0", "rules": [
+            {"effect": "Allow", "principal": "*", "resource": "web.get", "action": "execute", "conditions": []}
+        ]},
+        {"name": "broken", "version": "1.0", "rules": [{"effect": "Sometimes"}]}
+    ]"#;
+
+    #[test]
+    fn test_names_are_borrowed() {
+        let set = LazyPolicySet::parse(JSON).unwrap();
+        assert_eq!(set.names().collect::<Vec<_>>(), vec!["web", "broken"]);
+        assert!(matches!(set.get("web").unwrap().name, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_only_selected_policies_materialize() {
+        let set = LazyPolicySet::parse(JSON).unwrap();
+        let mut engine = PolicyEngine::new();
+        engine.add_lazy(&set, &["web"]).unwrap();
+
+        assert_eq!(
+            engine.evaluate("web.get", "execute", &serde_json::json!({})),
+            Effect::Allow
+        );
+        assert!(engine.add_lazy(&set, &["broken"]).is_err());
+    }
+}
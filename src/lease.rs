@@ -0,0 +1,170 @@
+//! Leased Authorizations.
+//!
+//! Streaming and long-running capabilities (file tail, shell sessions) hold a
+//! [`LeasedAuthorization`] that must be renewed with [`CapabilityGate::renew`]
+//! before it lapses. Renewal re-evaluates the original request, so a policy
+//! change that now denies it, or a lease left to expire, fails renewal and tells
+//! the executor to stop the operation.
+//!
+//! A renewal continues the call the lease was granted for rather than starting
+//! another: the lease keeps its outcome [`Ticket`](crate::outcome::Ticket), the
+//! call is not counted again toward session history, and consent given for the
+//! lease is not asked again.
+
+use crate::audit::now_ms;
+use crate::context::RequestContext;
+use crate::decision::{Authorization, Decision, DecisionRecord, Denial};
+use crate::gate::{CapabilityGate, Evaluation};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Lease length for decisions that do not carry their own TTL.
+pub const DEFAULT_LEASE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct LeasedAuthorization {
+    tool: String,
+    args: Value,
+    ctx: RequestContext,
+    authorization: Authorization,
+    expires_at_ms: u64,
+    renewals: u32,
+}
+
+impl LeasedAuthorization {
+    pub fn tool(&self) -> &str {
+        &self.tool
+    }
+
+    pub fn authorization(&self) -> &Authorization {
+        &self.authorization
+    }
+
+    pub fn expires_at_ms(&self) -> u64 {
+        self.expires_at_ms
+    }
+
+    pub fn renewals(&self) -> u32 {
+        self.renewals
+    }
+
+    pub fn is_expired_at(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_at_ms
+    }
+
+    fn expiry(authorization: &Authorization) -> u64 {
+        let record = &authorization.record;
+        let ttl = record.valid_for().unwrap_or(DEFAULT_LEASE);
+        record.timestamp_ms.saturating_add(ttl.as_millis() as u64)
+    }
+}
+
+impl CapabilityGate {
+    /// Authorizes `tool` and wraps the grant in a lease. Idempotency keys are
+    /// dropped from the context, so every renewal is evaluated afresh.
+    pub fn lease(
+        &self,
+        tool: &str,
+        args: Value,
+        ctx: &RequestContext,
+    ) -> Result<LeasedAuthorization, Denial> {
+        let mut ctx = ctx.clone();
+        ctx.idempotency_key = None;
+        let authorization = self.try_authorize(tool, &args, &ctx)?;
+        Ok(LeasedAuthorization {
+            tool: tool.to_string(),
+            expires_at_ms: LeasedAuthorization::expiry(&authorization),
+            args,
+            ctx,
+            authorization,
+            renewals: 0,
+        })
+    }
+
+    /// Extends `lease` if it has not lapsed and the request is still authorized.
+    pub fn renew(&self, lease: &mut LeasedAuthorization) -> Result<(), Denial> {
+        self.renew_at(lease, now_ms())
+    }
+
+    pub fn renew_at(&self, lease: &mut LeasedAuthorization, now_ms: u64) -> Result<(), Denial> {
+        if lease.is_expired_at(now_ms) {
+            let record = DecisionRecord::new(Decision::DeniedLeaseExpired, &lease.tool);
+            return Err(Denial::new(record));
+        }
+        let mut authorization =
+            self.try_authorize_as(&lease.tool, &lease.args, &lease.ctx, Evaluation::Renewal)?;
+        authorization.ticket = lease.authorization.ticket.take();
+        lease.expires_at_ms = LeasedAuthorization::expiry(&authorization);
+        lease.authorization = authorization;
+        lease.renewals += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_renewal_fails_after_policy_change_or_expiry() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.tail", "Tail a file"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("fs.tail")));
+
+        let ctx = RequestContext::default();
+        let mut lease = gate.lease("fs.tail", json!({}), &ctx).unwrap();
+        gate.renew(&mut lease).unwrap();
+        assert_eq!(lease.renewals(), 1);
+
+        let late = lease.expires_at_ms();
+        let expired = gate.renew_at(&mut lease.clone(), late).unwrap_err();
+        assert_eq!(expired.decision(), Decision::DeniedLeaseExpired);
+
+        gate.add_policy(Policy::new("default", "2.0").with_rule(Rule::deny("fs.tail")));
+        let revoked = gate.renew(&mut lease).unwrap_err();
+        assert_eq!(revoked.decision(), Decision::DeniedPolicyViolation);
+    }
+
+    #[test]
+    fn test_renewal_continues_the_leased_call() {
+        use crate::consent::{Consent, ConsentRequest};
+        use crate::flags::SESSION_ATTRIBUTE;
+        use crate::outcome::{Outcome, OutcomeTracker};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let tracker = Arc::new(OutcomeTracker::new());
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let mut gate = CapabilityGate::new()
+            .with_session_state()
+            .with_outcome_observer(tracker.clone())
+            .with_consent_provider(Arc::new(move |_: &ConsentRequest<'_>| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Consent::approve()
+            }));
+        gate.register_capability(Capability::new("fs.tail", "Tail a file"));
+        gate.add_policy(
+            Policy::new("default", "1.0").with_rule(Rule::allow("fs.tail").requiring_consent()),
+        );
+
+        let ctx = RequestContext::new()
+            .with_principal("alice")
+            .with_attribute(SESSION_ATTRIBUTE, "s1");
+        let mut lease = gate.lease("fs.tail", json!({}), &ctx).unwrap();
+        let ticket = lease.authorization().ticket.clone().unwrap();
+        for _ in 0..3 {
+            gate.renew(&mut lease).unwrap();
+        }
+        assert_eq!(lease.authorization().ticket.as_ref(), Some(&ticket));
+        assert_eq!(tracker.in_flight(Some("alice")), 1);
+        assert_eq!(asked.load(Ordering::SeqCst), 1);
+        assert_eq!(gate.session_state(&ctx).unwrap().count("fs.tail"), 1);
+
+        gate.report_outcome(&ticket, Outcome::Succeeded);
+        assert_eq!(tracker.in_flight(Some("alice")), 0);
+    }
+}
@@ -9,12 +9,138 @@
 //! - [`PolicyEngine`] - evaluates authorization rules
 //! - [`CapabilityGate`] - enforces authorization decisions
 //! - [`Decision`] - authorization decision types
+//! - [`AuditLog`] - hash-chained, signed audit trail of decisions
+//! - [`PolicyBundle`] - self-verifying policy bundles with embedded test vectors
+//! - [`sandbox`] - kernel sandbox ruleset export from policy constraints
 
+pub mod archive;
+pub mod args;
+pub mod audit;
+pub mod authorizer;
+pub mod backend;
+pub mod backoff;
+pub mod bloom;
+pub mod breaker;
+pub mod budget;
+pub mod builtin;
+pub mod bundle;
+pub mod cache;
+pub mod canary;
 pub mod capability;
+pub mod clock;
+pub mod codegen;
+pub mod codes;
+pub mod compat;
+pub mod condition;
+pub mod consent;
+#[cfg(feature = "consul")]
+pub mod consul;
+pub mod context;
+#[cfg(feature = "database")]
+pub mod database;
+pub mod dataset;
+pub mod debounce;
+pub mod debug;
+pub mod decision;
+pub mod defaults;
+pub mod degradation;
+pub mod diagnostic;
+pub mod digest;
+pub mod docgen;
+pub mod dot;
+pub mod encryption;
+pub mod explain;
+pub mod export;
+pub mod features;
+pub mod filesink;
+pub mod fingerprint;
+pub mod flags;
+pub mod format;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz;
 pub mod gate;
+pub mod generation;
+pub mod golden;
+pub mod grace;
+pub mod group;
+pub mod gzip;
+#[cfg(feature = "hcl")]
+pub mod hcl;
+pub mod idempotency;
+pub mod image;
+pub mod index;
+pub mod invariant;
+pub mod keys;
+#[cfg(feature = "kube")]
+pub mod kube;
+pub mod layer;
+pub mod lazy;
+pub mod lease;
+pub mod limits;
+pub mod lint;
+pub mod matrix;
+pub mod middleware;
+pub mod minimize;
+#[cfg(feature = "test-util")]
+pub mod mock;
+#[cfg(feature = "object-store")]
+pub mod objectstore;
+pub mod openapi;
+pub mod outcome;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod pattern;
+pub mod percent;
+pub mod plan;
 pub mod policy;
+pub mod preflight;
+pub mod preset;
+pub mod proof;
+pub mod provenance;
+#[cfg(feature = "json-schema")]
+pub mod regex;
+pub mod repl;
+pub mod sandbox;
+#[cfg(feature = "json-schema")]
+pub mod schema;
+pub mod scope;
+pub mod session;
+pub mod source;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod store;
+pub mod strict;
+pub mod subsume;
+pub mod suggest;
+pub mod summary;
+pub mod template;
+pub mod tenant;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod unicode;
+pub mod validation;
+pub mod wire;
 
-pub use capability::{Capability, CapabilityRegistry};
+pub use args::{ArgValue, ArgView, Args};
+pub use audit::{AuditEvent, AuditLog, AuditSink};
+pub use backend::{Combination, DecisionBackend};
+pub use budget::EvaluationBudget;
+pub use bundle::{BundleError, PolicyBundle, TestVector};
+pub use capability::{Capability, CapabilityCategory, CapabilityRegistry};
+pub use clock::{Clock, SystemClock};
+pub use condition::ParamConstraint;
+pub use context::RequestContext;
+pub use decision::{Authorization, DecisionCategory, DecisionRecord, Denial};
+pub use defaults::CategoryDefaults;
+pub use degradation::DegradationMode;
 pub use gate::CapabilityGate;
-pub use policy::{Policy, PolicyEngine, Rule};
 pub use gate::Decision;
+pub use group::{GroupResolver, StaticGroups};
+pub use layer::Layer;
+pub use lease::LeasedAuthorization;
+pub use lint::Lint;
+pub use middleware::GateMiddleware;
+pub use policy::{Policy, PolicyEngine, Rule};
+pub use preflight::{Preflight, PreflightResult};
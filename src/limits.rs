@@ -0,0 +1,131 @@
+//! Argument Limits.
+//!
+//! Tool arguments come from untrusted model output. Before anything walks them
+//! — condition evaluation, cache keys, audit serialization — the gate checks
+//! their nesting depth and approximate encoded size against [`ArgLimits`] and
+//! denies oversized payloads with `DeniedInvalidArguments`. The check itself is
+//! iterative, so hostile nesting cannot overflow the stack.
+
+use crate::codes::Reason;
+use serde_json::Value;
+use thiserror::Error;
+
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+pub const DEFAULT_MAX_BYTES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArgLimits {
+    pub max_depth: Option<usize>,
+    pub max_bytes: Option<usize>,
+}
+
+impl Default for ArgLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_bytes: Some(DEFAULT_MAX_BYTES),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ArgLimitError {
+    #[error("arguments nest deeper than {limit} levels")]
+    TooDeep { limit: usize },
+    #[error("arguments exceed {limit} bytes")]
+    TooLarge { limit: usize },
+}
+
+impl ArgLimitError {
+    pub fn reason(&self) -> Reason {
+        match self {
+            ArgLimitError::TooDeep { .. } => Reason::ArgsTooDeep,
+            ArgLimitError::TooLarge { .. } => Reason::ArgsTooLarge,
+        }
+    }
+}
+
+/// Bytes a scalar or container contributes to the encoded payload, roughly as
+/// serialized JSON.
+fn encoded_len(value: &Value) -> usize {
+    match value {
+        Value::Null => 4,
+        Value::Bool(_) => 5,
+        Value::Number(n) => n.to_string().len(),
+        Value::String(s) => s.len() + 2,
+        Value::Array(items) => 2 + items.len(),
+        Value::Object(map) => 2 + map.keys().map(|k| k.len() + 4).sum::<usize>(),
+    }
+}
+
+impl ArgLimits {
+    pub fn unlimited() -> Self {
+        Self {
+            max_depth: None,
+            max_bytes: None,
+        }
+    }
+
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    pub fn with_max_bytes(mut self, bytes: usize) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Checks `args` depth-first with an explicit stack. A scalar at the top
+    /// level has depth 0; each enclosing array or object adds one.
+    pub fn check(&self, args: &Value) -> Result<(), ArgLimitError> {
+        let mut bytes = 0usize;
+        let mut stack = vec![(args, 0usize)];
+        while let Some((value, depth)) = stack.pop() {
+            if let Some(limit) = self.max_depth.filter(|&limit| depth > limit) {
+                return Err(ArgLimitError::TooDeep { limit });
+            }
+            bytes = bytes.saturating_add(encoded_len(value));
+            if let Some(limit) = self.max_bytes.filter(|&limit| bytes > limit) {
+                return Err(ArgLimitError::TooLarge { limit });
+            }
+            match value {
+                Value::Array(items) => stack.extend(items.iter().map(|v| (v, depth + 1))),
+                Value::Object(map) => stack.extend(map.values().map(|v| (v, depth + 1))),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nested(depth: usize) -> Value {
+        (0..depth).fold(json!(1), |inner, _| json!([inner]))
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let limits = ArgLimits::default().with_max_depth(3);
+        assert_eq!(limits.check(&nested(3)), Ok(()));
+        assert_eq!(
+            limits.check(&nested(4)),
+            Err(ArgLimitError::TooDeep { limit: 3 })
+        );
+        assert!(ArgLimits::unlimited().check(&nested(500)).is_ok());
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let limits = ArgLimits::unlimited().with_max_bytes(64);
+        assert!(limits.check(&json!({"path": "/tmp"})).is_ok());
+        assert_eq!(
+            limits.check(&json!({"blob": "x".repeat(100)})),
+            Err(ArgLimitError::TooLarge { limit: 64 })
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! Policy Lints.
+//!
+//! Lints flag policies that still load and evaluate but are likely mistakes or
+//! due for maintenance, such as references to deprecated capability names.
+
+use crate::capability::CapabilityRegistry;
+use crate::clock::{is_time_condition, TIME_OPERATORS};
+use crate::condition::OPERATORS;
+use crate::diagnostic::nearest;
+use crate::policy::Policy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lint {
+    pub code: String,
+    pub severity: Severity,
+    pub policy: String,
+    pub rule: Option<usize>,
+    pub message: String,
+    /// Where the rule (or policy) was loaded from, when known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+}
+
+impl Lint {
+    pub fn warning(
+        code: &str,
+        policy: &str,
+        rule: Option<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            code: code.to_string(),
+            severity: Severity::Warning,
+            policy: policy.to_string(),
+            rule,
+            message: message.into(),
+            location: None,
+        }
+    }
+
+    pub fn error(
+        code: &str,
+        policy: &str,
+        rule: Option<usize>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Error,
+            ..Self::warning(code, policy, rule, message)
+        }
+    }
+}
+
+impl std::fmt::Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        if let Some(location) = &self.location {
+            write!(f, "{}: ", location)?;
+        }
+        write!(f, "{}[{}] {}", severity, self.code, self.policy)?;
+        if let Some(rule) = self.rule {
+            write!(f, " rule {}", rule)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+pub fn lint_policy(policy: &Policy, registry: &CapabilityRegistry) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        if let Some(replacement) = registry.deprecated(&rule.resource) {
+            lints.push(Lint::warning(
+                "deprecated-capability",
+                &policy.name,
+                Some(index),
+                format!(
+                    "capability `{}` was renamed to `{}`",
+                    rule.resource, replacement
+                ),
+            ));
+        }
+    }
+    lints.extend(operator_lints(policy));
+    lints.extend(crate::cache::cache_key_lints(policy));
+    lints.extend(crate::scope::scope_violations(policy, registry));
+    lints.extend(crate::subsume::subsumption_lints(policy, registry));
+    lints.extend(crate::unicode::unicode_lints(policy));
+    locate(policy, &mut lints);
+    lints
+}
+
+/// Unknown operators never match, which silently disables an `Allow` rule.
+fn operator_lints(policy: &Policy) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        for condition in &rule.conditions {
+            let known = match is_time_condition(condition) {
+                true => TIME_OPERATORS,
+                false => OPERATORS,
+            };
+            if known.contains(&condition.operator.as_str()) {
+                continue;
+            }
+            let mut message = format!("unknown operator `{}`", condition.operator);
+            if let Some(suggestion) = nearest(&condition.operator, known.iter().copied()) {
+                message.push_str(&format!("; did you mean `{}`?", suggestion));
+            }
+            lints.push(Lint::warning(
+                "unknown-operator",
+                &policy.name,
+                Some(index),
+                message,
+            ));
+        }
+    }
+    lints
+}
+
+/// Fills in each lint's location from the provenance of its rule or policy.
+pub(crate) fn locate(policy: &Policy, lints: &mut [Lint]) {
+    for lint in lints {
+        let provenance = match lint.rule {
+            Some(index) => policy.rules.get(index).and_then(|r| r.provenance.as_ref()),
+            None => policy.provenance.as_ref(),
+        };
+        lint.location = provenance.map(|p| p.to_string());
+    }
+}
+
+impl Policy {
+    /// Rewrites rule resources that use deprecated aliases to their canonical names.
+    pub fn apply_renames(&mut self, registry: &CapabilityRegistry) {
+        for rule in &mut self.rules {
+            if let Some(replacement) = registry.deprecated(&rule.resource) {
+                rule.resource = replacement.to_string();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::Rule;
+
+    #[test]
+    fn test_unknown_operator_suggests_nearest() {
+        let policy = Policy::new("p", "1").with_rule(Rule::allow("fs.read").with_conditions(vec![
+            crate::policy::Condition::new("path", "start_with", "/tmp".into()),
+        ]));
+        let lints = lint_policy(&policy, &CapabilityRegistry::new());
+        assert_eq!(lints.len(), 1);
+        assert_eq!(
+            lints[0].message,
+            "unknown operator `start_with`; did you mean `starts_with`?"
+        );
+    }
+
+    #[test]
+    fn test_deprecated_reference_linted_and_renamed() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("http.get", "HTTP GET requests"));
+        registry.register_alias("web.fetch", "http.get");
+
+        let mut policy = Policy::new("default", "1.0")
+            .with_rule(Rule::allow("fs.read"))
+            .with_rule(Rule::allow("web.fetch"));
+
+        let lints = lint_policy(&policy, &registry);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].rule, Some(1));
+        assert_eq!(
+            lints[0].to_string(),
+            "warning[deprecated-capability] default rule 1: capability `web.fetch` was renamed to `http.get`"
+        );
+
+        policy.apply_renames(&registry);
+        assert_eq!(policy.rules[1].resource, "http.get");
+        assert!(lint_policy(&policy, &registry).is_empty());
+    }
+}
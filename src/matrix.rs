@@ -0,0 +1,206 @@
+//! Permission Matrix Export.
+//!
+//! [`PolicyEngine::permission_matrix`] tabulates, for every principal and role
+//! named in the policies against every registered capability, the effect the
+//! engine would produce. Wildcard and `category:` rules are resolved against
+//! the registry. Cells whose `Allow` depends on argument conditions are
+//! reported as `conditional`. The table serializes to JSON or CSV for security
+//! reviews and audit evidence.
+
+use crate::capability::CapabilityRegistry;
+use crate::context::RequestContext;
+use crate::group::{GroupError, GroupResolver, GROUP_PREFIX};
+use crate::policy::{Effect, PolicyEngine};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+/// Row label for requests without a principal.
+pub const ANYONE: &str = "*";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cell {
+    Allow,
+    Deny,
+    Conditional,
+}
+
+impl Cell {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Cell::Allow => "allow",
+            Cell::Deny => "deny",
+            Cell::Conditional => "conditional",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PermissionMatrix {
+    pub principals: Vec<String>,
+    pub capabilities: Vec<String>,
+    /// `cells[row][column]`, rows following `principals`.
+    pub cells: Vec<Vec<Cell>>,
+}
+
+impl PermissionMatrix {
+    pub fn get(&self, principal: &str, capability: &str) -> Option<Cell> {
+        let row = self.principals.iter().position(|p| p == principal)?;
+        let column = self.capabilities.iter().position(|c| c == capability)?;
+        Some(self.cells[row][column])
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let field = |s: &str| {
+            if s.contains([',', '"', '\n']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        };
+        let mut out = String::from("principal");
+        for capability in &self.capabilities {
+            out.push(',');
+            out.push_str(&field(capability));
+        }
+        out.push('\n');
+        for (principal, row) in self.principals.iter().zip(&self.cells) {
+            out.push_str(&field(principal));
+            for cell in row {
+                out.push(',');
+                out.push_str(cell.as_str());
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Resolves groups as usual, but also counts the row's own `group:<name>`
+/// placeholder as a member of `<name>`, so the row inherits the role's rules
+/// (and, through the real resolver, those of enclosing roles).
+struct RowGroups {
+    inner: Option<Arc<dyn GroupResolver>>,
+    principal: String,
+}
+
+impl GroupResolver for RowGroups {
+    fn members(&self, group: &str) -> Result<Option<Vec<String>>, GroupError> {
+        let mut members = match &self.inner {
+            Some(inner) => inner.members(group)?.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        if self.principal.strip_prefix(GROUP_PREFIX) == Some(group) {
+            members.push(self.principal.clone());
+        }
+        Ok(Some(members))
+    }
+}
+
+impl PolicyEngine {
+    pub fn permission_matrix(&self, registry: &CapabilityRegistry) -> PermissionMatrix {
+        let mut principals: BTreeSet<String> = self
+            .policies()
+            .flat_map(|p| &p.rules)
+            .map(|r| r.principal.clone())
+            .collect();
+        principals.insert(ANYONE.to_string());
+        let principals: Vec<String> = principals.into_iter().collect();
+        let capabilities: Vec<String> = registry.list().iter().map(|c| c.name.clone()).collect();
+
+        let none = serde_json::json!({});
+        let cells = principals
+            .iter()
+            .map(|principal| {
+                let mut ctx = RequestContext::new();
+                let mut engine = self.clone();
+                if principal != ANYONE {
+                    ctx = ctx.with_principal(principal.clone());
+                    engine = engine.with_group_resolver(Arc::new(RowGroups {
+                        inner: self.group_resolver().cloned(),
+                        principal: principal.clone(),
+                    }));
+                }
+                capabilities
+                    .iter()
+                    .map(|capability| {
+                        match engine.evaluate_with(&ctx, capability, "execute", &none) {
+                            Effect::Allow | Effect::AllowWithWarning => Cell::Allow,
+                            // Watch-mode rules are reported as the denials they will become.
+                            Effect::Deny | Effect::Audit
+                                if engine.has_conditional_allow(&ctx, capability) =>
+                            {
+                                Cell::Conditional
+                            }
+                            Effect::Deny | Effect::Audit => Cell::Deny,
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        PermissionMatrix {
+            principals,
+            capabilities,
+            cells,
+        }
+    }
+
+    fn has_conditional_allow(&self, ctx: &RequestContext, capability: &str) -> bool {
+        let category = self.category_of(capability);
+        self.policies().flat_map(|p| &p.rules).any(|rule| {
+            matches!(rule.effect, Effect::Allow | Effect::AllowWithWarning)
+                && rule.applies_in(capability, category)
+                && !(rule.conditions.is_empty() && rule.param_constraints.is_empty())
+                && (rule.principal == ANYONE
+                    || ctx.principal.as_deref() == Some(rule.principal.as_str()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::condition::ParamConstraint;
+    use crate::group::StaticGroups;
+    use crate::policy::{Policy, Rule};
+
+    #[test]
+    fn test_matrix_resolves_roles_and_wildcards() {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("fs.read", "Read files"));
+        registry.register(Capability::new("shell", "Execute shell commands"));
+        registry.register(Capability::new("git", "Run git"));
+
+        let mut admin = Rule::allow("*");
+        admin.principal = "group:admins".to_string();
+        let mut engine = PolicyEngine::new().with_group_resolver(Arc::new(
+            StaticGroups::new().with_group("admins", ["group:oncall"]),
+        ));
+        engine.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(admin)
+                .with_rule(
+                    Rule::allow("git")
+                        .with_param_constraint(ParamConstraint::one_of("subcommand", ["status"])),
+                ),
+        );
+
+        let matrix = engine.permission_matrix(&registry);
+        assert_eq!(matrix.get("*", "fs.read"), Some(Cell::Allow));
+        assert_eq!(matrix.get("*", "shell"), Some(Cell::Deny));
+        assert_eq!(matrix.get("*", "git"), Some(Cell::Conditional));
+        assert_eq!(matrix.get("group:admins", "shell"), Some(Cell::Allow));
+
+        let csv = matrix.to_csv();
+        assert_eq!(csv.lines().next(), Some("principal,fs.read,git,shell"));
+        assert!(csv.contains("group:admins,allow,allow,allow"));
+    }
+}
@@ -0,0 +1,34 @@
+//! Gate Middleware.
+//!
+//! [`GateMiddleware`] layers cross-cutting concerns (rate limiting, consent checks,
+//! metrics) onto the gate without forking it. Middleware runs in registration
+//! order: `before` hooks may rewrite the request context or veto the request,
+//! and `after` hooks see the final [`DecisionRecord`] and may add details before
+//! it is audited. Neither can turn a denial into an authorization.
+
+use crate::args::ArgView;
+use crate::context::RequestContext;
+use crate::decision::{Decision, DecisionRecord};
+use std::collections::BTreeMap;
+
+pub trait GateMiddleware: Send + Sync {
+    /// Name recorded in `vetoed_by` when this middleware denies a request.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Called before policy evaluation. Returning a denial vetoes the request;
+    /// middleware cannot grant what policy would deny, so `Authorized` is ignored.
+    fn before(
+        &self,
+        _tool: &str,
+        _args: &dyn ArgView,
+        _ctx: &mut RequestContext,
+    ) -> Option<Decision> {
+        None
+    }
+
+    /// Called with the final record and its details; details added here are
+    /// audited. The record itself, decision included, is read-only.
+    fn after(&self, _record: &DecisionRecord, _details: &mut BTreeMap<String, String>) {}
+}
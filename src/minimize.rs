@@ -0,0 +1,240 @@
+//! Policy Minimization.
+//!
+//! [`Policy::minimize`] shrinks a policy without changing any decision it
+//! makes:
+//!
+//! - rules an earlier rule fully covers are unreachable and removed;
+//! - adjacent rules with the same outcome that differ only in the accepted
+//!   values of one `eq`/`in` condition are merged into a single `in`;
+//! - a rule fully covered by the adjacent rule after it, with the same
+//!   outcome, is collapsed into it.
+//!
+//! Only the rule order within the policy matters to first-match evaluation,
+//! so each step preserves decisions; rule indexes (and so rule ids) may shift.
+
+use crate::capability::CapabilityCategory;
+use crate::policy::{Condition, Policy, Rule};
+use crate::subsume::subsumes;
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MinimizeReport {
+    pub unreachable: usize,
+    pub merged: usize,
+    pub collapsed: usize,
+}
+
+impl MinimizeReport {
+    pub fn removed(&self) -> usize {
+        self.unreachable + self.merged + self.collapsed
+    }
+}
+
+/// Same effect, principal and per-decision attributes.
+fn same_outcome(a: &Rule, b: &Rule) -> bool {
+    a.effect == b.effect
+        && a.principal == b.principal
+        && a.action == b.action
+        && a.audit == b.audit
+        && a.ttl_secs == b.ttl_secs
+        && a.enforce_after_ms == b.enforce_after_ms
+}
+
+fn accepted_values(condition: &Condition) -> Option<Vec<Value>> {
+    match condition.operator.as_str() {
+        "eq" | "equals" => Some(vec![condition.value.clone()]),
+        "in" => condition.value.as_array().cloned(),
+        _ => None,
+    }
+}
+
+fn sorted(conditions: &[Condition]) -> Vec<&Condition> {
+    let mut sorted: Vec<&Condition> = conditions.iter().collect();
+    sorted.sort_by_key(|c| (c.key.clone(), c.operator.clone(), c.value.to_string()));
+    sorted
+}
+
+/// `a` and `b` as one rule, if they differ only in one value-set condition.
+fn merge(a: &Rule, b: &Rule) -> Option<Rule> {
+    if !same_outcome(a, b)
+        || a.resource != b.resource
+        || a.param_constraints != b.param_constraints
+        || a.conditions.len() != b.conditions.len()
+    {
+        return None;
+    }
+    let (sa, sb) = (sorted(&a.conditions), sorted(&b.conditions));
+    let differing: Vec<usize> = (0..sa.len()).filter(|&i| sa[i] != sb[i]).collect();
+    let [i] = differing.as_slice() else {
+        return None;
+    };
+    let (ca, cb) = (sa[*i], sb[*i]);
+    if ca.key != cb.key {
+        return None;
+    }
+    let mut values = accepted_values(ca)?;
+    for value in accepted_values(cb)? {
+        if !values.contains(&value) {
+            values.push(value);
+        }
+    }
+
+    let mut merged = a.clone();
+    let target = merged.conditions.iter().position(|c| c == ca)?;
+    merged.conditions[target] = Condition::new(ca.key.clone(), "in", Value::Array(values));
+    Some(merged)
+}
+
+impl Policy {
+    /// Removes unreachable rules and merges or collapses redundant ones until
+    /// nothing changes. Categories are inferred from capability names.
+    pub fn minimize(&mut self) -> MinimizeReport {
+        let infer = |name: &str| CapabilityCategory::infer(name);
+        let mut report = MinimizeReport::default();
+        loop {
+            let before = report;
+
+            let mut kept: Vec<Rule> = Vec::with_capacity(self.rules.len());
+            for rule in self.rules.drain(..) {
+                if kept.iter().any(|earlier| subsumes(earlier, &rule, &infer)) {
+                    report.unreachable += 1;
+                } else {
+                    kept.push(rule);
+                }
+            }
+            self.rules = kept;
+
+            let mut i = 0;
+            while i + 1 < self.rules.len() {
+                let (a, b) = (&self.rules[i], &self.rules[i + 1]);
+                if let Some(merged) = merge(a, b) {
+                    self.rules[i] = merged;
+                    self.rules.remove(i + 1);
+                    report.merged += 1;
+                } else if same_outcome(a, b) && subsumes(b, a, &infer) {
+                    self.rules.remove(i);
+                    report.collapsed += 1;
+                } else {
+                    i += 1;
+                }
+            }
+
+            if report == before {
+                return report;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Effect, PolicyEngine};
+    use serde_json::json;
+
+    fn when(resource: &str, effect: Effect, key: &str, op: &str, value: Value) -> Rule {
+        let rule = Rule {
+            effect,
+            ..Rule::deny(resource)
+        };
+        rule.with_conditions(vec![Condition::new(key, op, value)])
+    }
+
+    fn legacy() -> Policy {
+        Policy::new("legacy", "1.0")
+            .with_rule(when("git", Effect::Allow, "cmd", "eq", json!("status")))
+            .with_rule(when("git", Effect::Allow, "cmd", "eq", json!("log")))
+            .with_rule(when(
+                "git",
+                Effect::Allow,
+                "cmd",
+                "in",
+                json!(["log", "diff"]),
+            ))
+            .with_rule(when(
+                "fs.read",
+                Effect::Allow,
+                "path",
+                "starts_with",
+                json!("/work/src"),
+            ))
+            .with_rule(when(
+                "fs.read",
+                Effect::Allow,
+                "path",
+                "starts_with",
+                json!("/work"),
+            ))
+            .with_rule(when(
+                "fs.read",
+                Effect::Deny,
+                "path",
+                "starts_with",
+                json!("/work/x"),
+            ))
+            .with_rule(when("shell", Effect::Deny, "cmd", "eq", json!("rm")))
+            .with_rule(Rule::allow("shell"))
+    }
+
+    /// Tiny deterministic xorshift for the differential test.
+    fn rng(state: &mut u64) -> usize {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state as usize
+    }
+
+    #[test]
+    fn test_minimize_is_decision_equivalent() {
+        let original = legacy();
+        let mut minimized = original.clone();
+        let report = minimized.minimize();
+        assert_eq!(
+            report,
+            MinimizeReport {
+                unreachable: 1,
+                merged: 2,
+                collapsed: 1
+            }
+        );
+        assert_eq!(minimized.rules.len(), 4);
+
+        let mut before = PolicyEngine::new();
+        before.add_policy(original);
+        let mut after = PolicyEngine::new();
+        after.add_policy(minimized);
+
+        let resources = ["git", "fs.read", "shell", "net.get"];
+        let keys = ["cmd", "path", "other"];
+        let values = [
+            "status",
+            "log",
+            "diff",
+            "push",
+            "rm",
+            "/work",
+            "/work/src/a",
+            "/work/x/y",
+            "/etc",
+        ];
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..2000 {
+            let resource = resources[rng(&mut state) % resources.len()];
+            let mut args = serde_json::Map::new();
+            for _ in 0..rng(&mut state) % 3 {
+                args.insert(
+                    keys[rng(&mut state) % keys.len()].to_string(),
+                    json!(values[rng(&mut state) % values.len()]),
+                );
+            }
+            let args = Value::Object(args);
+            assert_eq!(
+                before.evaluate(resource, "execute", &args),
+                after.evaluate(resource, "execute", &args),
+                "{} {}",
+                resource,
+                args
+            );
+        }
+    }
+}
@@ -0,0 +1,278 @@
+//! Test Doubles.
+//!
+//! With the `test-util` feature, [`MockGate`] stands in for `CapabilityGate`
+//! in downstream tests. It implements [`Authorizer`], answers from scripted
+//! responses instead of policy, records every call, and has assertion helpers:
+//!
+//! ```
+//! use femtoclaw_policy::mock::MockGate;
+//!
+//! let gate = MockGate::new().allow("fs.read");
+//! assert!(gate.authorize("fs.read", &serde_json::json!({ "path": "a" })).is_allowed());
+//! assert!(!gate.authorize("shell", &()).is_allowed());
+//! gate.assert_authorized("fs.read");
+//! gate.assert_denied("shell");
+//! ```
+//!
+//! A response for `fs.*` covers every capability in the `fs` namespace and
+//! `*` covers everything; one-shot responses from `respond_once` are used
+//! before standing ones. Unscripted calls get the default decision,
+//! `DeniedPolicyViolation` unless changed with `with_default`.
+
+use crate::args::Args;
+use crate::authorizer::{AuthorizationRequest, Authorizer};
+use crate::context::RequestContext;
+use crate::decision::{Decision, DecisionRecord};
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub tool: String,
+    /// The arguments, if they were JSON.
+    pub args: Option<Value>,
+    pub principal: Option<String>,
+    pub decision: Decision,
+}
+
+#[derive(Debug)]
+struct MockState {
+    default: Decision,
+    standing: Vec<(String, Decision)>,
+    once: BTreeMap<String, VecDeque<Decision>>,
+    calls: Vec<RecordedCall>,
+}
+
+#[derive(Debug)]
+pub struct MockGate {
+    state: Mutex<MockState>,
+}
+
+impl Default for MockGate {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(MockState {
+                default: Decision::DeniedPolicyViolation,
+                standing: Vec::new(),
+                once: BTreeMap::new(),
+                calls: Vec::new(),
+            }),
+        }
+    }
+}
+
+fn covers(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some("") => true,
+        Some(prefix) if prefix.ends_with('.') => tool.starts_with(prefix),
+        _ => pattern == tool,
+    }
+}
+
+impl MockGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A mock that authorizes everything unless scripted otherwise.
+    pub fn allowing_all() -> Self {
+        Self::new().with_default(Decision::Authorized)
+    }
+
+    pub fn with_default(self, decision: Decision) -> Self {
+        self.lock().default = decision;
+        self
+    }
+
+    /// Answers every call to `tool` (or a `ns.*` / `*` pattern) with `decision`.
+    /// Later responses for the same pattern replace earlier ones.
+    pub fn respond(self, tool: impl Into<String>, decision: Decision) -> Self {
+        let tool = tool.into();
+        let mut state = self.lock();
+        state.standing.retain(|(pattern, _)| *pattern != tool);
+        state.standing.push((tool, decision));
+        drop(state);
+        self
+    }
+
+    pub fn allow(self, tool: impl Into<String>) -> Self {
+        self.respond(tool, Decision::Authorized)
+    }
+
+    pub fn deny(self, tool: impl Into<String>) -> Self {
+        self.respond(tool, Decision::DeniedPolicyViolation)
+    }
+
+    /// Answers the next call to exactly `tool` with `decision`; queued
+    /// responses are used in order.
+    pub fn respond_once(&self, tool: impl Into<String>, decision: Decision) {
+        self.lock()
+            .once
+            .entry(tool.into())
+            .or_default()
+            .push_back(decision);
+    }
+
+    pub fn authorize(&self, tool: &str, args: &(impl Args + ?Sized)) -> Decision {
+        self.authorize_with(tool, args, &RequestContext::default())
+    }
+
+    pub fn authorize_with(
+        &self,
+        tool: &str,
+        args: &(impl Args + ?Sized),
+        ctx: &RequestContext,
+    ) -> Decision {
+        self.record(tool, args.as_json(), ctx.principal.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn record(&self, tool: &str, args: Option<&Value>, principal: Option<String>) -> Decision {
+        let mut state = self.lock();
+        let queued = state.once.get_mut(tool).and_then(VecDeque::pop_front);
+        let decision = queued.unwrap_or_else(|| {
+            state
+                .standing
+                .iter()
+                .rev()
+                .find(|(pattern, _)| covers(pattern, tool))
+                .map_or(state.default, |(_, decision)| *decision)
+        });
+        state.calls.push(RecordedCall {
+            tool: tool.to_string(),
+            args: args.cloned(),
+            principal,
+            decision,
+        });
+        decision
+    }
+
+    /// Every call so far, oldest first.
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.lock().calls.clone()
+    }
+
+    pub fn calls_to(&self, tool: &str) -> Vec<RecordedCall> {
+        self.lock()
+            .calls
+            .iter()
+            .filter(|call| call.tool == tool)
+            .cloned()
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.lock().calls.clear();
+    }
+
+    /// Panics unless `tool` was called and at least one call was authorized.
+    #[track_caller]
+    pub fn assert_authorized(&self, tool: &str) {
+        let calls = self.calls_to(tool);
+        assert!(
+            calls.iter().any(|call| call.decision.is_allowed()),
+            "expected `{}` to be authorized; calls to it: {:?}",
+            tool,
+            calls.iter().map(|c| c.decision).collect::<Vec<_>>()
+        );
+    }
+
+    /// Panics unless `tool` was called and at least one call was denied.
+    #[track_caller]
+    pub fn assert_denied(&self, tool: &str) {
+        let calls = self.calls_to(tool);
+        assert!(
+            calls.iter().any(|call| !call.decision.is_allowed()),
+            "expected `{}` to be denied; calls to it: {:?}",
+            tool,
+            calls.iter().map(|c| c.decision).collect::<Vec<_>>()
+        );
+    }
+
+    #[track_caller]
+    pub fn assert_not_called(&self, tool: &str) {
+        let calls = self.calls_to(tool).len();
+        assert_eq!(calls, 0, "expected no calls to `{}`, got {}", tool, calls);
+    }
+
+    #[track_caller]
+    pub fn assert_call_count(&self, tool: &str, expected: usize) {
+        let calls = self.calls_to(tool).len();
+        assert_eq!(calls, expected, "calls to `{}`", tool);
+    }
+}
+
+impl Authorizer for MockGate {
+    fn authorize(&self, request: &AuthorizationRequest<'_>) -> DecisionRecord {
+        let principal = request.ctx.principal.clone();
+        let decision = self.record(request.tool, request.args.as_json(), principal.clone());
+        let mut record = DecisionRecord::new(decision, request.tool);
+        record.principal = principal;
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_scripted_responses() {
+        let gate = MockGate::new()
+            .allow("fs.*")
+            .deny("fs.delete")
+            .respond("shell", Decision::DeniedCapabilityDisabled);
+        gate.respond_once("fs.delete", Decision::Authorized);
+
+        assert_eq!(gate.authorize("fs.read", &()), Decision::Authorized);
+        assert_eq!(gate.authorize("fs.delete", &()), Decision::Authorized);
+        assert_eq!(
+            gate.authorize("fs.delete", &()),
+            Decision::DeniedPolicyViolation
+        );
+        assert_eq!(
+            gate.authorize("shell", &()),
+            Decision::DeniedCapabilityDisabled
+        );
+        assert_eq!(
+            gate.authorize("http.get", &()),
+            Decision::DeniedPolicyViolation
+        );
+        assert_eq!(
+            MockGate::allowing_all().authorize("x", &()),
+            Decision::Authorized
+        );
+    }
+
+    #[test]
+    fn test_records_calls_through_the_trait() {
+        let gate = MockGate::allowing_all().deny("shell");
+        let ctx = RequestContext::default().with_principal("alice");
+        let args = json!({ "command": "ls" });
+        let record = Authorizer::authorize(&gate, &AuthorizationRequest::new("shell", &args, &ctx));
+        assert_eq!(record.principal.as_deref(), Some("alice"));
+
+        gate.authorize("fs.read", &());
+        gate.assert_denied("shell");
+        gate.assert_authorized("fs.read");
+        gate.assert_not_called("http.get");
+        gate.assert_call_count("shell", 1);
+        assert_eq!(gate.calls_to("shell")[0].args, Some(args));
+
+        gate.clear_calls();
+        assert!(gate.calls().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "expected `shell` to be authorized")]
+    fn test_assertion_failure_message() {
+        let gate = MockGate::new();
+        gate.authorize("shell", &());
+        gate.assert_authorized("shell");
+    }
+}
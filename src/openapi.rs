@@ -0,0 +1,211 @@
+//! OpenAPI Capability Definitions.
+//!
+//! Many tools are HTTP APIs described by an OpenAPI 3 document. [`import`]
+//! creates one capability per `operationId`, with parameters taken from the
+//! operation's `parameters` and its JSON request body schema, so the registry
+//! stays in sync with the API surface. [`export`] writes a registry back out as
+//! a minimal document with one `POST /<capability>` operation each. Documents
+//! must be JSON; convert YAML specs before importing.
+
+use crate::capability::{Capability, CapabilityCategory, CapabilityParam, CapabilityRegistry};
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+#[derive(Debug, Error)]
+pub enum OpenApiError {
+    #[error("invalid OpenAPI JSON: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("document has no `paths` object")]
+    MissingPaths,
+    #[error("operation {method} {path} has no operationId")]
+    MissingOperationId { method: String, path: String },
+}
+
+fn param_type(schema: Option<&Value>) -> String {
+    schema
+        .and_then(|s| s.get("type"))
+        .and_then(Value::as_str)
+        .unwrap_or("string")
+        .to_string()
+}
+
+fn operation_params(operation: &Value, shared: Option<&Value>) -> Vec<CapabilityParam> {
+    let mut params = Vec::new();
+    let declared = shared
+        .and_then(Value::as_array)
+        .into_iter()
+        .chain(operation.get("parameters").and_then(Value::as_array))
+        .flatten();
+    for param in declared {
+        let Some(name) = param.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        params.retain(|p: &CapabilityParam| p.name != name);
+        params.push(CapabilityParam {
+            name: name.to_string(),
+            param_type: param_type(param.get("schema")),
+            required: param
+                .get("required")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        });
+    }
+
+    let body = operation
+        .pointer("/requestBody/content/application~1json/schema")
+        .filter(|schema| schema.get("properties").is_some());
+    if let Some(schema) = body {
+        let required: Vec<&str> = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|r| r.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        for (name, property) in schema["properties"].as_object().into_iter().flatten() {
+            params.push(CapabilityParam {
+                name: name.clone(),
+                param_type: param_type(Some(property)),
+                required: required.contains(&name.as_str()),
+            });
+        }
+    }
+    params
+}
+
+/// One capability per operation in `document`, in path then method order.
+pub fn import(document: &str) -> Result<Vec<Capability>, OpenApiError> {
+    let document: Value = serde_json::from_str(document)?;
+    let paths = document
+        .get("paths")
+        .and_then(Value::as_object)
+        .ok_or(OpenApiError::MissingPaths)?;
+
+    let mut capabilities = Vec::new();
+    for (path, item) in paths {
+        for method in METHODS {
+            let Some(operation) = item.get(method) else {
+                continue;
+            };
+            let id = operation
+                .get("operationId")
+                .and_then(Value::as_str)
+                .ok_or_else(|| OpenApiError::MissingOperationId {
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                })?;
+            let description = ["summary", "description"]
+                .iter()
+                .find_map(|k| operation.get(*k).and_then(Value::as_str))
+                .unwrap_or_default();
+            capabilities.push(
+                Capability::new(id, description)
+                    .with_category(CapabilityCategory::Network)
+                    .with_params(operation_params(operation, item.get("parameters"))),
+            );
+        }
+    }
+    Ok(capabilities)
+}
+
+impl CapabilityRegistry {
+    /// Registers every operation in an OpenAPI document, replacing capabilities
+    /// with the same name. Returns how many were registered.
+    pub fn import_openapi(&mut self, document: &str) -> Result<usize, OpenApiError> {
+        let capabilities = import(document)?;
+        let count = capabilities.len();
+        capabilities.into_iter().for_each(|c| self.register(c));
+        Ok(count)
+    }
+}
+
+pub fn export(registry: &CapabilityRegistry, title: &str) -> Value {
+    let mut paths = Map::new();
+    for capability in registry.list() {
+        let properties: Map<String, Value> = capability
+            .parameters
+            .iter()
+            .map(|p| (p.name.clone(), json!({ "type": p.param_type })))
+            .collect();
+        let required: Vec<&str> = capability
+            .parameters
+            .iter()
+            .filter(|p| p.required)
+            .map(|p| p.name.as_str())
+            .collect();
+        let operation = json!({
+            "operationId": capability.name,
+            "summary": capability.description,
+            "requestBody": { "content": { "application/json": { "schema": {
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            }}}},
+            "responses": { "200": { "description": "OK" } },
+        });
+        paths.insert(
+            format!("/{}", capability.name),
+            json!({ "post": operation }),
+        );
+    }
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": title, "version": "1.0" },
+        "paths": paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = r#"{
+        "openapi": "3.0.3",
+        "paths": {
+            "/issues/{id}": {
+                "parameters": [{ "name": "id", "in": "path", "required": true,
+                                 "schema": { "type": "integer" } }],
+                "get": { "operationId": "issues.get", "summary": "Fetch an issue" },
+                "patch": {
+                    "operationId": "issues.update",
+                    "requestBody": { "content": { "application/json": { "schema": {
+                        "type": "object",
+                        "properties": { "title": { "type": "string" } },
+                        "required": ["title"]
+                    }}}}
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_import_and_export() {
+        let mut registry = CapabilityRegistry::new();
+        assert_eq!(registry.import_openapi(SPEC).unwrap(), 2);
+
+        let get = registry.get("issues.get").unwrap();
+        assert_eq!(get.description, "Fetch an issue");
+        assert_eq!(get.category, CapabilityCategory::Network);
+        assert_eq!(get.parameters[0].param_type, "integer");
+        let update = registry.get("issues.update").unwrap();
+        let names: Vec<_> = update.parameters.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["id", "title"]);
+        assert!(update.parameters[1].required);
+
+        let exported = export(&registry, "tools").to_string();
+        let mut roundtrip = CapabilityRegistry::new();
+        roundtrip.import_openapi(&exported).unwrap();
+        assert_eq!(roundtrip.get("issues.update").unwrap().parameters.len(), 2);
+    }
+
+    #[test]
+    fn test_missing_operation_id() {
+        let spec = r#"{ "paths": { "/x": { "get": {} } } }"#;
+        assert!(matches!(
+            import(spec),
+            Err(OpenApiError::MissingOperationId { .. })
+        ));
+    }
+}
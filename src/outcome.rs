@@ -0,0 +1,219 @@
+//! Execution Outcome Feedback.
+//!
+//! An authorization only says a call may run. `try_authorize` also issues a
+//! [`Ticket`], and the caller reports what actually happened with
+//! `CapabilityGate::report_outcome`, so quotas, concurrency limits and anomaly
+//! detection can charge real work instead of counting authorized requests.
+//!
+//! Outcomes go to every [`OutcomeObserver`] on the gate. [`OutcomeTracker`] is
+//! the built-in observer: it keeps per principal and capability usage — calls
+//! in flight, terminal outcomes and output volume — and flags pairs whose
+//! failure rate looks anomalous.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded,
+    Failed,
+    Aborted,
+    /// Output produced so far; may be reported several times before the call ends.
+    OutputBytes(u64),
+}
+
+impl Outcome {
+    /// Whether the outcome ends the call the ticket was issued for.
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, Outcome::OutputBytes(_))
+    }
+}
+
+/// Identifies one authorized call for outcome reporting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ticket {
+    pub id: u64,
+    pub capability: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub principal: Option<String>,
+    pub issued_at_ms: u64,
+}
+
+pub trait OutcomeObserver: Send + Sync {
+    /// Called when the ticket is issued, before the call runs.
+    fn started(&self, _ticket: &Ticket) {}
+
+    fn outcome(&self, ticket: &Ticket, outcome: Outcome);
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub in_flight: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub aborted: u64,
+    pub output_bytes: u64,
+}
+
+impl Usage {
+    pub fn completed(&self) -> u64 {
+        self.succeeded + self.failed + self.aborted
+    }
+}
+
+type UsageKey = (Option<String>, String);
+
+#[derive(Debug, Default)]
+pub struct OutcomeTracker {
+    state: Mutex<TrackerState>,
+}
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    open: BTreeSet<u64>,
+    usage: BTreeMap<UsageKey, Usage>,
+}
+
+impl OutcomeTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn usage(&self, principal: Option<&str>, capability: &str) -> Usage {
+        let key = (principal.map(String::from), capability.to_string());
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.usage.get(&key).copied().unwrap_or_default()
+    }
+
+    /// Calls in flight for `principal` across all capabilities, for concurrency limits.
+    pub fn in_flight(&self, principal: Option<&str>) -> u64 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .usage
+            .iter()
+            .filter(|((p, _), _)| p.as_deref() == principal)
+            .map(|(_, usage)| usage.in_flight)
+            .sum()
+    }
+
+    /// Principal and capability pairs with at least `min_calls` completed calls
+    /// of which more than `max_failure_rate` failed.
+    pub fn anomalies(
+        &self,
+        min_calls: u64,
+        max_failure_rate: f64,
+    ) -> Vec<(Option<String>, String)> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .usage
+            .iter()
+            .filter(|(_, usage)| {
+                let completed = usage.completed();
+                completed >= min_calls.max(1)
+                    && usage.failed as f64 / completed as f64 > max_failure_rate
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+impl OutcomeObserver for OutcomeTracker {
+    fn started(&self, ticket: &Ticket) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.open.insert(ticket.id);
+        let key = (ticket.principal.clone(), ticket.capability.clone());
+        state.usage.entry(key).or_default().in_flight += 1;
+    }
+
+    fn outcome(&self, ticket: &Ticket, outcome: Outcome) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        // Outcomes for tickets already closed, or never issued here, are ignored
+        // so a retried report cannot be charged twice.
+        if !state.open.contains(&ticket.id) {
+            return;
+        }
+        if outcome.is_terminal() {
+            state.open.remove(&ticket.id);
+        }
+        let key = (ticket.principal.clone(), ticket.capability.clone());
+        let usage = state.usage.entry(key).or_default();
+        match outcome {
+            Outcome::OutputBytes(n) => usage.output_bytes = usage.output_bytes.saturating_add(n),
+            Outcome::Succeeded => usage.succeeded += 1,
+            Outcome::Failed => usage.failed += 1,
+            Outcome::Aborted => usage.aborted += 1,
+        }
+        if outcome.is_terminal() {
+            usage.in_flight = usage.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::CapabilityGate;
+    use crate::policy::{Policy, Rule};
+    use std::sync::Arc;
+
+    fn gate(tracker: Arc<OutcomeTracker>) -> CapabilityGate {
+        let mut gate = CapabilityGate::new().with_outcome_observer(tracker);
+        gate.register_capability(Capability::new("shell", "Shell"));
+        gate.add_policy(Policy::new("tools", "1").with_rule(Rule::allow("shell")));
+        gate
+    }
+
+    #[test]
+    fn test_outcomes_charge_usage() {
+        let tracker = Arc::new(OutcomeTracker::new());
+        let gate = gate(tracker.clone());
+        let ctx = RequestContext::new().with_principal("alice");
+
+        let first = gate.try_authorize("shell", &(), &ctx).unwrap();
+        let second = gate.try_authorize("shell", &(), &ctx).unwrap();
+        let (first, second) = (first.ticket.unwrap(), second.ticket.unwrap());
+        assert_ne!(first.id, second.id);
+        assert_eq!(tracker.in_flight(Some("alice")), 2);
+
+        gate.report_outcome(&first, Outcome::OutputBytes(512));
+        gate.report_outcome(&first, Outcome::Succeeded);
+        gate.report_outcome(&first, Outcome::Succeeded);
+        gate.report_outcome(&second, Outcome::Aborted);
+
+        let usage = tracker.usage(Some("alice"), "shell");
+        assert_eq!(usage.in_flight, 0);
+        assert_eq!((usage.succeeded, usage.aborted), (1, 1));
+        assert_eq!(usage.output_bytes, 512);
+    }
+
+    #[test]
+    fn test_failure_rate_anomalies() {
+        let tracker = Arc::new(OutcomeTracker::new());
+        let gate = gate(tracker.clone());
+        for (principal, fail) in [("alice", 1), ("mallory", 4)] {
+            let ctx = RequestContext::new().with_principal(principal);
+            for i in 0..5 {
+                let ticket = gate
+                    .try_authorize("shell", &(), &ctx)
+                    .unwrap()
+                    .ticket
+                    .unwrap();
+                let outcome = if i < fail {
+                    Outcome::Failed
+                } else {
+                    Outcome::Succeeded
+                };
+                gate.report_outcome(&ticket, outcome);
+            }
+        }
+        assert_eq!(
+            tracker.anomalies(5, 0.5),
+            vec![(Some("mallory".to_string()), "shell".to_string())]
+        );
+        assert!(tracker.anomalies(6, 0.5).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! Parallel Policy Loading.
+//!
+//! Splits a JSON policy array into its raw elements, then parses each policy
+//! and compiles its index on scoped worker threads. Results are merged back in
+//! document order, so the loaded engine — and the error reported for a bad
+//! document — is the same as with [`PolicyEngine::load_from_json`].
+
+use crate::index::PolicyIndex;
+use crate::layer::Layer;
+use crate::policy::{Policy, PolicyEngine};
+use serde_json::value::RawValue;
+use std::thread;
+
+/// Applies `f` to every item on up to `workers` threads, keeping input order.
+pub fn map_ordered<T, U, F>(items: &[T], workers: usize, f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    let chunk = items.len().div_ceil(workers.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk)
+            .map(|part| scope.spawn(|| part.iter().map(&f).collect::<Vec<U>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("policy loader thread panicked"))
+            .collect()
+    })
+}
+
+fn workers() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+impl PolicyEngine {
+    /// Like [`PolicyEngine::load_from_json`], parsing and compiling on all
+    /// available cores. Nothing is added if any policy fails to parse.
+    pub fn load_from_json_parallel(&mut self, json: &str) -> Result<(), serde_json::Error> {
+        self.load_from_json_with_workers(json, workers())
+    }
+
+    pub fn load_from_json_with_workers(
+        &mut self,
+        json: &str,
+        workers: usize,
+    ) -> Result<(), serde_json::Error> {
+        let raw: Vec<&RawValue> = serde_json::from_str(json)?;
+        let compiled = map_ordered(&raw, workers, |raw| {
+            let policy: Policy = serde_json::from_str(raw.get())?;
+            let index = PolicyIndex::compile(&policy);
+            Ok::<_, serde_json::Error>((policy, index))
+        });
+        let compiled = compiled.into_iter().collect::<Result<Vec<_>, _>>()?;
+        for (policy, index) in compiled {
+            self.insert_compiled(Layer::Org, policy, index);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Effect, Rule};
+
+    fn document(count: usize) -> String {
+        let policies: Vec<Policy> = (0..count)
+            .map(|i| {
+                let rule = if i % 3 == 0 {
+                    Rule::deny(format!("tool.{}", i % 7))
+                } else {
+                    Rule::allow(format!("tool.{}", i % 7))
+                };
+                Policy::new(format!("p{}", i), "1.0").with_rule(rule)
+            })
+            .collect();
+        serde_json::to_string(&policies).unwrap()
+    }
+
+    #[test]
+    fn test_parallel_load_matches_sequential() {
+        let json = document(200);
+        let mut sequential = PolicyEngine::new();
+        sequential.load_from_json(&json).unwrap();
+        let mut parallel = PolicyEngine::new();
+        parallel.load_from_json_with_workers(&json, 8).unwrap();
+
+        let names = |e: &PolicyEngine| e.policies().map(|p| p.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&sequential), names(&parallel));
+        for i in 0..7 {
+            let tool = format!("tool.{}", i);
+            assert_eq!(
+                sequential.evaluate(&tool, "execute", &crate::args::NO_ARGS),
+                parallel.evaluate(&tool, "execute", &crate::args::NO_ARGS)
+            );
+        }
+        assert_eq!(
+            parallel.evaluate("tool.0", "execute", &crate::args::NO_ARGS),
+            Effect::Deny
+        );
+    }
+
+    #[test]
+    fn test_parse_error_adds_nothing() {
+        let mut engine = PolicyEngine::new();
+        let json = r#"[{"name": "ok", "version": "1.0", "rules": []}, {"name": 3}]"#;
+        assert!(engine.load_from_json_with_workers(json, 2).is_err());
+        assert_eq!(engine.policies().count(), 0);
+    }
+}
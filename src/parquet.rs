@@ -0,0 +1,175 @@
+//! Parquet Decision Export.
+//!
+//! The crate does not link a Parquet implementation itself: callers wrap their
+//! writer (the `parquet` or `arrow` crates) in a [`RowGroupWriter`], and
+//! [`ParquetExporter`] buffers audit events into columns in the
+//! [`DECISION_COLUMNS`] schema and hands them over one row group at a time.
+//!
+//! As an [`AuditSink`], the exporter writes a row group whenever
+//! `row_group_size` events are buffered; [`ParquetExporter::flush`] writes the
+//! remainder and runs on drop. Write errors drop the row group.
+
+use crate::audit::{AuditEvent, AuditSink};
+use crate::export::{export_row, Column, ColumnType, ExportValue, DECISION_COLUMNS};
+use std::io;
+use std::sync::Mutex;
+
+/// One column of a row group; `None` is a null.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnData {
+    Int64(Vec<Option<i64>>),
+    Float64(Vec<Option<f64>>),
+    Boolean(Vec<Option<bool>>),
+    Utf8(Vec<Option<String>>),
+}
+
+impl ColumnData {
+    fn new(column_type: ColumnType) -> Self {
+        match column_type {
+            ColumnType::Int64 => ColumnData::Int64(Vec::new()),
+            ColumnType::Float64 => ColumnData::Float64(Vec::new()),
+            ColumnType::Boolean => ColumnData::Boolean(Vec::new()),
+            ColumnType::Utf8 => ColumnData::Utf8(Vec::new()),
+        }
+    }
+
+    fn push(&mut self, value: ExportValue) {
+        match (self, value) {
+            (ColumnData::Int64(v), ExportValue::Int64(n)) => v.push(Some(n)),
+            (ColumnData::Float64(v), ExportValue::Float64(x)) => v.push(Some(x)),
+            (ColumnData::Boolean(v), ExportValue::Boolean(b)) => v.push(Some(b)),
+            (ColumnData::Utf8(v), ExportValue::Utf8(s)) => v.push(Some(s)),
+            (ColumnData::Int64(v), _) => v.push(None),
+            (ColumnData::Float64(v), _) => v.push(None),
+            (ColumnData::Boolean(v), _) => v.push(None),
+            (ColumnData::Utf8(v), _) => v.push(None),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            ColumnData::Int64(v) => v.len(),
+            ColumnData::Float64(v) => v.len(),
+            ColumnData::Boolean(v) => v.len(),
+            ColumnData::Utf8(v) => v.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// `events` as one [`ColumnData`] per [`DECISION_COLUMNS`] entry.
+pub fn columns<'a>(events: impl IntoIterator<Item = &'a AuditEvent>) -> Vec<ColumnData> {
+    let mut columns: Vec<ColumnData> = DECISION_COLUMNS
+        .iter()
+        .map(|c| ColumnData::new(c.column_type))
+        .collect();
+    for event in events {
+        for (column, value) in columns.iter_mut().zip(export_row(event)) {
+            column.push(value);
+        }
+    }
+    columns
+}
+
+pub trait RowGroupWriter: Send {
+    /// Writes one row group; `data` holds one column per `schema` entry.
+    fn write_row_group(&mut self, schema: &[Column], data: &[ColumnData]) -> io::Result<()>;
+}
+
+struct Buffered<W> {
+    writer: W,
+    events: Vec<AuditEvent>,
+}
+
+pub struct ParquetExporter<W: RowGroupWriter> {
+    row_group_size: usize,
+    state: Mutex<Buffered<W>>,
+}
+
+impl<W: RowGroupWriter> ParquetExporter<W> {
+    pub fn new(writer: W, row_group_size: usize) -> Self {
+        Self {
+            row_group_size: row_group_size.max(1),
+            state: Mutex::new(Buffered {
+                writer,
+                events: Vec::new(),
+            }),
+        }
+    }
+
+    /// Writes the buffered events as a row group, if there are any.
+    pub fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.events.is_empty() {
+            return Ok(());
+        }
+        let events = std::mem::take(&mut state.events);
+        state
+            .writer
+            .write_row_group(&DECISION_COLUMNS, &columns(&events))
+    }
+
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap().events.len()
+    }
+}
+
+impl<W: RowGroupWriter> AuditSink for ParquetExporter<W> {
+    fn record(&self, event: &AuditEvent) {
+        let full = {
+            let mut state = self.state.lock().unwrap();
+            state.events.push(event.clone());
+            state.events.len() >= self.row_group_size
+        };
+        if full {
+            let _ = self.flush();
+        }
+    }
+}
+
+impl<W: RowGroupWriter> Drop for ParquetExporter<W> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[derive(Clone, Default)]
+    struct Groups(Arc<Mutex<Vec<Vec<ColumnData>>>>);
+
+    impl RowGroupWriter for Groups {
+        fn write_row_group(&mut self, schema: &[Column], data: &[ColumnData]) -> io::Result<()> {
+            assert_eq!(schema.len(), data.len());
+            self.0.lock().unwrap().push(data.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_events_are_written_in_row_groups() {
+        let groups = Groups::default();
+        let exporter = ParquetExporter::new(groups.clone(), 2);
+        for tool in ["a", "b", "c"] {
+            let mut event = AuditEvent::new(tool, "AUTHORIZED");
+            event.principal = (tool != "b").then(|| "agent".to_string());
+            exporter.record(&event);
+        }
+        assert_eq!(exporter.pending(), 1);
+        drop(exporter);
+
+        let groups = groups.0.lock().unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            groups[0][1],
+            ColumnData::Utf8(vec![Some("agent".into()), None])
+        );
+        assert_eq!(groups[1][2], ColumnData::Utf8(vec![Some("c".into())]));
+    }
+}
@@ -0,0 +1,254 @@
+//! Glob Pattern Conditions.
+//!
+//! The `glob` operator (alias `matches`) matches a string argument against a
+//! shell-style pattern: `?` is one character and `*` any run of characters,
+//! neither crossing `/`; `**` matches anything, including `/`.
+//!
+//! Patterns are compiled lazily: the engine compiles a rule's patterns the
+//! first time the rule is evaluated and caches them in the policy's
+//! [`crate::index::PolicyIndex`], so rules that are never hit cost nothing.
+//!
+//! Compiled patterns are shared through [`PatternCache::shared`], keyed by the
+//! pattern text: the same glob repeated across thousands of tenant policies is
+//! compiled once and held once, for as long as any rule still uses it.
+//! [`PatternCache::stats`] reports how well that sharing works.
+
+use crate::policy::Rule;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+
+pub fn is_pattern_operator(operator: &str) -> bool {
+    matches!(operator, "glob" | "matches")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(char),
+    AnyChar,
+    Star,
+    GlobStar,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    tokens: Vec<Token>,
+}
+
+impl Glob {
+    pub fn compile(pattern: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    Token::GlobStar
+                }
+                '*' => Token::Star,
+                '?' => Token::AnyChar,
+                c => Token::Literal(c),
+            });
+        }
+        Self { tokens }
+    }
+
+    /// Tracks the set of text positions reachable after each token, one linear
+    /// sweep per token, so matching is O(pattern × text) however many wildcards
+    /// the pattern has.
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        let mut reachable = vec![false; chars.len() + 1];
+        reachable[0] = true;
+        for token in &self.tokens {
+            let mut next = vec![false; chars.len() + 1];
+            match token {
+                Token::Literal(c) => {
+                    for at in (0..chars.len()).filter(|&i| reachable[i]) {
+                        next[at + 1] = chars[at] == *c;
+                    }
+                }
+                Token::AnyChar => {
+                    for at in (0..chars.len()).filter(|&i| reachable[i]) {
+                        next[at + 1] = chars[at] != '/';
+                    }
+                }
+                // Once a position is reachable, every later one is too, up to
+                // the next `/` for `*`.
+                Token::Star => {
+                    let mut open = false;
+                    for at in 0..=chars.len() {
+                        open |= reachable[at];
+                        next[at] = open;
+                        open &= chars.get(at).is_some_and(|&c| c != '/');
+                    }
+                }
+                Token::GlobStar => {
+                    if let Some(first) = reachable.iter().position(|&r| r) {
+                        next[first..].iter_mut().for_each(|slot| *slot = true);
+                    }
+                }
+            }
+            reachable = next;
+        }
+        reachable[chars.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PatternCacheStats {
+    /// Patterns currently compiled and in use by at least one rule.
+    pub live: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Content-addressed compiled patterns. Entries are weak, so a pattern is
+/// freed once the last rule using it is dropped.
+#[derive(Default)]
+pub struct PatternCache {
+    entries: Mutex<HashMap<Box<str>, Weak<Glob>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl PatternCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The process-wide cache every policy index compiles through.
+    pub fn shared() -> &'static PatternCache {
+        static SHARED: OnceLock<PatternCache> = OnceLock::new();
+        SHARED.get_or_init(PatternCache::new)
+    }
+
+    /// The compiled form of `pattern`, compiling it only if no live copy exists.
+    pub fn get(&self, pattern: &str) -> Arc<Glob> {
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(glob) = entries.get(pattern).and_then(Weak::upgrade) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return glob;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // Sweeping on growth keeps dead entries below the live count.
+        if entries.len() >= 64 && entries.len().is_power_of_two() {
+            entries.retain(|_, glob| glob.strong_count() > 0);
+        }
+        let glob = Arc::new(Glob::compile(pattern));
+        entries.insert(pattern.into(), Arc::downgrade(&glob));
+        glob
+    }
+
+    pub fn stats(&self) -> PatternCacheStats {
+        let entries = self.entries.lock().unwrap();
+        PatternCacheStats {
+            live: entries.values().filter(|g| g.strong_count() > 0).count(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Compiled patterns for one rule, aligned with its conditions; `None` for
+/// conditions that are not glob patterns.
+pub type RulePatterns = Vec<Option<Arc<Glob>>>;
+
+pub fn compile_rule(rule: &Rule) -> RulePatterns {
+    rule.conditions
+        .iter()
+        .map(|c| {
+            let pattern = c
+                .value
+                .as_str()
+                .filter(|_| is_pattern_operator(&c.operator));
+            pattern.map(|p| PatternCache::shared().get(p))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Policy, PolicyEngine};
+    use serde_json::json;
+
+    #[test]
+    fn test_glob_semantics() {
+        let glob = Glob::compile("/work/*.rs");
+        assert!(glob.is_match("/work/main.rs"));
+        assert!(!glob.is_match("/work/src/main.rs"));
+        assert!(Glob::compile("/work/**.rs").is_match("/work/src/main.rs"));
+        assert!(Glob::compile("v?.*").is_match("v1.2"));
+        assert!(!Glob::compile("v?").is_match("v/"));
+        assert!(Glob::compile("*a*a*a*a*b").is_match(&format!("{}b", "a".repeat(200))));
+    }
+
+    #[test]
+    fn test_large_inputs_match_in_linear_time() {
+        let text = format!("{}a{}", "b".repeat(100_000), "c".repeat(100_000));
+        let started = std::time::Instant::now();
+        assert!(Glob::compile("*a*").is_match(&text));
+        assert!(!Glob::compile("*a*d").is_match(&text));
+        assert!(Glob::compile("**a**").is_match(&format!("x/{}", text)));
+        assert!(!Glob::compile("*a*").is_match(&format!("{}/x", text)));
+        assert!(started.elapsed() < std::time::Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_identical_patterns_are_compiled_once() {
+        let cache = PatternCache::new();
+        let first = cache.get("/work/**");
+        let second = cache.get("/work/**");
+        assert!(Arc::ptr_eq(&first, &second));
+        let _tmp = cache.get("/tmp/*");
+        assert_eq!(
+            cache.stats(),
+            PatternCacheStats {
+                live: 2,
+                hits: 1,
+                misses: 2
+            }
+        );
+        drop((first, second));
+        assert_eq!(cache.stats().live, 1);
+
+        let rule = Rule::allow("fs.read").with_conditions(vec![Condition::new(
+            "path",
+            "glob",
+            json!("/tenant/shared/**"),
+        )]);
+        let (a, b) = (compile_rule(&rule), compile_rule(&rule.clone()));
+        assert!(Arc::ptr_eq(a[0].as_ref().unwrap(), b[0].as_ref().unwrap()));
+    }
+
+    #[test]
+    fn test_patterns_compile_on_first_use() {
+        let rule = Rule::allow("fs.read").with_conditions(vec![
+            Condition::new("path", "glob", json!("/work/**")),
+            Condition::new("mode", "eq", json!("r")),
+        ]);
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1.0")
+                .with_rule(rule)
+                .with_rule(Rule::allow("shell")),
+        );
+        assert_eq!(engine.compiled_pattern_rules(), 0);
+
+        let args = json!({"path": "/work/a/b", "mode": "r"});
+        assert_eq!(
+            engine.evaluate("fs.read", "execute", &args),
+            crate::policy::Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate(
+                "fs.read",
+                "execute",
+                &json!({"path": "/etc/passwd", "mode": "r"})
+            ),
+            crate::policy::Effect::Deny
+        );
+        assert_eq!(engine.compiled_pattern_rules(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+//! Percent-Encoding Aware Matching.
+//!
+//! Paths and URLs arrive percent-encoded as often as not, and `%2e%2e%2f` must
+//! not slip past a rule written against `../`. With hardened matching on (see
+//! `CapabilityGate::with_hardened_matching`) the gate decodes locator arguments
+//! — keys such as `path`, `url` or `*_path` — exactly once before conditions see
+//! them, and denies requests whose locators are malformed or still contain
+//! escapes after decoding (double encoding) with `DeniedInvalidArguments`.
+//!
+//! Only JSON arguments are validated up front; other [`ArgView`]s are decoded
+//! lazily and fall back to the raw value when decoding fails.
+
+use crate::args::{ArgValue, ArgView};
+use crate::codes::Reason;
+use serde_json::Value;
+use std::borrow::Cow;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EncodingError {
+    #[error("`{key}` has a malformed percent escape")]
+    Malformed { key: String },
+    #[error("`{key}` is not valid UTF-8 once decoded")]
+    NotUtf8 { key: String },
+    #[error("`{key}` is percent-encoded more than once")]
+    DoubleEncoded { key: String },
+}
+
+impl EncodingError {
+    pub fn reason(&self) -> Reason {
+        match self {
+            EncodingError::Malformed { .. } => Reason::ArgsMalformedEncoding,
+            EncodingError::NotUtf8 { .. } => Reason::ArgsInvalidUtf8,
+            EncodingError::DoubleEncoded { .. } => Reason::ArgsDoubleEncoded,
+        }
+    }
+}
+
+const LOCATOR_KEYS: [&str; 13] = [
+    "path",
+    "paths",
+    "url",
+    "urls",
+    "uri",
+    "file",
+    "files",
+    "dir",
+    "directory",
+    "target",
+    "href",
+    "location",
+    "endpoint",
+];
+const LOCATOR_SUFFIXES: [&str; 5] = ["_path", "_url", "_uri", "_file", "_dir"];
+
+/// Whether values under `key` — the last non-index segment of an argument
+/// path — are paths or URLs.
+pub fn is_locator_key(key: &str) -> bool {
+    let segment = key
+        .rsplit('.')
+        .find(|s| s.parse::<usize>().is_err())
+        .unwrap_or(key);
+    LOCATOR_KEYS.contains(&segment) || LOCATOR_SUFFIXES.iter().any(|s| segment.ends_with(s))
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+fn has_escape(text: &str) -> bool {
+    text.as_bytes()
+        .windows(3)
+        .any(|w| w[0] == b'%' && hex(w[1]).is_some() && hex(w[2]).is_some())
+}
+
+/// Decodes `%XX` escapes once. `+` is left alone: it only means space in
+/// query strings, which policies match as written.
+pub fn decode_once<'a>(key: &str, text: &'a str) -> Result<Cow<'a, str>, EncodingError> {
+    if !text.contains('%') {
+        return Ok(Cow::Borrowed(text));
+    }
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'%' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        let escape = bytes
+            .get(i + 1)
+            .copied()
+            .and_then(hex)
+            .zip(bytes.get(i + 2).copied().and_then(hex));
+        let Some((high, low)) = escape else {
+            return Err(EncodingError::Malformed {
+                key: key.to_string(),
+            });
+        };
+        decoded.push(high << 4 | low);
+        i += 3;
+    }
+    String::from_utf8(decoded)
+        .map(Cow::Owned)
+        .map_err(|_| EncodingError::NotUtf8 {
+            key: key.to_string(),
+        })
+}
+
+/// Decodes once and rejects values that would still decode further.
+pub fn decode_strict<'a>(key: &str, text: &'a str) -> Result<Cow<'a, str>, EncodingError> {
+    let decoded = decode_once(key, text)?;
+    if has_escape(&decoded) {
+        return Err(EncodingError::DoubleEncoded {
+            key: key.to_string(),
+        });
+    }
+    Ok(decoded)
+}
+
+/// Checks every locator string in `args`, iteratively.
+pub fn validate(args: &Value) -> Result<(), EncodingError> {
+    let mut stack = vec![("", args, false)];
+    while let Some((key, value, locator)) = stack.pop() {
+        match value {
+            Value::String(s) if locator => {
+                decode_strict(key, s)?;
+            }
+            Value::Array(items) => stack.extend(items.iter().map(|v| (key, v, locator))),
+            Value::Object(map) => {
+                stack.extend(map.iter().map(|(k, v)| (k.as_str(), v, is_locator_key(k))))
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn decode_arg<'a>(key: &str, value: ArgValue<'a>) -> ArgValue<'a> {
+    let decoded = match value.as_str() {
+        Some(text) => match decode_strict(key, text) {
+            Ok(Cow::Owned(decoded)) => decoded,
+            _ => return value,
+        },
+        None => match value {
+            ArgValue::List(items) => {
+                return ArgValue::List(items.into_iter().map(|v| decode_arg(key, v)).collect())
+            }
+            ArgValue::Json(Value::Array(items)) => {
+                return ArgValue::List(
+                    items
+                        .iter()
+                        .map(|v| decode_arg(key, ArgValue::Json(v)))
+                        .collect(),
+                )
+            }
+            other => return other,
+        },
+    };
+    ArgValue::Str(Cow::Owned(decoded))
+}
+
+/// An argument view whose locator leaves read percent-decoded.
+pub struct Decoded<'a>(pub &'a dyn ArgView);
+
+impl ArgView for Decoded<'_> {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        let value = self.0.lookup(path)?;
+        Some(match is_locator_key(path) {
+            true => decode_arg(path, value),
+            false => value,
+        })
+    }
+
+    fn session_lookup(&self, key: &str) -> Option<ArgValue<'_>> {
+        self.0.session_lookup(key)
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        self.0.cache_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_decode_exactly_once() {
+        assert_eq!(decode_once("path", "/a%20b").unwrap(), "/a b");
+        assert_eq!(decode_strict("path", "%2e%2e%2fetc").unwrap(), "../etc");
+        assert_eq!(
+            decode_strict("path", "%252e%252e%252f"),
+            Err(EncodingError::DoubleEncoded { key: "path".into() })
+        );
+        assert!(matches!(
+            decode_once("url", "/50%"),
+            Err(EncodingError::Malformed { .. })
+        ));
+        assert!(matches!(
+            decode_once("url", "%ff"),
+            Err(EncodingError::NotUtf8 { .. })
+        ));
+    }
+
+    #[test]
+    fn test_only_locators_are_decoded() {
+        let args = json!({"path": "/w%6Frk", "note": "100%25", "opts": {"log_file": ["%2Fvar"]}});
+        assert!(validate(&args).is_ok());
+        let view = Decoded(&args);
+        assert_eq!(view.lookup("path").unwrap().as_str(), Some("/work"));
+        assert_eq!(view.lookup("note").unwrap().as_str(), Some("100%25"));
+        assert_eq!(
+            view.lookup("opts.log_file.0").unwrap().as_str(),
+            Some("/var")
+        );
+        assert!(validate(&json!({"files": ["ok", "%25%32%65"]})).is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! Plan Authorization.
+//!
+//! An agent that knows its next steps can submit them up front with
+//! [`CapabilityGate::authorize_plan`]. Every step is evaluated as if it ran
+//! now, without the side effects of running it: nothing is audited, no consent
+//! is asked, and session history, breakers and backoff are left alone. The gate
+//! returns a [`PlanAuthorization`] with the per-step decisions and a plan token.
+//! Only a plan whose every step is allowed is approved; the token of any other
+//! plan authorizes nothing.
+//!
+//! Each step is then executed through [`CapabilityGate::authorize_plan_step`]
+//! with the token and its index. The call must be the next step of the plan,
+//! with the same principal, capability and arguments, and is authorized again
+//! against the current policies. A call that deviates — another capability or
+//! other arguments, a skipped or repeated step, an unknown token — is denied
+//! with `DeniedPlanDeviation` and voids the rest of the plan, so what runs is
+//! exactly what was approved, in order. A step that current policies deny
+//! voids the plan too. A plan is forgotten after its last step, or once
+//! [`PLAN_TTL_MS`] have passed since it was approved. At most [`MAX_PLANS`]
+//! plans are kept; approving another forgets the oldest.
+
+use crate::codes::{Reason, REASON_DETAIL};
+use crate::context::RequestContext;
+use crate::decision::{Decision, DecisionRecord};
+use crate::digest::{sha256_hex, to_hex};
+use crate::fingerprint::canonical_json;
+use crate::gate::CapabilityGate;
+use serde_json::Value;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Detail key on step records: the step index within its plan.
+pub const PLAN_STEP_DETAIL: &str = "plan_step";
+/// How long an approved plan can be executed.
+pub const PLAN_TTL_MS: u64 = 10 * 60 * 1000;
+/// How many approved plans the gate keeps.
+pub const MAX_PLANS: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedCall {
+    pub tool: String,
+    pub args: Value,
+}
+
+impl PlannedCall {
+    pub fn new(tool: impl Into<String>, args: Value) -> Self {
+        Self {
+            tool: tool.into(),
+            args,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanAuthorization {
+    pub token: String,
+    /// One decision per planned call, in plan order.
+    pub steps: Vec<DecisionRecord>,
+}
+
+impl PlanAuthorization {
+    pub fn is_allowed(&self) -> bool {
+        self.steps.iter().all(DecisionRecord::is_allowed)
+    }
+
+    /// Indexes of the steps that would be denied.
+    pub fn denied_steps(&self) -> Vec<usize> {
+        (0..self.steps.len())
+            .filter(|&i| !self.steps[i].is_allowed())
+            .collect()
+    }
+}
+
+struct ApprovedPlan {
+    principal: Option<String>,
+    /// Canonical capability and arguments of each step.
+    steps: Vec<(String, String)>,
+    next: usize,
+    approved_at_ms: u64,
+}
+
+impl ApprovedPlan {
+    fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms.saturating_sub(self.approved_at_ms) >= PLAN_TTL_MS
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct PlanLedger {
+    plans: Mutex<HashMap<String, ApprovedPlan>>,
+    issued: AtomicU64,
+}
+
+impl PlanLedger {
+    fn token(&self, steps: &[(String, String)]) -> String {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(self.issued.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(crate::audit::now_ms());
+        let seed = to_hex(&hasher.finish().to_le_bytes());
+        let json = serde_json::to_string(steps).unwrap_or_default();
+        format!(
+            "plan_{}",
+            &sha256_hex(format!("{}{}", seed, json).as_bytes())[..32]
+        )
+    }
+
+    fn approve(&self, token: String, plan: ApprovedPlan) {
+        let mut plans = self.plans.lock().unwrap();
+        plans.retain(|_, p| !p.is_expired(plan.approved_at_ms));
+        if plans.len() >= MAX_PLANS {
+            let oldest = plans
+                .iter()
+                .min_by_key(|(_, p)| p.approved_at_ms)
+                .map(|(token, _)| token.clone());
+            if let Some(oldest) = oldest {
+                plans.remove(&oldest);
+            }
+        }
+        plans.insert(token, plan);
+    }
+
+    /// Checks the call against the plan, advancing it on success and voiding it
+    /// on deviation.
+    fn take_step(
+        &self,
+        token: &str,
+        index: usize,
+        principal: Option<&str>,
+        step: &(String, String),
+    ) -> Result<(), Reason> {
+        let mut plans = self.plans.lock().unwrap();
+        let Some(plan) = plans.get_mut(token) else {
+            return Err(Reason::PlanUnknown);
+        };
+        if plan.is_expired(crate::audit::now_ms()) {
+            plans.remove(token);
+            return Err(Reason::PlanUnknown);
+        }
+        let expected = plan.steps.get(plan.next);
+        if index != plan.next || expected != Some(step) || plan.principal.as_deref() != principal {
+            plans.remove(token);
+            return Err(Reason::PlanDeviation);
+        }
+        plan.next += 1;
+        if plan.next == plan.steps.len() {
+            plans.remove(token);
+        }
+        Ok(())
+    }
+
+    fn void(&self, token: &str) {
+        self.plans.lock().unwrap().remove(token);
+    }
+}
+
+impl CapabilityGate {
+    fn canonical_step(&self, tool: &str, args: &Value) -> (String, String) {
+        let capability = self.registry().resolve(tool).to_string();
+        (capability, canonical_json(args))
+    }
+
+    /// Evaluates every call in `calls` and issues a token for executing them
+    /// in order with [`CapabilityGate::authorize_plan_step`]; see the module
+    /// docs. Idempotency keys are dropped from the context, as every step is
+    /// evaluated again when it runs.
+    pub fn authorize_plan(&self, calls: &[PlannedCall], ctx: &RequestContext) -> PlanAuthorization {
+        let mut ctx = ctx.clone();
+        ctx.idempotency_key = None;
+        let steps: Vec<DecisionRecord> = calls
+            .iter()
+            .map(|call| self.preview_record(&call.tool, &call.args, &ctx))
+            .collect();
+        let approved: Vec<(String, String)> = calls
+            .iter()
+            .map(|call| self.canonical_step(&call.tool, &call.args))
+            .collect();
+        let ledger = self.plans();
+        let token = ledger.token(&approved);
+        let authorization = PlanAuthorization { token, steps };
+        if !approved.is_empty() && authorization.is_allowed() {
+            let plan = ApprovedPlan {
+                principal: ctx.principal.clone(),
+                steps: approved,
+                next: 0,
+                approved_at_ms: crate::audit::now_ms(),
+            };
+            ledger.approve(authorization.token.clone(), plan);
+        }
+        authorization
+    }
+
+    /// Authorizes step `index` of the plan `token`; see the module docs.
+    pub fn authorize_plan_step(
+        &self,
+        token: &str,
+        index: usize,
+        tool: &str,
+        args: &Value,
+        ctx: &RequestContext,
+    ) -> DecisionRecord {
+        let step = self.canonical_step(tool, args);
+        let checked = self
+            .plans()
+            .take_step(token, index, ctx.principal.as_deref(), &step);
+        let mut record = match checked {
+            Ok(()) => {
+                let record = self.authorize_record(tool, args, ctx);
+                if !record.is_allowed() {
+                    self.plans().void(token);
+                }
+                record
+            }
+            Err(reason) => {
+                let mut record = DecisionRecord::new(Decision::DeniedPlanDeviation, tool)
+                    .with_detail(REASON_DETAIL, reason.code());
+                record.capability = step.0;
+                record.principal = ctx.principal.clone();
+                self.record(&record, None);
+                record
+            }
+        };
+        record
+            .details
+            .insert(PLAN_STEP_DETAIL.to_string(), index.to_string());
+        record
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    fn gate() -> CapabilityGate {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.register_capability(Capability::new("fs.write", "Write files"));
+        gate.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("fs.write")),
+        );
+        gate
+    }
+
+    fn plan() -> Vec<PlannedCall> {
+        vec![
+            PlannedCall::new("fs.read", json!({"path": "/tmp/in"})),
+            PlannedCall::new("fs.write", json!({"path": "/tmp/out"})),
+        ]
+    }
+
+    #[test]
+    fn test_plan_steps_run_in_order() {
+        let gate = gate();
+        let ctx = RequestContext::new().with_principal("agent");
+        let plan = gate.authorize_plan(&plan(), &ctx);
+        assert!(plan.is_allowed());
+
+        let read = json!({"path": "/tmp/in"});
+        let first = gate.authorize_plan_step(&plan.token, 0, "fs.read", &read, &ctx);
+        assert!(first.is_allowed());
+        assert_eq!(first.details[PLAN_STEP_DETAIL], "0");
+        let write = json!({"path": "/tmp/out"});
+        assert!(gate
+            .authorize_plan_step(&plan.token, 1, "fs.write", &write, &ctx)
+            .is_allowed());
+
+        // The plan is complete, so its token no longer authorizes anything.
+        let again = gate.authorize_plan_step(&plan.token, 1, "fs.write", &write, &ctx);
+        assert_eq!(again.decision, Decision::DeniedPlanDeviation);
+        assert_eq!(again.details[REASON_DETAIL], "PLAN_UNKNOWN");
+    }
+
+    #[test]
+    fn test_deviation_voids_the_plan() {
+        let gate = gate();
+        let ctx = RequestContext::new().with_principal("agent");
+        let approved = gate.authorize_plan(&plan(), &ctx);
+
+        let other = json!({"path": "/etc/passwd"});
+        let record = gate.authorize_plan_step(&approved.token, 0, "fs.read", &other, &ctx);
+        assert_eq!(record.decision, Decision::DeniedPlanDeviation);
+        assert_eq!(record.details[REASON_DETAIL], "PLAN_DEVIATION");
+
+        let read = json!({"path": "/tmp/in"});
+        let retry = gate.authorize_plan_step(&approved.token, 0, "fs.read", &read, &ctx);
+        assert_eq!(retry.details[REASON_DETAIL], "PLAN_UNKNOWN");
+
+        let plan = gate.authorize_plan(&plan(), &ctx);
+        let skipped = gate.authorize_plan_step(
+            &plan.token,
+            1,
+            "fs.write",
+            &json!({"path": "/tmp/out"}),
+            &ctx,
+        );
+        assert_eq!(skipped.decision, Decision::DeniedPlanDeviation);
+    }
+
+    #[test]
+    fn test_planning_has_no_side_effects() {
+        use crate::audit::AuditLog;
+        use std::sync::Arc;
+
+        let log = Arc::new(AuditLog::new());
+        let mut gate = gate().with_audit_sink(log.clone());
+        gate.register_capability(Capability::new("email.send", "Send email"));
+        gate.add_policy(
+            Policy::new("mail", "1").with_rule(Rule::allow("email.send").requiring_consent()),
+        );
+        let ctx = RequestContext::new().with_principal("agent");
+
+        let mut calls = plan();
+        calls.push(PlannedCall::new("email.send", json!({})));
+        let approved = gate.authorize_plan(&calls, &ctx);
+        assert!(approved.is_allowed());
+        assert_eq!(
+            approved.steps[2].details[crate::consent::CONSENT_DETAIL],
+            "pending"
+        );
+        assert!(log.records().is_empty());
+
+        calls.push(PlannedCall::new("shell", json!({})));
+        let denied = gate.authorize_plan(&calls, &ctx);
+        assert_eq!(denied.denied_steps(), vec![3]);
+        let read = json!({"path": "/tmp/in"});
+        let step = gate.authorize_plan_step(&denied.token, 0, "fs.read", &read, &ctx);
+        assert_eq!(step.details[REASON_DETAIL], "PLAN_UNKNOWN");
+        assert_eq!(gate.plans().plans.lock().unwrap().len(), 1);
+    }
+}
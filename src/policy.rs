@@ -1,15 +1,64 @@
 //! Policy Rule Definitions and Policy Engine.
 //!
 //! Policy Engine evaluates authorization rules to determine if execution is permitted.
+//! Within each [`Layer`], policies are evaluated in the order they were added (a
+//! re-added policy keeps its original position), so decisions are reproducible
+//! run-to-run. Each policy keeps a compiled [`PolicyIndex`] that is rebuilt only
+//! when that policy changes.
+//!
+//! A policy may `extends` another by name: its own rules are consulted first,
+//! then the base's (and the base's base), so it only needs to add or override
+//! rules. A policy extended by another policy of its own layer is consulted
+//! through that policy rather than on its own; one extended only from other
+//! layers is still consulted on its own layer, so a lower-layer policy cannot
+//! escape a higher-layer deny by extending it. Chains are resolved when
+//! policies change, not per request. [`PolicyEngine::effective_policy`] shows
+//! the flattened result.
+//!
+//! Precedence, highest first: `Deny` rules of guards
+//! ([`PolicyEngine::add_guard`]), rules of regular policies, the capability's own
+//! default ([`PolicyEngine::set_capability_default`]), the baseline policy (see
+//! [`crate::defaults`]) and the engine-wide [`PolicyEngine::with_default_effect`].
+//!
+//! A rule's resource is a capability name, `*` for every capability,
+//! `category:<Name>` for a category, or `<namespace>.*` for every capability
+//! in a namespace: `db.read.*` covers `db.read.users` and `db.read.logs.old`.
 
+use crate::args::ArgView;
+use crate::audit::AuditMode;
+use crate::budget::{BudgetExceeded, Meter};
+use crate::capability::{CapabilityCategory, CATEGORY_PREFIX};
+use crate::clock::{is_time_condition, Clock, TimeCheck};
+use crate::condition::{ParamConstraint, ParamViolation};
+use crate::context::RequestContext;
+use crate::debug::Step;
+use crate::diagnostic::ParseDiagnostic;
+use crate::generation::{Generations, PolicySnapshot};
+use crate::group::{is_member, GroupError, GroupResolver, GROUP_PREFIX};
+use crate::index::PolicyIndex;
+use crate::layer::{check_narrowing, Layer, LayerError};
+use crate::pattern::Glob;
+use crate::provenance::Provenance;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Policy {
     pub name: String,
     pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+    /// Templated rules are expanded on load; see [`crate::template`].
+    #[serde(deserialize_with = "crate::template::deserialize_rules")]
     pub rules: Vec<Rule>,
+    /// Opts out of the `non-ascii-pattern` lint; see [`crate::unicode`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_unicode: bool,
+    #[serde(skip)]
+    pub provenance: Option<Provenance>,
 }
 
 impl Policy {
@@ -17,26 +66,80 @@ impl Policy {
         Self {
             name: name.into(),
             version: version.into(),
+            extends: None,
             rules: Vec::new(),
+            allow_unicode: false,
+            provenance: None,
         }
     }
 
+    pub fn allowing_unicode(mut self) -> Self {
+        self.allow_unicode = true;
+        self
+    }
+
+    pub fn extending(mut self, base: impl Into<String>) -> Self {
+        self.extends = Some(base.into());
+        self
+    }
+
     pub fn with_rule(mut self, rule: Rule) -> Self {
         self.rules.push(rule);
         self
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rule {
     pub effect: Effect,
     pub principal: String,
     pub resource: String,
     pub action: String,
     pub conditions: Vec<Condition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub param_constraints: Vec<ParamConstraint>,
+    #[serde(default, skip_serializing_if = "AuditMode::is_always")]
+    pub audit: AuditMode,
+    /// How long an `Allow` from this rule stays valid before callers re-check.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl_secs: Option<u64>,
+    /// A `Deny` rule in its grace period is not enforced before this instant,
+    /// only reported; see [`crate::grace`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enforce_after_ms: Option<u64>,
+    /// An `Allow` from this rule also needs the user's consent; see
+    /// [`crate::consent`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_user_consent: bool,
+    /// The request fields the decision depends on, argument paths and
+    /// `principal`; see [`crate::cache`].
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_key: Vec<String>,
+    #[serde(skip)]
+    pub provenance: Option<Provenance>,
+}
+
+/// The namespace, with its trailing dot, that a `<namespace>.*` resource covers.
+pub fn resource_namespace(resource: &str) -> Option<&str> {
+    resource
+        .strip_suffix('*')
+        .filter(|namespace| namespace.len() > 1 && namespace.ends_with('.'))
+}
+
+/// Whether a rule naming `pattern` applies to the capability `resource`.
+pub fn resource_covers(pattern: &str, resource: &str) -> bool {
+    pattern == resource
+        || pattern == "*"
+        || resource_namespace(pattern)
+            .is_some_and(|ns| resource.len() > ns.len() && resource.starts_with(ns))
 }
 
 impl Rule {
+    pub fn for_principal(mut self, principal: impl Into<String>) -> Self {
+        self.principal = principal.into();
+        self
+    }
+
     pub fn allow(resource: impl Into<String>) -> Self {
         Self {
             effect: Effect::Allow,
@@ -44,6 +147,13 @@ impl Rule {
             resource: resource.into(),
             action: "execute".to_string(),
             conditions: Vec::new(),
+            param_constraints: Vec::new(),
+            audit: AuditMode::Always,
+            ttl_secs: None,
+            enforce_after_ms: None,
+            require_user_consent: false,
+            cache_key: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -54,6 +164,13 @@ impl Rule {
             resource: resource.into(),
             action: "execute".to_string(),
             conditions: Vec::new(),
+            param_constraints: Vec::new(),
+            audit: AuditMode::Always,
+            ttl_secs: None,
+            enforce_after_ms: None,
+            require_user_consent: false,
+            cache_key: Vec::new(),
+            provenance: None,
         }
     }
 
@@ -61,25 +178,185 @@ impl Rule {
         self.conditions = conditions;
         self
     }
+
+    pub fn with_param_constraint(mut self, constraint: ParamConstraint) -> Self {
+        self.param_constraints.push(constraint);
+        self
+    }
+
+    pub fn with_audit(mut self, audit: AuditMode) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    pub fn requiring_consent(mut self) -> Self {
+        self.require_user_consent = true;
+        self
+    }
+
+    pub fn with_cache_key(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.cache_key = fields.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl_secs = Some(ttl.as_secs());
+        self
+    }
+
+    pub fn applies_to(&self, resource: &str) -> bool {
+        resource_covers(&self.resource, resource)
+    }
+
+    /// Like [`Rule::applies_to`], but also true for a `category:<Name>` rule
+    /// targeting `category`.
+    pub fn applies_in(&self, resource: &str, category: CapabilityCategory) -> bool {
+        self.applies_to(resource)
+            || self.resource.strip_prefix(CATEGORY_PREFIX) == Some(category.as_str())
+    }
+
+    pub fn matches(&self, resource: &str, args: &dyn ArgView) -> bool {
+        self.applies_to(resource) && self.args_match(args)
+    }
+
+    pub(crate) fn args_match(&self, args: &dyn ArgView) -> bool {
+        self.args_match_at(args, &TimeCheck::now())
+    }
+
+    /// Time conditions are judged by `time`; malformed ones never match.
+    pub(crate) fn args_match_at(&self, args: &dyn ArgView, time: &TimeCheck) -> bool {
+        self.args_match_compiled(args, time, &[])
+    }
+
+    /// Like [`Rule::args_match_at`] with the rule's precompiled glob patterns;
+    /// conditions without one are compiled on the spot.
+    pub(crate) fn args_match_compiled(
+        &self,
+        args: &dyn ArgView,
+        time: &TimeCheck,
+        patterns: &[Option<Arc<Glob>>],
+    ) -> bool {
+        (0..self.conditions.len()).all(|i| self.condition_holds(i, args, time, patterns))
+            && self.param_constraints.iter().all(|c| c.check(args).is_ok())
+    }
+
+    /// Whether condition `i` holds. One that cannot be evaluated, with an
+    /// unknown operator or a malformed time window, holds for a `Deny` rule and
+    /// not for any other, so either way the rule fails closed.
+    pub(crate) fn condition_holds(
+        &self,
+        i: usize,
+        args: &dyn ArgView,
+        time: &TimeCheck,
+        patterns: &[Option<Arc<Glob>>],
+    ) -> bool {
+        let condition = &self.conditions[i];
+        let deny = self.effect == Effect::Deny;
+        if is_time_condition(condition) {
+            return time.holds(condition, self.effect).unwrap_or(deny);
+        }
+        if !condition.is_known_operator() {
+            return deny;
+        }
+        let glob = patterns.get(i).and_then(|g| g.as_deref());
+        condition.evaluate_compiled(args, glob) || (deny && condition.evaluate_folded(args))
+    }
 }
 
+/// `AllowWithWarning` and `Audit` allow like `Allow`; matched requests are
+/// always audited, and `Audit` ones are reported as if denied, for rules in
+/// watch mode during a staged rollout.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Effect {
     Allow,
     Deny,
+    AllowWithWarning,
+    Audit,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Effect {
+    /// Whether a match lets the request through.
+    pub fn allows(&self) -> bool {
+        *self != Effect::Deny
+    }
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Effect {
+    fn default() -> Self {
+        Effect::Deny
+    }
+}
+
+/// Deserializing rejects unknown operators; see [`crate::condition`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Condition {
     pub key: String,
     pub operator: String,
     pub value: serde_json::Value,
 }
 
-#[derive(Default)]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum EvaluationError {
+    #[error(transparent)]
+    Budget(#[from] BudgetExceeded),
+    #[error(transparent)]
+    Resolver(#[from] GroupError),
+    #[error(transparent)]
+    Inheritance(#[from] InheritanceError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InheritanceError {
+    #[error("policy `{policy}` extends unknown policy `{base}`")]
+    MissingBase { policy: String, base: String },
+    #[error("policy inheritance cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// How one entry is consulted.
+#[derive(Debug, Clone)]
+struct Link {
+    /// Its inheritance chain, as entry positions.
+    chain: Result<Vec<usize>, InheritanceError>,
+    /// Whether it is consulted on its own layer.
+    own: bool,
+    /// Whether it is consulted before every layer instead.
+    guard: bool,
+}
+
+/// The rule that decided a request.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleMatch<'a> {
+    pub policy: &'a str,
+    pub index: usize,
+    pub rule: &'a Rule,
+}
+
+impl RuleMatch<'_> {
+    pub fn id(&self) -> String {
+        format!("{}#{}", self.policy, self.index)
+    }
+}
+
+#[derive(Clone, Default)]
 pub struct PolicyEngine {
-    policies: HashMap<String, Policy>,
+    policies: Vec<Policy>,
+    indexes: Vec<PolicyIndex>,
+    layers: Vec<Layer>,
     default_effect: Effect,
+    groups: Option<Arc<dyn GroupResolver>>,
+    categories: BTreeMap<String, CapabilityCategory>,
+    capability_defaults: BTreeMap<String, Effect>,
+    declared_keys: BTreeMap<String, BTreeSet<String>>,
+    baseline: Option<(Policy, PolicyIndex)>,
+    /// Names of policies added with [`PolicyEngine::add_guard`].
+    guards: BTreeSet<String>,
+    /// Per entry (policies, then the baseline), resolved when policies change.
+    chains: Vec<Link>,
+    clock: Option<Arc<dyn Clock>>,
+    skew_tolerance: Duration,
+    generations: Generations,
 }
 
 impl PolicyEngine {
@@ -92,23 +369,597 @@ impl PolicyEngine {
         self
     }
 
+    pub fn with_group_resolver(mut self, resolver: Arc<dyn GroupResolver>) -> Self {
+        self.groups = Some(resolver);
+        self
+    }
+
+    pub fn group_resolver(&self) -> Option<&Arc<dyn GroupResolver>> {
+        self.groups.as_ref()
+    }
+
+    /// Sources the time for `time` conditions from `clock` instead of the system clock.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    /// How far the clock may be off; see [`crate::clock`].
+    pub fn with_skew_tolerance(mut self, tolerance: Duration) -> Self {
+        self.skew_tolerance = tolerance;
+        self
+    }
+
+    pub fn skew_tolerance(&self) -> Duration {
+        self.skew_tolerance
+    }
+
+    pub fn time_check(&self) -> TimeCheck {
+        let clock = self.clock.clone().unwrap_or_else(crate::clock::system);
+        TimeCheck {
+            now_ms: clock.now_ms(),
+            tolerance_ms: self.skew_tolerance.as_millis() as u64,
+        }
+    }
+
+    /// The time check for `ctx`: at its `evaluated_at_ms` if set, else now.
+    pub fn time_check_for(&self, ctx: &RequestContext) -> TimeCheck {
+        match ctx.evaluated_at_ms {
+            Some(now_ms) => TimeCheck {
+                now_ms,
+                tolerance_ms: self.skew_tolerance.as_millis() as u64,
+            },
+            None => self.time_check(),
+        }
+    }
+
+    /// Records the category of `resource`, overriding the one inferred from its name.
+    pub fn set_category(&mut self, resource: impl Into<String>, category: CapabilityCategory) {
+        self.categories.insert(resource.into(), category);
+    }
+
+    pub fn category_of(&self, resource: &str) -> CapabilityCategory {
+        self.categories
+            .get(resource)
+            .copied()
+            .unwrap_or_else(|| CapabilityCategory::infer(resource))
+    }
+
+    /// Sets the effect for `resource` when no regular policy matches; it takes
+    /// precedence over the baseline and the engine default. `None` clears it.
+    pub fn set_capability_default(&mut self, resource: impl Into<String>, effect: Option<Effect>) {
+        let resource = resource.into();
+        match effect {
+            Some(effect) => self.capability_defaults.insert(resource, effect),
+            None => self.capability_defaults.remove(&resource),
+        };
+    }
+
+    pub fn capability_default(&self, resource: &str) -> Option<Effect> {
+        self.capability_defaults.get(resource).copied()
+    }
+
+    /// Records the argument keys `resource` declares, for
+    /// [`PolicyEngine::known_condition_keys`]; an empty set clears them.
+    pub fn set_declared_keys(&mut self, resource: impl Into<String>, keys: BTreeSet<String>) {
+        let resource = resource.into();
+        match keys.is_empty() {
+            true => self.declared_keys.remove(&resource),
+            false => self.declared_keys.insert(resource, keys),
+        };
+    }
+
+    pub(crate) fn declared_keys(&self, resource: &str) -> Option<&BTreeSet<String>> {
+        self.declared_keys.get(resource)
+    }
+
+    /// The effect for `resource` when no rule matches.
+    pub fn default_effect_for(&self, resource: &str) -> Effect {
+        self.capability_default(resource)
+            .unwrap_or(self.default_effect)
+    }
+
+    /// Adds an organization-layer policy; see [`crate::layer`].
     pub fn add_policy(&mut self, policy: Policy) {
-        self.policies.insert(policy.name.clone(), policy);
+        self.guards.remove(&policy.name);
+        self.insert(Layer::Org, policy);
+    }
+
+    /// Adds a guard: a policy consulted before every layer, whatever order
+    /// policies were added in, so its `Deny` rules cannot be pre-empted by an
+    /// earlier `Allow`. A guard only denies; when its first matching rule
+    /// allows, evaluation carries on as if it had not matched.
+    pub fn add_guard(&mut self, policy: Policy) {
+        self.guards.insert(policy.name.clone());
+        self.insert(Layer::Org, policy);
+    }
+
+    pub fn is_guard(&self, name: &str) -> bool {
+        self.guards.contains(name)
+    }
+
+    /// Adds a policy at `layer`, rejecting it if it would loosen a higher layer.
+    pub fn add_policy_at(&mut self, layer: Layer, policy: Policy) -> Result<(), LayerError> {
+        check_narrowing(self, layer, &policy)?;
+        self.insert(layer, policy);
+        Ok(())
+    }
+
+    fn insert(&mut self, layer: Layer, policy: Policy) {
+        if layer != Layer::Org {
+            self.guards.remove(&policy.name);
+        }
+        let index = PolicyIndex::compile(&policy);
+        self.insert_compiled(layer, policy, index);
+    }
+
+    /// Inserts a policy whose index was compiled elsewhere, e.g. on a loader thread.
+    pub(crate) fn insert_compiled(&mut self, layer: Layer, policy: Policy, index: PolicyIndex) {
+        match self.policies.iter().position(|p| p.name == policy.name) {
+            Some(pos) => {
+                self.policies[pos] = policy;
+                self.indexes[pos] = index;
+                self.layers[pos] = layer;
+            }
+            None => {
+                self.policies.push(policy);
+                self.indexes.push(index);
+                self.layers.push(layer);
+            }
+        }
+        self.relink();
+    }
+
+    /// The layer of a regular policy; `None` for unknown names and the baseline.
+    pub fn layer_of(&self, name: &str) -> Option<Layer> {
+        let pos = self.policies.iter().position(|p| p.name == name)?;
+        Some(self.layers[pos])
+    }
+
+    /// Installs a policy that is consulted only after every other policy.
+    pub fn set_baseline(&mut self, policy: Policy) {
+        let index = PolicyIndex::compile(&policy);
+        self.baseline = Some((policy, index));
+        self.relink();
+    }
+
+    pub fn clear_baseline(&mut self) {
+        self.baseline = None;
+        self.relink();
+    }
+
+    /// Recompiles every policy index from scratch.
+    pub fn recompile(&mut self) {
+        self.indexes = self.policies.iter().map(PolicyIndex::compile).collect();
+        if let Some((policy, index)) = &mut self.baseline {
+            *index = PolicyIndex::compile(policy);
+        }
+    }
+
+    pub(crate) fn snapshot(&self) -> PolicySnapshot {
+        PolicySnapshot {
+            policies: self.policies.clone(),
+            indexes: self.indexes.clone(),
+            layers: self.layers.clone(),
+            baseline: self.baseline.clone(),
+            guards: self.guards.clone(),
+        }
+    }
+
+    pub(crate) fn restore(&mut self, snapshot: PolicySnapshot) {
+        self.policies = snapshot.policies;
+        self.indexes = snapshot.indexes;
+        self.layers = snapshot.layers;
+        self.baseline = snapshot.baseline;
+        self.guards = snapshot.guards;
+        self.relink();
     }
 
-    pub fn evaluate(&self, resource: &str, _action: &str, _args: &serde_json::Value) -> Effect {
-        for policy in self.policies.values() {
-            for rule in &policy.rules {
-                if rule.resource == resource || rule.resource == "*" {
-                    return rule.effect;
+    pub(crate) fn generations_ref(&self) -> &Generations {
+        &self.generations
+    }
+
+    pub(crate) fn generations_mut(&mut self) -> &mut Generations {
+        &mut self.generations
+    }
+
+    /// Rules whose glob patterns have been compiled, across all policies.
+    pub fn compiled_pattern_rules(&self) -> usize {
+        self.entries()
+            .map(|(_, index)| index.compiled_rules())
+            .sum()
+    }
+
+    pub fn get_policy(&self, name: &str) -> Option<&Policy> {
+        self.policies().find(|p| p.name == name)
+    }
+
+    /// `name` followed by every policy it transitively extends.
+    pub fn inheritance_chain(&self, name: &str) -> Result<Vec<&Policy>, InheritanceError> {
+        let chain = self.chain_entries(name)?;
+        Ok(chain.into_iter().map(|(policy, _)| policy).collect())
+    }
+
+    fn entries(&self) -> impl Iterator<Item = (&Policy, &PolicyIndex)> {
+        let baseline = self.baseline.iter().map(|(policy, index)| (policy, index));
+        self.policies.iter().zip(&self.indexes).chain(baseline)
+    }
+
+    /// Every rule, baseline included, that may apply to `resource` under some
+    /// principal.
+    pub(crate) fn candidate_rules<'a>(
+        &'a self,
+        resource: &'a str,
+    ) -> impl Iterator<Item = &'a Rule> {
+        let category = self.category_of(resource);
+        self.entries().flat_map(move |(policy, index)| {
+            index
+                .candidates(resource, category)
+                .into_iter()
+                .filter_map(|position| policy.rules.get(position))
+        })
+    }
+
+    fn entry(&self, pos: usize) -> (&Policy, &PolicyIndex) {
+        match self.policies.get(pos) {
+            Some(policy) => (policy, &self.indexes[pos]),
+            None => {
+                let (policy, index) = self.baseline.as_ref().expect("entry position");
+                (policy, index)
+            }
+        }
+    }
+
+    fn chain_entries(&self, name: &str) -> Result<Vec<(&Policy, &PolicyIndex)>, InheritanceError> {
+        let Some(start) = self.entries().position(|(p, _)| p.name == name) else {
+            return Ok(Vec::new());
+        };
+        let chain = self.chain_positions(start)?;
+        Ok(chain.into_iter().map(|pos| self.entry(pos)).collect())
+    }
+
+    fn chain_positions(&self, start: usize) -> Result<Vec<usize>, InheritanceError> {
+        let mut chain: Vec<usize> = Vec::new();
+        let mut current = Some(start);
+        while let Some(pos) = current {
+            let (policy, _) = self.entry(pos);
+            if chain.contains(&pos) {
+                let mut names: Vec<_> = chain
+                    .iter()
+                    .map(|&p| self.entry(p).0.name.clone())
+                    .collect();
+                names.push(policy.name.clone());
+                return Err(InheritanceError::Cycle(names));
+            }
+            chain.push(pos);
+            current = match &policy.extends {
+                Some(base) => Some(
+                    self.entries()
+                        .position(|(p, _)| &p.name == base)
+                        .ok_or_else(|| InheritanceError::MissingBase {
+                            policy: policy.name.clone(),
+                            base: base.clone(),
+                        })?,
+                ),
+                None => None,
+            };
+        }
+        Ok(chain)
+    }
+
+    /// Resolves every entry's inheritance chain, after policies change.
+    fn relink(&mut self) {
+        let layers: Vec<Option<Layer>> = (0..self.entries().count())
+            .map(|pos| self.layers.get(pos).copied())
+            .collect();
+        let chains = (0..layers.len())
+            .map(|pos| {
+                let name = &self.entry(pos).0.name;
+                let extended_in_layer = self.entries().enumerate().any(|(other, (p, _))| {
+                    p.extends.as_ref() == Some(name) && layers[other] == layers[pos]
+                });
+                let guard = pos < self.policies.len() && self.guards.contains(name);
+                Link {
+                    chain: self.chain_positions(pos),
+                    own: !extended_in_layer && !guard,
+                    guard,
+                }
+            })
+            .collect();
+        self.chains = chains;
+    }
+
+    /// The policy `name` with inherited rules appended after its own, in the
+    /// order they are consulted. `None` if no such policy exists.
+    pub fn effective_policy(&self, name: &str) -> Option<Result<Policy, InheritanceError>> {
+        let policy = self.get_policy(name)?;
+        Some(self.inheritance_chain(name).map(|chain| {
+            let mut flat = Policy::new(&policy.name, &policy.version);
+            flat.rules = chain.iter().flat_map(|p| p.rules.clone()).collect();
+            flat
+        }))
+    }
+
+    /// Looks up a rule by its [`RuleMatch::id`] (`policy#index`).
+    pub fn rule(&self, id: &str) -> Option<&Rule> {
+        let (policy, index) = id.rsplit_once('#')?;
+        self.get_policy(policy)?
+            .rules
+            .get(index.parse::<usize>().ok()?)
+    }
+
+    /// Policies in insertion order, ending with the baseline if one is set.
+    pub fn policies(&self) -> impl Iterator<Item = &Policy> {
+        self.entries().map(|(policy, _)| policy)
+    }
+
+    pub fn evaluate(&self, resource: &str, action: &str, args: &dyn ArgView) -> Effect {
+        self.evaluate_with(&RequestContext::default(), resource, action, args)
+    }
+
+    pub fn evaluate_with(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+    ) -> Effect {
+        self.find_rule(ctx, resource, action, args)
+            .map(|m| m.rule.effect)
+            .unwrap_or_else(|| self.default_effect_for(resource))
+    }
+
+    /// The first rule matching the request, or `None` if
+    /// [`PolicyEngine::default_effect_for`] applies.
+    pub fn find_rule(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+    ) -> Option<RuleMatch<'_>> {
+        self.scan(
+            ctx,
+            resource,
+            action,
+            args,
+            &mut Meter::unlimited(),
+            false,
+            None,
+        )
+        .unwrap_or(None)
+    }
+
+    /// Like [`PolicyEngine::find_rule`], charging every rule, condition and group
+    /// lookup against `meter` and surfacing group resolver failures instead of
+    /// resolving them fail-closed.
+    pub fn find_rule_metered(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+        meter: &mut Meter,
+    ) -> Result<Option<RuleMatch<'_>>, EvaluationError> {
+        self.scan(ctx, resource, action, args, meter, true, None)
+    }
+
+    /// Like [`PolicyEngine::find_rule`], recording every check in `steps`; see
+    /// [`crate::debug`]. Returns the deciding effect and rule id.
+    pub(crate) fn debug_scan(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        args: &dyn ArgView,
+        steps: &mut Vec<Step>,
+    ) -> Option<(Effect, String)> {
+        let mut meter = Meter::unlimited();
+        let found = self.scan(ctx, resource, "", args, &mut meter, false, Some(steps));
+        found
+            .unwrap_or(None)
+            .map(|found| (found.rule.effect, found.id()))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn scan(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        _action: &str,
+        args: &dyn ArgView,
+        meter: &mut Meter,
+        strict: bool,
+        mut trace: Option<&mut Vec<Step>>,
+    ) -> Result<Option<RuleMatch<'_>>, EvaluationError> {
+        let category = self.category_of(resource);
+        let time = self.time_check_for(ctx);
+        // Within a layer the first match decides; across layers a deny is final,
+        // and an allow needs the highest populated layer to allow too.
+        let mut allowed = None;
+        let mut granted = true;
+        let top = Layer::ALL.into_iter().find(|l| {
+            (0..self.policies.len()).any(|pos| self.layers[pos] == *l && !self.chains[pos].guard)
+        });
+        for link in self.chains.iter().filter(|link| link.guard) {
+            let chain = link.chain.as_ref().map_err(|e| e.clone())?;
+            for (policy, compiled) in chain.iter().map(|&pos| self.entry(pos)) {
+                if let Some(steps) = trace.as_deref_mut() {
+                    steps.push(Step::Policy {
+                        name: policy.name.clone(),
+                        layer: Some(Layer::Org),
+                    });
+                }
+                let found = self.scan_policy(
+                    policy,
+                    compiled,
+                    ctx,
+                    resource,
+                    category,
+                    args,
+                    &time,
+                    meter,
+                    strict,
+                    trace.as_deref_mut(),
+                )?;
+                if let Some(found) = found.filter(|f| f.rule.effect == Effect::Deny) {
+                    return Ok(Some(found));
                 }
             }
         }
+        let passes = Layer::ALL.map(Some).into_iter().chain([None]);
+        for pass in passes {
+            if pass.is_some() && pass > top && allowed.is_none() {
+                granted = false;
+            }
+            // The baseline is only consulted for capabilities without a default.
+            if pass.is_none()
+                && (allowed.is_some() || self.capability_defaults.contains_key(resource))
+            {
+                break;
+            }
+            'layer: for (pos, link) in self.chains.iter().enumerate() {
+                if self.layers.get(pos).copied() != pass {
+                    continue;
+                }
+                let chain = link.chain.as_ref().map_err(|e| e.clone())?;
+                if !link.own {
+                    continue;
+                }
+                for (policy, compiled) in chain.iter().map(|&link| self.entry(link)) {
+                    if let Some(steps) = trace.as_deref_mut() {
+                        steps.push(Step::Policy {
+                            name: policy.name.clone(),
+                            layer: pass,
+                        });
+                    }
+                    if let Some(found) = self.scan_policy(
+                        policy,
+                        compiled,
+                        ctx,
+                        resource,
+                        category,
+                        args,
+                        &time,
+                        meter,
+                        strict,
+                        trace.as_deref_mut(),
+                    )? {
+                        if found.rule.effect == Effect::Deny {
+                            return Ok(Some(found));
+                        }
+                        if granted || pass.is_none() {
+                            allowed.get_or_insert(found);
+                        }
+                        break 'layer;
+                    }
+                }
+            }
+        }
+        Ok(allowed)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn scan_policy<'a>(
+        &self,
+        policy: &'a Policy,
+        compiled: &PolicyIndex,
+        ctx: &RequestContext,
+        resource: &str,
+        category: CapabilityCategory,
+        args: &dyn ArgView,
+        time: &TimeCheck,
+        meter: &mut Meter,
+        strict: bool,
+        mut trace: Option<&mut Vec<Step>>,
+    ) -> Result<Option<RuleMatch<'a>>, EvaluationError> {
+        for index in compiled.candidates(resource, category) {
+            let rule = &policy.rules[index];
+            meter.step(1)?;
+            if rule.principal.starts_with(GROUP_PREFIX) {
+                meter.step(1)?;
+            }
+            meter.step((rule.conditions.len() + rule.param_constraints.len()) as u64)?;
+
+            let principal_matches = match self.principal_matches(rule, ctx) {
+                Ok(matches) => matches,
+                Err(e) if strict => return Err(e.into()),
+                Err(_) => rule.effect == Effect::Deny,
+            };
+            let matched = match trace.as_deref_mut() {
+                Some(steps) => crate::debug::trace_rule(
+                    steps,
+                    format!("{}#{}", policy.name, index),
+                    rule,
+                    principal_matches,
+                    resource,
+                    category,
+                    args,
+                    time,
+                    compiled.patterns(index, rule),
+                ),
+                None => {
+                    principal_matches
+                        && !rule.in_grace(time.now_ms)
+                        && rule.applies_in(resource, category)
+                        && rule.args_match_compiled(args, time, compiled.patterns(index, rule))
+                }
+            };
+            if matched {
+                return Ok(Some(RuleMatch {
+                    policy: &policy.name,
+                    index,
+                    rule,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn default_effect(&self) -> Effect {
         self.default_effect
     }
 
-    pub fn load_from_json(&mut self, json: &str) -> Result<(), serde_json::Error> {
-        let policies: Vec<Policy> = serde_json::from_str(json)?;
+    /// `*` matches everyone, `group:<name>` is expanded through the group resolver
+    /// and anything else must equal the request principal. Without a resolver,
+    /// group principals only match `Deny` rules, so the gap fails closed.
+    pub(crate) fn principal_matches(
+        &self,
+        rule: &Rule,
+        ctx: &RequestContext,
+    ) -> Result<bool, GroupError> {
+        if rule.principal == "*" {
+            return Ok(true);
+        }
+        let Some(principal) = ctx.principal.as_deref() else {
+            return Ok(false);
+        };
+        match rule.principal.strip_prefix(GROUP_PREFIX) {
+            Some(group) => match &self.groups {
+                Some(resolver) => is_member(resolver.as_ref(), principal, group),
+                None => Ok(rule.effect == Effect::Deny),
+            },
+            None => Ok(rule.principal == principal),
+        }
+    }
+
+    /// Parameter constraints that kept `Allow` rules for `resource` from matching.
+    pub fn param_violations(&self, resource: &str, args: &dyn ArgView) -> Vec<ParamViolation> {
+        let category = self.category_of(resource);
+        self.policies()
+            .flat_map(|p| &p.rules)
+            .filter(|r| r.effect.allows() && r.applies_in(resource, category))
+            .flat_map(|r| {
+                r.param_constraints
+                    .iter()
+                    .filter_map(|c| c.check(args).err())
+            })
+            .collect()
+    }
+
+    /// Adds the policies in a JSON array; see [`crate::diagnostic`] for errors.
+    pub fn load_from_json(&mut self, json: &str) -> Result<(), ParseDiagnostic> {
+        let policies: Vec<Policy> =
+            serde_json::from_str(json).map_err(|e| crate::diagnostic::diagnose(json, e))?;
         for policy in policies {
             self.add_policy(policy);
         }
@@ -116,12 +967,6 @@ impl PolicyEngine {
     }
 }
 
-impl Default for Effect {
-    fn default() -> Self {
-        Effect::Deny
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -163,4 +1008,185 @@ mod tests {
             Effect::Deny
         );
     }
+
+    #[test]
+    fn test_evaluation_follows_insertion_order() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(Policy::new("zz-first", "1.0").with_rule(Rule::allow("shell")));
+        engine.add_policy(Policy::new("aa-second", "1.0").with_rule(Rule::deny("shell")));
+
+        for _ in 0..16 {
+            assert_eq!(
+                engine.evaluate("shell", "execute", &serde_json::json!({})),
+                Effect::Allow
+            );
+        }
+
+        engine.add_policy(Policy::new("zz-first", "2.0").with_rule(Rule::deny("shell")));
+        let names: Vec<_> = engine.policies().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["zz-first", "aa-second"]);
+        assert_eq!(engine.get_policy("zz-first").unwrap().version, "2.0");
+    }
+
+    #[test]
+    fn test_policy_inheritance() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("org", "1.0")
+                .with_rule(Rule::deny("shell"))
+                .with_rule(Rule::allow("fs.read").for_principal("admin")),
+        );
+        engine.add_policy(
+            Policy::new("project", "1.0")
+                .extending("org")
+                .with_rule(Rule::allow("shell")),
+        );
+
+        let flat = engine.effective_policy("project").unwrap().unwrap();
+        let resources: Vec<_> = flat.rules.iter().map(|r| r.resource.as_str()).collect();
+        assert_eq!(resources, vec!["shell", "shell", "fs.read"]);
+
+        let matched = engine
+            .find_rule(
+                &RequestContext::new().with_principal("admin"),
+                "fs.read",
+                "execute",
+                &crate::args::NO_ARGS,
+            )
+            .unwrap();
+        assert_eq!(matched.id(), "org#1");
+        assert_eq!(
+            engine.evaluate("shell", "execute", &crate::args::NO_ARGS),
+            Effect::Allow
+        );
+
+        engine.add_policy(Policy::new("org", "2.0").extending("project"));
+        assert!(matches!(
+            engine.effective_policy("project"),
+            Some(Err(InheritanceError::Cycle(_)))
+        ));
+        assert!(engine
+            .find_rule_metered(
+                &RequestContext::default(),
+                "shell",
+                "execute",
+                &crate::args::NO_ARGS,
+                &mut Meter::unlimited()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_extending_from_a_lower_layer_keeps_base_denies() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("org", "1.0")
+                .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                    "command",
+                    "starts_with",
+                    serde_json::json!("rm"),
+                )]))
+                .with_rule(Rule::allow("shell")),
+        );
+        engine
+            .add_policy_at(
+                Layer::Session,
+                Policy::new("session", "1.0")
+                    .extending("org")
+                    .with_rule(Rule::allow("shell")),
+            )
+            .unwrap();
+
+        let rm = serde_json::json!({"command": "rm -rf /"});
+        assert_eq!(engine.evaluate("shell", "execute", &rm), Effect::Deny);
+        let ls = serde_json::json!({"command": "ls"});
+        assert_eq!(engine.evaluate("shell", "execute", &ls), Effect::Allow);
+    }
+
+    #[test]
+    fn test_param_constraints_scope_allow() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(Policy::new("git", "1.0").with_rule(
+            Rule::allow("git").with_param_constraint(ParamConstraint::one_of(
+                "subcommand",
+                ["status", "diff", "log"],
+            )),
+        ));
+
+        let status = serde_json::json!({ "subcommand": "status" });
+        let push = serde_json::json!({ "subcommand": "push" });
+        assert_eq!(engine.evaluate("git", "execute", &status), Effect::Allow);
+        assert_eq!(engine.evaluate("git", "execute", &push), Effect::Deny);
+
+        let violations = engine.param_violations("git", &push);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].param, "subcommand");
+    }
+
+    #[test]
+    fn test_group_principals() {
+        use crate::group::StaticGroups;
+
+        let groups = StaticGroups::new().with_group("engineers", ["alice"]);
+        let mut engine = PolicyEngine::new().with_group_resolver(Arc::new(groups));
+        engine.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::allow("shell").for_principal("group:engineers"))
+                .with_rule(Rule::allow("fs.read").for_principal("bob")),
+        );
+
+        let alice = RequestContext::new().with_principal("alice");
+        let bob = RequestContext::new().with_principal("bob");
+        let args = serde_json::json!({});
+        assert_eq!(
+            engine.evaluate_with(&alice, "shell", "execute", &args),
+            Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate_with(&bob, "shell", "execute", &args),
+            Effect::Deny
+        );
+        assert_eq!(
+            engine.evaluate_with(&bob, "fs.read", "execute", &args),
+            Effect::Allow
+        );
+        assert_eq!(engine.evaluate("fs.read", "execute", &args), Effect::Deny);
+    }
+
+    #[test]
+    fn test_incremental_and_full_compilation_agree() {
+        let corpus = ["fs.read", "fs.write", "shell", "web.get", "unknown"];
+        let args = serde_json::json!({});
+
+        let mut incremental = PolicyEngine::new();
+        for (i, resource) in corpus.iter().enumerate() {
+            incremental
+                .add_policy(Policy::new(format!("p{}", i), "1.0").with_rule(Rule::deny(*resource)));
+        }
+        incremental.add_policy(Policy::new("p0", "2.0").with_rule(Rule::allow("fs.read")));
+        incremental.add_policy(Policy::new("p9", "1.0").with_rule(Rule::allow("*")));
+
+        let mut full = incremental.clone();
+        full.recompile();
+        assert_eq!(full.indexes, incremental.indexes);
+
+        for resource in corpus {
+            assert_eq!(
+                incremental.evaluate(resource, "execute", &args),
+                full.evaluate(resource, "execute", &args)
+            );
+        }
+        assert_eq!(
+            incremental.evaluate("fs.read", "execute", &args),
+            Effect::Allow
+        );
+        assert_eq!(
+            incremental.evaluate("shell", "execute", &args),
+            Effect::Deny
+        );
+        assert_eq!(
+            incremental.evaluate("other", "execute", &args),
+            Effect::Allow
+        );
+    }
 }
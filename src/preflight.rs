@@ -0,0 +1,58 @@
+//! Capability Preflight Checks.
+//!
+//! A [`Preflight`] verifies a capability's preconditions (the file exists, the
+//! host resolves) before the gate returns `Authorized`. A failing check turns the
+//! decision into `DeniedPreflightFailed`, with the reason recorded in the
+//! decision's `preflight` detail. Checks only run for requests policy allows.
+
+use crate::args::ArgView;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightResult {
+    Passed,
+    Failed(String),
+}
+
+pub trait Preflight: Send + Sync {
+    fn check(&self, args: &dyn ArgView) -> PreflightResult;
+}
+
+impl<F> Preflight for F
+where
+    F: Fn(&dyn ArgView) -> PreflightResult + Send + Sync,
+{
+    fn check(&self, args: &dyn ArgView) -> PreflightResult {
+        self(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::ArgValue;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::{CapabilityGate, Decision};
+    use crate::policy::{Policy, Rule};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_failed_preflight_denies() {
+        let exists =
+            |args: &dyn ArgView| match args.lookup("path").as_ref().and_then(ArgValue::as_str) {
+                Some(path) if path.starts_with("/work") => PreflightResult::Passed,
+                _ => PreflightResult::Failed("no such file".into()),
+            };
+        let mut gate = CapabilityGate::new().with_preflight("fs.read", Arc::new(exists));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("default", "1.0").with_rule(Rule::allow("fs.read")));
+
+        let ok = serde_json::json!({ "path": "/work/a.txt" });
+        let missing = serde_json::json!({ "path": "/tmp/gone" });
+        assert_eq!(gate.authorize("fs.read", &ok), Decision::Authorized);
+
+        let record = gate.authorize_record("fs.read", &missing, &RequestContext::default());
+        assert_eq!(record.decision, Decision::DeniedPreflightFailed);
+        assert_eq!(record.details["preflight"], "no such file");
+    }
+}
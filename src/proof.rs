@@ -0,0 +1,156 @@
+//! Authorization Proofs.
+//!
+//! An [`AuthorizationProof`] is a compact statement that a tool call was gated:
+//! the capability, the decision and deciding rule, the digest of the policy set
+//! that decided it (see [`PolicyEngine::policy_digest`]), the decision time
+//! and, when the gate fingerprints arguments, the argument fingerprint.
+//! Callers attach it to the tool's output so downstream systems can check
+//! that the execution was authorized and by which policy version.
+//!
+//! Proofs are optionally signed with an [`AuditSigner`] over their JSON form
+//! without the signature; [`AuthorizationProof::verify`] checks it.
+
+use crate::audit::AuditSigner;
+use crate::decision::{Decision, DecisionRecord};
+use crate::fingerprint::FINGERPRINT_DETAIL;
+use crate::gate::CapabilityGate;
+use crate::policy::PolicyEngine;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizationProof {
+    pub capability: String,
+    pub decision: Decision,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    pub policy_digest: String,
+    pub timestamp_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub args_fingerprint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+impl AuthorizationProof {
+    /// An unsigned proof of `record`, decided by policies with `policy_digest`.
+    pub fn new(record: &DecisionRecord, policy_digest: impl Into<String>) -> Self {
+        Self {
+            capability: record.capability.clone(),
+            decision: record.decision,
+            rule: record.rule.clone(),
+            policy_digest: policy_digest.into(),
+            timestamp_ms: record.timestamp_ms,
+            args_fingerprint: record.details.get(FINGERPRINT_DETAIL).cloned(),
+            signature: None,
+        }
+    }
+
+    fn message(&self) -> Vec<u8> {
+        let unsigned = Self {
+            signature: None,
+            ..self.clone()
+        };
+        serde_json::to_vec(&unsigned).unwrap_or_default()
+    }
+
+    pub fn signed(mut self, signer: &dyn AuditSigner) -> Self {
+        self.signature = Some(signer.sign(&self.message()));
+        self
+    }
+
+    /// Whether the proof carries a valid signature; unsigned proofs fail.
+    pub fn verify(&self, signer: &dyn AuditSigner) -> bool {
+        self.signature
+            .as_deref()
+            .is_some_and(|signature| signer.verify(&self.message(), signature))
+    }
+
+    /// Whether `engine` still holds the policy set that made the decision.
+    pub fn is_current(&self, engine: &PolicyEngine) -> bool {
+        self.policy_digest == engine.policy_digest()
+    }
+}
+
+impl CapabilityGate {
+    /// An unsigned proof of `record` against the current policies of the
+    /// engine that decided it, the canary's for routed requests; call it right
+    /// after authorizing, before policies can change.
+    pub fn proof(&self, record: &DecisionRecord) -> AuthorizationProof {
+        AuthorizationProof::new(record, self.deciding_engine(record).policy_digest())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::HmacSigner;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::fingerprint::{Fingerprinter, HashAlgorithm};
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_signed_proof_round_trips_and_tracks_policy_version() {
+        let mut gate =
+            CapabilityGate::new().with_args_fingerprint(Fingerprinter::new(HashAlgorithm::Sha256));
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("p", "1").with_rule(Rule::allow("fs.read")));
+
+        let record = gate.authorize_record(
+            "fs.read",
+            &json!({"path": "/tmp/a"}),
+            &RequestContext::new(),
+        );
+        let signer = HmacSigner::new(b"proof key".to_vec());
+        let proof = gate.proof(&record).signed(&signer);
+        assert_eq!(proof.rule.as_deref(), Some("p#0"));
+        assert!(proof
+            .args_fingerprint
+            .as_deref()
+            .is_some_and(|f| f.starts_with("sha256:")));
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let parsed: AuthorizationProof = serde_json::from_str(&json).unwrap();
+        assert!(parsed.verify(&signer));
+        assert!(!parsed.verify(&HmacSigner::new(b"other".to_vec())));
+        let tampered = AuthorizationProof {
+            rule: Some("p#1".into()),
+            ..parsed.clone()
+        };
+        assert!(!tampered.verify(&signer));
+
+        assert!(parsed.is_current(gate.engine()));
+        gate.add_policy(Policy::new("q", "1").with_rule(Rule::deny("shell")));
+        assert!(!parsed.is_current(gate.engine()));
+    }
+
+    #[test]
+    fn test_canary_decisions_name_the_canary_policies() {
+        use crate::flags::SESSION_ATTRIBUTE;
+
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.add_policy(Policy::new("p", "1").with_rule(Rule::allow("fs.read")));
+        let mut candidate = PolicyEngine::new();
+        candidate.add_policy(Policy::new("p", "2").with_rule(Rule::allow("fs.read")));
+        let candidate_digest = candidate.policy_digest();
+        let gate = gate.with_canary(candidate, 0.5);
+
+        let records: Vec<DecisionRecord> = (0..100)
+            .map(|i| {
+                let ctx = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, i.to_string());
+                gate.authorize_record("fs.read", &(), &ctx)
+            })
+            .collect();
+        let (routed, stable): (Vec<_>, Vec<_>) = records
+            .iter()
+            .partition(|r| r.details.contains_key(crate::canary::CANARY_DETAIL));
+        assert!(!routed.is_empty() && !stable.is_empty());
+        assert_eq!(gate.proof(routed[0]).policy_digest, candidate_digest);
+        assert_eq!(
+            gate.proof(stable[0]).policy_digest,
+            gate.engine().policy_digest()
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Rule Provenance.
+//!
+//! Policies and rules loaded from files remember where they came from: the file
+//! path, the line and column of their JSON object, and the bundle that shipped
+//! them. Provenance is never serialized; it is attached at load time and shows up
+//! in lint diagnostics and in the `source` detail of decision records, so
+//! authors can jump straight to the offending rule.
+
+use crate::policy::{Policy, PolicyEngine};
+use std::fmt;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Provenance {
+    pub source: Option<String>,
+    pub bundle: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut wrote = false;
+        if let Some(source) = &self.source {
+            write!(f, "{}", source)?;
+            wrote = true;
+        }
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            let sep = if wrote { ":" } else { "line " };
+            write!(f, "{}{}:{}", sep, line, column)?;
+            wrote = true;
+        }
+        if let Some(bundle) = &self.bundle {
+            let sep = if wrote { " " } else { "" };
+            write!(f, "{}(bundle {})", sep, bundle)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("reading {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Parse(#[from] serde_json::Error),
+}
+
+fn skip_ws(bytes: &[u8], mut pos: usize) -> usize {
+    while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+        pos += 1;
+    }
+    pos
+}
+
+fn skip_string(bytes: &[u8], mut pos: usize) -> usize {
+    pos += 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'"' => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    pos
+}
+
+/// End of the JSON value starting at `pos`. Input must already be valid JSON.
+fn skip_value(bytes: &[u8], pos: usize) -> usize {
+    match bytes.get(pos) {
+        Some(b'"') => skip_string(bytes, pos),
+        Some(b'{' | b'[') => {
+            let mut depth = 0;
+            let mut pos = pos;
+            while pos < bytes.len() {
+                match bytes[pos] {
+                    b'"' => {
+                        pos = skip_string(bytes, pos);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return pos + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                pos += 1;
+            }
+            pos
+        }
+        _ => {
+            let mut pos = pos;
+            while pos < bytes.len() && !matches!(bytes[pos], b',' | b'}' | b']') {
+                pos += 1;
+            }
+            pos
+        }
+    }
+}
+
+/// Start offsets of the elements of the array at `pos`.
+fn array_elements(bytes: &[u8], pos: usize) -> Vec<usize> {
+    let mut starts = Vec::new();
+    let mut pos = skip_ws(bytes, pos + 1);
+    while pos < bytes.len() && bytes[pos] != b']' {
+        starts.push(pos);
+        pos = skip_ws(bytes, skip_value(bytes, pos));
+        if bytes.get(pos) == Some(&b',') {
+            pos = skip_ws(bytes, pos + 1);
+        }
+    }
+    starts
+}
+
+/// Offset of the value of `key` in the object at `pos`.
+fn member(bytes: &[u8], pos: usize, key: &str) -> Option<usize> {
+    let mut pos = skip_ws(bytes, pos + 1);
+    while bytes.get(pos) == Some(&b'"') {
+        let end = skip_string(bytes, pos);
+        let name = &bytes[pos + 1..end - 1];
+        let value = skip_ws(bytes, skip_ws(bytes, end) + 1);
+        if name == key.as_bytes() {
+            return Some(value);
+        }
+        pos = skip_ws(bytes, skip_value(bytes, value));
+        if bytes.get(pos) == Some(&b',') {
+            pos = skip_ws(bytes, pos + 1);
+        }
+    }
+    None
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+/// Attaches provenance to `policies`, which were parsed from the JSON array in
+/// `json`. `source` is typically the file path.
+pub fn annotate(policies: &mut [Policy], json: &str, source: Option<&str>) {
+    let bytes = json.as_bytes();
+    let start = skip_ws(bytes, 0);
+    if bytes.get(start) != Some(&b'[') {
+        return;
+    }
+    let at = |offset: usize| {
+        let (line, column) = line_column(json, offset);
+        Provenance {
+            source: source.map(String::from),
+            bundle: None,
+            line: Some(line),
+            column: Some(column),
+        }
+    };
+    for (policy, offset) in policies.iter_mut().zip(array_elements(bytes, start)) {
+        policy.provenance = Some(at(offset));
+        if let Some(rules) = member(bytes, offset, "rules") {
+            for (rule, offset) in policy.rules.iter_mut().zip(array_elements(bytes, rules)) {
+                rule.provenance = Some(at(offset));
+            }
+        }
+    }
+}
+
+/// Records `bundle` as the origin of every policy and rule.
+pub fn mark_bundle(policy: &mut Policy, bundle: &str) {
+    let provenances = std::iter::once(&mut policy.provenance)
+        .chain(policy.rules.iter_mut().map(|r| &mut r.provenance));
+    for provenance in provenances {
+        provenance.get_or_insert_with(Provenance::default).bundle = Some(bundle.to_string());
+    }
+}
+
+impl PolicyEngine {
+    /// Loads a JSON policy file, recording the file and position of every rule.
+    pub fn load_from_file(&mut self, path: impl AsRef<Path>) -> Result<(), LoadError> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|source| LoadError::Io {
+            path: path.display().to_string(),
+            source,
+        })?;
+        let mut policies: Vec<Policy> = serde_json::from_str(&json)?;
+        annotate(&mut policies, &json, Some(&path.display().to_string()));
+        for policy in policies {
+            self.add_policy(policy);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::{Capability, CapabilityRegistry};
+    use crate::lint::lint_policy;
+
+    const JSON: &str = r#"[
+  {
+    "name": "legacy",
+    "version": "1.0",
+    "rules": [
+      { "effect": "Deny", "principal": "*", "resource": "shell", "action": "execute", "conditions": [] },
+      { "effect": "Allow", "principal": "*", "resource": "web.fetch", "action": "execute", "conditions": [] }
+    ]
+  }
+]"#;
+
+    #[test]
+    fn test_positions_reach_lints() {
+        let mut policies: Vec<Policy> = serde_json::from_str(JSON).unwrap();
+        annotate(&mut policies, JSON, Some("policies.json"));
+        mark_bundle(&mut policies[0], "core");
+
+        let rule = policies[0].rules[1].provenance.clone().unwrap();
+        assert_eq!((rule.line, rule.column), (Some(7), Some(7)));
+        assert_eq!(rule.to_string(), "policies.json:7:7 (bundle core)");
+
+        let mut registry = CapabilityRegistry::new();
+        registry.register(Capability::new("http.get", "HTTP GET"));
+        registry.register_alias("web.fetch", "http.get");
+        let lints = lint_policy(&policies[0], &registry);
+        assert!(lints[0]
+            .to_string()
+            .starts_with("policies.json:7:7 (bundle core): warning"));
+    }
+}
@@ -0,0 +1,520 @@
+//! Regular Expressions for Schema `pattern` Keywords.
+//!
+//! A small engine covering the ECMA-262 subset JSON Schema `pattern` values
+//! use in practice: literals, `.`, classes with ranges and `\d \w \s` (and
+//! their negations), anchors, groups (`(?:` too), alternation, and the
+//! `* + ? {n} {n,} {n,m}` quantifiers, greedy or lazy. Backreferences and
+//! lookaround are rejected at compile time.
+//!
+//! Patterns compile to a Thompson NFA that is simulated without backtracking
+//! or recursion (a Pike VM), so matching is unanchored, as in JSON Schema, and
+//! takes time linear in the text for a given pattern, however long the text or
+//! pathological the pattern. Counted repeats are expanded when compiling;
+//! patterns whose program would exceed [`PROGRAM_LIMIT`] instructions, or whose
+//! groups nest deeper than [`NESTING_LIMIT`], are rejected.
+
+use thiserror::Error;
+
+pub const PROGRAM_LIMIT: usize = 10_000;
+pub const NESTING_LIMIT: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("invalid pattern at offset {offset}: {reason}")]
+pub struct RegexError {
+    pub offset: usize,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')];
+const SPACE: &[(char, char)] = &[
+    (' ', ' '),
+    ('\t', '\r'),
+    ('\u{a0}', '\u{a0}'),
+    ('\u{2028}', '\u{2029}'),
+];
+
+impl Node {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Node::Char(expected) => c == *expected,
+            Node::Any => c != '\n' && c != '\r',
+            Node::Class { ranges, negated } => {
+                ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    at: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn error(&self, reason: &'static str) -> RegexError {
+        RegexError {
+            offset: self.at,
+            reason,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.at).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let found = self.peek() == Some(c);
+        if found {
+            self.at += 1;
+        }
+        found
+    }
+
+    fn alternation(&mut self) -> Result<Vec<Vec<Node>>, RegexError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, RegexError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, RegexError> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.at += 1;
+        Ok(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("lookaround and named groups are not supported"));
+                }
+                if self.depth == NESTING_LIMIT {
+                    return Err(self.error("groups nest too deeply"));
+                }
+                self.depth += 1;
+                let group = self.alternation()?;
+                self.depth -= 1;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                Node::Group(group)
+            }
+            '[' => self.class()?,
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => return Err(self.error("nothing to repeat")),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Node, RegexError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        self.at += 1;
+        let class = |ranges: &[(char, char)], negated| Node::Class {
+            ranges: ranges.to_vec(),
+            negated,
+        };
+        Ok(match c {
+            'd' => class(DIGIT, false),
+            'D' => class(DIGIT, true),
+            'w' => class(WORD, false),
+            'W' => class(WORD, true),
+            's' => class(SPACE, false),
+            'S' => class(SPACE, true),
+            'n' => Node::Char('\n'),
+            'r' => Node::Char('\r'),
+            't' => Node::Char('\t'),
+            '1'..='9' => return Err(self.error("backreferences are not supported")),
+            c if c.is_ascii_alphanumeric() => return Err(self.error("unknown escape")),
+            c => Node::Char(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, RegexError> {
+        let negated = self.eat('^');
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("unclosed class"))?;
+            self.at += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let lo = match c {
+                '\\' => match self.escape()? {
+                    Node::Char(c) => c,
+                    Node::Class {
+                        ranges: r,
+                        negated: false,
+                    } => {
+                        ranges.extend(r);
+                        continue;
+                    }
+                    _ => return Err(self.error("negated escape inside a class")),
+                },
+                c => c,
+            };
+            let is_range =
+                self.peek() == Some('-') && self.chars.get(self.at + 1).is_some_and(|&c| c != ']');
+            if !is_range {
+                ranges.push((lo, lo));
+                continue;
+            }
+            self.at += 1;
+            let hi = match self.peek() {
+                Some('\\') => {
+                    self.at += 1;
+                    match self.escape()? {
+                        Node::Char(c) => c,
+                        _ => return Err(self.error("class escape as range bound")),
+                    }
+                }
+                Some(c) => {
+                    self.at += 1;
+                    c
+                }
+                None => return Err(self.error("unclosed class")),
+            };
+            if hi < lo {
+                return Err(self.error("range out of order"));
+            }
+            ranges.push((lo, hi));
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn number(&mut self) -> Option<u32> {
+        let start = self.at;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.at += 1;
+        }
+        let digits: String = self.chars[start..self.at].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn quantified(&mut self, node: Node) -> Result<Node, RegexError> {
+        let start = self.at;
+        self.at += 1;
+        let (min, max) = match self.chars.get(start) {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                let Some(min) = self.number() else {
+                    // Not a quantifier: ECMA-262 treats a stray `{` as a literal.
+                    self.at = start;
+                    return Ok(node);
+                };
+                let max = match self.eat(',') {
+                    true => self.number(),
+                    false => Some(min),
+                };
+                if !self.eat('}') {
+                    return Err(self.error("unclosed quantifier"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(self.error("quantifier out of order"));
+                }
+                (min, max)
+            }
+            _ => {
+                self.at = start;
+                return Ok(node);
+            }
+        };
+        if matches!(node, Node::Start | Node::End) {
+            return Err(self.error("nothing to repeat"));
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    /// Consumes one character the atom matches.
+    Atom(Node),
+    Start,
+    End,
+    Jump(usize),
+    Split(usize, usize),
+    Match,
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn emit(&mut self, inst: Inst) -> Result<usize, RegexError> {
+        if self.program.len() == PROGRAM_LIMIT {
+            return Err(RegexError {
+                offset: 0,
+                reason: "pattern is too large",
+            });
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn patch(&mut self, at: usize, target: usize) {
+        match &mut self.program[at] {
+            Inst::Jump(t) => *t = target,
+            Inst::Split(_, t) => *t = target,
+            _ => unreachable!("only jumps and splits are patched"),
+        }
+    }
+
+    fn sequence(&mut self, nodes: &[Node]) -> Result<(), RegexError> {
+        nodes.iter().try_for_each(|node| self.node(node))
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), RegexError> {
+        match node {
+            Node::Start => self.emit(Inst::Start).map(drop),
+            Node::End => self.emit(Inst::End).map(drop),
+            Node::Group(alternatives) => {
+                let mut exits = Vec::new();
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i + 1 == alternatives.len() {
+                        self.sequence(alternative)?;
+                        break;
+                    }
+                    let split = self.emit(Inst::Split(0, 0))?;
+                    self.program[split] = Inst::Split(split + 1, 0);
+                    self.sequence(alternative)?;
+                    exits.push(self.emit(Inst::Jump(0))?);
+                    let next = self.program.len();
+                    self.patch(split, next);
+                }
+                let end = self.program.len();
+                exits.into_iter().for_each(|exit| self.patch(exit, end));
+                Ok(())
+            }
+            // Greediness only orders the matches; it does not change whether
+            // one exists.
+            Node::Repeat { node, min, max, .. } => {
+                for _ in 0..*min {
+                    self.node(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.emit(Inst::Split(0, 0))?;
+                        self.program[split] = Inst::Split(split + 1, 0);
+                        self.node(node)?;
+                        self.emit(Inst::Jump(split))?;
+                        let end = self.program.len();
+                        self.patch(split, end);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            let split = self.emit(Inst::Split(0, 0))?;
+                            self.program[split] = Inst::Split(split + 1, 0);
+                            splits.push(split);
+                            self.node(node)?;
+                        }
+                        let end = self.program.len();
+                        splits.into_iter().for_each(|split| self.patch(split, end));
+                    }
+                }
+                Ok(())
+            }
+            atom => self.emit(Inst::Atom(atom.clone())).map(drop),
+        }
+    }
+}
+
+/// The threads alive at one text position: the program counters waiting on a
+/// character or matching, and every counter visited to reach them.
+struct Threads {
+    pcs: Vec<usize>,
+    visited: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Self {
+            pcs: Vec::with_capacity(size),
+            visited: Vec::with_capacity(size),
+            seen: vec![false; size],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.visited.iter().for_each(|&pc| self.seen[pc] = false);
+        self.visited.clear();
+        self.pcs.clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Regex {
+    program: Vec<Inst>,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexError> {
+        let mut parser = Parser {
+            chars: pattern.chars().collect(),
+            at: 0,
+            depth: 0,
+        };
+        let root = parser.alternation()?;
+        if parser.at < parser.chars.len() {
+            return Err(parser.error("unmatched `)`"));
+        }
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.node(&Node::Group(root))?;
+        compiler.emit(Inst::Match)?;
+        Ok(Self {
+            program: compiler.program,
+        })
+    }
+
+    /// Follows jumps, splits and anchors from `pc` at position `at` of a text
+    /// of `len` characters, adding the threads that wait on a character or
+    /// match to `threads`.
+    fn add(&self, threads: &mut Threads, stack: &mut Vec<usize>, pc: usize, at: usize, len: usize) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if std::mem::replace(&mut threads.seen[pc], true) {
+                continue;
+            }
+            threads.visited.push(pc);
+            match &self.program[pc] {
+                Inst::Jump(target) => stack.push(*target),
+                Inst::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Inst::Start if at == 0 => stack.push(pc + 1),
+                Inst::End if at == len => stack.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                Inst::Atom(_) | Inst::Match => threads.pcs.push(pc),
+            }
+        }
+    }
+
+    /// Whether the pattern matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let chars: Vec<char> = text.chars().collect();
+        let size = self.program.len();
+        let (mut current, mut next) = (Threads::new(size), Threads::new(size));
+        let mut stack = Vec::new();
+        for at in 0..=chars.len() {
+            // A thread starts at every position: matching is unanchored.
+            self.add(&mut current, &mut stack, 0, at, chars.len());
+            if current
+                .pcs
+                .iter()
+                .any(|&pc| self.program[pc] == Inst::Match)
+            {
+                return true;
+            }
+            let Some(&c) = chars.get(at) else {
+                break;
+            };
+            next.clear();
+            for &pc in &current.pcs {
+                if let Inst::Atom(atom) = &self.program[pc] {
+                    if atom.matches(c) {
+                        self.add(&mut next, &mut stack, pc + 1, at + 1, chars.len());
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let cases = [
+            ("^[a-z][a-z0-9_-]*$", "repo_name-2", true),
+            ("^[a-z][a-z0-9_-]*$", "Repo", false),
+            ("^\\d{3}-\\d{4}$", "555-1234", true),
+            ("^\\d{3}-\\d{4}$", "555-12345", false),
+            ("^(https?|ftp)://", "https://example.com", true),
+            ("^(https?|ftp)://", "file:///etc/passwd", false),
+            ("\\.\\.", "a/../b", true),
+            ("^(?:ab)+?c$", "ababc", true),
+            ("^[^/]+$", "no/slash", false),
+            ("^(a*)*$", "aaaa", true),
+            ("colou?r", "my color", true),
+            ("^x{2,}$", "x", false),
+        ];
+        for (pattern, text, expected) in cases {
+            let regex = Regex::new(pattern).unwrap();
+            assert_eq!(regex.is_match(text), expected, "{} on {}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsupported_syntax() {
+        for pattern in ["(?=a)", "(a)\\1", "a)", "[z-a]", "*a", "(ab", "[ab"] {
+            assert!(Regex::new(pattern).is_err(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_pathological_inputs_run_in_linear_time() {
+        let regex = Regex::new("^(a|a)*b$").unwrap();
+        assert!(!regex.is_match(&"a".repeat(10_000)));
+        assert!(regex.is_match(&format!("{}b", "a".repeat(10_000))));
+        let regex = Regex::new("^[a-z]*$").unwrap();
+        assert!(regex.is_match(&"a".repeat(200_000)));
+        assert!(Regex::new("(a{100}){200}").is_err());
+        assert!(Regex::new(&format!("{}a{}", "(".repeat(64), ")".repeat(64))).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+//! Policy Simulation Sandbox.
+//!
+//! [`Repl`] is a line-oriented evaluation session for policy authors: load
+//! policies, register sample capabilities and query decisions, each answered
+//! with a trace of the rules that were considered and why they did or did not
+//! match. The `femtoclaw-policy repl` command drives it from stdin; every
+//! command returns its output as a string, so editors can embed it too.
+//!
+//! ```text
+//! cap shell Process
+//! policy {"name": "p", "version": "1", "rules": [...]}
+//! as alice
+//! check shell {"cmd": "ls"}
+//! ```
+
+use crate::builtin::BuiltinError;
+use crate::capability::{Capability, CapabilityCategory};
+use crate::clock::{is_time_condition, TimeCheck};
+use crate::context::RequestContext;
+use crate::debug::DebugEvaluator;
+use crate::gate::CapabilityGate;
+use crate::policy::{Condition, Effect, Policy, Rule};
+use serde_json::Value;
+use std::fmt::Write;
+use thiserror::Error;
+
+pub const HELP: &str = "\
+commands:
+  cap <name> [category]   register a capability
+  policy <json>           add a policy (object) or policies (array)
+  load <file>             add the policies in a JSON file
+  builtin <name>          add a built-in policy pack, e.g. baseline/v1
+  as <principal>|-        set or clear the request principal
+  check <tool> [json]     authorize a call and trace the rules considered
+  debug <tool> [json]     list every evaluation step for a call
+  explain <tool> [json]   print the decision tree for a call as JSON
+  keys <tool>             list the argument keys conditions can test
+  policies                list loaded policies
+  lints                   show lints for loaded policies
+  reset                   start over";
+
+#[derive(Debug, Error)]
+pub enum ReplError {
+    #[error("{0}")]
+    Usage(String),
+    #[error("unknown category `{0}`")]
+    Category(String),
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("reading {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Builtin(#[from] BuiltinError),
+}
+
+#[derive(Default)]
+pub struct Repl {
+    gate: CapabilityGate,
+    ctx: RequestContext,
+}
+
+fn usage(text: &str) -> ReplError {
+    ReplError::Usage(format!("usage: {}", text))
+}
+
+fn describe(condition: &Condition) -> String {
+    format!(
+        "`{} {} {}`",
+        condition.key, condition.operator, condition.value
+    )
+}
+
+impl Repl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gate(&self) -> &CapabilityGate {
+        &self.gate
+    }
+
+    /// Runs one command line; blank lines and `#` comments produce no output.
+    pub fn execute(&mut self, line: &str) -> Result<String, ReplError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(String::new());
+        }
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        match command {
+            "help" => Ok(HELP.to_string()),
+            "cap" => self.register(rest),
+            "policy" => self.add_policies(rest),
+            "load" => {
+                let json = std::fs::read_to_string(rest).map_err(|source| ReplError::Io {
+                    path: rest.to_string(),
+                    source,
+                })?;
+                self.add_policies(&json)
+            }
+            "builtin" => {
+                let mut added = Vec::new();
+                for policy in crate::builtin::builtin(rest)? {
+                    added.push(policy.name.clone());
+                    self.gate.add_guard(policy);
+                }
+                Ok(format!("added {}", added.join(", ")))
+            }
+            "as" => {
+                self.ctx.principal = match rest {
+                    "" => return Err(usage("as <principal>|-")),
+                    "-" => None,
+                    principal => Some(principal.to_string()),
+                };
+                Ok(format!(
+                    "principal: {}",
+                    self.ctx.principal.as_deref().unwrap_or("(none)")
+                ))
+            }
+            "check" => self.check(rest),
+            "debug" => self.debug(rest),
+            "explain" => {
+                let (tool, args) = self.call(rest, "explain")?;
+                let tool = self.gate.registry().resolve(tool);
+                let explanation = self.gate.engine().explain(&self.ctx, tool, &args);
+                Ok(serde_json::to_string_pretty(&explanation)?)
+            }
+            "keys" => {
+                let tool = self.gate.registry().resolve(rest);
+                Ok(self.gate.engine().known_condition_keys(tool).join("\n"))
+            }
+            "policies" => Ok(self
+                .gate
+                .engine()
+                .policies()
+                .map(|p| format!("{} {} ({} rules)", p.name, p.version, p.rules.len()))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "lints" => Ok(self
+                .gate
+                .lints()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "reset" => {
+                *self = Self::new();
+                Ok("reset".to_string())
+            }
+            other => Err(ReplError::Usage(format!(
+                "unknown command `{}`; try `help`",
+                other
+            ))),
+        }
+    }
+
+    fn register(&mut self, rest: &str) -> Result<String, ReplError> {
+        let mut words = rest.split_whitespace();
+        let name = words.next().ok_or_else(|| usage("cap <name> [category]"))?;
+        let mut capability = Capability::new(name, "registered in repl");
+        if let Some(category) = words.next() {
+            let parsed = CapabilityCategory::parse(category)
+                .ok_or_else(|| ReplError::Category(category.to_string()))?;
+            capability = capability.with_category(parsed);
+        }
+        let category = capability.category;
+        self.gate.register_capability(capability);
+        Ok(format!("registered {} ({})", name, category))
+    }
+
+    fn add_policies(&mut self, json: &str) -> Result<String, ReplError> {
+        let policies: Vec<Policy> = match serde_json::from_str::<Value>(json)? {
+            Value::Array(_) => serde_json::from_str(json)?,
+            _ => vec![serde_json::from_str(json)?],
+        };
+        let names: Vec<String> = policies.iter().map(|p| p.name.clone()).collect();
+        for policy in policies {
+            self.gate.add_policy(policy);
+        }
+        Ok(format!("added {}", names.join(", ")))
+    }
+
+    fn call<'a>(&self, rest: &'a str, command: &str) -> Result<(&'a str, Value), ReplError> {
+        let (tool, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if tool.is_empty() {
+            return Err(usage(&format!("{} <tool> [json]", command)));
+        }
+        let args: Value = match args.trim() {
+            "" => Value::Object(Default::default()),
+            json => serde_json::from_str(json)?,
+        };
+        Ok((tool, args))
+    }
+
+    fn debug(&self, rest: &str) -> Result<String, ReplError> {
+        let (tool, args) = self.call(rest, "debug")?;
+        let tool = self.gate.registry().resolve(tool);
+        let steps: Vec<String> = DebugEvaluator::new(self.gate.engine())
+            .steps(&self.ctx, tool, &args)
+            .map(|step| step.to_string())
+            .collect();
+        Ok(steps.join("\n"))
+    }
+
+    fn check(&self, rest: &str) -> Result<String, ReplError> {
+        let (tool, args) = self.call(rest, "check")?;
+
+        let record = self.gate.authorize_record(tool, &args, &self.ctx);
+        let mut out = record.decision.to_string();
+        if let Some(rule) = &record.rule {
+            let _ = write!(out, " (rule {})", rule);
+        }
+        for line in self.trace(&record.capability, &args) {
+            let _ = write!(out, "\n  {}", line);
+        }
+        Ok(out)
+    }
+
+    /// One line per rule that targets `capability`, in policy order.
+    fn trace(&self, capability: &str, args: &Value) -> Vec<String> {
+        let engine = self.gate.engine();
+        let category = engine.category_of(capability);
+        let time = engine.time_check_for(&self.ctx);
+        let mut lines = Vec::new();
+        for policy in engine.policies() {
+            for (index, rule) in policy.rules.iter().enumerate() {
+                if !rule.applies_in(capability, category) {
+                    continue;
+                }
+                let effect = match rule.effect {
+                    Effect::Allow => "allow",
+                    Effect::Deny => "deny",
+                    Effect::AllowWithWarning => "allow-with-warning",
+                    Effect::Audit => "audit",
+                };
+                lines.push(format!(
+                    "{}#{} {} {}: {}",
+                    policy.name,
+                    index,
+                    effect,
+                    rule.resource,
+                    self.explain(rule, args, &time)
+                ));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(match engine.capability_default(capability) {
+                Some(effect) => format!(
+                    "no rule targets this capability; its default {:?} applies",
+                    effect
+                ),
+                None => "no rule targets this capability; the default effect applies".into(),
+            });
+        }
+        lines
+    }
+
+    fn explain(&self, rule: &Rule, args: &Value, time: &TimeCheck) -> String {
+        let principal = self.ctx.principal.as_deref();
+        if rule.principal != "*" && Some(rule.principal.as_str()) != principal {
+            return format!("principal `{}` does not match", rule.principal);
+        }
+        for condition in &rule.conditions {
+            let holds = match is_time_condition(condition) {
+                true => time.holds(condition, rule.effect).unwrap_or(false),
+                false => {
+                    condition.evaluate(args)
+                        || (rule.effect == Effect::Deny && condition.evaluate_folded(args))
+                }
+            };
+            if !holds {
+                return format!("condition {} did not hold", describe(condition));
+            }
+        }
+        for constraint in &rule.param_constraints {
+            if let Err(violation) = constraint.check(args) {
+                return format!("constraint failed: {}", violation);
+            }
+        }
+        "matched".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_traces_decisions() {
+        let mut repl = Repl::new();
+        repl.execute("cap shell").unwrap();
+        repl.execute(
+            r#"policy {"name": "p", "version": "1", "rules": [
+                {"effect": "Deny", "principal": "*", "resource": "shell", "action": "execute",
+                 "conditions": [{"key": "cmd", "operator": "eq", "value": "rm"}]},
+                {"effect": "Allow", "principal": "alice", "resource": "shell", "action": "execute",
+                 "conditions": []}]}"#,
+        )
+        .unwrap();
+
+        let out = repl.execute(r#"check shell {"cmd": "ls"}"#).unwrap();
+        assert_eq!(
+            out,
+            "DENIED_POLICY_VIOLATION\n  p#0 deny shell: condition `cmd eq \"rm\"` did not hold\n  p#1 allow shell: principal `alice` does not match"
+        );
+
+        repl.execute("as alice").unwrap();
+        let out = repl.execute(r#"check shell {"cmd": "ls"}"#).unwrap();
+        assert!(out.starts_with("AUTHORIZED (rule p#1)"), "{}", out);
+    }
+
+    #[test]
+    fn test_debug_lists_steps() {
+        let mut repl = Repl::new();
+        repl.execute("cap shell").unwrap();
+        repl.execute(
+            r#"policy {"name": "p", "version": "1", "rules": [
+                {"effect": "Allow", "principal": "*", "resource": "shell", "action": "execute",
+                 "conditions": [{"key": "cmd", "operator": "eq", "value": "ls"}]}]}"#,
+        )
+        .unwrap();
+
+        let out = repl.execute(r#"debug shell {"cmd": "ls"}"#).unwrap();
+        assert_eq!(out.lines().last(), Some("decided Allow by p#0"), "{}", out);
+    }
+
+    #[test]
+    fn test_errors_are_reported_not_fatal() {
+        let mut repl = Repl::new();
+        assert!(matches!(
+            repl.execute("cap x Nope"),
+            Err(ReplError::Category(_))
+        ));
+        assert!(matches!(repl.execute("policy {"), Err(ReplError::Json(_))));
+        assert!(matches!(
+            repl.execute("frobnicate"),
+            Err(ReplError::Usage(_))
+        ));
+        assert_eq!(repl.execute("# comment").unwrap(), "");
+    }
+}
@@ -0,0 +1,344 @@
+//! Sandbox Ruleset Export.
+//!
+//! Translates `fs.*` path constraints declared in policy rules into Landlock
+//! ruleset descriptions (Linux), so the kernel enforces the same path boundaries
+//! the policy declares and a path swapped between authorization and execution
+//! cannot escape them.
+//!
+//! A rule's `path` conditions all have to hold, so their paths are intersected:
+//! `starts_with` allows the paths beneath a prefix, `eq` and `in` allow exact
+//! paths only, and a rule whose conditions leave no path allows nothing. An
+//! `Allow` rule without path conditions allows `/`.
+//!
+//! Landlock is allow-list only: `Allow` rules become `path_beneath` rules, while
+//! `Deny` rules, constrained or not, cannot be expressed and are reported in
+//! [`LandlockRuleset::unenforced`].
+
+use crate::policy::{Effect, PolicyEngine, Rule};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const LANDLOCK_ABI: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum FsAccess {
+    Execute,
+    WriteFile,
+    ReadFile,
+    ReadDir,
+    RemoveDir,
+    RemoveFile,
+    MakeChar,
+    MakeDir,
+    MakeReg,
+    MakeSock,
+    MakeFifo,
+    MakeBlock,
+    MakeSym,
+    Refer,
+    Truncate,
+}
+
+impl FsAccess {
+    pub fn bit(&self) -> u64 {
+        match self {
+            FsAccess::Execute => 1 << 0,
+            FsAccess::WriteFile => 1 << 1,
+            FsAccess::ReadFile => 1 << 2,
+            FsAccess::ReadDir => 1 << 3,
+            FsAccess::RemoveDir => 1 << 4,
+            FsAccess::RemoveFile => 1 << 5,
+            FsAccess::MakeChar => 1 << 6,
+            FsAccess::MakeDir => 1 << 7,
+            FsAccess::MakeReg => 1 << 8,
+            FsAccess::MakeSock => 1 << 9,
+            FsAccess::MakeFifo => 1 << 10,
+            FsAccess::MakeBlock => 1 << 11,
+            FsAccess::MakeSym => 1 << 12,
+            FsAccess::Refer => 1 << 13,
+            FsAccess::Truncate => 1 << 14,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FsAccess::Execute => "LANDLOCK_ACCESS_FS_EXECUTE",
+            FsAccess::WriteFile => "LANDLOCK_ACCESS_FS_WRITE_FILE",
+            FsAccess::ReadFile => "LANDLOCK_ACCESS_FS_READ_FILE",
+            FsAccess::ReadDir => "LANDLOCK_ACCESS_FS_READ_DIR",
+            FsAccess::RemoveDir => "LANDLOCK_ACCESS_FS_REMOVE_DIR",
+            FsAccess::RemoveFile => "LANDLOCK_ACCESS_FS_REMOVE_FILE",
+            FsAccess::MakeChar => "LANDLOCK_ACCESS_FS_MAKE_CHAR",
+            FsAccess::MakeDir => "LANDLOCK_ACCESS_FS_MAKE_DIR",
+            FsAccess::MakeReg => "LANDLOCK_ACCESS_FS_MAKE_REG",
+            FsAccess::MakeSock => "LANDLOCK_ACCESS_FS_MAKE_SOCK",
+            FsAccess::MakeFifo => "LANDLOCK_ACCESS_FS_MAKE_FIFO",
+            FsAccess::MakeBlock => "LANDLOCK_ACCESS_FS_MAKE_BLOCK",
+            FsAccess::MakeSym => "LANDLOCK_ACCESS_FS_MAKE_SYM",
+            FsAccess::Refer => "LANDLOCK_ACCESS_FS_REFER",
+            FsAccess::Truncate => "LANDLOCK_ACCESS_FS_TRUNCATE",
+        }
+    }
+
+    /// Access rights implied by a filesystem capability name.
+    pub fn for_capability(name: &str) -> &'static [FsAccess] {
+        match name {
+            "fs.read" => &[FsAccess::ReadFile, FsAccess::ReadDir],
+            "fs.list" | "fs.stat" => &[FsAccess::ReadDir],
+            "fs.write" => &[
+                FsAccess::WriteFile,
+                FsAccess::MakeReg,
+                FsAccess::MakeDir,
+                FsAccess::Truncate,
+            ],
+            "fs.delete" => &[FsAccess::RemoveFile, FsAccess::RemoveDir],
+            "fs.exec" => &[FsAccess::Execute],
+            _ => &[],
+        }
+    }
+}
+
+pub fn access_mask(access: &[FsAccess]) -> u64 {
+    access.iter().fold(0, |mask, a| mask | a.bit())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LandlockPathRule {
+    pub path: String,
+    /// The policy allows `path` itself rather than everything beneath it.
+    /// Landlock still covers the hierarchy beneath a directory, so exact rules
+    /// are only tight for files.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exact: bool,
+    pub access: Vec<FsAccess>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LandlockRuleset {
+    pub abi: u32,
+    pub handled_access_fs: Vec<FsAccess>,
+    pub rules: Vec<LandlockPathRule>,
+    pub unenforced: Vec<String>,
+}
+
+impl LandlockRuleset {
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// A set of paths one condition allows.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PathBound {
+    Exact(String),
+    Beneath(String),
+}
+
+impl PathBound {
+    fn intersect(&self, other: &PathBound) -> Option<PathBound> {
+        use PathBound::*;
+        match (self, other) {
+            (Exact(a), Exact(b)) => (a == b).then(|| self.clone()),
+            (Exact(path), Beneath(prefix)) | (Beneath(prefix), Exact(path)) => path
+                .starts_with(prefix.as_str())
+                .then(|| Exact(path.clone())),
+            (Beneath(a), Beneath(b)) if a.starts_with(b.as_str()) => Some(self.clone()),
+            (Beneath(a), Beneath(b)) if b.starts_with(a.as_str()) => Some(other.clone()),
+            _ => None,
+        }
+    }
+}
+
+/// The paths each of a rule's conditions allows, skipping those that do not
+/// bound the path.
+fn condition_bounds(rule: &Rule) -> impl Iterator<Item = Vec<PathBound>> + '_ {
+    rule.conditions.iter().filter_map(|condition| {
+        let key = condition
+            .key
+            .strip_prefix("args.")
+            .unwrap_or(&condition.key);
+        if key != "path" {
+            return None;
+        }
+        let strings = |value: &serde_json::Value| -> Vec<String> {
+            match value {
+                serde_json::Value::String(s) => vec![s.clone()],
+                serde_json::Value::Array(items) => items
+                    .iter()
+                    .filter_map(|v| v.as_str())
+                    .map(String::from)
+                    .collect(),
+                _ => Vec::new(),
+            }
+        };
+        let bound: fn(String) -> PathBound = match condition.operator.as_str() {
+            "eq" | "equals" if condition.value.is_string() => PathBound::Exact,
+            "in" if condition.value.is_array() => PathBound::Exact,
+            "starts_with" | "prefix" if condition.value.is_string() => PathBound::Beneath,
+            _ => return None,
+        };
+        Some(strings(&condition.value).into_iter().map(bound).collect())
+    })
+}
+
+/// The paths a rule is constrained to, the intersection of its path
+/// conditions, or `None` if it is unconstrained.
+fn path_constraints(rule: &Rule) -> Option<Vec<PathBound>> {
+    condition_bounds(rule).fold(None, |allowed, bounds| {
+        let Some(allowed) = allowed else {
+            return Some(bounds);
+        };
+        let mut both: Vec<PathBound> = allowed
+            .iter()
+            .flat_map(|a| bounds.iter().filter_map(move |b| a.intersect(b)))
+            .collect();
+        both.sort();
+        both.dedup();
+        Some(both)
+    })
+}
+
+pub fn landlock_ruleset(engine: &PolicyEngine) -> LandlockRuleset {
+    let mut handled: Vec<FsAccess> = Vec::new();
+    let mut by_path: BTreeMap<(String, bool), Vec<FsAccess>> = BTreeMap::new();
+    let mut unenforced = Vec::new();
+
+    for policy in engine.policies() {
+        for rule in &policy.rules {
+            let access = FsAccess::for_capability(&rule.resource);
+            if access.is_empty() {
+                continue;
+            }
+            handled.extend_from_slice(access);
+
+            if rule.effect == Effect::Deny {
+                unenforced.push(format!("{}: deny {}", policy.name, rule.resource));
+                continue;
+            }
+
+            let paths =
+                path_constraints(rule).unwrap_or_else(|| vec![PathBound::Beneath("/".to_string())]);
+            for bound in paths {
+                let key = match bound {
+                    PathBound::Exact(path) => (path, true),
+                    PathBound::Beneath(path) => (path, false),
+                };
+                by_path.entry(key).or_default().extend_from_slice(access);
+            }
+        }
+    }
+
+    handled.sort();
+    handled.dedup();
+    let rules = by_path
+        .into_iter()
+        .map(|((path, exact), mut access)| {
+            access.sort();
+            access.dedup();
+            LandlockPathRule {
+                path,
+                exact,
+                access,
+            }
+        })
+        .collect();
+
+    LandlockRuleset {
+        abi: LANDLOCK_ABI,
+        handled_access_fs: handled,
+        rules,
+        unenforced,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Policy};
+
+    fn path_prefix(path: &str) -> Condition {
+        Condition {
+            key: "path".to_string(),
+            operator: "starts_with".to_string(),
+            value: serde_json::json!(path),
+        }
+    }
+
+    fn rule_paths(conditions: Vec<Condition>) -> Vec<(String, bool)> {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1.0").with_rule(Rule::allow("fs.read").with_conditions(conditions)),
+        );
+        landlock_ruleset(&engine)
+            .rules
+            .into_iter()
+            .map(|r| (r.path, r.exact))
+            .collect()
+    }
+
+    #[test]
+    fn test_path_constraints_become_rules() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("workspace", "1.0")
+                .with_rule(Rule::allow("fs.read").with_conditions(vec![path_prefix("/work")]))
+                .with_rule(Rule::allow("fs.write").with_conditions(vec![path_prefix("/work/out")])),
+        );
+
+        let ruleset = landlock_ruleset(&engine);
+        assert_eq!(ruleset.rules.len(), 2);
+        assert_eq!(ruleset.rules[0].path, "/work");
+        assert_eq!(
+            ruleset.rules[0].access,
+            vec![FsAccess::ReadFile, FsAccess::ReadDir]
+        );
+        assert!(ruleset.handled_access_fs.contains(&FsAccess::WriteFile));
+    }
+
+    #[test]
+    fn test_deny_rules_are_unenforced() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("default", "1.0")
+                .with_rule(Rule::deny("fs.write").with_conditions(vec![path_prefix("/etc")]))
+                .with_rule(Rule::deny("fs.delete"))
+                .with_rule(Rule::allow("shell")),
+        );
+
+        let ruleset = landlock_ruleset(&engine);
+        assert!(ruleset.rules.is_empty());
+        assert_eq!(
+            ruleset.unenforced,
+            vec![
+                "default: deny fs.write".to_string(),
+                "default: deny fs.delete".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_conditions_are_intersected() {
+        let eq = |path: &str| Condition::new("args.path", "eq", serde_json::json!(path));
+        assert_eq!(
+            rule_paths(vec![path_prefix("/work"), path_prefix("/work/out")]),
+            vec![("/work/out".to_string(), false)]
+        );
+        assert_eq!(
+            rule_paths(vec![eq("/work/a.txt")]),
+            vec![("/work/a.txt".to_string(), true)]
+        );
+        assert_eq!(
+            rule_paths(vec![
+                path_prefix("/work"),
+                Condition::new("path", "in", serde_json::json!(["/work/a", "/etc/b"])),
+            ]),
+            vec![("/work/a".to_string(), true)]
+        );
+        assert!(rule_paths(vec![path_prefix("/work"), eq("/etc/passwd")]).is_empty());
+    }
+
+    #[test]
+    fn test_access_mask() {
+        assert_eq!(access_mask(FsAccess::for_capability("fs.read")), 0b1100);
+    }
+}
@@ -0,0 +1,500 @@
+//! Capability Argument Schemas.
+//!
+//! With the `json-schema` feature, a capability's `schema` is compiled when it
+//! is registered and the gate checks JSON arguments against it before
+//! evaluating policy; arguments that do not conform are denied with
+//! `DeniedInvalidArguments` and reason `ARGS_SCHEMA_VIOLATION`. Non-JSON
+//! arguments cannot be checked, so they are denied the same way for any
+//! capability that declares a schema.
+//!
+//! Validation follows JSON Schema draft 2020-12 for the assertion keywords:
+//! `type`, `enum`, `const`, string length and `pattern` (see [`crate::regex`]),
+//! numeric bounds and `multipleOf`, `properties`, `patternProperties`,
+//! `additionalProperties`, `required`, property counts, `prefixItems`, `items`,
+//! `contains`, item counts, `uniqueItems`, and the `allOf`/`anyOf`/`oneOf`/
+//! `not`/`if` combinators. References (`$ref`, `$dynamicRef`) are rejected at
+//! compile time rather than silently skipped; annotation keywords are ignored.
+
+use crate::regex::{Regex, RegexError};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+const UNSUPPORTED: [&str; 3] = ["$ref", "$dynamicRef", "$recursiveRef"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SchemaError {
+    #[error("invalid pattern `{pattern}`: {source}")]
+    Pattern { pattern: String, source: RegexError },
+    #[error("unsupported keyword `{0}`")]
+    Unsupported(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// JSON Pointer to the offending value; empty for the root.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.path.as_str() {
+            "" => write!(f, "{}", self.message),
+            path => write!(f, "{}: {}", path, self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    root: Value,
+    patterns: BTreeMap<String, Regex>,
+}
+
+fn collect_patterns(
+    schema: &Value,
+    patterns: &mut BTreeMap<String, Regex>,
+) -> Result<(), SchemaError> {
+    let mut compile = |pattern: &str| -> Result<(), SchemaError> {
+        if !patterns.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|source| SchemaError::Pattern {
+                pattern: pattern.to_string(),
+                source,
+            })?;
+            patterns.insert(pattern.to_string(), regex);
+        }
+        Ok(())
+    };
+    match schema {
+        Value::Object(map) => {
+            if let Some(keyword) = UNSUPPORTED.iter().find(|k| map.contains_key(**k)) {
+                return Err(SchemaError::Unsupported(keyword.to_string()));
+            }
+            if let Some(pattern) = map.get("pattern").and_then(Value::as_str) {
+                compile(pattern)?;
+            }
+            for pattern in map
+                .get("patternProperties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flat_map(Map::keys)
+            {
+                compile(pattern)?;
+            }
+            for (key, value) in map {
+                // `enum` and `const` hold data, not subschemas.
+                if key != "enum" && key != "const" {
+                    collect_patterns(value, patterns)?;
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_patterns(item, patterns)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn type_matches(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        "number" => value.is_number(),
+        "integer" => {
+            value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|n| n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+struct Walk<'a> {
+    patterns: &'a BTreeMap<String, Regex>,
+    violations: Vec<SchemaViolation>,
+}
+
+impl Walk<'_> {
+    fn fail(&mut self, path: &str, message: String) {
+        self.violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        });
+    }
+
+    /// Whether `value` conforms, without recording anything.
+    fn conforms(&self, schema: &Value, value: &Value) -> bool {
+        let mut probe = Walk {
+            patterns: self.patterns,
+            violations: Vec::new(),
+        };
+        probe.check(schema, value, "");
+        probe.violations.is_empty()
+    }
+
+    fn pattern_matches(&self, pattern: &str, text: &str) -> bool {
+        self.patterns
+            .get(pattern)
+            .is_some_and(|regex| regex.is_match(text))
+    }
+
+    fn check(&mut self, schema: &Value, value: &Value, path: &str) {
+        let schema = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return self.fail(path, "no value is allowed here".to_string()),
+            Value::Object(schema) => schema,
+            _ => return,
+        };
+        let number = |key: &str| schema.get(key).and_then(Value::as_f64);
+        let count = |key: &str| schema.get(key).and_then(Value::as_u64);
+
+        if let Some(expected) = schema.get("type") {
+            let ok = match expected {
+                Value::String(name) => type_matches(name, value),
+                Value::Array(names) => names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|name| type_matches(name, value)),
+                _ => true,
+            };
+            if !ok {
+                self.fail(
+                    path,
+                    format!("expected {}, got {}", expected, type_name(value)),
+                );
+            }
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+            if !options.contains(value) {
+                self.fail(path, "value is not one of the allowed values".to_string());
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected != value {
+                self.fail(path, format!("expected {}", expected));
+            }
+        }
+
+        match value {
+            Value::String(text) => {
+                let len = text.chars().count() as u64;
+                if let Some(min) = count("minLength").filter(|min| len < *min) {
+                    self.fail(path, format!("shorter than {} characters", min));
+                }
+                if let Some(max) = count("maxLength").filter(|max| len > *max) {
+                    self.fail(path, format!("longer than {} characters", max));
+                }
+                if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+                    if !self.pattern_matches(pattern, text) {
+                        self.fail(path, format!("does not match `{}`", pattern));
+                    }
+                }
+            }
+            Value::Number(n) => {
+                let n = n.as_f64().unwrap_or(f64::NAN);
+                let bounds = [
+                    (
+                        "minimum",
+                        n >= number("minimum").unwrap_or(f64::NEG_INFINITY),
+                    ),
+                    ("maximum", n <= number("maximum").unwrap_or(f64::INFINITY)),
+                    (
+                        "exclusiveMinimum",
+                        n > number("exclusiveMinimum").unwrap_or(f64::NEG_INFINITY),
+                    ),
+                    (
+                        "exclusiveMaximum",
+                        n < number("exclusiveMaximum").unwrap_or(f64::INFINITY),
+                    ),
+                ];
+                for (keyword, ok) in bounds {
+                    if !ok {
+                        self.fail(path, format!("violates {} {}", keyword, schema[keyword]));
+                    }
+                }
+                if let Some(step) = number("multipleOf").filter(|s| *s > 0.0) {
+                    let ratio = n / step;
+                    if (ratio - ratio.round()).abs() > 1e-9 {
+                        self.fail(path, format!("not a multiple of {}", step));
+                    }
+                }
+            }
+            Value::Object(object) => self.check_object(schema, object, path),
+            Value::Array(items) => self.check_array(schema, items, path),
+            _ => {}
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Value::as_array) {
+            for subschema in all {
+                self.check(subschema, value, path);
+            }
+        }
+        if let Some(any) = schema.get("anyOf").and_then(Value::as_array) {
+            if !any.iter().any(|s| self.conforms(s, value)) {
+                self.fail(path, "matches none of `anyOf`".to_string());
+            }
+        }
+        if let Some(one) = schema.get("oneOf").and_then(Value::as_array) {
+            let matched = one.iter().filter(|s| self.conforms(s, value)).count();
+            if matched != 1 {
+                self.fail(path, format!("matches {} of `oneOf`, expected 1", matched));
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if self.conforms(not, value) {
+                self.fail(path, "matches `not`".to_string());
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = match self.conforms(condition, value) {
+                true => schema.get("then"),
+                false => schema.get("else"),
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path);
+            }
+        }
+    }
+
+    fn check_object(
+        &mut self,
+        schema: &Map<String, Value>,
+        object: &Map<String, Value>,
+        path: &str,
+    ) {
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let pattern_properties = schema.get("patternProperties").and_then(Value::as_object);
+        for name in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            if let Some(name) = name.as_str().filter(|n| !object.contains_key(*n)) {
+                self.fail(path, format!("missing required property `{}`", name));
+            }
+        }
+        let len = object.len() as u64;
+        if schema
+            .get("minProperties")
+            .and_then(Value::as_u64)
+            .is_some_and(|min| len < min)
+        {
+            self.fail(path, "too few properties".to_string());
+        }
+        if schema
+            .get("maxProperties")
+            .and_then(Value::as_u64)
+            .is_some_and(|max| len > max)
+        {
+            self.fail(path, "too many properties".to_string());
+        }
+        for (key, value) in object {
+            let child = format!("{}/{}", path, escape_pointer(key));
+            let mut covered = false;
+            if let Some(subschema) = properties.and_then(|p| p.get(key)) {
+                covered = true;
+                self.check(subschema, value, &child);
+            }
+            for (pattern, subschema) in pattern_properties.into_iter().flatten() {
+                if self.pattern_matches(pattern, key) {
+                    covered = true;
+                    self.check(subschema, value, &child);
+                }
+            }
+            match schema.get("additionalProperties") {
+                Some(Value::Bool(false)) if !covered => {
+                    self.fail(path, format!("unexpected property `{}`", key));
+                }
+                Some(additional) if !covered => self.check(additional, value, &child),
+                _ => {}
+            }
+        }
+    }
+
+    fn check_array(&mut self, schema: &Map<String, Value>, items: &[Value], path: &str) {
+        let len = items.len() as u64;
+        if schema
+            .get("minItems")
+            .and_then(Value::as_u64)
+            .is_some_and(|min| len < min)
+        {
+            self.fail(path, "too few items".to_string());
+        }
+        if schema
+            .get("maxItems")
+            .and_then(Value::as_u64)
+            .is_some_and(|max| len > max)
+        {
+            self.fail(path, "too many items".to_string());
+        }
+        let prefix = schema.get("prefixItems").and_then(Value::as_array);
+        let prefix_len = prefix.map_or(0, Vec::len);
+        for (index, item) in items.iter().enumerate() {
+            let child = format!("{}/{}", path, index);
+            match prefix.and_then(|p| p.get(index)) {
+                Some(subschema) => self.check(subschema, item, &child),
+                None if index >= prefix_len => {
+                    if let Some(subschema) = schema.get("items") {
+                        self.check(subschema, item, &child);
+                    }
+                }
+                None => {}
+            }
+        }
+        if let Some(contains) = schema.get("contains") {
+            if !items.iter().any(|item| self.conforms(contains, item)) {
+                self.fail(path, "no item matches `contains`".to_string());
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].contains(item));
+            if duplicate {
+                self.fail(path, "items are not unique".to_string());
+            }
+        }
+    }
+}
+
+impl CompiledSchema {
+    pub fn compile(schema: &Value) -> Result<Self, SchemaError> {
+        let mut patterns = BTreeMap::new();
+        collect_patterns(schema, &mut patterns)?;
+        Ok(Self {
+            root: schema.clone(),
+            patterns,
+        })
+    }
+
+    /// Every way `value` fails the schema; empty if it conforms.
+    pub fn validate(&self, value: &Value) -> Vec<SchemaViolation> {
+        let mut walk = Walk {
+            patterns: &self.patterns,
+            violations: Vec::new(),
+        };
+        walk.check(&self.root, value, "");
+        walk.violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::codes::{Reason, REASON_DETAIL};
+    use crate::context::RequestContext;
+    use crate::gate::{CapabilityGate, Decision};
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "repo": { "type": "string", "pattern": "^[a-z0-9_-]+/[a-z0-9_-]+$" },
+                "state": { "enum": ["open", "closed"] },
+                "labels": { "type": "array", "items": { "type": "string" }, "uniqueItems": true },
+                "page": {
+                    "type": "object",
+                    "properties": { "size": { "type": "integer", "minimum": 1, "maximum": 100 } },
+                    "additionalProperties": false,
+                },
+            },
+            "required": ["repo"],
+            "additionalProperties": false,
+        })
+    }
+
+    #[test]
+    fn test_validates_nested_enums_and_patterns() {
+        let compiled = CompiledSchema::compile(&schema()).unwrap();
+        let ok = json!({ "repo": "acme/api", "state": "open", "page": { "size": 50 } });
+        assert!(compiled.validate(&ok).is_empty());
+
+        let bad = json!({
+            "repo": "../../etc",
+            "state": "merged",
+            "labels": ["a", "a"],
+            "page": { "size": 500, "cursor": "x" },
+            "extra": true,
+        });
+        let messages: Vec<String> = compiled
+            .validate(&bad)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(messages.len(), 6, "{:?}", messages);
+        assert!(messages.contains(&"/page/size: violates maximum 100".to_string()));
+        assert!(messages.contains(&"/page: unexpected property `cursor`".to_string()));
+
+        assert!(matches!(
+            CompiledSchema::compile(&json!({ "$ref": "#/$defs/x" })),
+            Err(SchemaError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_gate_denies_nonconforming_args() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(
+            Capability::new("github.issues", "List issues").with_schema(schema()),
+        );
+        gate.add_policy(Policy::new("tools", "1").with_rule(Rule::allow("github.issues")));
+        let ctx = RequestContext::default();
+
+        let record = gate.authorize_record("github.issues", &json!({ "repo": "acme/api" }), &ctx);
+        assert_eq!(record.decision, Decision::Authorized);
+
+        let record = gate.authorize_record("github.issues", &json!({ "repo": "Acme API" }), &ctx);
+        assert_eq!(record.decision, Decision::DeniedInvalidArguments);
+        assert_eq!(
+            record.details[REASON_DETAIL],
+            Reason::ArgsSchemaViolation.code()
+        );
+        assert!(record.details["invalid_arguments"].starts_with("/repo: does not match"));
+    }
+
+    #[test]
+    fn test_gate_denies_non_json_args_against_a_schema() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(
+            Capability::new("github.issues", "List issues").with_schema(schema()),
+        );
+        gate.add_policy(Policy::new("tools", "1").with_rule(Rule::allow("github.issues")));
+        let ctx = RequestContext::default();
+
+        let args: BTreeMap<String, String> = [("repo".to_string(), "acme/api".to_string())].into();
+        let record = gate.authorize_record("github.issues", &args, &ctx);
+        assert_eq!(record.decision, Decision::DeniedInvalidArguments);
+        assert_eq!(
+            record.details[REASON_DETAIL],
+            Reason::ArgsSchemaViolation.code()
+        );
+        let record = gate.authorize_record("github.issues", &(), &ctx);
+        assert_eq!(record.decision, Decision::DeniedInvalidArguments);
+    }
+}
@@ -0,0 +1,181 @@
+//! Capability Scope Validation.
+//!
+//! A capability may declare constraints on its own parameters (for example a
+//! root path prefix). Policies can only narrow that scope: every `Allow` rule for
+//! the capability must itself constrain each declared parameter at least as
+//! tightly, through a parameter constraint or an equivalent condition.
+//! [`scope_violations`] reports rules that would grant broader access.
+//!
+//! A declared prefix starting with `/` is a directory: a rule narrows it only at
+//! a path separator (`/work/src` narrows `/work`, `/workspace-secrets` does not),
+//! and never with a `..` segment.
+
+use crate::capability::{Capability, CapabilityRegistry};
+use crate::condition::{arg_key, ParamConstraint, ParamRule};
+use crate::lint::Lint;
+use crate::policy::{Condition, Policy, Rule};
+use serde_json::Value;
+
+/// The condition as a parameter rule, if its operator has one.
+pub(crate) fn as_param_rule(condition: &Condition) -> Option<ParamRule> {
+    let value = condition.value.clone();
+    Some(match condition.operator.as_str() {
+        "eq" | "equals" => ParamRule::Equals(value),
+        "ne" | "not_equals" => ParamRule::NoneOf(vec![value]),
+        "in" => ParamRule::OneOf(value.as_array()?.clone()),
+        "not_in" => ParamRule::NoneOf(value.as_array()?.clone()),
+        "starts_with" | "prefix" => ParamRule::Prefix(value.as_str()?.to_string()),
+        _ => return None,
+    })
+}
+
+fn finite(rule: &ParamRule) -> Option<&[Value]> {
+    match rule {
+        ParamRule::Equals(value) => Some(std::slice::from_ref(value)),
+        ParamRule::OneOf(values) => Some(values),
+        _ => None,
+    }
+}
+
+fn has_prefix(value: &Value, prefix: &str) -> bool {
+    value.as_str().is_some_and(|s| s.starts_with(prefix))
+}
+
+/// Whether `value` stays within the declared `prefix`; see the module docs.
+fn within(value: &str, prefix: &str) -> bool {
+    if !prefix.starts_with('/') {
+        return value.starts_with(prefix);
+    }
+    let inside = match prefix.ends_with('/') {
+        true => value.starts_with(prefix),
+        false => value == prefix || value.starts_with(&format!("{}/", prefix)),
+    };
+    inside && !value.split('/').any(|segment| segment == "..")
+}
+
+/// Whether every value `narrow` admits is also admitted by `wide`.
+pub(crate) fn implies(narrow: &ParamRule, wide: &ParamRule) -> bool {
+    match (finite(narrow), wide) {
+        (Some(values), ParamRule::Prefix(prefix)) => values
+            .iter()
+            .all(|v| v.as_str().is_some_and(|s| within(s, prefix))),
+        (Some(values), ParamRule::OneOf(allowed)) => values.iter().all(|v| allowed.contains(v)),
+        (Some(values), ParamRule::Equals(only)) => values.iter().all(|v| v == only),
+        (Some(values), ParamRule::NoneOf(denied)) => values.iter().all(|v| !denied.contains(v)),
+        (None, ParamRule::Prefix(prefix)) => {
+            matches!(narrow, ParamRule::Prefix(p) if within(p, prefix))
+        }
+        (None, ParamRule::NoneOf(denied)) => match narrow {
+            ParamRule::NoneOf(excluded) => denied.iter().all(|d| excluded.contains(d)),
+            ParamRule::Prefix(p) => !denied.iter().any(|d| has_prefix(d, p)),
+            _ => false,
+        },
+        (None, _) => false,
+    }
+}
+
+fn narrows(rule: &Rule, declared: &ParamConstraint) -> bool {
+    let param = arg_key(&declared.param);
+    let from_constraints = rule
+        .param_constraints
+        .iter()
+        .filter(|c| arg_key(&c.param) == param)
+        .map(|c| c.rule.clone());
+    let from_conditions = rule
+        .conditions
+        .iter()
+        .filter(|c| arg_key(&c.key) == param)
+        .filter_map(as_param_rule);
+    from_constraints
+        .chain(from_conditions)
+        .any(|r| implies(&r, &declared.rule))
+}
+
+fn check_rule(policy: &Policy, index: usize, rule: &Rule, capability: &Capability) -> Vec<Lint> {
+    capability
+        .constraints
+        .iter()
+        .filter(|declared| !narrows(rule, declared))
+        .map(|declared| {
+            Lint::error(
+                "widens-capability",
+                &policy.name,
+                Some(index),
+                format!(
+                    "allows `{}` without restricting `{}` to {}",
+                    capability.name,
+                    declared.param,
+                    declared.describe()
+                ),
+            )
+        })
+        .collect()
+}
+
+/// `Allow` rules in `policy` that grant more than a capability declares.
+pub fn scope_violations(policy: &Policy, registry: &CapabilityRegistry) -> Vec<Lint> {
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        if !rule.effect.allows() {
+            continue;
+        }
+        for capability in registry.list() {
+            if rule.applies_in(&capability.name, capability.category) {
+                lints.extend(check_rule(policy, index, rule, capability));
+            }
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn registry() -> CapabilityRegistry {
+        let mut registry = CapabilityRegistry::new();
+        registry.register(
+            Capability::new("fs.read", "Read files")
+                .with_constraint(ParamConstraint::prefix("path", "/work")),
+        );
+        registry.register(Capability::new("shell", "Shell commands"));
+        registry
+    }
+
+    #[test]
+    fn test_rules_must_narrow_declared_scope() {
+        let policy = Policy::new("p", "1.0")
+            .with_rule(Rule::allow("fs.read").with_conditions(vec![Condition::new(
+                "args.path",
+                "starts_with",
+                json!("/work/src"),
+            )]))
+            .with_rule(
+                Rule::allow("fs.read").with_param_constraint(ParamConstraint::one_of(
+                    "path",
+                    ["/work/a", "/etc/passwd"],
+                )),
+            )
+            .with_rule(Rule::allow("*"))
+            .with_rule(Rule::deny("fs.read"))
+            .with_rule(
+                Rule::allow("fs.read")
+                    .with_param_constraint(ParamConstraint::prefix("path", "/workspace-secrets")),
+            )
+            .with_rule(
+                Rule::allow("fs.read").with_param_constraint(ParamConstraint::one_of(
+                    "path",
+                    ["/work/../etc/shadow"],
+                )),
+            );
+
+        let lints = scope_violations(&policy, &registry());
+        let rules: Vec<_> = lints.iter().map(|l| l.rule).collect();
+        assert_eq!(rules, vec![Some(1), Some(2), Some(4), Some(5)]);
+        assert_eq!(
+            lints[1].to_string(),
+            "error[widens-capability] p rule 2: allows `fs.read` without restricting `path` to a string starting with \"/work\""
+        );
+    }
+}
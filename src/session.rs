@@ -0,0 +1,227 @@
+//! Session-State Conditions.
+//!
+//! With [`CapabilityGate::with_session_state`], the gate remembers which
+//! capabilities each session has been allowed to run, keyed by the `session`
+//! attribute, and conditions can test that history through `session.` keys:
+//!
+//! - `session.executed`: the capabilities allowed so far, e.g.
+//!   `{"key": "session.executed", "operator": "contains", "value": "diff.preview"}`;
+//! - `session.count.<capability>`: how many times one was allowed, e.g.
+//!   `{"key": "session.count.web.get", "operator": "lt", "value": 5}`;
+//! - `session.calls`: allowed calls of any capability.
+//!
+//! Session keys are answered by the gate only, never by the arguments, so a
+//! caller cannot forge its history. Requests without a `session` attribute, or
+//! through a gate without session state, have no history; their session
+//! conditions never hold. Decisions that depend on session conditions are not
+//! cached. [`CapabilityGate::end_session`] forgets a session.
+
+use crate::args::{ArgValue, ArgView};
+use crate::context::RequestContext;
+use crate::flags::SESSION_ATTRIBUTE;
+use crate::gate::CapabilityGate;
+use crate::policy::{Condition, PolicyEngine};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+pub const SESSION_PREFIX: &str = "session.";
+
+pub fn is_session_condition(condition: &Condition) -> bool {
+    condition.key.starts_with(SESSION_PREFIX)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionState {
+    executed: BTreeMap<String, u64>,
+}
+
+impl SessionState {
+    pub fn count(&self, capability: &str) -> u64 {
+        self.executed.get(capability).copied().unwrap_or(0)
+    }
+
+    pub fn has_executed(&self, capability: &str) -> bool {
+        self.count(capability) > 0
+    }
+
+    pub fn calls(&self) -> u64 {
+        self.executed.values().sum()
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SessionHistory {
+    sessions: Mutex<HashMap<String, SessionState>>,
+}
+
+fn session_id(ctx: &RequestContext) -> Option<&str> {
+    ctx.attributes.get(SESSION_ATTRIBUTE).map(String::as_str)
+}
+
+impl SessionHistory {
+    pub(crate) fn state(&self, ctx: &RequestContext) -> Option<SessionState> {
+        let session = session_id(ctx)?;
+        let sessions = self.sessions.lock().unwrap();
+        Some(sessions.get(session).cloned().unwrap_or_default())
+    }
+
+    pub(crate) fn record(&self, ctx: &RequestContext, capability: &str) {
+        if let Some(session) = session_id(ctx) {
+            let mut sessions = self.sessions.lock().unwrap();
+            let state = sessions.entry(session.to_string()).or_default();
+            *state.executed.entry(capability.to_string()).or_default() += 1;
+        }
+    }
+
+    pub(crate) fn end(&self, session: &str) {
+        self.sessions.lock().unwrap().remove(session);
+    }
+}
+
+/// Arguments plus the session state that answers `session.` keys.
+pub struct SessionView<'a> {
+    pub args: &'a dyn ArgView,
+    pub state: &'a SessionState,
+}
+
+impl ArgView for SessionView<'_> {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        self.args.lookup(path)
+    }
+
+    fn session_lookup(&self, key: &str) -> Option<ArgValue<'_>> {
+        match key {
+            "executed" => Some(ArgValue::List(
+                self.state
+                    .executed
+                    .keys()
+                    .map(|c| ArgValue::Str(Cow::Borrowed(c)))
+                    .collect(),
+            )),
+            "calls" => Some(ArgValue::Int(self.state.calls() as i64)),
+            key => {
+                let capability = key.strip_prefix("count.")?;
+                Some(ArgValue::Int(self.state.count(capability) as i64))
+            }
+        }
+    }
+
+    fn cache_key(&self) -> Option<String> {
+        self.args.cache_key()
+    }
+}
+
+impl PolicyEngine {
+    /// Whether any rule that may apply to `resource` tests session state.
+    pub(crate) fn uses_session_state(&self, resource: &str) -> bool {
+        self.candidate_rules(resource)
+            .any(|rule| rule.conditions.iter().any(is_session_condition))
+    }
+}
+
+impl CapabilityGate {
+    /// The session history of the request `ctx` belongs to, if the gate keeps
+    /// session state and the request names a session.
+    pub fn session_state(&self, ctx: &RequestContext) -> Option<SessionState> {
+        self.sessions()?.state(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Policy, Rule};
+    use serde_json::json;
+
+    fn gate() -> CapabilityGate {
+        let mut gate = CapabilityGate::new().with_session_state();
+        for name in ["diff.preview", "fs.write", "web.get"] {
+            gate.register_capability(Capability::new(name, name));
+        }
+        gate.add_policy(
+            Policy::new("p", "1")
+                .with_rule(Rule::allow("diff.preview"))
+                .with_rule(Rule::allow("fs.write").with_conditions(vec![Condition::new(
+                    "session.executed",
+                    "contains",
+                    json!("diff.preview"),
+                )]))
+                .with_rule(Rule::allow("web.get").with_conditions(vec![Condition::new(
+                    "session.count.web.get",
+                    "lt",
+                    json!(2),
+                )])),
+        );
+        gate
+    }
+
+    #[test]
+    fn test_allow_after_a_prior_step() {
+        let gate = gate();
+        let ctx = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, "s1");
+        assert!(!gate
+            .authorize_with("fs.write", &json!({}), &ctx)
+            .is_allowed());
+        // The arguments cannot stand in for the session history.
+        let forged = json!({"session": {"executed": ["diff.preview"]}});
+        assert!(!gate.authorize_with("fs.write", &forged, &ctx).is_allowed());
+
+        assert!(gate
+            .authorize_with("diff.preview", &json!({}), &ctx)
+            .is_allowed());
+        assert!(gate
+            .authorize_with("fs.write", &json!({}), &ctx)
+            .is_allowed());
+        assert_eq!(gate.session_state(&ctx).unwrap().calls(), 2);
+
+        let other = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, "s2");
+        assert!(!gate
+            .authorize_with("fs.write", &json!({}), &other)
+            .is_allowed());
+        gate.end_session("s1");
+        assert!(!gate
+            .authorize_with("fs.write", &json!({}), &ctx)
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_session_counts_limit_calls() {
+        let gate = gate();
+        let ctx = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, "s1");
+        let allowed: Vec<bool> = (0..3)
+            .map(|_| {
+                gate.authorize_with("web.get", &json!({}), &ctx)
+                    .is_allowed()
+            })
+            .collect();
+        assert_eq!(allowed, vec![true, true, false]);
+        assert!(!gate
+            .authorize_with("web.get", &json!({}), &RequestContext::new())
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_planning_does_not_count_as_executing() {
+        use crate::plan::PlannedCall;
+
+        let gate = gate();
+        let ctx = RequestContext::new().with_attribute(SESSION_ATTRIBUTE, "s1");
+        let planned = [
+            PlannedCall::new("diff.preview", json!({})),
+            PlannedCall::new("web.get", json!({})),
+            PlannedCall::new("web.get", json!({})),
+        ];
+        for _ in 0..3 {
+            assert!(gate.authorize_plan(&planned, &ctx).is_allowed());
+        }
+        assert_eq!(gate.session_state(&ctx).map(|s| s.calls()).unwrap_or(0), 0);
+        assert!(!gate
+            .authorize_with("fs.write", &json!({}), &ctx)
+            .is_allowed());
+        assert!(gate
+            .authorize_with("web.get", &json!({}), &ctx)
+            .is_allowed());
+    }
+}
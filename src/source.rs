@@ -0,0 +1,111 @@
+//! Policy Sources.
+//!
+//! A [`PolicySource`] fetches the raw bytes of a policy bundle from wherever a
+//! deployment keeps it — a local file, object storage, a cluster API. Sources
+//! only transport bytes; [`PolicyEngine::load_from_source`] verifies and
+//! activates them like any other bundle.
+
+use crate::bundle::BundleError;
+use crate::digest::{ct_eq, from_hex, sha256, to_hex};
+use crate::policy::PolicyEngine;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SourceError {
+    #[error("{source_name}: fetch failed: {message}")]
+    Fetch {
+        source_name: String,
+        message: String,
+    },
+    #[error("invalid source location {0:?}")]
+    Location(String),
+    #[error("{source_name}: checksum mismatch (expected {expected}, got {actual})")]
+    Checksum {
+        source_name: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("policy bundle is not UTF-8")]
+    Encoding,
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+}
+
+pub trait PolicySource: Send + Sync {
+    /// Human-readable identity, e.g. a path or `s3://bucket/key`.
+    fn name(&self) -> String;
+    fn fetch(&self) -> Result<Vec<u8>, SourceError>;
+}
+
+pub struct FileSource {
+    path: PathBuf,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl PolicySource for FileSource {
+    fn name(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn fetch(&self) -> Result<Vec<u8>, SourceError> {
+        std::fs::read(&self.path).map_err(|e| SourceError::Fetch {
+            source_name: self.name(),
+            message: e.to_string(),
+        })
+    }
+}
+
+/// Checks `bytes` against a hex-encoded SHA-256 digest.
+pub fn verify_sha256(source_name: &str, bytes: &[u8], expected: &str) -> Result<(), SourceError> {
+    let actual = sha256(bytes);
+    let matches = from_hex(expected.trim()).is_some_and(|e| ct_eq(&e, &actual));
+    if matches {
+        Ok(())
+    } else {
+        Err(SourceError::Checksum {
+            source_name: source_name.to_string(),
+            expected: expected.trim().to_string(),
+            actual: to_hex(&actual),
+        })
+    }
+}
+
+impl PolicyEngine {
+    /// Fetches a bundle from `source` and activates it if its test vectors pass.
+    pub fn load_from_source(&mut self, source: &dyn PolicySource) -> Result<(), SourceError> {
+        let bytes = source.fetch()?;
+        let json = std::str::from_utf8(&bytes).map_err(|_| SourceError::Encoding)?;
+        Ok(self.load_bundle(json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::sha256_hex;
+
+    #[test]
+    fn test_checksum_verification() {
+        assert!(verify_sha256("b", b"abc", &sha256_hex(b"abc")).is_ok());
+        let err = verify_sha256("b", b"abd", &sha256_hex(b"abc")).unwrap_err();
+        assert!(matches!(err, SourceError::Checksum { .. }));
+        assert!(verify_sha256("b", b"abc", "not hex").is_err());
+    }
+
+    #[test]
+    fn test_missing_file_source() {
+        let mut engine = PolicyEngine::new();
+        let err = engine
+            .load_from_source(&FileSource::new("/nonexistent/bundle.json"))
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("/nonexistent/bundle.json: fetch failed"));
+    }
+}
@@ -0,0 +1,433 @@
+//! SQLite Persistence.
+//!
+//! Single-host deployments can keep audit records, session grants, quota
+//! counters and approval requests in one SQLite file instead of external
+//! infrastructure. The crate does not link SQLite itself: callers wrap their
+//! connection (rusqlite, sqlx) in a [`SqlConnection`], and [`SqliteStore`] owns
+//! the schema, its migrations and the retention policy.
+
+use crate::audit::{AuditEvent, AuditSink};
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Integer(i64),
+    Text(String),
+}
+
+impl SqlValue {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            SqlValue::Integer(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            SqlValue::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for SqlValue {
+    fn from(s: &str) -> Self {
+        SqlValue::Text(s.to_string())
+    }
+}
+
+impl From<i64> for SqlValue {
+    fn from(n: i64) -> Self {
+        SqlValue::Integer(n)
+    }
+}
+
+impl From<Option<&str>> for SqlValue {
+    fn from(s: Option<&str>) -> Self {
+        s.map(SqlValue::from).unwrap_or(SqlValue::Null)
+    }
+}
+
+pub trait SqlConnection: Send {
+    /// Runs a statement with `?` placeholders and returns the affected rows.
+    fn execute(&mut self, sql: &str, params: &[SqlValue]) -> Result<u64, String>;
+    fn query(&mut self, sql: &str, params: &[SqlValue]) -> Result<Vec<Vec<SqlValue>>, String>;
+}
+
+#[derive(Debug, Error)]
+pub enum SqliteError {
+    #[error("sqlite: {0}")]
+    Sql(String),
+    #[error("database schema version {found} is newer than supported {supported}")]
+    FutureSchema { found: i64, supported: i64 },
+}
+
+/// Schema migrations; entry `i` moves the database to `user_version = i + 1`.
+pub const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE audit (
+        id INTEGER PRIMARY KEY,
+        timestamp_ms INTEGER NOT NULL,
+        principal TEXT,
+        tool TEXT NOT NULL,
+        decision TEXT NOT NULL,
+        rule TEXT,
+        event TEXT NOT NULL
+    );
+    CREATE INDEX audit_timestamp ON audit (timestamp_ms);
+    CREATE TABLE grants (
+        session TEXT NOT NULL,
+        capability TEXT NOT NULL,
+        expires_at_ms INTEGER NOT NULL,
+        PRIMARY KEY (session, capability)
+    );",
+    "CREATE TABLE quotas (
+        principal TEXT NOT NULL,
+        capability TEXT NOT NULL,
+        window_start_ms INTEGER NOT NULL,
+        count INTEGER NOT NULL,
+        PRIMARY KEY (principal, capability, window_start_ms)
+    );
+    CREATE TABLE approvals (
+        id INTEGER PRIMARY KEY,
+        principal TEXT,
+        tool TEXT NOT NULL,
+        args TEXT NOT NULL,
+        requested_at_ms INTEGER NOT NULL,
+        status TEXT NOT NULL DEFAULT 'pending'
+    );",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ApprovalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApprovalStatus::Pending => "pending",
+            ApprovalStatus::Approved => "approved",
+            ApprovalStatus::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalRequest {
+    pub id: i64,
+    pub principal: Option<String>,
+    pub tool: String,
+    pub args: String,
+    pub requested_at_ms: i64,
+}
+
+/// How long rows are kept by [`SqliteStore::compact`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    pub audit: Duration,
+    pub quotas: Duration,
+    pub approvals: Duration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        let day = Duration::from_secs(24 * 60 * 60);
+        Self {
+            audit: day * 90,
+            quotas: day * 2,
+            approvals: day * 30,
+        }
+    }
+}
+
+pub struct SqliteStore {
+    conn: Mutex<Box<dyn SqlConnection>>,
+}
+
+fn sql(e: String) -> SqliteError {
+    SqliteError::Sql(e)
+}
+
+impl SqliteStore {
+    /// Opens the store, applying any migrations the database has not seen.
+    pub fn open(conn: Box<dyn SqlConnection>) -> Result<Self, SqliteError> {
+        let store = Self {
+            conn: Mutex::new(conn),
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    pub fn schema_version(&self) -> Result<i64, SqliteError> {
+        let rows = self.query("PRAGMA user_version", &[])?;
+        Ok(rows
+            .first()
+            .and_then(|r| r.first())
+            .and_then(SqlValue::as_i64)
+            .unwrap_or(0))
+    }
+
+    fn migrate(&self) -> Result<(), SqliteError> {
+        let supported = MIGRATIONS.len() as i64;
+        let found = self.schema_version()?;
+        if found > supported {
+            return Err(SqliteError::FutureSchema { found, supported });
+        }
+        for (i, migration) in MIGRATIONS.iter().enumerate().skip(found as usize) {
+            let script = format!(
+                "BEGIN; {} PRAGMA user_version = {}; COMMIT;",
+                migration,
+                i + 1
+            );
+            self.execute(&script, &[])?;
+        }
+        Ok(())
+    }
+
+    fn execute(&self, statement: &str, params: &[SqlValue]) -> Result<u64, SqliteError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .execute(statement, params)
+            .map_err(sql)
+    }
+
+    fn query(
+        &self,
+        statement: &str,
+        params: &[SqlValue],
+    ) -> Result<Vec<Vec<SqlValue>>, SqliteError> {
+        self.conn
+            .lock()
+            .unwrap()
+            .query(statement, params)
+            .map_err(sql)
+    }
+
+    pub fn append_audit(&self, event: &AuditEvent) -> Result<(), SqliteError> {
+        let json = serde_json::to_string(event).map_err(|e| sql(e.to_string()))?;
+        self.execute(
+            "INSERT INTO audit (timestamp_ms, principal, tool, decision, rule, event) \
+             VALUES (?, ?, ?, ?, ?, ?)",
+            &[
+                (event.timestamp_ms as i64).into(),
+                event.principal.as_deref().into(),
+                event.tool.as_str().into(),
+                event.decision.as_str().into(),
+                event.rule.as_deref().into(),
+                json.as_str().into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn grant(
+        &self,
+        session: &str,
+        capability: &str,
+        expires_at_ms: i64,
+    ) -> Result<(), SqliteError> {
+        self.execute(
+            "INSERT OR REPLACE INTO grants (session, capability, expires_at_ms) VALUES (?, ?, ?)",
+            &[session.into(), capability.into(), expires_at_ms.into()],
+        )?;
+        Ok(())
+    }
+
+    pub fn has_grant(
+        &self,
+        session: &str,
+        capability: &str,
+        now_ms: i64,
+    ) -> Result<bool, SqliteError> {
+        let rows = self.query(
+            "SELECT 1 FROM grants WHERE session = ? AND capability = ? AND expires_at_ms > ?",
+            &[session.into(), capability.into(), now_ms.into()],
+        )?;
+        Ok(!rows.is_empty())
+    }
+
+    pub fn revoke_session(&self, session: &str) -> Result<u64, SqliteError> {
+        self.execute("DELETE FROM grants WHERE session = ?", &[session.into()])
+    }
+
+    /// Increments the counter for `window_start_ms` and returns the new count.
+    pub fn increment_quota(
+        &self,
+        principal: &str,
+        capability: &str,
+        window_start_ms: i64,
+    ) -> Result<i64, SqliteError> {
+        let key = [principal.into(), capability.into(), window_start_ms.into()];
+        self.execute(
+            "INSERT INTO quotas (principal, capability, window_start_ms, count) VALUES (?, ?, ?, 1) \
+             ON CONFLICT (principal, capability, window_start_ms) DO UPDATE SET count = count + 1",
+            &key,
+        )?;
+        let rows = self.query(
+            "SELECT count FROM quotas WHERE principal = ? AND capability = ? AND window_start_ms = ?",
+            &key,
+        )?;
+        Ok(rows
+            .first()
+            .and_then(|r| r.first())
+            .and_then(SqlValue::as_i64)
+            .unwrap_or(0))
+    }
+
+    pub fn request_approval(
+        &self,
+        principal: Option<&str>,
+        tool: &str,
+        args: &serde_json::Value,
+        now_ms: i64,
+    ) -> Result<(), SqliteError> {
+        self.execute(
+            "INSERT INTO approvals (principal, tool, args, requested_at_ms) VALUES (?, ?, ?, ?)",
+            &[
+                principal.into(),
+                tool.into(),
+                args.to_string().as_str().into(),
+                now_ms.into(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn pending_approvals(&self) -> Result<Vec<ApprovalRequest>, SqliteError> {
+        let rows = self.query(
+            "SELECT id, principal, tool, args, requested_at_ms FROM approvals \
+             WHERE status = 'pending' ORDER BY id",
+            &[],
+        )?;
+        Ok(rows
+            .into_iter()
+            .filter_map(|row| match row.as_slice() {
+                [id, principal, tool, args, at] => Some(ApprovalRequest {
+                    id: id.as_i64()?,
+                    principal: principal.as_str().map(String::from),
+                    tool: tool.as_str()?.to_string(),
+                    args: args.as_str()?.to_string(),
+                    requested_at_ms: at.as_i64()?,
+                }),
+                _ => None,
+            })
+            .collect())
+    }
+
+    /// Resolves a pending request; returns `false` if it was not pending.
+    pub fn resolve_approval(&self, id: i64, status: ApprovalStatus) -> Result<bool, SqliteError> {
+        let changed = self.execute(
+            "UPDATE approvals SET status = ? WHERE id = ? AND status = 'pending'",
+            &[status.as_str().into(), id.into()],
+        )?;
+        Ok(changed > 0)
+    }
+
+    /// Deletes rows older than `retention` and expired grants, then vacuums.
+    /// Returns the number of deleted rows.
+    pub fn compact(&self, retention: &Retention, now_ms: i64) -> Result<u64, SqliteError> {
+        let cutoff = |d: Duration| SqlValue::Integer(now_ms - d.as_millis() as i64);
+        let mut deleted = 0;
+        deleted += self.execute(
+            "DELETE FROM audit WHERE timestamp_ms < ?",
+            &[cutoff(retention.audit)],
+        )?;
+        deleted += self.execute(
+            "DELETE FROM quotas WHERE window_start_ms < ?",
+            &[cutoff(retention.quotas)],
+        )?;
+        deleted += self.execute(
+            "DELETE FROM approvals WHERE status != 'pending' AND requested_at_ms < ?",
+            &[cutoff(retention.approvals)],
+        )?;
+        deleted += self.execute(
+            "DELETE FROM grants WHERE expires_at_ms <= ?",
+            &[now_ms.into()],
+        )?;
+        self.execute("VACUUM", &[])?;
+        Ok(deleted)
+    }
+}
+
+impl AuditSink for SqliteStore {
+    fn record(&self, event: &AuditEvent) {
+        let _ = self.append_audit(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// Records statements and answers `PRAGMA user_version` from its state.
+    #[derive(Default)]
+    struct Recorder {
+        version: i64,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl SqlConnection for Recorder {
+        fn execute(&mut self, sql: &str, _params: &[SqlValue]) -> Result<u64, String> {
+            if let Some(v) = sql
+                .rsplit("user_version = ")
+                .next()
+                .filter(|_| sql.starts_with("BEGIN"))
+            {
+                self.version = v.trim_end_matches("; COMMIT;").parse().unwrap();
+            }
+            self.log.lock().unwrap().push(sql.to_string());
+            Ok(1)
+        }
+
+        fn query(&mut self, sql: &str, _params: &[SqlValue]) -> Result<Vec<Vec<SqlValue>>, String> {
+            if sql == "PRAGMA user_version" {
+                return Ok(vec![vec![SqlValue::Integer(self.version)]]);
+            }
+            Ok(vec![vec![SqlValue::Integer(3)]])
+        }
+    }
+
+    #[test]
+    fn test_migrations_apply_once_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let store = SqliteStore::open(Box::new(Recorder {
+            version: 1,
+            log: log.clone(),
+        }))
+        .unwrap();
+        assert_eq!(store.schema_version().unwrap(), 2);
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 1);
+        assert!(log[0].contains("CREATE TABLE quotas"));
+
+        let err = SqliteStore::open(Box::new(Recorder {
+            version: 9,
+            log: Arc::default(),
+        }));
+        assert!(matches!(
+            err,
+            Err(SqliteError::FutureSchema { found: 9, .. })
+        ));
+    }
+
+    #[test]
+    fn test_quota_and_compaction() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let store = SqliteStore::open(Box::new(Recorder {
+            version: 0,
+            log: log.clone(),
+        }))
+        .unwrap();
+        assert_eq!(store.increment_quota("alice", "shell", 0).unwrap(), 3);
+        assert_eq!(store.compact(&Retention::default(), 1_000).unwrap(), 4);
+        assert_eq!(log.lock().unwrap().last().unwrap(), "VACUUM");
+    }
+}
@@ -0,0 +1,187 @@
+//! Distributed Policy Store.
+//!
+//! Multi-node fleets keep the active bundle in a shared key-value store. Every
+//! write carries a revision and is fenced with compare-and-swap, so two
+//! publishers cannot silently overwrite each other, and [`StoreSync`] only ever
+//! moves a gate forward: a revision at or below the one already applied is
+//! ignored, so all nodes converge on the newest revision.
+
+use crate::bundle::BundleError;
+use crate::policy::PolicyEngine;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredBundle {
+    pub revision: u64,
+    pub bundle: String,
+}
+
+#[derive(Debug, Error)]
+pub enum StoreError {
+    #[error("store unavailable: {0}")]
+    Unavailable(String),
+    #[error("revision conflict: expected {expected:?}, store is at {actual:?}")]
+    Conflict {
+        expected: Option<u64>,
+        actual: Option<u64>,
+    },
+    #[error(transparent)]
+    Bundle(#[from] BundleError),
+}
+
+pub trait PolicyStore: Send + Sync {
+    fn get(&self) -> Result<Option<StoredBundle>, StoreError>;
+
+    /// Writes `bundle` if the store is still at `expected` (`None` meaning
+    /// empty) and returns the new revision.
+    fn put(&self, bundle: &str, expected: Option<u64>) -> Result<u64, StoreError>;
+
+    /// Blocks until the store holds a revision newer than `after`, or `timeout`
+    /// elapses.
+    fn watch(&self, after: u64, timeout: Duration) -> Result<Option<StoredBundle>, StoreError>;
+}
+
+/// In-process store, for tests and single-host deployments.
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    current: Mutex<Option<StoredBundle>>,
+    changed: Condvar,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl PolicyStore for MemoryStore {
+    fn get(&self) -> Result<Option<StoredBundle>, StoreError> {
+        Ok(self.current.lock().unwrap().clone())
+    }
+
+    fn put(&self, bundle: &str, expected: Option<u64>) -> Result<u64, StoreError> {
+        let mut current = self.current.lock().unwrap();
+        let actual = current.as_ref().map(|b| b.revision);
+        if actual != expected {
+            return Err(StoreError::Conflict { expected, actual });
+        }
+        let revision = actual.unwrap_or(0) + 1;
+        *current = Some(StoredBundle {
+            revision,
+            bundle: bundle.to_string(),
+        });
+        self.changed.notify_all();
+        Ok(revision)
+    }
+
+    fn watch(&self, after: u64, timeout: Duration) -> Result<Option<StoredBundle>, StoreError> {
+        let current = self.current.lock().unwrap();
+        let (current, _) = self
+            .changed
+            .wait_timeout_while(current, timeout, |c| {
+                !c.as_ref().is_some_and(|b| b.revision > after)
+            })
+            .unwrap();
+        Ok(current.clone().filter(|b| b.revision > after))
+    }
+}
+
+/// Keeps an engine on the newest revision in a [`PolicyStore`].
+#[derive(Debug, Clone, Default)]
+pub struct StoreSync {
+    applied: Option<u64>,
+}
+
+impl StoreSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn applied(&self) -> Option<u64> {
+        self.applied
+    }
+
+    /// Activates `stored` unless a revision at least as new is already applied.
+    pub fn apply(
+        &mut self,
+        engine: &mut PolicyEngine,
+        stored: &StoredBundle,
+    ) -> Result<bool, StoreError> {
+        if self.applied.is_some_and(|a| stored.revision <= a) {
+            return Ok(false);
+        }
+        engine.load_bundle(&stored.bundle)?;
+        self.applied = Some(stored.revision);
+        Ok(true)
+    }
+
+    /// Waits up to `timeout` for a newer revision and activates it.
+    pub fn poll(
+        &mut self,
+        store: &dyn PolicyStore,
+        engine: &mut PolicyEngine,
+        timeout: Duration,
+    ) -> Result<bool, StoreError> {
+        match store.watch(self.applied.unwrap_or(0), timeout)? {
+            Some(stored) => self.apply(engine, &stored),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::Effect;
+    use std::sync::Arc;
+
+    fn bundle(effect: &str) -> String {
+        format!(
+            r#"{{"name":"fleet","version":"1","policies":[{{"name":"fleet","version":"1","rules":[{{"effect":"{}","principal":"*","resource":"shell","action":"execute","conditions":[]}}]}}]}}"#,
+            effect
+        )
+    }
+
+    #[test]
+    fn test_puts_are_fenced() {
+        let store = MemoryStore::new();
+        assert_eq!(store.put(&bundle("Allow"), None).unwrap(), 1);
+        assert!(matches!(
+            store.put(&bundle("Deny"), None),
+            Err(StoreError::Conflict {
+                actual: Some(1),
+                ..
+            })
+        ));
+        assert_eq!(store.put(&bundle("Deny"), Some(1)).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_sync_follows_watch_and_never_goes_back() {
+        let store = Arc::new(MemoryStore::new());
+        let publisher = store.clone();
+        let handle = std::thread::spawn(move || publisher.put(&bundle("Allow"), None).unwrap());
+
+        let mut engine = PolicyEngine::new();
+        let mut sync = StoreSync::new();
+        assert!(sync
+            .poll(store.as_ref(), &mut engine, Duration::from_secs(5))
+            .unwrap());
+        handle.join().unwrap();
+        assert_eq!(
+            engine.evaluate("shell", "execute", &serde_json::json!({})),
+            Effect::Allow
+        );
+
+        let stale = StoredBundle {
+            revision: 1,
+            bundle: bundle("Deny"),
+        };
+        assert!(!sync.apply(&mut engine, &stale).unwrap());
+        assert!(!sync
+            .poll(store.as_ref(), &mut engine, Duration::from_millis(10))
+            .unwrap());
+    }
+}
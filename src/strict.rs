@@ -0,0 +1,256 @@
+//! Strict Policy Loading.
+//!
+//! Serde ignores fields it does not know, so a typo such as `"efect": "Deny"`
+//! either fails with an error about a missing field or, for an optional field
+//! like `ttl_secs`, silently falls back to the default. [`LoadMode::Strict`]
+//! rejects unknown fields in policies, rules, conditions and parameter
+//! constraints, and reports every error with the path to the offending value,
+//! e.g. `[0].rules[2].efect`. Unknown operators, such as `"start_with"`, are
+//! reported at their path too, e.g. `[0].rules[2].conditions[0].operator`.
+//!
+//! [`LoadMode::Lenient`] is what [`PolicyEngine::load_from_json`] does.
+
+use crate::condition::operators_for;
+use crate::policy::{Policy, PolicyEngine, Rule};
+use crate::template::expand_rule;
+use serde::Deserialize;
+use serde_json::Value;
+use thiserror::Error;
+
+pub const POLICY_FIELDS: &[&str] = &["name", "version", "extends", "rules", "allow_unicode"];
+pub const RULE_FIELDS: &[&str] = &[
+    "effect",
+    "principal",
+    "resource",
+    "resources_matching",
+    "action",
+    "conditions",
+    "param_constraints",
+    "audit",
+    "ttl_secs",
+    "enforce_after_ms",
+    "require_user_consent",
+    "cache_key",
+];
+pub const CONDITION_FIELDS: &[&str] = &["key", "operator", "value"];
+pub const CONSTRAINT_FIELDS: &[&str] = &["param", "one_of", "none_of", "equals", "prefix"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadMode {
+    #[default]
+    Lenient,
+    Strict,
+}
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("{path}: unknown field `{field}`")]
+    UnknownField { path: String, field: String },
+    #[error("{path}: {message}")]
+    Invalid { path: String, message: String },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+fn check_object(value: &Value, known: &[&str], path: &str) -> Result<(), LoadError> {
+    let Some(object) = value.as_object() else {
+        return Ok(());
+    };
+    match object.keys().find(|k| !known.contains(&k.as_str())) {
+        Some(field) => Err(LoadError::UnknownField {
+            path: format!("{}.{}", path, field),
+            field: field.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn each<'a>(value: &'a Value, key: &str) -> impl Iterator<Item = (usize, &'a Value)> {
+    value
+        .get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+}
+
+/// Checks one policy object for unknown fields; `path` prefixes error paths.
+pub fn check_policy(policy: &Value, path: &str) -> Result<(), LoadError> {
+    check_object(policy, POLICY_FIELDS, path)?;
+    for (i, rule) in each(policy, "rules") {
+        let path = format!("{}.rules[{}]", path, i);
+        check_object(rule, RULE_FIELDS, &path)?;
+        for (j, condition) in each(rule, "conditions") {
+            let path = format!("{}.conditions[{}]", path, j);
+            check_object(condition, CONDITION_FIELDS, &path)?;
+            check_operator(condition, &path)?;
+        }
+        for (j, constraint) in each(rule, "param_constraints") {
+            let path = format!("{}.param_constraints[{}]", path, j);
+            check_object(constraint, CONSTRAINT_FIELDS, &path)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_operator(condition: &Value, path: &str) -> Result<(), LoadError> {
+    let key = condition
+        .get("key")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let Some(operator) = condition.get("operator").and_then(Value::as_str) else {
+        return Ok(());
+    };
+    let known = operators_for(key);
+    match known.contains(&operator) {
+        true => Ok(()),
+        false => Err(invalid(
+            format!("{}.operator", path),
+            format!(
+                "unknown operator `{}`, expected one of {}",
+                operator,
+                known.join(", ")
+            ),
+        )),
+    }
+}
+
+fn invalid(path: String, error: impl std::fmt::Display) -> LoadError {
+    LoadError::Invalid {
+        path,
+        message: error.to_string(),
+    }
+}
+
+/// The innermost rule that fails to deserialize, else the policy itself.
+fn locate(policy: &Value, path: String, error: serde_json::Error) -> LoadError {
+    for (i, rule) in each(policy, "rules") {
+        let path = format!("{}.rules[{}]", path, i);
+        let expanded = match expand_rule(rule.clone()) {
+            Ok(expanded) => expanded,
+            Err(e) => return invalid(path, e),
+        };
+        for concrete in expanded {
+            if let Err(e) = Rule::deserialize(concrete) {
+                return invalid(path, e);
+            }
+        }
+    }
+    invalid(path, error)
+}
+
+/// Parses a JSON array of policies according to `mode`.
+pub fn parse_policies(json: &str, mode: LoadMode) -> Result<Vec<Policy>, LoadError> {
+    if mode == LoadMode::Lenient {
+        return Ok(serde_json::from_str(json)?);
+    }
+    let values: Vec<Value> = serde_json::from_str(json)?;
+    let mut policies = Vec::with_capacity(values.len());
+    for (i, value) in values.into_iter().enumerate() {
+        let path = format!("[{}]", i);
+        check_policy(&value, &path)?;
+        match Policy::deserialize(&value) {
+            Ok(policy) => policies.push(policy),
+            Err(e) => return Err(locate(&value, path, e)),
+        }
+    }
+    Ok(policies)
+}
+
+impl PolicyEngine {
+    /// Like [`PolicyEngine::load_from_json`], in the given [`LoadMode`]. Nothing
+    /// is loaded if any policy is rejected.
+    pub fn load_from_json_with(&mut self, json: &str, mode: LoadMode) -> Result<(), LoadError> {
+        for policy in parse_policies(json, mode)? {
+            self.add_policy(policy);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::condition::{ParamConstraint, ParamRule};
+    use crate::policy::Condition;
+    use serde_json::json;
+
+    #[test]
+    fn test_strict_mode_rejects_typos_with_a_path() {
+        let json = r#"[{"name": "p", "version": "1", "rules": [
+            {"effect": "Allow", "principal": "*", "resource": "fs.read", "action": "execute",
+             "conditions": []},
+            {"effect": "Allow", "principal": "*", "resource": "fs.write", "action": "execute",
+             "conditions": [], "ttl_sec": 60}
+        ]}]"#;
+
+        assert!(parse_policies(json, LoadMode::Lenient).is_ok());
+        let err = parse_policies(json, LoadMode::Strict).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[0].rules[1].ttl_sec: unknown field `ttl_sec`"
+        );
+
+        let mut engine = PolicyEngine::new();
+        assert!(engine.load_from_json_with(json, LoadMode::Strict).is_err());
+        assert_eq!(engine.policies().count(), 0);
+    }
+
+    #[test]
+    fn test_strict_mode_locates_invalid_rules() {
+        let json = r#"[{"name": "p", "version": "1", "rules": [
+            {"effect": "Allow", "principal": "*", "resource": "fs.read", "action": "execute",
+             "conditions": []},
+            {"effect": "Perhaps", "principal": "*", "resource": "fs.write", "action": "execute",
+             "conditions": []}
+        ]}]"#;
+        match parse_policies(json, LoadMode::Strict).unwrap_err() {
+            LoadError::Invalid { path, message } => {
+                assert_eq!(path, "[0].rules[1]");
+                assert!(message.contains("Perhaps"), "{}", message);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_unknown_operators_with_a_path() {
+        let json = r#"[{"name": "p", "version": "1", "rules": [
+            {"effect": "Deny", "principal": "*", "resource": "shell", "action": "execute",
+             "conditions": [{"key": "command", "operator": "start_with", "value": "rm"}]}
+        ]}]"#;
+        match parse_policies(json, LoadMode::Strict).unwrap_err() {
+            LoadError::Invalid { path, message } => {
+                assert_eq!(path, "[0].rules[0].conditions[0].operator");
+                assert!(message.starts_with("unknown operator `start_with`"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_policies(json, LoadMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn test_field_lists_cover_serialized_fields() {
+        let mut rule = Rule::allow("fs.read").with_conditions(vec![Condition::new(
+            "path",
+            "eq",
+            json!("/tmp"),
+        )]);
+        rule.param_constraints.push(ParamConstraint {
+            param: "mode".into(),
+            rule: ParamRule::Prefix("r".into()),
+        });
+        rule.ttl_secs = Some(1);
+        rule.enforce_after_ms = Some(1);
+        rule.audit = crate::audit::AuditMode::Never;
+        rule.require_user_consent = true;
+        rule.cache_key = vec!["path".into()];
+        let policy = Policy::new("p", "1")
+            .extending("base")
+            .allowing_unicode()
+            .with_rule(rule);
+        let value = serde_json::to_value(&policy).unwrap();
+        check_policy(&value, "").unwrap();
+        assert_eq!(value.as_object().unwrap().len(), POLICY_FIELDS.len());
+    }
+}
@@ -0,0 +1,276 @@
+//! Rule Subsumption.
+//!
+//! Decides whether everything one rule matches is also matched by another,
+//! comparing principals, resources (including `*` and `category:` selectors)
+//! and, key by key, conditions and parameter constraints: value sets, path
+//! prefixes and numeric bounds. The check is sound but incomplete — `false`
+//! means "not proven", never "disjoint".
+//!
+//! [`subsumption_lints`] turns this into findings such as "rule 7 is
+//! redundant because rule 3 covers it".
+
+use crate::capability::{CapabilityCategory, CapabilityRegistry, CATEGORY_PREFIX};
+use crate::clock::is_time_condition;
+use crate::condition::{arg_key, ParamConstraint};
+use crate::lint::Lint;
+use crate::policy::{self, Condition, Policy, Rule};
+use crate::scope::{as_param_rule, implies};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// A restriction on one argument key.
+enum Predicate<'a> {
+    Condition(&'a Condition),
+    Constraint(&'a ParamConstraint),
+}
+
+impl Predicate<'_> {
+    fn key(&self) -> &str {
+        match self {
+            Predicate::Condition(c) => arg_key(&c.key),
+            Predicate::Constraint(c) => arg_key(&c.param),
+        }
+    }
+}
+
+fn predicates(rule: &Rule) -> impl Iterator<Item = Predicate<'_>> {
+    let conditions = rule.conditions.iter().map(Predicate::Condition);
+    let constraints = rule.param_constraints.iter().map(Predicate::Constraint);
+    conditions.chain(constraints)
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    a.as_f64()?.partial_cmp(&b.as_f64()?)
+}
+
+/// Values a predicate admits, when it admits only finitely many.
+fn finite(p: &Predicate) -> Option<Vec<Value>> {
+    match p {
+        Predicate::Condition(c) => match c.operator.as_str() {
+            "eq" | "equals" => Some(vec![c.value.clone()]),
+            "in" => c.value.as_array().cloned(),
+            _ => None,
+        },
+        Predicate::Constraint(c) => match &c.rule {
+            crate::condition::ParamRule::Equals(v) => Some(vec![v.clone()]),
+            crate::condition::ParamRule::OneOf(v) => Some(v.clone()),
+            _ => None,
+        },
+    }
+}
+
+/// Whether every value `narrow` admits is admitted by `wide`; both restrict
+/// the same key.
+fn predicate_implies(narrow: &Predicate, wide: &Predicate) -> bool {
+    if let (Predicate::Condition(n), Predicate::Condition(w)) = (narrow, wide) {
+        if n.operator == w.operator && n.value == w.value {
+            return true;
+        }
+        if is_time_condition(n) || is_time_condition(w) {
+            return false;
+        }
+    }
+
+    let narrow_rule = match narrow {
+        Predicate::Condition(n) => as_param_rule(n),
+        Predicate::Constraint(n) => Some(n.rule.clone()),
+    };
+    let w = match wide {
+        Predicate::Condition(w) => w,
+        Predicate::Constraint(w) => return narrow_rule.is_some_and(|n| implies(&n, &w.rule)),
+    };
+
+    let bound = |op: &str, v: &Value, x: &Value| -> bool {
+        let Some(ord) = compare(x, v) else {
+            return false;
+        };
+        match op {
+            "gt" => ord == Ordering::Greater,
+            "gte" => ord != Ordering::Less,
+            "lt" => ord == Ordering::Less,
+            "lte" => ord != Ordering::Greater,
+            _ => false,
+        }
+    };
+    match w.operator.as_str() {
+        // Every other predicate fails on a missing key.
+        "exists" if w.value.as_bool().unwrap_or(true) => !matches!(
+            narrow,
+            Predicate::Condition(n) if n.operator == "exists" && n.value == Value::Bool(false)
+        ),
+        "gt" | "gte" | "lt" | "lte" => {
+            if let Some(values) = finite(narrow) {
+                return values.iter().all(|x| bound(&w.operator, &w.value, x));
+            }
+            let Predicate::Condition(n) = narrow else {
+                return false;
+            };
+            let Some(ord) = compare(&n.value, &w.value) else {
+                return false;
+            };
+            match (n.operator.as_str(), w.operator.as_str()) {
+                ("gt", "gt") | ("gt", "gte") | ("gte", "gte") => ord != Ordering::Less,
+                ("gte", "gt") => ord == Ordering::Greater,
+                ("lt", "lt") | ("lt", "lte") | ("lte", "lte") => ord != Ordering::Greater,
+                ("lte", "lt") => ord == Ordering::Less,
+                _ => false,
+            }
+        }
+        "ends_with" | "suffix" | "contains" => match (finite(narrow), w.value.as_str()) {
+            (Some(values), Some(s)) => values.iter().all(|v| {
+                v.as_str().is_some_and(|v| {
+                    if w.operator == "contains" {
+                        v.contains(s)
+                    } else {
+                        v.ends_with(s)
+                    }
+                })
+            }),
+            _ => false,
+        },
+        _ => match (narrow_rule, as_param_rule(w)) {
+            (Some(n), Some(w)) => implies(&n, &w),
+            _ => false,
+        },
+    }
+}
+
+fn selector(resource: &str) -> Option<&str> {
+    resource.strip_prefix(CATEGORY_PREFIX)
+}
+
+fn resource_covers(
+    wide: &str,
+    narrow: &str,
+    category_of: &dyn Fn(&str) -> CapabilityCategory,
+) -> bool {
+    if policy::resource_covers(wide, narrow) {
+        return true;
+    }
+    match (selector(wide), selector(narrow)) {
+        (Some(w), None) if narrow != "*" => category_of(narrow).as_str() == w,
+        _ => false,
+    }
+}
+
+/// Whether every request `narrow` matches is also matched by `wide`.
+pub fn subsumes(
+    wide: &Rule,
+    narrow: &Rule,
+    category_of: &dyn Fn(&str) -> CapabilityCategory,
+) -> bool {
+    let principal = wide.principal == "*" || wide.principal == narrow.principal;
+    // A rule in its grace period does not decide yet.
+    principal
+        && wide.enforce_after_ms.is_none()
+        && resource_covers(&wide.resource, &narrow.resource, category_of)
+        && predicates(wide).all(|w| {
+            predicates(narrow)
+                .filter(|n| n.key() == w.key())
+                .any(|n| predicate_implies(&n, &w))
+        })
+}
+
+/// Rules that can never decide a request because an earlier rule in the same
+/// policy matches everything they match.
+pub fn subsumption_lints(policy: &Policy, registry: &CapabilityRegistry) -> Vec<Lint> {
+    let category_of = |name: &str| {
+        registry
+            .get(name)
+            .map(|c| c.category)
+            .unwrap_or_else(|| CapabilityCategory::infer(name))
+    };
+    let mut lints = Vec::new();
+    for (later, rule) in policy.rules.iter().enumerate() {
+        let covered_by = policy.rules[..later]
+            .iter()
+            .position(|earlier| subsumes(earlier, rule, &category_of));
+        let Some(earlier) = covered_by else {
+            continue;
+        };
+        let (code, message) = if policy.rules[earlier].effect == rule.effect {
+            (
+                "redundant-rule",
+                format!(
+                    "rule {} is redundant because rule {} covers it",
+                    later, earlier
+                ),
+            )
+        } else {
+            (
+                "shadowed-rule",
+                format!(
+                    "rule {} never applies: rule {} matches first with the opposite effect",
+                    later, earlier
+                ),
+            )
+        };
+        lints.push(Lint::warning(code, &policy.name, Some(later), message));
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn infer(name: &str) -> CapabilityCategory {
+        CapabilityCategory::infer(name)
+    }
+
+    fn rule(resource: &str, conditions: Vec<(&str, &str, Value)>) -> Rule {
+        Rule::allow(resource).with_conditions(
+            conditions
+                .into_iter()
+                .map(|(k, o, v)| Condition::new(k, o, v))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_numeric_and_path_subsumption() {
+        let wide = rule(
+            "fs.write",
+            vec![
+                ("path", "starts_with", json!("/work")),
+                ("size", "lt", json!(1000)),
+            ],
+        );
+        let narrow = rule(
+            "fs.write",
+            vec![
+                ("path", "in", json!(["/work/a", "/work/b"])),
+                ("size", "lte", json!(10)),
+                ("mode", "eq", json!("append")),
+            ],
+        );
+        assert!(subsumes(&wide, &narrow, &infer));
+        assert!(!subsumes(&narrow, &wide, &infer));
+
+        let category = rule("category:Filesystem", vec![]);
+        assert!(subsumes(&category, &wide, &infer));
+        assert!(!subsumes(&rule("shell", vec![]), &wide, &infer));
+    }
+
+    #[test]
+    fn test_redundant_rule_lint() {
+        let policy = Policy::new("p", "1.0")
+            .with_rule(rule(
+                "git",
+                vec![("subcommand", "in", json!(["status", "log"]))],
+            ))
+            .with_rule(rule("shell", vec![]))
+            .with_rule(rule("git", vec![("subcommand", "eq", json!("status"))]))
+            .with_rule(Rule::deny("shell"));
+        let lints = subsumption_lints(&policy, &CapabilityRegistry::new());
+        let messages: Vec<&str> = lints.iter().map(|l| l.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "rule 2 is redundant because rule 0 covers it",
+                "rule 3 never applies: rule 1 matches first with the opposite effect",
+            ]
+        );
+    }
+}
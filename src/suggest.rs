@@ -0,0 +1,251 @@
+//! Minimal-change Suggestions.
+//!
+//! For a denied request, [`PolicyEngine::suggest_allow`] works out the smallest
+//! policy edits that would allow it, e.g. "add `/tmp/foo` to `path` in rule
+//! `workspace#0`". Suggestions are structured for admin tooling and ranked by
+//! the number of edits; they are never applied by the engine.
+
+use crate::args::ArgView;
+use crate::condition::{arg_key, ParamRule};
+use crate::context::RequestContext;
+use crate::policy::{PolicyEngine, Rule};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind", content = "index")]
+pub enum Target {
+    Condition(usize),
+    Constraint(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "edit")]
+pub enum Edit {
+    /// Also accept `value` for `key`.
+    AllowValue {
+        target: Target,
+        key: String,
+        value: Value,
+    },
+    /// Drop a condition or constraint that cannot be widened to fit.
+    Remove {
+        target: Target,
+    },
+    SetPrincipal {
+        principal: String,
+    },
+    /// Add a new rule to the policy.
+    AddRule {
+        rule: Box<Rule>,
+    },
+    /// Remove (or narrow) the deny rule that decided the request.
+    RemoveRule,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub policy: String,
+    /// Index of the rule being edited; `None` for [`Edit::AddRule`].
+    pub rule: Option<usize>,
+    pub edits: Vec<Edit>,
+    pub summary: String,
+}
+
+fn describe(edit: &Edit, rule_id: &str) -> String {
+    match edit {
+        Edit::AllowValue { key, value, .. } => {
+            format!("add {} to `{}` in rule {}", value, key, rule_id)
+        }
+        Edit::Remove { target } => match target {
+            Target::Condition(i) => format!("remove condition {} from rule {}", i, rule_id),
+            Target::Constraint(i) => format!("remove constraint {} from rule {}", i, rule_id),
+        },
+        Edit::SetPrincipal { principal } => {
+            format!("grant rule {} to principal `{}`", rule_id, principal)
+        }
+        Edit::AddRule { rule } => format!("add `allow {}` to policy {}", rule.resource, rule_id),
+        Edit::RemoveRule => format!("remove or narrow deny rule {}", rule_id),
+    }
+}
+
+/// Whether accepting an extra value is enough for an operator to match.
+fn widenable(operator: &str) -> bool {
+    matches!(
+        operator,
+        "eq" | "equals" | "in" | "starts_with" | "prefix" | "ends_with" | "suffix"
+    )
+}
+
+fn edits_for(rule: &Rule, ctx: &RequestContext, args: &dyn ArgView) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    if rule.principal != "*" && ctx.principal.as_deref() != Some(rule.principal.as_str()) {
+        if let Some(principal) = &ctx.principal {
+            edits.push(Edit::SetPrincipal {
+                principal: principal.clone(),
+            });
+        }
+    }
+    let actual = |key: &str| {
+        args.lookup(arg_key(key))
+            .map(|v| v.to_value().into_owned())
+            .filter(|v| !v.is_array() && !v.is_object())
+    };
+    for (i, condition) in rule.conditions.iter().enumerate() {
+        if crate::clock::is_time_condition(condition) || condition.evaluate(args) {
+            continue;
+        }
+        let target = Target::Condition(i);
+        edits.push(match actual(&condition.key) {
+            Some(value) if widenable(&condition.operator) => Edit::AllowValue {
+                target,
+                key: arg_key(&condition.key).to_string(),
+                value,
+            },
+            _ => Edit::Remove { target },
+        });
+    }
+    for (i, constraint) in rule.param_constraints.iter().enumerate() {
+        if constraint.check(args).is_ok() {
+            continue;
+        }
+        let target = Target::Constraint(i);
+        let widen = !matches!(constraint.rule, ParamRule::NoneOf(_));
+        edits.push(match actual(&constraint.param) {
+            Some(value) if widen => Edit::AllowValue {
+                target,
+                key: arg_key(&constraint.param).to_string(),
+                value,
+            },
+            _ => Edit::Remove { target },
+        });
+    }
+    edits
+}
+
+impl PolicyEngine {
+    /// Minimal edits that would allow a request, fewest edits first. Empty if
+    /// the request is already allowed.
+    pub fn suggest_allow(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+    ) -> Vec<Suggestion> {
+        let decided = self.find_rule(ctx, resource, action, args);
+        match decided {
+            Some(m) if m.rule.effect.allows() => return Vec::new(),
+            Some(m) => {
+                return vec![Suggestion {
+                    policy: m.policy.to_string(),
+                    rule: Some(m.index),
+                    edits: vec![Edit::RemoveRule],
+                    summary: describe(&Edit::RemoveRule, &m.id()),
+                }]
+            }
+            None if self.default_effect_for(resource).allows() => return Vec::new(),
+            None => {}
+        }
+
+        let category = self.category_of(resource);
+        let mut suggestions: Vec<Suggestion> = self
+            .policies()
+            .flat_map(|policy| {
+                policy
+                    .rules
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(index, rule)| {
+                        let candidate = rule.effect.allows() && rule.applies_in(resource, category);
+                        candidate.then_some((policy, index, rule))
+                    })
+            })
+            .filter_map(|(policy, index, rule)| {
+                let edits = edits_for(rule, ctx, args);
+                if edits.is_empty() {
+                    return None;
+                }
+                let id = format!("{}#{}", policy.name, index);
+                let summary: Vec<String> = edits.iter().map(|e| describe(e, &id)).collect();
+                Some(Suggestion {
+                    policy: policy.name.clone(),
+                    rule: Some(index),
+                    edits,
+                    summary: summary.join("; "),
+                })
+            })
+            .collect();
+
+        if suggestions.is_empty() {
+            if let Some(policy) = self.policies().next() {
+                let mut rule = Rule::allow(resource);
+                if let Some(principal) = &ctx.principal {
+                    rule.principal = principal.clone();
+                }
+                let edit = Edit::AddRule {
+                    rule: Box::new(rule),
+                };
+                suggestions.push(Suggestion {
+                    policy: policy.name.clone(),
+                    rule: None,
+                    summary: describe(&edit, &policy.name),
+                    edits: vec![edit],
+                });
+            }
+        }
+        suggestions.sort_by_key(|s| s.edits.len());
+        suggestions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Condition, Policy};
+    use serde_json::json;
+
+    #[test]
+    fn test_suggests_adding_the_missing_value() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("fs-write-workspace", "1.0")
+                .with_rule(Rule::allow("fs.write").with_conditions(vec![
+                    Condition::new("path", "in", json!(["/work/a"])),
+                    Condition::new("mode", "eq", json!("append")),
+                ]))
+                .with_rule(Rule::allow("fs.write").with_conditions(vec![Condition::new(
+                    "path",
+                    "starts_with",
+                    json!("/work/"),
+                )])),
+        );
+
+        let args = json!({"path": "/tmp/foo", "mode": "append"});
+        let suggestions =
+            engine.suggest_allow(&RequestContext::new(), "fs.write", "execute", &args);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(suggestions[0].edits.len(), 1);
+        assert_eq!(
+            suggestions[0].summary,
+            "add \"/tmp/foo\" to `path` in rule fs-write-workspace#0"
+        );
+    }
+
+    #[test]
+    fn test_blocking_deny_and_missing_rule() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(Policy::new("default", "1.0").with_rule(Rule::deny("shell")));
+
+        let ctx = RequestContext::new().with_principal("alice");
+        let args = json!({});
+        let deny = engine.suggest_allow(&ctx, "shell", "execute", &args);
+        assert_eq!(deny[0].edits, vec![Edit::RemoveRule]);
+
+        let add = engine.suggest_allow(&ctx, "fs.read", "execute", &args);
+        match &add[0].edits[0] {
+            Edit::AddRule { rule } => assert_eq!(rule.principal, "alice"),
+            other => panic!("unexpected edit: {:?}", other),
+        }
+    }
+}
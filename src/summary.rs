@@ -0,0 +1,220 @@
+//! Natural-Language Policy Summaries.
+//!
+//! A [`PolicySummarizer`] turns rules into plain-English sentences for people
+//! who do not read policy files, e.g. "shell is denied unless command is in:
+//! git status, git diff". The gate uses one for denial messages (see
+//! [`CapabilityGate::with_summarizer`](crate::CapabilityGate::with_summarizer))
+//! and [`crate::docgen`] for the rules it documents.
+//!
+//! [`TemplateSummarizer`] is the default, built from fixed phrases per effect
+//! and operator. A deny rule whose requirements are all negative reads as
+//! "denied unless" the positive form holds; operators without a phrase are
+//! shown as written. Deployments that want other wording, or another language,
+//! implement the trait.
+
+use crate::capability::CATEGORY_PREFIX;
+use crate::condition::{ParamConstraint, ParamRule};
+use crate::group::GROUP_PREFIX;
+use crate::policy::{Condition, Effect, Policy, Rule};
+use serde_json::Value;
+
+pub trait PolicySummarizer: Send + Sync {
+    fn summarize_rule(&self, rule: &Rule) -> String;
+
+    /// One sentence per rule, in rule order.
+    fn summarize(&self, policy: &Policy) -> Vec<String> {
+        policy
+            .rules
+            .iter()
+            .map(|rule| self.summarize_rule(rule))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TemplateSummarizer;
+
+/// A requirement phrased positively, and whether the rule states it negated.
+struct Requirement {
+    subject: String,
+    positive: String,
+    negated: bool,
+}
+
+impl Requirement {
+    fn phrase(&self) -> String {
+        match self.negated {
+            true => format!("{} {}", self.subject, negate(&self.positive)),
+            false => format!("{} {}", self.subject, self.positive),
+        }
+    }
+}
+
+fn negate(positive: &str) -> String {
+    match positive.strip_prefix("is ") {
+        Some(rest) => format!("is not {}", rest),
+        None => format!("does not satisfy `{}`", positive),
+    }
+}
+
+fn value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(self::value).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+fn condition(condition: &Condition) -> Requirement {
+    let v = value(&condition.value);
+    let (positive, negated) = match condition.operator.as_str() {
+        "eq" | "equals" => (format!("is {}", v), false),
+        "ne" | "not_equals" => (format!("is {}", v), true),
+        "in" => (format!("is in: {}", v), false),
+        "not_in" => (format!("is in: {}", v), true),
+        "starts_with" | "prefix" => (format!("starts with {}", v), false),
+        "ends_with" | "suffix" => (format!("ends with {}", v), false),
+        "contains" => (format!("contains {}", v), false),
+        "gt" => (format!("is greater than {}", v), false),
+        "gte" => (format!("is at least {}", v), false),
+        "lt" => (format!("is less than {}", v), false),
+        "lte" => (format!("is at most {}", v), false),
+        "glob" | "matches" => (format!("matches {}", v), false),
+        "host" => (format!("has host {}", v), false),
+        "exists" => (
+            "is present".to_string(),
+            condition.value == Value::Bool(false),
+        ),
+        other => (format!("{} {}", other, v), false),
+    };
+    Requirement {
+        subject: condition.key.clone(),
+        positive,
+        negated,
+    }
+}
+
+fn constraint(constraint: &ParamConstraint) -> Requirement {
+    let (positive, negated) = match &constraint.rule {
+        ParamRule::OneOf(values) => (
+            format!("is in: {}", value(&Value::from(values.clone()))),
+            false,
+        ),
+        ParamRule::NoneOf(values) => (
+            format!("is in: {}", value(&Value::from(values.clone()))),
+            true,
+        ),
+        ParamRule::Equals(v) => (format!("is {}", value(v)), false),
+        ParamRule::Prefix(prefix) => (format!("starts with {}", prefix), false),
+    };
+    Requirement {
+        subject: constraint.param.clone(),
+        positive,
+        negated,
+    }
+}
+
+fn resource(rule: &Rule) -> String {
+    match rule.resource.strip_prefix(CATEGORY_PREFIX) {
+        _ if rule.resource == "*" => "every capability".to_string(),
+        Some(category) => format!("every {} capability", category),
+        None => rule.resource.clone(),
+    }
+}
+
+fn principal(rule: &Rule) -> String {
+    match rule.principal.strip_prefix(GROUP_PREFIX) {
+        _ if rule.principal == "*" => String::new(),
+        Some(group) => format!(" for members of {}", group),
+        None => format!(" for {}", rule.principal),
+    }
+}
+
+impl PolicySummarizer for TemplateSummarizer {
+    fn summarize_rule(&self, rule: &Rule) -> String {
+        let verb = match rule.effect {
+            Effect::Allow => "is allowed",
+            Effect::Deny => "is denied",
+            Effect::AllowWithWarning => "is allowed with a warning",
+            Effect::Audit => "is allowed but audited",
+        };
+        let requirements: Vec<Requirement> = rule
+            .conditions
+            .iter()
+            .map(condition)
+            .chain(rule.param_constraints.iter().map(constraint))
+            .collect();
+        let head = format!("{} {}{}", resource(rule), verb, principal(rule));
+        if requirements.is_empty() {
+            return head;
+        }
+        if rule.effect == Effect::Deny && requirements.iter().all(|r| r.negated) {
+            let unless: Vec<String> = requirements
+                .iter()
+                .map(|r| format!("{} {}", r.subject, r.positive))
+                .collect();
+            return format!("{} unless {}", head, unless.join(" or "));
+        }
+        let when: Vec<String> = requirements.iter().map(Requirement::phrase).collect();
+        format!("{} when {}", head, when.join(" and "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::context::RequestContext;
+    use crate::gate::CapabilityGate;
+    use serde_json::json;
+
+    #[test]
+    fn test_template_sentences() {
+        let summarizer = TemplateSummarizer;
+        let policy = Policy::new("p", "1")
+            .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                "args.command",
+                "not_in",
+                json!(["git status", "git diff"]),
+            )]))
+            .with_rule(
+                Rule::allow("fs.read")
+                    .for_principal("group:ops")
+                    .with_conditions(vec![Condition::new("size", "lt", json!(1024))])
+                    .with_param_constraint(ParamConstraint::none_of("path", ["/etc/shadow"])),
+            )
+            .with_rule(Rule::deny("category:Network"))
+            .with_rule(Rule::allow("*").with_conditions(vec![Condition::new(
+                "mode",
+                "regex_like",
+                json!("x"),
+            )]));
+
+        assert_eq!(
+            summarizer.summarize(&policy),
+            vec![
+                "shell is denied unless args.command is in: git status, git diff",
+                "fs.read is allowed for members of ops when size is less than 1024 and path is not in: /etc/shadow",
+                "every Network capability is denied",
+                "every capability is allowed when mode regex_like x",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_denials_carry_the_rule_summary() {
+        let mut gate = CapabilityGate::new();
+        gate.register_capability(Capability::new("shell", "Run commands"));
+        gate.add_policy(Policy::new("p", "1").with_rule(
+            Rule::deny("shell").with_conditions(vec![Condition::new("cmd", "eq", json!("rm"))]),
+        ));
+
+        let denial = gate
+            .try_authorize("shell", &json!({"cmd": "rm"}), &RequestContext::new())
+            .unwrap_err();
+        assert_eq!(
+            denial.to_string(),
+            "`shell` denied: DENIED_POLICY_VIOLATION; shell is denied when cmd is rm"
+        );
+    }
+}
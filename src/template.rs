@@ -0,0 +1,299 @@
+//! Rule Templates.
+//!
+//! Hand-written policies can use compact forms that expand into concrete rules
+//! when the policy is loaded:
+//!
+//! - `"resource": ["fs.read", "fs.stat"]` and `"principal": ["alice", "bob"]`
+//!   take lists; a rule is produced for every principal and resource pair.
+//! - `"resources_matching": "db.{read,list}.*"` brace-expands into resources,
+//!   with `{a,b}` alternatives and `{1..3}` numeric ranges, nested or chained.
+//!   Here the results are `db.read.*` and `db.list.*`, namespace resources
+//!   covering `db.read.users` and the like (see [`crate::policy`]).
+//!
+//! Expansion happens during deserialization, so the engine, lints and indexes
+//! only ever see plain rules. A rule expands to at most [`MAX_EXPANSION`]
+//! rules.
+
+use crate::policy::Rule;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use thiserror::Error;
+
+pub const MAX_EXPANSION: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("unbalanced braces in `{0}`")]
+    Unbalanced(String),
+    #[error("invalid range `{{{0}}}`")]
+    InvalidRange(String),
+    #[error("`{0}` expands to more than {MAX_EXPANSION} rules")]
+    TooLarge(String),
+    #[error("a rule takes `resource` or `resources_matching`, not both")]
+    ConflictingResources,
+    #[error("`{0}` must be a string or a list of strings")]
+    NotAString(&'static str),
+}
+
+/// Brace-expands `pattern`, in order: `db.{read,list}` is `db.read`, `db.list`.
+pub fn expand(pattern: &str) -> Result<Vec<String>, TemplateError> {
+    let mut out = Vec::new();
+    expand_into(pattern, pattern, &mut out)?;
+    Ok(out)
+}
+
+fn expand_into(pattern: &str, whole: &str, out: &mut Vec<String>) -> Result<(), TemplateError> {
+    let Some(open) = pattern.find('{') else {
+        if pattern.contains('}') {
+            return Err(TemplateError::Unbalanced(whole.to_string()));
+        }
+        out.push(pattern.to_string());
+        return Ok(());
+    };
+    let (prefix, rest) = pattern.split_at(open);
+    if prefix.contains('}') {
+        return Err(TemplateError::Unbalanced(whole.to_string()));
+    }
+    let mut depth = 0;
+    let mut close = None;
+    let mut commas = Vec::new();
+    for (i, c) in rest.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            ',' if depth == 1 => commas.push(i),
+            _ => {}
+        }
+    }
+    let close = close.ok_or_else(|| TemplateError::Unbalanced(whole.to_string()))?;
+    let body = &rest[1..close];
+    let suffix = &rest[close + 1..];
+
+    // A brace pair that is neither a list nor a range is kept literally.
+    let mut literal = false;
+    let alternatives: Vec<String> = if commas.is_empty() {
+        match body.split_once("..") {
+            Some((lo, hi)) => {
+                range(lo, hi).ok_or_else(|| TemplateError::InvalidRange(body.into()))?
+            }
+            None => {
+                literal = true;
+                vec![format!("{{{}}}", body)]
+            }
+        }
+    } else {
+        let mut bounds = vec![0];
+        bounds.extend(commas);
+        bounds.push(close);
+        bounds
+            .windows(2)
+            .map(|w| rest[w[0] + 1..w[1]].to_string())
+            .collect()
+    };
+    for alternative in alternatives {
+        let mut heads = Vec::new();
+        match literal {
+            true => heads.push(alternative),
+            false => expand_into(&alternative, whole, &mut heads)?,
+        }
+        for head in heads {
+            let mut tails = Vec::new();
+            expand_into(suffix, whole, &mut tails)?;
+            for tail in tails {
+                if out.len() == MAX_EXPANSION {
+                    return Err(TemplateError::TooLarge(whole.to_string()));
+                }
+                out.push(format!("{}{}{}", prefix, head, tail));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn range(lo: &str, hi: &str) -> Option<Vec<String>> {
+    let (lo, hi): (i64, i64) = (lo.parse().ok()?, hi.parse().ok()?);
+    if hi.abs_diff(lo) >= MAX_EXPANSION as u64 {
+        return None;
+    }
+    let values: Vec<i64> = match lo <= hi {
+        true => (lo..=hi).collect(),
+        false => (hi..=lo).rev().collect(),
+    };
+    Some(values.into_iter().map(|v| v.to_string()).collect())
+}
+
+fn strings(rule: &Value, key: &'static str) -> Result<Option<Vec<String>>, TemplateError> {
+    match rule.get(key) {
+        None => Ok(None),
+        Some(Value::String(s)) => Ok(Some(vec![s.clone()])),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|v| v.as_str().map(String::from))
+            .collect::<Option<Vec<_>>>()
+            .map(Some)
+            .ok_or(TemplateError::NotAString(key)),
+        Some(_) => Err(TemplateError::NotAString(key)),
+    }
+}
+
+/// Expands one rule object into the concrete rule objects it stands for; a
+/// rule without templates is returned as is.
+pub fn expand_rule(mut rule: Value) -> Result<Vec<Value>, TemplateError> {
+    let matching = strings(&rule, "resources_matching")?;
+    let resources = match (strings(&rule, "resource")?, matching) {
+        (Some(_), Some(_)) => return Err(TemplateError::ConflictingResources),
+        (Some(resources), None) => resources,
+        (None, Some(patterns)) => {
+            let mut resources = Vec::new();
+            for pattern in patterns {
+                resources.extend(expand(&pattern)?);
+            }
+            resources
+        }
+        (None, None) => return Ok(vec![rule]),
+    };
+    let principals = strings(&rule, "principal")?;
+    if let Some(object) = rule.as_object_mut() {
+        object.remove("resources_matching");
+    }
+    let principals = principals.unwrap_or_default();
+    let principals: Vec<Option<&String>> = match principals.is_empty() {
+        true => vec![None],
+        false => principals.iter().map(Some).collect(),
+    };
+    if principals.len() * resources.len() > MAX_EXPANSION {
+        return Err(TemplateError::TooLarge(format!(
+            "{} principals x {} resources",
+            principals.len(),
+            resources.len()
+        )));
+    }
+    let mut rules = Vec::new();
+    for principal in &principals {
+        for resource in &resources {
+            let mut concrete = rule.clone();
+            if let Some(object) = concrete.as_object_mut() {
+                object.insert("resource".into(), Value::String(resource.clone()));
+                if let Some(principal) = principal {
+                    object.insert("principal".into(), Value::String((*principal).clone()));
+                }
+            }
+            rules.push(concrete);
+        }
+    }
+    Ok(rules)
+}
+
+/// `deserialize_with` for policy rule lists, expanding templates.
+pub(crate) fn deserialize_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Rule>, D::Error> {
+    let mut rules = Vec::new();
+    for value in Vec::<Value>::deserialize(deserializer)? {
+        for concrete in expand_rule(value).map_err(D::Error::custom)? {
+            rules.push(Rule::deserialize(concrete).map_err(D::Error::custom)?);
+        }
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Effect, Policy, PolicyEngine};
+    use serde_json::json;
+
+    #[test]
+    fn test_brace_expansion() {
+        assert_eq!(
+            expand("db.{read,list}.*").unwrap(),
+            vec!["db.read.*", "db.list.*"]
+        );
+        assert_eq!(expand("shard{1..3}.{r,w}").unwrap().len(), 6);
+        assert_eq!(
+            expand("fs.{read,dir.{list,stat}}").unwrap(),
+            vec!["fs.read", "fs.dir.list", "fs.dir.stat"]
+        );
+        assert_eq!(expand("a{b}c{1..2}").unwrap(), vec!["a{b}c1", "a{b}c2"]);
+        assert_eq!(
+            expand("a{b").unwrap_err(),
+            TemplateError::Unbalanced("a{b".into())
+        );
+        assert!(matches!(
+            expand("{1..x}"),
+            Err(TemplateError::InvalidRange(_))
+        ));
+        assert!(matches!(
+            expand("{0..9}{0..9}{0..9}{0..9}"),
+            Err(TemplateError::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn test_policy_rules_expand_on_load() {
+        let policy: Policy = serde_json::from_value(json!({
+            "name": "p", "version": "1", "rules": [
+                {"effect": "Allow", "principal": ["alice", "bob"],
+                 "resource": ["fs.read", "fs.stat"], "action": "execute", "conditions": []},
+                {"effect": "Deny", "principal": "*", "resources_matching": "db.{drop,truncate}",
+                 "action": "execute", "conditions": []}
+            ]
+        }))
+        .unwrap();
+
+        let rules: Vec<_> = policy
+            .rules
+            .iter()
+            .map(|r| format!("{} {}", r.principal, r.resource))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                "alice fs.read",
+                "alice fs.stat",
+                "bob fs.read",
+                "bob fs.stat",
+                "* db.drop",
+                "* db.truncate"
+            ]
+        );
+        assert!(serde_json::to_string(&policy)
+            .unwrap()
+            .contains(r#""resource":"db.drop""#));
+    }
+
+    #[test]
+    fn test_namespace_templates_authorize_their_capabilities() {
+        let mut engine = PolicyEngine::new();
+        engine
+            .load_from_json(
+                r#"[{"name": "p", "version": "1", "rules": [
+                {"effect": "Allow", "principal": "*", "resources_matching": "db.{read,list}.*",
+                 "action": "execute", "conditions": []}
+            ]}]"#,
+            )
+            .unwrap();
+
+        let args = json!({});
+        assert_eq!(
+            engine.evaluate("db.read.users", "execute", &args),
+            Effect::Allow
+        );
+        assert_eq!(
+            engine.evaluate("db.list.tables", "execute", &args),
+            Effect::Allow
+        );
+        assert_eq!(engine.evaluate("db.read", "execute", &args), Effect::Deny);
+        assert_eq!(
+            engine.evaluate("db.write.users", "execute", &args),
+            Effect::Deny
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Hierarchical Tenants.
+//!
+//! Tenants nest (org → team → project): each [`TenantTree`] node has at most
+//! one parent and its own policies. A tenant inherits every ancestor's policies
+//! with restrict-only semantics, generalizing [`crate::layer`] to any depth:
+//! each level is evaluated on its own, a `Deny` from any level is final, and an
+//! `Allow` stands only if no level denies and the topmost level with policies
+//! allows too. Policies whose `Allow` rules would loosen an ancestor's
+//! unconditional `Deny`, or grant what no ancestor allows, are rejected when
+//! added. Policies are keyed by tenant and name, so a child may reuse an
+//! ancestor's policy name without escaping these checks.
+
+use crate::args::ArgView;
+use crate::context::RequestContext;
+use crate::layer::{check_narrowing, Layer, LayerError};
+use crate::policy::{Effect, Policy, PolicyEngine};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TenantError {
+    #[error("unknown tenant `{0}`")]
+    Unknown(String),
+    #[error("tenant `{0}` already exists")]
+    Duplicate(String),
+    #[error("tenant `{tenant}` loosens an ancestor: {source}")]
+    Loosening {
+        tenant: String,
+        #[source]
+        source: LayerError,
+    },
+}
+
+#[derive(Debug, Clone, Default)]
+struct TenantNode {
+    parent: Option<String>,
+    policies: Vec<Policy>,
+}
+
+#[derive(Clone)]
+pub struct TenantTree {
+    template: PolicyEngine,
+    nodes: BTreeMap<String, TenantNode>,
+}
+
+impl Default for TenantTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TenantTree {
+    pub fn new() -> Self {
+        Self::with_template(PolicyEngine::new().with_default_effect(Effect::Deny))
+    }
+
+    /// Every level is evaluated by a clone of `template`, so its default
+    /// effect, categories, clock and group resolver apply throughout.
+    pub fn with_template(template: PolicyEngine) -> Self {
+        Self {
+            template,
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a tenant; `parent` must already exist, so the tree stays acyclic.
+    pub fn add_tenant(&mut self, id: &str, parent: Option<&str>) -> Result<(), TenantError> {
+        if self.nodes.contains_key(id) {
+            return Err(TenantError::Duplicate(id.to_string()));
+        }
+        if let Some(parent) = parent.filter(|p| !self.nodes.contains_key(*p)) {
+            return Err(TenantError::Unknown(parent.to_string()));
+        }
+        let node = TenantNode {
+            parent: parent.map(String::from),
+            policies: Vec::new(),
+        };
+        self.nodes.insert(id.to_string(), node);
+        Ok(())
+    }
+
+    pub fn parent(&self, id: &str) -> Option<&str> {
+        self.nodes.get(id)?.parent.as_deref()
+    }
+
+    /// The tenant and its ancestors, root first.
+    pub fn lineage(&self, id: &str) -> Result<Vec<&str>, TenantError> {
+        let mut lineage = Vec::new();
+        let mut current = Some(id);
+        while let Some(tenant) = current {
+            let (name, node) = self
+                .nodes
+                .get_key_value(tenant)
+                .ok_or_else(|| TenantError::Unknown(tenant.to_string()))?;
+            lineage.push(name.as_str());
+            current = node.parent.as_deref();
+        }
+        lineage.reverse();
+        Ok(lineage)
+    }
+
+    /// Adds or replaces a policy of `tenant`, rejecting it if it loosens an
+    /// ancestor.
+    pub fn add_policy(&mut self, tenant: &str, policy: Policy) -> Result<(), TenantError> {
+        let keyed = |tenant: &str, policy: &Policy| Policy {
+            name: format!("{}:{}", tenant, policy.name),
+            ..policy.clone()
+        };
+        let mut ancestors = self.template.clone();
+        let lineage = self.lineage(tenant)?;
+        for ancestor in &lineage[..lineage.len() - 1] {
+            for inherited in &self.nodes[*ancestor].policies {
+                ancestors.add_policy(keyed(ancestor, inherited));
+            }
+        }
+        check_narrowing(&ancestors, Layer::Project, &keyed(tenant, &policy)).map_err(|source| {
+            TenantError::Loosening {
+                tenant: tenant.to_string(),
+                source,
+            }
+        })?;
+
+        let node = self
+            .nodes
+            .get_mut(tenant)
+            .expect("lineage checked the tenant");
+        node.policies.retain(|p| p.name != policy.name);
+        node.policies.push(policy);
+        Ok(())
+    }
+
+    /// Every policy in effect for `tenant` with the tenant that owns it, root
+    /// first.
+    pub fn effective_policies(&self, tenant: &str) -> Result<Vec<(&str, &Policy)>, TenantError> {
+        Ok(self
+            .lineage(tenant)?
+            .into_iter()
+            .flat_map(|t| self.nodes[t].policies.iter().map(move |p| (t, p)))
+            .collect())
+    }
+
+    /// Compiles the levels of `tenant` for evaluation.
+    pub fn resolve(&self, tenant: &str) -> Result<TenantEngine, TenantError> {
+        let lineage = self.lineage(tenant)?;
+        let top = lineage
+            .iter()
+            .position(|t| !self.nodes[*t].policies.is_empty());
+        let levels = lineage
+            .into_iter()
+            .map(|t| {
+                let mut engine = self.template.clone();
+                for policy in &self.nodes[t].policies {
+                    engine.add_policy(policy.clone());
+                }
+                (t.to_string(), engine)
+            })
+            .collect();
+        Ok(TenantEngine {
+            levels,
+            top,
+            default_effect: self.template.default_effect(),
+        })
+    }
+}
+
+/// The compiled levels of one tenant, root first.
+#[derive(Clone)]
+pub struct TenantEngine {
+    levels: Vec<(String, PolicyEngine)>,
+    /// The topmost level with policies of its own, whose allow every allow needs.
+    top: Option<usize>,
+    default_effect: Effect,
+}
+
+impl TenantEngine {
+    pub fn evaluate_with(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+    ) -> Effect {
+        self.decide(ctx, resource, action, args)
+            .map(|(_, effect, _)| effect)
+            .unwrap_or(self.default_effect)
+    }
+
+    /// The deciding tenant, effect and rule id, or `None` if no level has a
+    /// matching rule. The first `Deny` found wins; otherwise the first `Allow`,
+    /// if the topmost level with policies allowed.
+    pub fn decide(
+        &self,
+        ctx: &RequestContext,
+        resource: &str,
+        action: &str,
+        args: &dyn ArgView,
+    ) -> Option<(&str, Effect, String)> {
+        let mut allowed = None;
+        let mut granted = self.top.is_none();
+        for (level, (tenant, engine)) in self.levels.iter().enumerate() {
+            let Some(found) = engine.find_rule(ctx, resource, action, args) else {
+                continue;
+            };
+            let decision = (tenant.as_str(), found.rule.effect, found.id());
+            if found.rule.effect == Effect::Deny {
+                return Some(decision);
+            }
+            granted |= self.top == Some(level);
+            allowed.get_or_insert(decision);
+        }
+        allowed.filter(|_| granted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::NO_ARGS;
+    use crate::policy::Rule;
+
+    fn tree() -> TenantTree {
+        let mut tree = TenantTree::new();
+        tree.add_tenant("acme", None).unwrap();
+        tree.add_tenant("acme/infra", Some("acme")).unwrap();
+        tree.add_tenant("acme/infra/ci", Some("acme/infra"))
+            .unwrap();
+        tree.add_policy(
+            "acme",
+            Policy::new("org", "1")
+                .with_rule(Rule::deny("secrets.read"))
+                .with_rule(Rule::allow("fs.read"))
+                .with_rule(Rule::allow("shell").for_principal("ci")),
+        )
+        .unwrap();
+        tree.add_policy(
+            "acme/infra",
+            Policy::new("team", "1").with_rule(Rule::allow("shell")),
+        )
+        .unwrap();
+        tree.add_policy(
+            "acme/infra/ci",
+            Policy::new("project", "1").with_rule(Rule::deny("fs.read")),
+        )
+        .unwrap();
+        tree
+    }
+
+    #[test]
+    fn test_children_inherit_and_only_restrict() {
+        let tree = tree();
+        let ctx = RequestContext::default();
+        let ci = RequestContext::new().with_principal("ci");
+        let eval_as = |ctx: &RequestContext, tenant, tool| {
+            let engine = tree.resolve(tenant).unwrap();
+            engine.evaluate_with(ctx, tool, "execute", &NO_ARGS)
+        };
+        let eval = |tenant, tool| eval_as(&ctx, tenant, tool);
+        assert_eq!(eval("acme/infra", "fs.read"), Effect::Allow);
+        assert_eq!(eval_as(&ci, "acme/infra", "shell"), Effect::Allow);
+        assert_eq!(eval_as(&ci, "acme", "shell"), Effect::Allow);
+        assert_eq!(eval("acme", "shell"), Effect::Deny);
+        // The team allows shell for everyone, but the org only grants it to ci.
+        assert_eq!(eval("acme/infra", "shell"), Effect::Deny);
+        assert_eq!(eval("acme/infra/ci", "fs.read"), Effect::Deny);
+        assert_eq!(eval_as(&ci, "acme/infra/ci", "shell"), Effect::Allow);
+
+        let engine = tree.resolve("acme/infra/ci").unwrap();
+        let decided = engine.decide(&ctx, "fs.read", "execute", &NO_ARGS);
+        assert_eq!(
+            decided,
+            Some(("acme/infra/ci", Effect::Deny, "project#0".to_string()))
+        );
+
+        let names: Vec<(&str, &str)> = tree
+            .effective_policies("acme/infra/ci")
+            .unwrap()
+            .into_iter()
+            .map(|(t, p)| (t, p.name.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("acme", "org"),
+                ("acme/infra", "team"),
+                ("acme/infra/ci", "project")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_loosening_and_unknown_parents_rejected() {
+        let mut tree = tree();
+        let loosen = Policy::new("oops", "1").with_rule(Rule::allow("secrets.read"));
+        assert!(matches!(
+            tree.add_policy("acme/infra/ci", loosen),
+            Err(TenantError::Loosening { .. })
+        ));
+        // Reusing the org policy's name does not skip the checks.
+        let renamed = Policy::new("org", "2").with_rule(Rule::allow("secrets.read"));
+        assert!(matches!(
+            tree.add_policy("acme/infra", renamed),
+            Err(TenantError::Loosening { .. })
+        ));
+        let ungranted = Policy::new("org", "2").with_rule(Rule::allow("email.send"));
+        assert!(matches!(
+            tree.add_policy("acme/infra", ungranted),
+            Err(TenantError::Loosening {
+                source: LayerError::NotGranted { .. },
+                ..
+            })
+        ));
+        assert_eq!(
+            tree.add_tenant("x", Some("nope")),
+            Err(TenantError::Unknown("nope".into()))
+        );
+        assert_eq!(
+            tree.add_tenant("acme", None),
+            Err(TenantError::Duplicate("acme".into()))
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Terminal Dashboard for Live Gate Inspection.
+//!
+//! For on-call debugging of a misbehaving agent: [`DashboardFeed`] is an
+//! [`AuditSink`] that keeps the latest decisions and per-capability counters,
+//! and [`Dashboard`] renders them next to the gate's capabilities and policy
+//! generations, with keys to move through the capability list and toggle the
+//! selected one on or off.
+//!
+//! Frames are plain text, so they can be drawn by any terminal backend; [`run`]
+//! is a minimal loop that redraws with ANSI escapes and reads one key per line
+//! (`j`/`k` to move, `t` to toggle, `q` to quit).
+
+use crate::audit::{AuditEvent, AuditSink};
+use crate::clock::format_utc;
+use crate::decision::{AUDIT_EFFECT, EFFECT_DETAIL};
+use crate::gate::{CapabilityGate, Decision};
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+
+pub const DEFAULT_RECENT: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Counters {
+    pub allowed: u64,
+    pub denied: u64,
+}
+
+#[derive(Default)]
+struct FeedState {
+    recent: VecDeque<AuditEvent>,
+    counters: BTreeMap<String, Counters>,
+}
+
+pub struct DashboardFeed {
+    keep: usize,
+    state: Mutex<FeedState>,
+}
+
+impl Default for DashboardFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_RECENT)
+    }
+}
+
+impl DashboardFeed {
+    /// A feed that shows the `keep` most recent decisions.
+    pub fn new(keep: usize) -> Self {
+        Self {
+            keep,
+            state: Mutex::new(FeedState::default()),
+        }
+    }
+
+    /// Most recent first.
+    pub fn recent(&self) -> Vec<AuditEvent> {
+        self.state.lock().unwrap().recent.iter().cloned().collect()
+    }
+
+    pub fn counters(&self) -> BTreeMap<String, Counters> {
+        self.state.lock().unwrap().counters.clone()
+    }
+}
+
+impl AuditSink for DashboardFeed {
+    fn record(&self, event: &AuditEvent) {
+        // Watch-mode allows count as the denials they will become.
+        let allowed = Decision::from_code(&event.decision).is_some_and(|d| d.is_allowed())
+            && event.details.get(EFFECT_DETAIL).map(String::as_str) != Some(AUDIT_EFFECT);
+        let count = event.count.unwrap_or(1);
+        let mut state = self.state.lock().unwrap();
+        let counters = state.counters.entry(event.tool.clone()).or_default();
+        match allowed {
+            true => counters.allowed += count,
+            false => counters.denied += count,
+        }
+        state.recent.push_front(event.clone());
+        state.recent.truncate(self.keep);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Up,
+    Down,
+    Toggle,
+    Quit,
+}
+
+impl Key {
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim() {
+            "k" | "up" => Some(Key::Up),
+            "j" | "down" => Some(Key::Down),
+            "t" | "toggle" => Some(Key::Toggle),
+            "q" | "quit" => Some(Key::Quit),
+            _ => None,
+        }
+    }
+}
+
+pub struct Dashboard {
+    feed: Arc<DashboardFeed>,
+    selected: usize,
+}
+
+impl Dashboard {
+    /// Pass the same `feed` to [`CapabilityGate::with_audit_sink`].
+    pub fn new(feed: Arc<DashboardFeed>) -> Self {
+        Self { feed, selected: 0 }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Applies a key; returns `false` on [`Key::Quit`].
+    pub fn handle_key(&mut self, gate: &mut CapabilityGate, key: Key) -> bool {
+        let names: Vec<String> = gate
+            .registry()
+            .list()
+            .iter()
+            .map(|c| c.name.clone())
+            .collect();
+        match key {
+            Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Down => self.selected = (self.selected + 1).min(names.len().saturating_sub(1)),
+            Key::Toggle => {
+                if let Some(name) = names.get(self.selected) {
+                    let enabled = gate.registry().is_enabled(name);
+                    gate.set_enabled(name, !enabled);
+                }
+            }
+            Key::Quit => return false,
+        }
+        true
+    }
+
+    pub fn render(&self, gate: &CapabilityGate) -> String {
+        let counters = self.feed.counters();
+        let mut out = String::from("CAPABILITIES                      ON   ALLOWED   DENIED\n");
+        for (i, capability) in gate.registry().list().iter().enumerate() {
+            let counts = counters.get(&capability.name).copied().unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{} {:<32}{}  {:>8} {:>8}",
+                if i == self.selected { '>' } else { ' ' },
+                capability.name,
+                if capability.enabled { "[x]" } else { "[ ]" },
+                counts.allowed,
+                counts.denied
+            );
+        }
+        out.push_str("\nGENERATIONS\n");
+        for generation in gate.engine().generations() {
+            let _ = writeln!(
+                out,
+                "  #{} {} {} {}",
+                generation.number,
+                &generation.digest[..generation.digest.len().min(12)],
+                format_utc(generation.activated_at_ms),
+                generation.bundle.as_deref().unwrap_or("-")
+            );
+        }
+        out.push_str("\nDECISIONS\n");
+        for event in self.feed.recent() {
+            let _ = writeln!(
+                out,
+                "  {} {} {} {}{}",
+                format_utc(event.timestamp_ms),
+                event.principal.as_deref().unwrap_or("-"),
+                event.tool,
+                event.decision,
+                event
+                    .rule
+                    .as_deref()
+                    .map(|r| format!(" ({})", r))
+                    .unwrap_or_default()
+            );
+        }
+        out
+    }
+}
+
+/// Redraws `dashboard` to `output` after every key read from `input` until
+/// [`Key::Quit`] or end of input.
+pub fn run(
+    dashboard: &mut Dashboard,
+    gate: &mut CapabilityGate,
+    mut input: impl BufRead,
+    mut output: impl Write,
+) -> std::io::Result<()> {
+    loop {
+        write!(
+            output,
+            "\x1b[H\x1b[2J{}\n[j/k] move  [t] toggle  [q] quit\n",
+            dashboard.render(gate)
+        )?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if let Some(key) = Key::parse(&line) {
+            if !dashboard.handle_key(gate, key) {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::Capability;
+    use crate::policy::{Policy, Rule};
+
+    fn gate(feed: &Arc<DashboardFeed>) -> CapabilityGate {
+        let mut gate = CapabilityGate::new().with_audit_sink(feed.clone());
+        gate.register_capability(Capability::new("fs.read", "Read files"));
+        gate.register_capability(Capability::new("shell", "Run commands"));
+        gate.add_policy(Policy::new("p", "1").with_rule(Rule::allow("fs.read")));
+        gate
+    }
+
+    #[test]
+    fn test_feed_counts_decisions() {
+        let feed = Arc::new(DashboardFeed::new(2));
+        let gate = gate(&feed);
+        for tool in ["fs.read", "fs.read", "shell"] {
+            gate.authorize(tool, &());
+        }
+
+        let counters = feed.counters();
+        assert_eq!(counters["fs.read"].allowed, 2);
+        assert_eq!(counters["shell"].denied, 1);
+        let recent = feed.recent();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].tool, "shell");
+    }
+
+    #[test]
+    fn test_toggle_disables_selected_capability() {
+        let feed = Arc::new(DashboardFeed::default());
+        let mut gate = gate(&feed);
+        let mut dashboard = Dashboard::new(feed);
+
+        let input = "j\nt\nq\n".as_bytes();
+        run(&mut dashboard, &mut gate, input, std::io::sink()).unwrap();
+        assert!(!gate.registry().is_enabled("shell"));
+        assert!(gate.registry().is_enabled("fs.read"));
+        assert!(dashboard.render(&gate).contains("> shell"));
+    }
+}
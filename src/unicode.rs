@@ -0,0 +1,213 @@
+//! Unicode Hardening.
+//!
+//! Policies are written in ASCII but tool arguments are not: a `Deny` rule on
+//! `/etc` must not be bypassed by `/ｅtc` (fullwidth), `/еtc` (Cyrillic `е`),
+//! `/ETC` on a case-insensitive filesystem or `/et\u{200B}c` with a hidden
+//! zero-width space. [`skeleton`] strips invisible characters, folds common
+//! confusables and fullwidth forms to ASCII and lowercases the result.
+//!
+//! `Deny` conditions that do not hold on the raw arguments are re-checked on
+//! the skeletons of both argument and condition value, so folding can only turn
+//! requests into denials. `Allow` rules still match exactly.
+//!
+//! Patterns containing non-ASCII are linted unless the policy opts in with
+//! `allow_unicode`.
+
+use crate::args::{ArgValue, ArgView};
+use crate::lint::Lint;
+use crate::policy::{Condition, Policy};
+use serde_json::Value;
+use std::borrow::Cow;
+
+/// Zero-width, soft hyphen, byte order mark and bidirectional controls.
+pub fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+    )
+}
+
+/// The ASCII character `c` is commonly confused with, if any.
+fn confusable(c: char) -> Option<char> {
+    if ('\u{FF01}'..='\u{FF5E}').contains(&c) {
+        return char::from_u32(c as u32 - 0xFEE0);
+    }
+    Some(match c {
+        'а' | 'А' | 'α' | 'Α' => 'a',
+        'В' | 'Β' => 'b',
+        'с' | 'С' | 'ϲ' => 'c',
+        'ԁ' => 'd',
+        'е' | 'Е' | 'Ε' => 'e',
+        'Н' | 'Η' | 'һ' => 'h',
+        'і' | 'І' | 'ı' | 'Ι' | 'ι' => 'i',
+        'ј' | 'Ј' => 'j',
+        'К' | 'Κ' | 'κ' => 'k',
+        'М' | 'Μ' => 'm',
+        'Ν' => 'n',
+        'о' | 'О' | 'ο' | 'Ο' => 'o',
+        'р' | 'Р' | 'ρ' | 'Ρ' => 'p',
+        'ѕ' | 'Ѕ' => 's',
+        'Т' | 'Τ' => 't',
+        'ν' | 'ѵ' => 'v',
+        'х' | 'Х' | 'Χ' | 'χ' => 'x',
+        'у' | 'У' | 'Υ' => 'y',
+        'Ζ' => 'z',
+        '∕' | '⁄' | '⧸' => '/',
+        '‐' | '‑' | '‒' | '–' | '−' => '-',
+        '．' | '․' => '.',
+        _ => return None,
+    })
+}
+
+pub fn skeleton(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !is_invisible(c))
+        .map(|c| confusable(c).unwrap_or(c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn fold_value(value: &Value) -> Value {
+    match value {
+        Value::String(s) => Value::String(skeleton(s)),
+        Value::Array(items) => Value::Array(items.iter().map(fold_value).collect()),
+        other => other.clone(),
+    }
+}
+
+fn fold_arg<'a>(value: ArgValue<'a>) -> ArgValue<'a> {
+    match value {
+        ArgValue::Str(s) => ArgValue::Str(Cow::Owned(skeleton(&s))),
+        ArgValue::Json(Value::String(s)) => ArgValue::Str(Cow::Owned(skeleton(s))),
+        ArgValue::Json(Value::Array(items)) => {
+            ArgValue::List(items.iter().map(|v| fold_arg(ArgValue::Json(v))).collect())
+        }
+        ArgValue::List(items) => ArgValue::List(items.into_iter().map(fold_arg).collect()),
+        other => other,
+    }
+}
+
+/// An argument view whose string leaves read as their skeletons.
+pub struct Folded<'a>(pub &'a dyn ArgView);
+
+impl ArgView for Folded<'_> {
+    fn lookup(&self, path: &str) -> Option<ArgValue<'_>> {
+        self.0.lookup(path).map(fold_arg)
+    }
+
+    fn session_lookup(&self, key: &str) -> Option<ArgValue<'_>> {
+        self.0.session_lookup(key)
+    }
+}
+
+impl Condition {
+    /// Whether the condition holds once the argument and the condition value
+    /// are both folded to their skeletons.
+    pub fn evaluate_folded(&self, args: &dyn ArgView) -> bool {
+        let folded = Condition::new(
+            self.key.clone(),
+            self.operator.clone(),
+            fold_value(&self.value),
+        );
+        folded.evaluate(&Folded(args))
+    }
+}
+
+fn has_non_ascii(value: &Value) -> bool {
+    match value {
+        Value::String(s) => !s.is_ascii(),
+        Value::Array(items) => items.iter().any(has_non_ascii),
+        _ => false,
+    }
+}
+
+/// `non-ascii-pattern` warnings for resources and condition values containing
+/// non-ASCII characters, unless the policy sets `allow_unicode`.
+pub fn unicode_lints(policy: &Policy) -> Vec<Lint> {
+    if policy.allow_unicode {
+        return Vec::new();
+    }
+    let mut lints = Vec::new();
+    for (index, rule) in policy.rules.iter().enumerate() {
+        let in_condition = rule.conditions.iter().find(|c| has_non_ascii(&c.value));
+        let offending = match in_condition {
+            _ if !rule.resource.is_ascii() => format!("resource `{}`", rule.resource),
+            Some(c) => format!("condition `{}` value {}", c.key, c.value),
+            None => continue,
+        };
+        lints.push(Lint::warning(
+            "non-ascii-pattern",
+            &policy.name,
+            Some(index),
+            format!(
+                "{} contains non-ASCII characters; set `allow_unicode` if intended",
+                offending
+            ),
+        ));
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{Effect, PolicyEngine, Rule};
+    use serde_json::json;
+
+    #[test]
+    fn test_skeleton_folds_bypass_attempts() {
+        for attempt in [
+            "/ｅtc/passwd",
+            "/еtc/passwd",
+            "/ETC/passwd",
+            "/et\u{200B}c/passwd",
+        ] {
+            assert_eq!(skeleton(attempt), "/etc/passwd", "{:?}", attempt);
+        }
+        assert_eq!(skeleton("ѕhell"), "shell");
+    }
+
+    #[test]
+    fn test_deny_rules_see_through_confusables() {
+        let mut engine = PolicyEngine::new();
+        engine.add_policy(
+            Policy::new("p", "1.0")
+                .with_rule(Rule::deny("fs.read").with_conditions(vec![Condition::new(
+                    "path",
+                    "starts_with",
+                    json!("/etc"),
+                )]))
+                .with_rule(Rule::allow("fs.read")),
+        );
+        for path in ["/ｅtc/passwd", "/ETC/shadow", "/e\u{200D}tc/hosts"] {
+            let args = json!({ "path": path });
+            assert_eq!(
+                engine.evaluate("fs.read", "execute", &args),
+                Effect::Deny,
+                "{:?}",
+                path
+            );
+        }
+        let args = json!({"path": "/work/ｅtc"});
+        assert_eq!(engine.evaluate("fs.read", "execute", &args), Effect::Allow);
+    }
+
+    #[test]
+    fn test_non_ascii_patterns_linted_without_opt_in() {
+        let rule = Rule::deny("fs.read").with_conditions(vec![Condition::new(
+            "path",
+            "starts_with",
+            json!("/däten"),
+        )]);
+        let policy = Policy::new("p", "1.0").with_rule(rule);
+        let lints = unicode_lints(&policy);
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].code, "non-ascii-pattern");
+        assert!(unicode_lints(&policy.allowing_unicode()).is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+//! Capability Definition Validation.
+//!
+//! `CapabilityRegistry::register` stores whatever it is given;
+//! `register_checked` first validates the definition and reports every problem:
+//!
+//! - names are 1 to [`MAX_NAME_LEN`] characters of dot-separated segments, each
+//!   lowercase ASCII letters, digits, `_` or `-` and starting with a letter or
+//!   digit (`fs.read`, `github.create-issue`);
+//! - parameters have distinct, non-empty names and one of the [`PARAM_TYPES`];
+//! - a params schema, if present, is an `object` schema whose properties have
+//!   known types and whose `required` names exist; with the `json-schema`
+//!   feature it must also compile, see [`crate::schema`].
+
+use crate::capability::{Capability, CapabilityRegistry};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use thiserror::Error;
+
+pub const MAX_NAME_LEN: usize = 128;
+pub const PARAM_TYPES: [&str; 6] = ["string", "integer", "number", "boolean", "array", "object"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CapabilityError {
+    #[error("capability name is empty")]
+    EmptyName,
+    #[error("capability name `{name}` is longer than {MAX_NAME_LEN} characters")]
+    NameTooLong { name: String },
+    #[error("capability name `{name}` is not dot-separated lowercase segments")]
+    InvalidName { name: String },
+    #[error("`{capability}` has a parameter with an empty name")]
+    EmptyParamName { capability: String },
+    #[error("`{capability}` declares parameter `{param}` more than once")]
+    DuplicateParam { capability: String, param: String },
+    #[error("`{capability}` parameter `{param}` has unknown type `{param_type}`")]
+    UnknownParamType {
+        capability: String,
+        param: String,
+        param_type: String,
+    },
+    #[error("`{capability}` has an invalid params schema: {reason}")]
+    InvalidSchema { capability: String, reason: String },
+}
+
+fn valid_segment(segment: &str) -> bool {
+    let mut chars = segment.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-')
+}
+
+fn check_name(name: &str) -> Option<CapabilityError> {
+    let name_owned = || name.to_string();
+    if name.is_empty() {
+        Some(CapabilityError::EmptyName)
+    } else if name.chars().count() > MAX_NAME_LEN {
+        Some(CapabilityError::NameTooLong { name: name_owned() })
+    } else if !name.split('.').all(valid_segment) {
+        Some(CapabilityError::InvalidName { name: name_owned() })
+    } else {
+        None
+    }
+}
+
+fn known_type(value: &Value) -> bool {
+    match value {
+        Value::String(t) => t == "null" || PARAM_TYPES.contains(&t.as_str()),
+        Value::Array(types) => types.iter().all(known_type),
+        _ => false,
+    }
+}
+
+/// Structural problems with a params schema, as human-readable reasons.
+fn schema_problems(schema: &Value) -> Vec<String> {
+    let Some(schema) = schema.as_object() else {
+        return vec!["schema is not an object".to_string()];
+    };
+    let mut problems = Vec::new();
+    if schema.get("type").is_some_and(|t| t != "object") {
+        problems.push("top-level type must be `object`".to_string());
+    }
+    let properties = match schema.get("properties") {
+        None => None,
+        Some(Value::Object(properties)) => Some(properties),
+        Some(_) => {
+            problems.push("`properties` is not an object".to_string());
+            None
+        }
+    };
+    for (name, property) in properties.into_iter().flatten() {
+        match property.get("type") {
+            Some(t) if !known_type(t) => {
+                problems.push(format!("property `{}` has unknown type", name))
+            }
+            _ if !property.is_object() => {
+                problems.push(format!("property `{}` is not a schema", name))
+            }
+            _ => {}
+        }
+    }
+    match schema.get("required") {
+        None => {}
+        Some(Value::Array(required)) => {
+            for name in required {
+                let known = name
+                    .as_str()
+                    .is_some_and(|n| properties.is_some_and(|p| p.contains_key(n)));
+                if !known {
+                    problems.push(format!("required `{}` is not a property", name));
+                }
+            }
+        }
+        Some(_) => problems.push("`required` is not an array".to_string()),
+    }
+    problems
+}
+
+impl Capability {
+    /// Every problem with this definition; empty if it may be registered.
+    pub fn validate(&self) -> Vec<CapabilityError> {
+        let mut errors: Vec<CapabilityError> = check_name(&self.name).into_iter().collect();
+        let capability = || self.name.clone();
+        let mut seen = BTreeSet::new();
+        for param in &self.parameters {
+            if param.name.is_empty() {
+                errors.push(CapabilityError::EmptyParamName {
+                    capability: capability(),
+                });
+            } else if !seen.insert(param.name.as_str()) {
+                errors.push(CapabilityError::DuplicateParam {
+                    capability: capability(),
+                    param: param.name.clone(),
+                });
+            }
+            if !PARAM_TYPES.contains(&param.param_type.as_str()) {
+                errors.push(CapabilityError::UnknownParamType {
+                    capability: capability(),
+                    param: param.name.clone(),
+                    param_type: param.param_type.clone(),
+                });
+            }
+        }
+        #[cfg(feature = "json-schema")]
+        if let Some(Err(e)) = self
+            .schema
+            .as_ref()
+            .map(crate::schema::CompiledSchema::compile)
+        {
+            errors.push(CapabilityError::InvalidSchema {
+                capability: capability(),
+                reason: e.to_string(),
+            });
+        }
+        for reason in self.schema.iter().flat_map(schema_problems) {
+            errors.push(CapabilityError::InvalidSchema {
+                capability: capability(),
+                reason,
+            });
+        }
+        errors
+    }
+}
+
+impl CapabilityRegistry {
+    /// Registers `capability` only if [`Capability::validate`] finds no problems.
+    pub fn register_checked(&mut self, capability: Capability) -> Result<(), Vec<CapabilityError>> {
+        let errors = capability.validate();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        self.register(capability);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capability::CapabilityParam;
+    use serde_json::json;
+
+    fn param(name: &str, param_type: &str) -> CapabilityParam {
+        CapabilityParam {
+            name: name.to_string(),
+            param_type: param_type.to_string(),
+            required: false,
+        }
+    }
+
+    #[test]
+    fn test_names() {
+        for name in ["fs.read", "shell", "tool.7", "github.create-issue", "a_b.c"] {
+            assert_eq!(check_name(name), None, "{}", name);
+        }
+        for name in [
+            "",
+            "Fs.read",
+            "fs..read",
+            ".fs",
+            "fs.read.",
+            "category:Process",
+            "rm -rf",
+        ] {
+            assert!(check_name(name).is_some(), "{}", name);
+        }
+        assert!(matches!(
+            check_name(&"a".repeat(MAX_NAME_LEN + 1)),
+            Some(CapabilityError::NameTooLong { .. })
+        ));
+    }
+
+    #[test]
+    fn test_register_checked_reports_every_problem() {
+        let mut registry = CapabilityRegistry::new();
+        let capability = Capability::new("http.get", "HTTP GET")
+            .with_params(vec![
+                param("url", "string"),
+                param("url", "uri"),
+                param("", "integer"),
+            ])
+            .with_schema(json!({
+                "type": "object",
+                "properties": { "url": { "type": "string" }, "n": { "type": "float" } },
+                "required": ["url", "timeout"],
+            }));
+
+        let errors = registry.register_checked(capability).unwrap_err();
+        assert_eq!(errors.len(), 5, "{:?}", errors);
+        assert!(errors.contains(&CapabilityError::DuplicateParam {
+            capability: "http.get".to_string(),
+            param: "url".to_string(),
+        }));
+        assert!(!registry.is_registered("http.get"));
+
+        let capability =
+            Capability::new("http.get", "HTTP GET").with_params(vec![param("url", "string")]);
+        assert!(registry.register_checked(capability).is_ok());
+        assert!(registry.is_registered("http.get"));
+    }
+}
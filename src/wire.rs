@@ -0,0 +1,402 @@
+//! Binary Wire Formats.
+//!
+//! CBOR (`cbor` feature) and MessagePack (`msgpack` feature) encodings for any
+//! serde type in this crate — policies, bundles, audit records — so sidecar
+//! protocols on constrained transports don't have to ship JSON. Values go through
+//! the `serde_json` data model, so every type encodes exactly as its JSON form
+//! does, with no additional dependencies.
+
+use thiserror::Error;
+
+/// Nesting limit on decode, so hostile input cannot exhaust the stack.
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug, Error)]
+pub enum WireError {
+    #[error("unexpected end of input")]
+    Eof,
+    #[error("unsupported item 0x{0:02x} at byte {1}")]
+    Unsupported(u8, usize),
+    #[error("invalid UTF-8 string at byte {0}")]
+    InvalidUtf8(usize),
+    #[error("map keys must be strings (byte {0})")]
+    NonStringKey(usize),
+    #[error("nesting deeper than {MAX_DEPTH}")]
+    TooDeep,
+    #[error("{0} trailing bytes after value")]
+    TrailingBytes(usize),
+    #[error(transparent)]
+    Data(#[from] serde_json::Error),
+}
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+struct Input<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+impl<'a> Input<'a> {
+    fn byte(&mut self) -> Result<u8, WireError> {
+        let b = *self.bytes.get(self.pos).ok_or(WireError::Eof)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], WireError> {
+        let end = self.pos.checked_add(n).ok_or(WireError::Eof)?;
+        let slice = self.bytes.get(self.pos..end).ok_or(WireError::Eof)?;
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn uint(&mut self, n: usize) -> Result<u64, WireError> {
+        Ok(self
+            .take(n)?
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | *b as u64))
+    }
+
+    fn text(&mut self, n: usize) -> Result<String, WireError> {
+        let at = self.pos;
+        let raw = self.take(n)?;
+        String::from_utf8(raw.to_vec()).map_err(|_| WireError::InvalidUtf8(at))
+    }
+
+    fn finish(&self) -> Result<(), WireError> {
+        match self.bytes.len() - self.pos {
+            0 => Ok(()),
+            n => Err(WireError::TrailingBytes(n)),
+        }
+    }
+}
+
+#[cfg(feature = "cbor")]
+pub mod cbor {
+    use super::{Input, WireError, MAX_DEPTH};
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::{Map, Number, Value};
+
+    pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, WireError> {
+        let mut out = Vec::new();
+        encode(&serde_json::to_value(value)?, &mut out);
+        Ok(out)
+    }
+
+    pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, WireError> {
+        let mut input = Input { bytes, pos: 0 };
+        let value = decode(&mut input, 0)?;
+        input.finish()?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn head(major: u8, n: u64, out: &mut Vec<u8>) {
+        let major = major << 5;
+        match n {
+            0..=23 => out.push(major | n as u8),
+            24..=0xff => out.extend_from_slice(&[major | 24, n as u8]),
+            0x100..=0xffff => {
+                out.push(major | 25);
+                out.extend_from_slice(&(n as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                out.push(major | 26);
+                out.extend_from_slice(&(n as u32).to_be_bytes());
+            }
+            _ => {
+                out.push(major | 27);
+                out.extend_from_slice(&n.to_be_bytes());
+            }
+        }
+    }
+
+    fn encode(value: &Value, out: &mut Vec<u8>) {
+        match value {
+            Value::Null => out.push(0xf6),
+            Value::Bool(false) => out.push(0xf4),
+            Value::Bool(true) => out.push(0xf5),
+            Value::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    head(0, u, out);
+                } else if let Some(i) = n.as_i64() {
+                    head(1, (-1 - i) as u64, out);
+                } else {
+                    out.push(0xfb);
+                    out.extend_from_slice(&n.as_f64().unwrap_or(f64::NAN).to_be_bytes());
+                }
+            }
+            Value::String(s) => {
+                head(3, s.len() as u64, out);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Value::Array(items) => {
+                head(4, items.len() as u64, out);
+                items.iter().for_each(|i| encode(i, out));
+            }
+            Value::Object(map) => {
+                head(5, map.len() as u64, out);
+                for (k, v) in map {
+                    head(3, k.len() as u64, out);
+                    out.extend_from_slice(k.as_bytes());
+                    encode(v, out);
+                }
+            }
+        }
+    }
+
+    fn float(f: f64) -> Value {
+        Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+
+    fn decode(input: &mut Input<'_>, depth: usize) -> Result<Value, WireError> {
+        if depth > MAX_DEPTH {
+            return Err(WireError::TooDeep);
+        }
+        let at = input.pos;
+        let initial = input.byte()?;
+        let (major, info) = (initial >> 5, initial & 0x1f);
+        if major == 7 {
+            return match info {
+                20 => Ok(Value::Bool(false)),
+                21 => Ok(Value::Bool(true)),
+                22 | 23 => Ok(Value::Null),
+                26 => Ok(float(f32::from_bits(input.uint(4)? as u32) as f64)),
+                27 => Ok(float(f64::from_bits(input.uint(8)?))),
+                _ => Err(WireError::Unsupported(initial, at)),
+            };
+        }
+        let n = match info {
+            0..=23 => info as u64,
+            24 => input.uint(1)?,
+            25 => input.uint(2)?,
+            26 => input.uint(4)?,
+            27 => input.uint(8)?,
+            _ => return Err(WireError::Unsupported(initial, at)),
+        };
+        match major {
+            0 => Ok(Value::from(n)),
+            1 => match i64::try_from(n) {
+                Ok(n) => Ok(Value::from(-1 - n)),
+                Err(_) => Ok(float(-1.0 - n as f64)),
+            },
+            3 => Ok(Value::String(input.text(n as usize)?)),
+            4 => {
+                let mut items = Vec::new();
+                for _ in 0..n {
+                    items.push(decode(input, depth + 1)?);
+                }
+                Ok(Value::Array(items))
+            }
+            5 => {
+                let mut map = Map::new();
+                for _ in 0..n {
+                    let key = match decode(input, depth + 1)? {
+                        Value::String(k) => k,
+                        _ => return Err(WireError::NonStringKey(at)),
+                    };
+                    map.insert(key, decode(input, depth + 1)?);
+                }
+                Ok(Value::Object(map))
+            }
+            6 => decode(input, depth + 1),
+            _ => Err(WireError::Unsupported(initial, at)),
+        }
+    }
+}
+
+#[cfg(feature = "msgpack")]
+pub mod msgpack {
+    use super::{Input, WireError, MAX_DEPTH};
+    use serde::{de::DeserializeOwned, Serialize};
+    use serde_json::{Map, Number, Value};
+
+    pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, WireError> {
+        let mut out = Vec::new();
+        encode(&serde_json::to_value(value)?, &mut out);
+        Ok(out)
+    }
+
+    pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, WireError> {
+        let mut input = Input { bytes, pos: 0 };
+        let value = decode(&mut input, 0)?;
+        input.finish()?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    fn len(fix: u8, fix_max: usize, wide: [u8; 3], n: usize, out: &mut Vec<u8>) {
+        if n <= fix_max {
+            out.push(fix | n as u8);
+        } else if wide[0] != 0 && n <= 0xff {
+            out.extend_from_slice(&[wide[0], n as u8]);
+        } else if n <= 0xffff {
+            out.push(wide[1]);
+            out.extend_from_slice(&(n as u16).to_be_bytes());
+        } else {
+            out.push(wide[2]);
+            out.extend_from_slice(&(n as u32).to_be_bytes());
+        }
+    }
+
+    fn encode(value: &Value, out: &mut Vec<u8>) {
+        match value {
+            Value::Null => out.push(0xc0),
+            Value::Bool(false) => out.push(0xc2),
+            Value::Bool(true) => out.push(0xc3),
+            Value::Number(n) => {
+                if let Some(u) = n.as_u64() {
+                    if u <= 0x7f {
+                        out.push(u as u8);
+                    } else {
+                        out.push(0xcf);
+                        out.extend_from_slice(&u.to_be_bytes());
+                    }
+                } else if let Some(i) = n.as_i64() {
+                    if i >= -32 {
+                        out.push(i as i8 as u8);
+                    } else {
+                        out.push(0xd3);
+                        out.extend_from_slice(&i.to_be_bytes());
+                    }
+                } else {
+                    out.push(0xcb);
+                    out.extend_from_slice(&n.as_f64().unwrap_or(f64::NAN).to_be_bytes());
+                }
+            }
+            Value::String(s) => {
+                len(0xa0, 31, [0xd9, 0xda, 0xdb], s.len(), out);
+                out.extend_from_slice(s.as_bytes());
+            }
+            Value::Array(items) => {
+                len(0x90, 15, [0, 0xdc, 0xdd], items.len(), out);
+                items.iter().for_each(|i| encode(i, out));
+            }
+            Value::Object(map) => {
+                len(0x80, 15, [0, 0xde, 0xdf], map.len(), out);
+                for (k, v) in map {
+                    encode(&Value::String(k.clone()), out);
+                    encode(v, out);
+                }
+            }
+        }
+    }
+
+    fn float(f: f64) -> Value {
+        Number::from_f64(f)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+
+    fn decode(input: &mut Input<'_>, depth: usize) -> Result<Value, WireError> {
+        if depth > MAX_DEPTH {
+            return Err(WireError::TooDeep);
+        }
+        let at = input.pos;
+        let b = input.byte()?;
+        let (items, pairs) = match b {
+            0x00..=0x7f => return Ok(Value::from(b)),
+            0xe0..=0xff => return Ok(Value::from(b as i8)),
+            0xc0 => return Ok(Value::Null),
+            0xc2 => return Ok(Value::Bool(false)),
+            0xc3 => return Ok(Value::Bool(true)),
+            0xcc => return Ok(Value::from(input.uint(1)?)),
+            0xcd => return Ok(Value::from(input.uint(2)?)),
+            0xce => return Ok(Value::from(input.uint(4)?)),
+            0xcf => return Ok(Value::from(input.uint(8)?)),
+            0xd0 => return Ok(Value::from(input.uint(1)? as u8 as i8)),
+            0xd1 => return Ok(Value::from(input.uint(2)? as u16 as i16)),
+            0xd2 => return Ok(Value::from(input.uint(4)? as u32 as i32)),
+            0xd3 => return Ok(Value::from(input.uint(8)? as i64)),
+            0xca => return Ok(float(f32::from_bits(input.uint(4)? as u32) as f64)),
+            0xcb => return Ok(float(f64::from_bits(input.uint(8)?))),
+            0xa0..=0xbf => return Ok(Value::String(input.text((b & 0x1f) as usize)?)),
+            0xd9 => {
+                let n = input.uint(1)? as usize;
+                return Ok(Value::String(input.text(n)?));
+            }
+            0xda => {
+                let n = input.uint(2)? as usize;
+                return Ok(Value::String(input.text(n)?));
+            }
+            0xdb => {
+                let n = input.uint(4)? as usize;
+                return Ok(Value::String(input.text(n)?));
+            }
+            0x90..=0x9f => ((b & 0x0f) as u64, false),
+            0xdc => (input.uint(2)?, false),
+            0xdd => (input.uint(4)?, false),
+            0x80..=0x8f => ((b & 0x0f) as u64, true),
+            0xde => (input.uint(2)?, true),
+            0xdf => (input.uint(4)?, true),
+            _ => return Err(WireError::Unsupported(b, at)),
+        };
+        if pairs {
+            let mut map = Map::new();
+            for _ in 0..items {
+                let key = match decode(input, depth + 1)? {
+                    Value::String(k) => k,
+                    _ => return Err(WireError::NonStringKey(at)),
+                };
+                map.insert(key, decode(input, depth + 1)?);
+            }
+            Ok(Value::Object(map))
+        } else {
+            let mut values = Vec::new();
+            for _ in 0..items {
+                values.push(decode(input, depth + 1)?);
+            }
+            Ok(Value::Array(values))
+        }
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "cbor", feature = "msgpack"))]
+mod tests {
+    use crate::policy::{Condition, Policy, Rule};
+    use serde_json::json;
+
+    fn policy() -> Policy {
+        Policy::new("default", "1.0")
+            .with_rule(Rule::allow("fs.read").with_conditions(vec![Condition::new(
+                "size",
+                "lt",
+                json!(-40000),
+            )]))
+            .with_rule(Rule::deny("shell").with_conditions(vec![Condition::new(
+                "ratio",
+                "gt",
+                json!(0.5),
+            )]))
+    }
+
+    fn same(a: &Policy, b: &Policy) -> bool {
+        serde_json::to_value(a).unwrap() == serde_json::to_value(b).unwrap()
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_roundtrip() {
+        use crate::wire::cbor;
+        let bytes = cbor::to_vec(&policy()).unwrap();
+        assert!(same(&cbor::from_slice(&bytes).unwrap(), &policy()));
+        assert_eq!(cbor::to_vec(&json!(500)).unwrap(), vec![0x19, 0x01, 0xf4]);
+        assert_eq!(cbor::to_vec(&json!(-1)).unwrap(), vec![0x20]);
+        assert!(cbor::from_slice::<Policy>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_roundtrip() {
+        use crate::wire::msgpack;
+        let bytes = msgpack::to_vec(&policy()).unwrap();
+        assert!(same(&msgpack::from_slice(&bytes).unwrap(), &policy()));
+        assert_eq!(msgpack::to_vec(&json!(-5)).unwrap(), vec![0xfb]);
+        assert_eq!(
+            msgpack::from_slice::<i64>(&[0xd1, 0xff, 0x00]).unwrap(),
+            -256
+        );
+        assert!(msgpack::from_slice::<Policy>(&[0xc1]).is_err());
+    }
+}